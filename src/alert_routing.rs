@@ -0,0 +1,426 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use color_eyre::eyre::{Result, bail, eyre};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::alerts::{Alert, AlertLevel, new_alerts};
+use crate::http;
+use crate::lock;
+use crate::models::{AppConfig, NetworkConfig, write_atomically};
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+/// A hook still running after this is killed, so one stuck script does not
+/// hold up every later alert.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+const COMMAND_POLL: Duration = Duration::from_millis(50);
+/// Channel name that keeps an alert on the dashboard only.
+const TUI_CHANNEL: &str = "tui";
+
+/// Where routed alerts can be sent, keyed by name in `alert_channels`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum AlertChannel {
+    /// A desktop notification through `notify-send` or `osascript`.
+    Desktop,
+    /// A JSON POST, with a `text` field so Slack-style incoming webhooks
+    /// show it as is.
+    Webhook { url: String },
+    /// A program run with its arguments (no shell), given the alert event
+    /// as JSON on stdin. Unlike the others it also hears about resolved
+    /// alerts, so a script can undo what it did.
+    Command { command: Vec<String> },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum AlertState {
+    Firing,
+    Resolved,
+}
+
+/// An alert starting or stopping to hold.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct AlertEvent {
+    pub(crate) state: AlertState,
+    #[serde(flatten)]
+    pub(crate) alert: Alert,
+}
+
+/// One rule of `alert_routes`. Empty lists match anything; the first rule
+/// that matches an alert decides its channels.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct AlertRoute {
+    /// Alert keys or key prefixes, e.g. `budget` or `codex.5h`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) kinds: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) providers: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) levels: Vec<AlertLevel>,
+    pub(crate) channels: Vec<String>,
+}
+
+impl AlertRoute {
+    fn matches(&self, alert: &Alert) -> bool {
+        let kind = self.kinds.is_empty()
+            || self.kinds.iter().any(|kind| {
+                alert.key == *kind
+                    || alert
+                        .key
+                        .strip_prefix(kind.as_str())
+                        .is_some_and(|rest| rest.starts_with('.'))
+            });
+        let provider = self.providers.is_empty()
+            || alert
+                .provider
+                .as_ref()
+                .is_some_and(|provider| self.providers.contains(provider));
+        let level = self.levels.is_empty() || self.levels.contains(&alert.level);
+        kind && provider && level
+    }
+}
+
+/// Names of the channels `alert` goes to; empty when no rule matches or
+/// the rule keeps it on the dashboard.
+pub(crate) fn route<'a>(config: &'a AppConfig, alert: &Alert) -> Vec<&'a str> {
+    config
+        .alert_routes
+        .iter()
+        .find(|route| route.matches(alert))
+        .map(|route| {
+            route
+                .channels
+                .iter()
+                .map(String::as_str)
+                .filter(|channel| *channel != TUI_CHANNEL)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Events for the routed alerts that started or stopped firing since the
+/// ones recorded in `state_file`, which then records `current`. The record
+/// outlives the process and is shared with any other process routing for
+/// the same config, so a restart or a dashboard next to the daemon does not
+/// send an alert again. A missing or unreadable record counts as empty.
+pub(crate) fn take_new_events(
+    config: &AppConfig,
+    state_file: &Path,
+    current: &[Alert],
+) -> Result<Vec<AlertEvent>> {
+    let _lock = lock::exclusive(state_file)?;
+    let previous = fs::read_to_string(state_file)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<Vec<Alert>>(&contents).ok())
+        .unwrap_or_default();
+    let fired = new_alerts(&previous, current)
+        .into_iter()
+        .map(|alert| (AlertState::Firing, alert));
+    let resolved = new_alerts(current, &previous)
+        .into_iter()
+        .map(|alert| (AlertState::Resolved, alert));
+    let events = fired
+        .chain(resolved)
+        .filter(|(_, alert)| !route(config, alert).is_empty())
+        .map(|(state, alert)| AlertEvent {
+            state,
+            alert: alert.clone(),
+        })
+        .collect();
+    if previous != current {
+        write_atomically(state_file, &serde_json::to_vec_pretty(current)?)?;
+    }
+    Ok(events)
+}
+
+/// Sends each event to its alert's routed channels. Blocks on network,
+/// notification and hook calls; returns one message per failed delivery.
+pub(crate) fn deliver(config: &AppConfig, events: &[AlertEvent]) -> Vec<String> {
+    let mut failures = Vec::new();
+    for event in events {
+        let alert = &event.alert;
+        for name in route(config, alert) {
+            let firing = event.state == AlertState::Firing;
+            let result = match config.alert_channels.get(name) {
+                Some(AlertChannel::Desktop) if firing => notify_desktop(alert),
+                Some(AlertChannel::Webhook { url }) if firing => {
+                    post_webhook(&config.network, url, alert)
+                }
+                Some(AlertChannel::Command { command }) => run_hook(command, event),
+                Some(_) => Ok(()),
+                None => Err(eyre!("no alert channel named {name:?}")),
+            };
+            if let Err(err) = result {
+                failures.push(format!("{name}: {err}"));
+            }
+        }
+    }
+    failures
+}
+
+fn post_webhook(network: &NetworkConfig, url: &str, alert: &Alert) -> Result<()> {
+    let agent = http::agent(network, WEBHOOK_TIMEOUT)?;
+    let payload = json!({
+        "text": format!("PromptPetrol: {}", alert.message),
+        "key": alert.key,
+        "level": alert.level,
+        "provider": alert.provider,
+        "message": alert.message,
+    });
+    agent
+        .post(url)
+        .content_type("application/json")
+        .send(payload.to_string())?;
+    Ok(())
+}
+
+fn run_hook(command: &[String], event: &AlertEvent) -> Result<()> {
+    let Some((program, args)) = command.split_first() else {
+        bail!("empty command");
+    };
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .map_err(|err| eyre!("{program}: {err}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook that exits without reading stdin is fine.
+        let _ = stdin.write_all(serde_json::to_string(event)?.as_bytes());
+    }
+    let deadline = Instant::now() + COMMAND_TIMEOUT;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("{program} timed out after {}s", COMMAND_TIMEOUT.as_secs());
+        }
+        thread::sleep(COMMAND_POLL);
+    };
+    if !status.success() {
+        bail!("{program} exited with {status}");
+    }
+    Ok(())
+}
+
+fn notify_desktop(alert: &Alert) -> Result<()> {
+    let status = if cfg!(target_os = "macos") {
+        Command::new("osascript")
+            .arg("-e")
+            .arg(format!(
+                "display notification {:?} with title \"PromptPetrol\"",
+                alert.message
+            ))
+            .status()
+    } else {
+        Command::new("notify-send")
+            .arg("PromptPetrol")
+            .arg(&alert.message)
+            .status()
+    }
+    .map_err(|err| eyre!("desktop notification: {err}"))?;
+    if !status.success() {
+        bail!("desktop notification exited with {status}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::make_temp_dir;
+
+    #[test]
+    fn first_matching_route_picks_the_channels() {
+        let config = serde_json::from_str::<AppConfig>(
+            r#"{
+                "alert_channels": {
+                    "me": {"type": "desktop"},
+                    "team": {"type": "webhook", "url": "https://hooks.example/team"}
+                },
+                "alert_routes": [
+                    {"kinds": ["codex"], "channels": ["me"]},
+                    {"kinds": ["budget"], "levels": ["alert"], "channels": ["team", "tui"]},
+                    {"kinds": ["unpriced"], "channels": ["tui"]}
+                ]
+            }"#,
+        )
+        .expect("config");
+        let alert = |key: &str, level: AlertLevel, provider: Option<&str>| Alert {
+            key: key.to_string(),
+            level,
+            provider: provider.map(str::to_string),
+            message: String::new(),
+        };
+
+        assert_eq!(
+            route(
+                &config,
+                &alert("codex.5h", AlertLevel::Alert, Some("codex"))
+            ),
+            vec!["me"]
+        );
+        assert_eq!(
+            route(&config, &alert("budget.exhausted", AlertLevel::Alert, None)),
+            vec!["team"]
+        );
+        assert!(route(&config, &alert("budget.watch", AlertLevel::Watch, None)).is_empty());
+        assert!(route(&config, &alert("unpriced", AlertLevel::Watch, None)).is_empty());
+        assert!(route(&config, &alert("codexx", AlertLevel::Alert, None)).is_empty());
+
+        let mut config = config;
+        config.alert_routes = vec![AlertRoute {
+            providers: vec!["codex".to_string()],
+            channels: vec!["missing".to_string()],
+            ..AlertRoute::default()
+        }];
+        let firing = |alert| AlertEvent {
+            state: AlertState::Firing,
+            alert,
+        };
+        let failures = deliver(
+            &config,
+            &[firing(alert(
+                "session.runaway.a",
+                AlertLevel::Alert,
+                Some("codex"),
+            ))],
+        );
+        assert_eq!(
+            failures,
+            vec!["missing: no alert channel named \"missing\""]
+        );
+        assert!(
+            deliver(
+                &config,
+                &[firing(alert("budget.alert", AlertLevel::Alert, None))]
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn alerts_already_routed_are_not_sent_again_after_a_restart() {
+        let state_file = make_temp_dir("routed").join("routed-alerts.json");
+        let config = serde_json::from_str::<AppConfig>(
+            r#"{
+                "alert_channels": {"team": {"type": "webhook", "url": "https://hooks.example/team"}},
+                "alert_routes": [{"kinds": ["budget"], "channels": ["team"]}]
+            }"#,
+        )
+        .expect("config");
+        let alert = |key: &str, message: &str| Alert {
+            key: key.to_string(),
+            level: AlertLevel::Alert,
+            provider: None,
+            message: message.to_string(),
+        };
+        let states = |events: Vec<AlertEvent>| {
+            events
+                .into_iter()
+                .map(|event| (event.state, event.alert.key))
+                .collect::<Vec<_>>()
+        };
+        let firing = [
+            alert("budget.alert", "92% used"),
+            alert("unpriced", "2 entries"),
+        ];
+
+        let events = take_new_events(&config, &state_file, &firing).expect("first run");
+        assert_eq!(
+            states(events),
+            [(AlertState::Firing, "budget.alert".to_string())]
+        );
+        // A restarted process, or a second one, reads what was sent.
+        let still = [
+            alert("budget.alert", "93% used"),
+            alert("unpriced", "2 entries"),
+        ];
+        assert!(
+            take_new_events(&config, &state_file, &still)
+                .expect("restart")
+                .is_empty()
+        );
+        let events = take_new_events(&config, &state_file, &[]).expect("cleared");
+        assert_eq!(
+            states(events),
+            [(AlertState::Resolved, "budget.alert".to_string())]
+        );
+        assert_eq!(fs::read_to_string(&state_file).expect("state").trim(), "[]");
+        let _ = fs::remove_dir_all(state_file.parent().expect("dir"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hooks_get_firing_and_resolved_events_on_stdin() {
+        let out = make_temp_dir("hook").join("hook.jsonl");
+        let hook = vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            format!("cat >> {}; echo >> {}", out.display(), out.display()),
+        ];
+        let mut config = AppConfig::default();
+        config
+            .alert_channels
+            .insert("hook".to_string(), AlertChannel::Command { command: hook });
+        config.alert_channels.insert(
+            "failing".to_string(),
+            AlertChannel::Command {
+                command: vec!["false".to_string()],
+            },
+        );
+        config.alert_routes = vec![
+            AlertRoute {
+                kinds: vec!["spend".to_string()],
+                channels: vec!["failing".to_string()],
+                ..AlertRoute::default()
+            },
+            AlertRoute {
+                channels: vec!["hook".to_string()],
+                ..AlertRoute::default()
+            },
+        ];
+        let alert = Alert {
+            key: "budget.alert".to_string(),
+            level: AlertLevel::Alert,
+            provider: None,
+            message: "spent $95.00 of $100.00 budget (95%)".to_string(),
+        };
+        let events = [AlertState::Firing, AlertState::Resolved].map(|state| AlertEvent {
+            state,
+            alert: alert.clone(),
+        });
+        assert!(deliver(&config, &events).is_empty());
+        let lines = std::fs::read_to_string(&out).expect("hook output");
+        let lines = lines.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![
+                r#"{"state":"firing","key":"budget.alert","level":"alert","message":"spent $95.00 of $100.00 budget (95%)"}"#,
+                r#"{"state":"resolved","key":"budget.alert","level":"alert","message":"spent $95.00 of $100.00 budget (95%)"}"#,
+            ]
+        );
+
+        let spend = AlertEvent {
+            state: AlertState::Firing,
+            alert: Alert {
+                key: "spend.rate".to_string(),
+                ..alert
+            },
+        };
+        let failures = deliver(&config, &[spend]);
+        assert_eq!(failures.len(), 1);
+        assert!(failures[0].starts_with("failing: false exited with"));
+        let _ = std::fs::remove_dir_all(out.parent().expect("dir"));
+    }
+}
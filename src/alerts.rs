@@ -0,0 +1,417 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::budget_period::budget_spend;
+use crate::clock;
+use crate::codex_import::{CodexRateLimit, SessionCost};
+use crate::context_growth::HeavyLoad;
+use crate::freeze::{frozen_since, spend_while_frozen};
+use crate::models::{
+    AppConfig, PricingMatch, SpendRateAlertConfig, UsageData, UsageEntry, spend_rate_per_hour,
+};
+use crate::team_budget::TeamBudgetState;
+use crate::worker::Snapshot;
+
+/// Budget share at which spend starts to be reported.
+const BUDGET_WATCH_RATIO: f64 = 0.75;
+const BUDGET_ALERT_RATIO: f64 = 0.9;
+const CODEX_LIMIT_ALERT_PERCENT: f64 = 90.0;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum AlertLevel {
+    Watch,
+    Alert,
+}
+
+/// A condition worth telling someone about. `key` identifies the condition
+/// independently of the numbers in `message`, so callers can tell a new
+/// alert from one that is merely still firing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Alert {
+    pub(crate) key: String,
+    pub(crate) level: AlertLevel,
+    /// Set when the condition is about one provider's usage, for routing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) provider: Option<String>,
+    pub(crate) message: String,
+}
+
+/// Alerts that hold for a snapshot: overall budget use, the rate of
+/// spend, Codex rate-limit windows near exhaustion or just reset, runaway
+/// and heavily loaded sessions, and entries that could not be priced.
+pub(crate) fn evaluate_alerts(snapshot: &Snapshot) -> Vec<Alert> {
+    let mut alerts = Vec::new();
+    let now_epoch_secs = clock::now_epoch_secs();
+    match snapshot.team_budget.as_ref() {
+        Some(team) => alerts.extend(team_budget_alert(team, snapshot.data.budget_usd)),
+        None => alerts.extend(budget_alert(
+            &snapshot.data,
+            &snapshot.config,
+            now_epoch_secs,
+        )),
+    }
+    if let Some(since) = frozen_since(&snapshot.config) {
+        alerts.push(freeze_alert(since));
+        alerts.extend(
+            spend_while_frozen(&snapshot.data, &snapshot.config)
+                .into_iter()
+                .map(frozen_spend_alert),
+        );
+    }
+    if let Some(rate) = snapshot.config.spend_rate_alert.as_ref() {
+        alerts.extend(spend_rate_alert(&snapshot.data, rate, now_epoch_secs));
+    }
+    if let Some(limits) = snapshot.codex_limits.as_ref() {
+        for (window, limit) in [
+            ("5h", limits.primary.as_ref()),
+            ("weekly", limits.secondary.as_ref()),
+        ] {
+            alerts.extend(codex_limit_alert(window, limit, now_epoch_secs));
+            alerts.extend(codex_reset_alert(window, limit, now_epoch_secs));
+        }
+    }
+    if let Some(ceiling) = snapshot.config.session_cost_ceiling_usd {
+        alerts.extend(
+            snapshot
+                .runaway_sessions
+                .iter()
+                .map(|session| runaway_session_alert(session, ceiling)),
+        );
+    }
+    alerts.extend(snapshot.heavy_load.as_ref().map(heavy_load_alert));
+
+    let unpriced = snapshot
+        .data
+        .entries
+        .iter()
+        .filter(|entry| entry.pricing_match == Some(PricingMatch::Missing))
+        .count();
+    if unpriced > 0 {
+        alerts.push(Alert {
+            key: "unpriced".to_string(),
+            level: AlertLevel::Watch,
+            provider: None,
+            message: format!("{unpriced} entries have no matching pricing"),
+        });
+    }
+    alerts
+}
+
+/// Against all recorded spend, or this month's once `budget_rollover`
+/// makes the budget a monthly one.
+fn budget_alert(data: &UsageData, config: &AppConfig, now_epoch_secs: i64) -> Option<Alert> {
+    spend_alert(
+        budget_spend(data, config, now_epoch_secs),
+        data.budget_usd,
+        "",
+    )
+}
+
+/// Same thresholds as the local budget, applied to everyone's spend.
+fn team_budget_alert(team: &TeamBudgetState, local_budget: Option<f64>) -> Option<Alert> {
+    spend_alert(team.spent_usd(), team.budget_or(local_budget), "team ")
+}
+
+fn spend_alert(spent: f64, budget: Option<f64>, scope: &str) -> Option<Alert> {
+    let budget = budget.filter(|budget| *budget > 0.0)?;
+    let ratio = spent / budget;
+    let (key, level) = if ratio >= 1.0 {
+        ("budget.exhausted", AlertLevel::Alert)
+    } else if ratio >= BUDGET_ALERT_RATIO {
+        ("budget.alert", AlertLevel::Alert)
+    } else if ratio >= BUDGET_WATCH_RATIO {
+        ("budget.watch", AlertLevel::Watch)
+    } else {
+        return None;
+    };
+    Some(Alert {
+        key: key.to_string(),
+        level,
+        provider: None,
+        message: format!(
+            "{scope}spent ${spent:.2} of ${budget:.2} budget ({:.0}%)",
+            ratio * 100.0
+        ),
+    })
+}
+
+/// Fires on a burst that may be fine in total but suggests something is
+/// looping.
+pub(crate) fn spend_rate_alert(
+    data: &UsageData,
+    config: &SpendRateAlertConfig,
+    now_epoch_secs: i64,
+) -> Option<Alert> {
+    let window_secs = config.window_minutes as i64 * 60;
+    let rate = spend_rate_per_hour(data, now_epoch_secs, window_secs);
+    (rate > config.usd_per_hour).then(|| Alert {
+        key: "spend.rate".to_string(),
+        level: AlertLevel::Alert,
+        provider: None,
+        message: format!(
+            "spending ${rate:.2}/h over the last {} min (alarm at ${:.2}/h)",
+            config.window_minutes, config.usd_per_hour
+        ),
+    })
+}
+
+/// Stops holding once the window resets, even before a new reading says
+/// so.
+fn codex_limit_alert(
+    window: &str,
+    limit: Option<&CodexRateLimit>,
+    now_epoch_secs: i64,
+) -> Option<Alert> {
+    let limit = limit.filter(|limit| {
+        limit.used_percent >= CODEX_LIMIT_ALERT_PERCENT && !limit.has_reset(now_epoch_secs)
+    })?;
+    Some(Alert {
+        key: format!("codex.{window}"),
+        level: AlertLevel::Alert,
+        provider: Some("codex".to_string()),
+        message: format!("codex {window} limit at {:.1}% used", limit.used_percent),
+    })
+}
+
+/// Holds from the moment the window resets until a reading from the new
+/// window comes in, so a route hears about it once, when it resets. Its
+/// kind is `codex_reset` rather than `codex`, so routes for the limit
+/// alerts don't pick it up unasked. A window nothing was used in is left
+/// out.
+fn codex_reset_alert(
+    window: &str,
+    limit: Option<&CodexRateLimit>,
+    now_epoch_secs: i64,
+) -> Option<Alert> {
+    let limit =
+        limit.filter(|limit| limit.used_percent > 0.0 && limit.has_reset(now_epoch_secs))?;
+    let resets_at = clock::format_timestamp(limit.resets_at? as i64);
+    Some(Alert {
+        key: format!("codex_reset.{window}"),
+        level: AlertLevel::Watch,
+        provider: Some("codex".to_string()),
+        message: format!(
+            "codex {window} limit reset at {} UTC (was {:.1}% used)",
+            &resets_at[11..16],
+            limit.used_percent
+        ),
+    })
+}
+
+/// Holds for as long as the freeze does, and stops the throttle file.
+fn freeze_alert(since: i64) -> Alert {
+    Alert {
+        key: "spend.frozen".to_string(),
+        level: AlertLevel::Alert,
+        provider: None,
+        message: format!(
+            "SPENDING FROZEN since {}; the governor allows $0.00/day",
+            clock::format_timestamp(since)
+        ),
+    }
+}
+
+/// One per entry, so every bit of spend during a freeze is news.
+fn frozen_spend_alert(entry: &UsageEntry) -> Alert {
+    Alert {
+        key: format!("spend.frozen.{}.{}", entry.provider, entry.timestamp),
+        level: AlertLevel::Alert,
+        provider: Some(entry.provider.to_string()),
+        message: format!(
+            "{}/{} spent ${:.2} at {} while spending is frozen",
+            entry.provider, entry.model, entry.cost_usd, entry.timestamp
+        ),
+    }
+}
+
+/// One per session, so a second runaway is news even while the first is
+/// still firing.
+fn runaway_session_alert(session: &SessionCost, ceiling: f64) -> Alert {
+    Alert {
+        key: format!("session.runaway.{}", session.path.display()),
+        level: AlertLevel::Alert,
+        provider: Some("codex".to_string()),
+        message: format!(
+            "RUNAWAY SESSION {} at ${:.2} (ceiling ${ceiling:.2})",
+            session.path.display(),
+            session.cost_usd
+        ),
+    }
+}
+
+/// Advice rather than a breach, so it is a `Watch` and never throttles.
+fn heavy_load_alert(load: &HeavyLoad) -> Alert {
+    Alert {
+        key: format!("session.heavy_load.{}", load.path.display()),
+        level: AlertLevel::Watch,
+        provider: Some("codex".to_string()),
+        message: format!(
+            "HEAVY LOAD {} sent {} input tokens last turn (advisory at {}); consider compacting its context",
+            load.path.display(),
+            load.turn_input_tokens,
+            load.threshold
+        ),
+    }
+}
+
+/// Alerts in `current` whose key was not present in `previous`.
+pub(crate) fn new_alerts<'a>(previous: &[Alert], current: &'a [Alert]) -> Vec<&'a Alert> {
+    current
+        .iter()
+        .filter(|alert| !previous.iter().any(|seen| seen.key == alert.key))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_alert_steps_through_watch_alert_and_exhausted() {
+        let config = AppConfig::default();
+        let mut data = UsageData::default();
+        let spent = data.entries.iter().map(|entry| entry.cost_usd).sum::<f64>();
+
+        data.budget_usd = Some(spent * 10.0);
+        assert!(budget_alert(&data, &config, 0).is_none());
+
+        data.budget_usd = Some(spent / 0.8);
+        let watch = budget_alert(&data, &config, 0).expect("watch");
+        assert_eq!(watch.key, "budget.watch");
+        assert_eq!(watch.level, AlertLevel::Watch);
+
+        data.budget_usd = Some(spent / 2.0);
+        assert_eq!(
+            budget_alert(&data, &config, 0).expect("over").key,
+            "budget.exhausted"
+        );
+    }
+
+    #[test]
+    fn team_budget_alert_uses_everyones_spend() {
+        let team = serde_json::from_str::<TeamBudgetState>(
+            r#"{"members": {
+                "ana": {"spent_usd": 60, "updated_at": "2026-03-01T09:00:00Z"},
+                "bo": {"spent_usd": 35, "updated_at": "2026-03-01T09:00:00Z"}
+            }}"#,
+        )
+        .expect("team");
+        let alert = team_budget_alert(&team, Some(100.0)).expect("alert");
+        assert_eq!(alert.key, "budget.alert");
+        assert!(alert.message.starts_with("team spent $95.00"));
+        assert!(team_budget_alert(&team, None).is_none());
+    }
+
+    #[test]
+    fn spend_rate_alert_looks_at_the_recent_window_only() {
+        let now = clock::parse_timestamp("2026-02-10T04:00:00Z").expect("timestamp");
+        let data = UsageData::default();
+        let config = SpendRateAlertConfig {
+            usd_per_hour: 0.1,
+            window_minutes: 60,
+        };
+        // Only the 03:15 gemini entry ($0.056) is within the hour.
+        assert!(spend_rate_alert(&data, &config, now).is_none());
+
+        let config = SpendRateAlertConfig {
+            usd_per_hour: 0.1,
+            window_minutes: 30,
+        };
+        assert!(spend_rate_alert(&data, &config, now).is_none());
+        let alert = spend_rate_alert(&data, &config, now - 15 * 60).expect("burst");
+        assert_eq!(alert.key, "spend.rate");
+        assert_eq!(
+            alert.message,
+            "spending $0.11/h over the last 30 min (alarm at $0.10/h)"
+        );
+    }
+
+    #[test]
+    fn codex_windows_announce_their_reset_once_it_passes() {
+        let resets_at = clock::parse_timestamp("2026-02-18T14:00:00Z").expect("timestamp");
+        let limit = CodexRateLimit {
+            used_percent: 96.0,
+            window_minutes: 300,
+            resets_at: Some(resets_at as u64),
+        };
+        let before = resets_at - 60;
+        assert_eq!(
+            codex_limit_alert("5h", Some(&limit), before)
+                .expect("near the limit")
+                .key,
+            "codex.5h"
+        );
+        assert!(codex_reset_alert("5h", Some(&limit), before).is_none());
+
+        assert!(codex_limit_alert("5h", Some(&limit), resets_at).is_none());
+        let reset = codex_reset_alert("5h", Some(&limit), resets_at).expect("reset");
+        assert_eq!(reset.key, "codex_reset.5h");
+        assert_eq!(reset.level, AlertLevel::Watch);
+        assert_eq!(
+            reset.message,
+            "codex 5h limit reset at 14:00 UTC (was 96.0% used)"
+        );
+
+        let unused = CodexRateLimit {
+            used_percent: 0.0,
+            ..limit.clone()
+        };
+        assert!(codex_reset_alert("5h", Some(&unused), resets_at).is_none());
+        let unknown = CodexRateLimit {
+            resets_at: None,
+            ..limit
+        };
+        assert!(codex_reset_alert("5h", Some(&unknown), resets_at).is_none());
+    }
+
+    #[test]
+    fn runaway_session_alert_names_the_session_file() {
+        let session = SessionCost {
+            path: "sessions/2026/02/18/rollout-a.jsonl".into(),
+            cost_usd: 12.5,
+        };
+        let alert = runaway_session_alert(&session, 10.0);
+        assert_eq!(
+            alert.key,
+            "session.runaway.sessions/2026/02/18/rollout-a.jsonl"
+        );
+        assert_eq!(alert.level, AlertLevel::Alert);
+        assert_eq!(
+            alert.message,
+            "RUNAWAY SESSION sessions/2026/02/18/rollout-a.jsonl at $12.50 (ceiling $10.00)"
+        );
+    }
+
+    #[test]
+    fn heavy_load_alert_is_advisory() {
+        let alert = heavy_load_alert(&HeavyLoad {
+            path: "sessions/rollout-b.jsonl".into(),
+            turn_input_tokens: 140_000,
+            threshold: 120_000,
+        });
+        assert_eq!(alert.key, "session.heavy_load.sessions/rollout-b.jsonl");
+        assert_eq!(alert.level, AlertLevel::Watch);
+        assert_eq!(
+            alert.message,
+            "HEAVY LOAD sessions/rollout-b.jsonl sent 140000 input tokens last turn (advisory at 120000); consider compacting its context"
+        );
+    }
+
+    #[test]
+    fn new_alerts_compares_by_key_not_message() {
+        let alert = |key: &str, message: &str| Alert {
+            key: key.to_string(),
+            level: AlertLevel::Alert,
+            provider: None,
+            message: message.to_string(),
+        };
+        let previous = vec![alert("budget.alert", "91%")];
+        let current = vec![alert("budget.alert", "93%"), alert("codex.5h", "95%")];
+        let fresh = new_alerts(&previous, &current);
+        assert_eq!(fresh.len(), 1);
+        assert_eq!(fresh[0].key, "codex.5h");
+    }
+}
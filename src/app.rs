@@ -1,92 +1,626 @@
-use std::io;
-use std::path::PathBuf;
-use std::time::{Duration, Instant, SystemTime};
+use std::backtrace::Backtrace;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use color_eyre::Result;
-use crossterm::event::{self, Event, KeyCode};
+use crossterm::cursor::{Hide, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
 use crossterm::execute;
 use crossterm::terminal::{
     EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
 };
 use ratatui::DefaultTerminal;
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio::time;
 
-use crate::codex_import::{CodexImportCache, codex_import_diagnostics, merge_codex_usage};
+use crate::clock;
+use crate::codex_import::{CodexImportDiagnostics, CodexRateLimits, ImportProgress, SessionCost};
+use crate::context_growth::{ContextGrowth, HeavyLoad};
+use crate::editor_import::{EditorSource, EditorSourceStatus, PlanQuota};
+use crate::importers::{Importer, ImporterStatus};
+use crate::keymap::{Action, KeyContext, Keymap};
+use crate::langfuse_import::base64_encode;
 use crate::models::{
-    AppConfig, UsageData, default_config_file, default_data_file, load_or_bootstrap_config,
-    load_or_bootstrap_data, provider_summaries,
+    AppConfig, ProviderItem, ProviderStats, UsageData, WeightedTotals, decayed_totals,
+    model_leaderboard, provider_items,
 };
-use crate::ui::draw;
+use crate::pricing_editor::{PricingEditOutcome, PricingEditor};
+use crate::reconcile::Reconciliation;
+use crate::redact::{self, Redactor};
+use crate::retry::PollerHealth;
+use crate::summary::UsageSummary;
+use crate::team_budget::TeamBudgetState;
+use crate::theme::{Severity, to_monochrome};
+use crate::ui::{alert_lines, collect_alerts, draw, draw_setup_wizard};
+use crate::widgets::{AppState, Dial, table_rows};
+use crate::wizard::{SetupChoices, SetupWizard, WizardOutcome};
+use crate::worker::{RefreshWorker, Snapshot, WorkerCommand, run_refresh_worker};
 
 pub(crate) const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+pub(crate) const DEFAULT_KIOSK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How the dashboard presents itself, from the command line.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct DisplayOptions {
+    /// `--kiosk`: how often to move on to the next provider.
+    pub(crate) kiosk: Option<Duration>,
+    pub(crate) no_canvas: bool,
+    /// `--monochrome`, or a terminal without 256 colours: reverse video
+    /// and bold instead of colour.
+    pub(crate) monochrome: bool,
+    /// Open the onboarding tour; set once the setup wizard has run.
+    pub(crate) tour: bool,
+}
+
+const INPUT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const STATUS_HISTORY_LIMIT: usize = 50;
+/// Largest count prefix, so `99999999j` can't overflow anything.
+const MAX_COUNT: usize = 9999;
+/// Snapshots of dial readings kept for the zoomed dial's trend.
+const DIAL_HISTORY_LIMIT: usize = 240;
+
+enum LoopAction {
+    Continue,
+    /// Nothing on screen changed, so the frame is not drawn again.
+    Unchanged,
+    Quit,
+    Suspend,
+    /// Put the text on the terminal's clipboard.
+    Copy(String),
+}
+
+enum JobControl {
+    Suspend,
+    Resume,
+}
+
+/// Job-control signals (SIGTSTP/SIGCONT) delivered while the TUI owns the
+/// terminal. Never fires on platforms without them.
+struct JobControlSignals {
+    #[cfg(unix)]
+    stop: tokio::signal::unix::Signal,
+    #[cfg(unix)]
+    cont: tokio::signal::unix::Signal,
+}
+
+impl JobControlSignals {
+    #[cfg(unix)]
+    fn new() -> Result<Self> {
+        use tokio::signal::unix::{SignalKind, signal};
+        Ok(Self {
+            stop: signal(SignalKind::from_raw(libc::SIGTSTP))?,
+            cont: signal(SignalKind::from_raw(libc::SIGCONT))?,
+        })
+    }
+
+    #[cfg(not(unix))]
+    fn new() -> Result<Self> {
+        Ok(Self {})
+    }
+
+    async fn recv(&mut self) -> JobControl {
+        #[cfg(unix)]
+        {
+            tokio::select! {
+                _ = self.stop.recv() => JobControl::Suspend,
+                _ = self.cont.recv() => JobControl::Resume,
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            std::future::pending().await
+        }
+    }
+}
+
+/// How far back the model leaderboard looks; `w` cycles through these.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LeaderboardWindow {
+    Week,
+    Month,
+    All,
+}
+
+impl LeaderboardWindow {
+    fn next(self) -> Self {
+        match self {
+            LeaderboardWindow::Week => LeaderboardWindow::Month,
+            LeaderboardWindow::Month => LeaderboardWindow::All,
+            LeaderboardWindow::All => LeaderboardWindow::Week,
+        }
+    }
+
+    /// Start of the window, `None` for all time.
+    pub(crate) fn since(self, now_epoch_secs: i64) -> Option<i64> {
+        match self {
+            LeaderboardWindow::Week => Some(now_epoch_secs - 7 * clock::SECS_PER_DAY),
+            LeaderboardWindow::Month => Some(now_epoch_secs - 30 * clock::SECS_PER_DAY),
+            LeaderboardWindow::All => None,
+        }
+    }
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            LeaderboardWindow::Week => "last 7 days",
+            LeaderboardWindow::Month => "last 30 days",
+            LeaderboardWindow::All => "all time",
+        }
+    }
+}
+
+/// The onboarding tour's stops, in order. It opens after the setup wizard
+/// and again with `o`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TourStep {
+    Providers,
+    Info,
+    Alerts,
+    Dials,
+    Keys,
+}
+
+impl TourStep {
+    pub(crate) const ALL: [TourStep; 5] = [
+        TourStep::Providers,
+        TourStep::Info,
+        TourStep::Alerts,
+        TourStep::Dials,
+        TourStep::Keys,
+    ];
+
+    /// The stop after this one, `None` after the last.
+    fn next(self) -> Option<TourStep> {
+        TourStep::ALL.get(self as usize + 1).copied()
+    }
+
+    fn prev(self) -> TourStep {
+        TourStep::ALL[(self as usize).saturating_sub(1)]
+    }
+
+    /// 1-based, for "2/5".
+    pub(crate) fn number(self) -> usize {
+        self as usize + 1
+    }
+}
+
+/// What the lower panel shows; `Tab` cycles through these.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DashboardView {
+    Dials,
+    Forecast,
+    Keys,
+    Projects,
+    Models,
+}
+
+impl DashboardView {
+    pub(crate) fn is_table(self) -> bool {
+        matches!(
+            self,
+            DashboardView::Keys | DashboardView::Projects | DashboardView::Models
+        )
+    }
+
+    fn next(self) -> Self {
+        match self {
+            DashboardView::Dials => DashboardView::Forecast,
+            DashboardView::Forecast => DashboardView::Keys,
+            DashboardView::Keys => DashboardView::Projects,
+            DashboardView::Projects => DashboardView::Models,
+            DashboardView::Models => DashboardView::Dials,
+        }
+    }
+}
+
+/// A list the row keys (`j`/`k`, `gg`/`G`, `y`) move through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RowList {
+    StatusHistory,
+    Alerts,
+    Table,
+}
 
 pub(crate) struct App {
-    data_file: PathBuf,
-    config_file: PathBuf,
     pub(crate) config: AppConfig,
     pub(crate) data: UsageData,
+    /// `data` added up per provider and day, worked out once per snapshot
+    /// rather than on every frame.
+    pub(crate) summary: UsageSummary,
+    /// Per-provider totals weighted by `gauge_half_life_hours`, when set,
+    /// for the dials; see [`decayed_totals`].
+    pub(crate) decayed_totals: Option<Vec<WeightedTotals>>,
+    pub(crate) codex_limits: Option<CodexRateLimits>,
+    pub(crate) codex_limit_history: Vec<(i64, CodexRateLimits)>,
+    pub(crate) codex_diagnostics: CodexImportDiagnostics,
     pub(crate) selected_provider: Option<String>,
     pub(crate) status: String,
-    pub(crate) codex_cache: CodexImportCache,
+    pub(crate) status_history: VecDeque<StatusMessage>,
     pub(crate) show_help: bool,
+    pub(crate) show_status_history: bool,
+    pub(crate) show_unpriced: bool,
+    pub(crate) show_diagnostics: bool,
+    pub(crate) show_context_growth: bool,
+    pub(crate) show_period_preview: bool,
+    pub(crate) show_estimate_accuracy: bool,
+    /// Set by `b` in the model view: how the cost of the row under the
+    /// cursor was computed.
+    pub(crate) show_pricing_breakdown: bool,
+    /// Set by `e` in the model view: the row's pricing being edited.
+    pub(crate) pricing_editor: Option<PricingEditor>,
+    pub(crate) show_sidebar: bool,
+    /// Providers marked with space; when non-empty the gauges show their
+    /// combined stats instead of the selected provider's.
+    pub(crate) marked_providers: BTreeSet<String>,
+    /// Group from `provider_groups` currently drilled into, if any.
+    pub(crate) open_group: Option<String>,
+    /// Set by Enter on a provider: its models are listed in place of the
+    /// view, following the selection, until Esc.
+    pub(crate) show_provider_models: bool,
+    pub(crate) view: DashboardView,
+    pub(crate) leaderboard_window: LeaderboardWindow,
+    /// Set by `f`; the next letter jumps to a provider starting with it.
+    pub(crate) pending_jump: bool,
+    /// Progress of the first Codex import, until its snapshot arrives.
+    pub(crate) import_progress: Option<ImportProgress>,
+    pub(crate) team_budget: Option<TeamBudgetState>,
+    pub(crate) editor_imports: Vec<(EditorSource, EditorSourceStatus)>,
+    pub(crate) plan_quotas: Vec<PlanQuota>,
+    pub(crate) importers: Vec<ImporterStatus>,
+    pub(crate) runaway_sessions: Vec<SessionCost>,
+    pub(crate) context_growth: Vec<ContextGrowth>,
+    pub(crate) heavy_load: Option<HeavyLoad>,
+    pub(crate) pollers: Vec<PollerHealth>,
+    pub(crate) reconciliation: Reconciliation,
+    /// Unspent budget brought into this month; see `budget_rollover`.
+    pub(crate) carried_over_usd: f64,
+    /// Row of the importer list the diagnostics overlay has selected.
+    pub(crate) selected_importer: usize,
+    /// Set by `--kiosk`: how often to move on to the next provider. Only
+    /// `q` is handled and the status chrome is hidden.
+    pub(crate) kiosk: Option<Duration>,
+    /// Set by `--no-canvas`: no dials or charts drawn with the canvas.
+    pub(crate) no_canvas: bool,
+    /// Set by `z`; the dial shown full screen.
+    pub(crate) zoomed_dial: Option<Dial>,
+    /// Set by `i`; the dial whose reading is explained.
+    pub(crate) inspected_dial: Option<Dial>,
+    /// The onboarding tour's current stop while it is open.
+    pub(crate) tour: Option<TourStep>,
+    /// Set by `p`: the reading of `codex_limit_history` being replayed.
+    pub(crate) limit_replay: Option<usize>,
+    /// Row cursor of the status history, newest message first.
+    pub(crate) history_row: usize,
+    /// Row cursor of the table views.
+    pub(crate) table_row: usize,
+    /// Set by `a`: the row keys scroll the Alerts panel.
+    pub(crate) alerts_focused: bool,
+    pub(crate) alert_row: usize,
+    /// The least severe alert shown, cycled by `A`.
+    pub(crate) alert_filter: Severity,
+    /// Text the alerts shown must contain, typed after `/`.
+    pub(crate) alert_search: String,
+    /// Set while the search is being typed.
+    pub(crate) alert_search_open: bool,
+    /// When each alert key in the panel reached its current severity.
+    pub(crate) alert_raised: HashMap<String, (Severity, SystemTime)>,
+    /// Digits typed before a row key, e.g. the 3 of `3j`.
+    pending_count: Option<usize>,
+    /// Set by the first `g` of `gg`.
+    pending_g: bool,
+    /// Rows yanked with `y`, or gathered with `Y`.
+    register: Vec<String>,
+    /// Every dial's reading at each snapshot, oldest first, with the
+    /// providers they were read for.
+    dial_history: VecDeque<(Vec<String>, [f64; Dial::ALL.len()])>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct StatusMessage {
+    pub(crate) at: SystemTime,
+    pub(crate) text: String,
 }
 
 impl App {
-    pub(crate) fn new(data_file: PathBuf, config_file: PathBuf) -> Result<Self> {
-        let config = load_or_bootstrap_config(&config_file)?;
-        let mut data = load_or_bootstrap_data(&data_file, &config)?;
-        let mut codex_cache = CodexImportCache::default();
-        merge_codex_usage(&mut data, &config, &mut codex_cache);
-        let status = build_status_line(&config, &codex_cache);
-        Ok(Self {
-            data_file,
-            config_file,
-            config,
-            data,
+    /// The model leaderboard is on screen, in its own view or placed by
+    /// `dashboard_layout`.
+    fn shows_model_leaderboard(&self) -> bool {
+        match self.view {
+            DashboardView::Models => true,
+            DashboardView::Dials => self
+                .config
+                .dashboard_layout
+                .iter()
+                .flatten()
+                .any(|widget| widget == "table:models"),
+            _ => false,
+        }
+    }
+
+    pub(crate) fn new(snapshot: Snapshot) -> Self {
+        let mut app = Self {
+            summary: UsageSummary::from_data(&snapshot.data),
+            decayed_totals: decayed_totals(
+                &snapshot.data,
+                snapshot.config.gauge_half_life_hours,
+                clock::now_epoch_secs(),
+            ),
+            config: snapshot.config,
+            data: snapshot.data,
+            codex_limits: snapshot.codex_limits,
+            codex_limit_history: snapshot.codex_limit_history,
+            codex_diagnostics: snapshot.codex_diagnostics,
             selected_provider: None,
-            status,
-            codex_cache,
+            status: String::new(),
+            status_history: VecDeque::new(),
             show_help: false,
+            show_status_history: false,
+            show_unpriced: false,
+            show_diagnostics: false,
+            show_context_growth: false,
+            show_period_preview: false,
+            show_estimate_accuracy: false,
+            show_pricing_breakdown: false,
+            pricing_editor: None,
+            show_sidebar: true,
+            marked_providers: BTreeSet::new(),
+            open_group: None,
+            show_provider_models: false,
+            view: DashboardView::Dials,
+            leaderboard_window: LeaderboardWindow::Month,
+            pending_jump: false,
+            import_progress: None,
+            team_budget: snapshot.team_budget,
+            editor_imports: snapshot.editor_imports,
+            plan_quotas: snapshot.plan_quotas,
+            importers: snapshot.importers,
+            runaway_sessions: snapshot.runaway_sessions,
+            context_growth: snapshot.context_growth,
+            heavy_load: snapshot.heavy_load,
+            pollers: snapshot.pollers,
+            reconciliation: snapshot.reconciliation,
+            carried_over_usd: snapshot.carried_over_usd,
+            selected_importer: 0,
+            kiosk: None,
+            no_canvas: false,
+            zoomed_dial: None,
+            inspected_dial: None,
+            tour: None,
+            limit_replay: None,
+            history_row: 0,
+            table_row: 0,
+            alerts_focused: false,
+            alert_row: 0,
+            alert_filter: Severity::Ok,
+            alert_search: String::new(),
+            alert_search_open: false,
+            alert_raised: HashMap::new(),
+            pending_count: None,
+            pending_g: false,
+            register: Vec::new(),
+            dial_history: VecDeque::new(),
+        };
+        app.set_status(snapshot.status);
+        app.sync_selected_provider();
+        app.record_dials();
+        app
+    }
+
+    fn apply_snapshot(&mut self, snapshot: Snapshot) {
+        self.config = snapshot.config;
+        self.summary
+            .update(&self.data.entries, &snapshot.data.entries);
+        self.decayed_totals = decayed_totals(
+            &snapshot.data,
+            self.config.gauge_half_life_hours,
+            clock::now_epoch_secs(),
+        );
+        self.data = snapshot.data;
+        self.codex_limits = snapshot.codex_limits;
+        self.codex_limit_history = snapshot.codex_limit_history;
+        if let Some(index) = self.limit_replay.as_mut() {
+            *index = (*index).min(self.codex_limit_history.len().saturating_sub(1));
         }
-        .with_selected_provider())
+        self.codex_diagnostics = snapshot.codex_diagnostics;
+        self.import_progress = None;
+        self.team_budget = snapshot.team_budget;
+        self.editor_imports = snapshot.editor_imports;
+        self.plan_quotas = snapshot.plan_quotas;
+        self.importers = snapshot.importers;
+        self.runaway_sessions = snapshot.runaway_sessions;
+        self.context_growth = snapshot.context_growth;
+        self.heavy_load = snapshot.heavy_load;
+        self.pollers = snapshot.pollers;
+        self.reconciliation = snapshot.reconciliation;
+        self.carried_over_usd = snapshot.carried_over_usd;
+        self.selected_importer = self
+            .selected_importer
+            .min(self.importers.len().saturating_sub(1));
+        self.set_status(snapshot.status);
+        self.sync_selected_provider();
+        self.record_dials();
     }
 
-    pub(crate) fn reload(&mut self) {
-        match load_or_bootstrap_config(&self.config_file) {
-            Ok(config) => {
-                self.config = config;
-            }
-            Err(err) => {
-                self.status = format!("Reload failed: {err}");
-                return;
-            }
+    fn record_dials(&mut self) {
+        let state = AppState::new(self, clock::now_epoch_secs());
+        let readings = Dial::ALL.map(|dial| dial.reading(&state).1);
+        let members = self
+            .selected_members()
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        if self.dial_history.len() == DIAL_HISTORY_LIMIT {
+            self.dial_history.pop_front();
         }
+        self.dial_history.push_back((members, readings));
+    }
 
-        match load_or_bootstrap_data(&self.data_file, &self.config) {
-            Ok(mut data) => {
-                merge_codex_usage(&mut data, &self.config, &mut self.codex_cache);
-                self.data = data;
-                self.sync_selected_provider();
-                self.status = build_status_line(&self.config, &self.codex_cache);
-            }
-            Err(err) => {
-                self.status = format!("Reload failed: {err}");
+    /// `dial`'s recorded readings for the current selection, in thousandths.
+    pub(crate) fn dial_trend(&self, dial: Dial) -> Vec<u64> {
+        let members = self.selected_members();
+        self.dial_history
+            .iter()
+            .filter(|(read_for, _)| {
+                read_for
+                    .iter()
+                    .map(String::as_str)
+                    .eq(members.iter().copied())
+            })
+            .map(|(_, readings)| (readings[dial as usize].clamp(0.0, 1.0) * 1000.0).round() as u64)
+            .collect()
+    }
+
+    fn zoom_next_dial(&mut self) {
+        self.zoomed_dial = match self.zoomed_dial {
+            Some(dial) => dial.next(),
+            None => Some(Dial::Fuel),
+        };
+    }
+
+    /// Closes whatever covers the panels the tour points at.
+    fn start_tour(&mut self) {
+        self.show_help = false;
+        self.zoomed_dial = None;
+        self.inspected_dial = None;
+        self.show_pricing_breakdown = false;
+        self.tour = Some(TourStep::Providers);
+    }
+
+    fn advance_tour(&mut self) {
+        self.tour = self.tour.and_then(TourStep::next);
+        if self.tour.is_none() {
+            self.set_status("Tour finished (o to take it again)");
+        }
+    }
+
+    fn end_tour(&mut self) {
+        self.tour = None;
+        self.set_status("Tour closed (o to take it again)");
+    }
+
+    /// Starts with the zoomed dial, if any.
+    fn inspect_next_dial(&mut self) {
+        self.inspected_dial = match self.inspected_dial {
+            Some(dial) => dial.next(),
+            None => Some(self.zoomed_dial.unwrap_or(Dial::Fuel)),
+        };
+    }
+
+    /// Shows `text` on the Info line and records it in the history ring so
+    /// errors stay reachable after later messages replace them.
+    pub(crate) fn set_status(&mut self, text: impl Into<String>) {
+        let text = text.into();
+        let repeated = self
+            .status_history
+            .back()
+            .is_some_and(|last| last.text == text);
+        if !text.is_empty() && !repeated {
+            if self.status_history.len() == STATUS_HISTORY_LIMIT {
+                self.status_history.pop_front();
             }
+            self.status_history.push_back(StatusMessage {
+                at: SystemTime::now(),
+                text: text.clone(),
+            });
         }
+        self.status = text;
     }
 
-    fn with_selected_provider(mut self) -> Self {
-        self.sync_selected_provider();
-        self
+    /// Rows of the provider list at the current drill-down level.
+    pub(crate) fn provider_items(&self) -> Vec<ProviderItem> {
+        provider_items(
+            self.summary.provider_summaries(),
+            &self.config.provider_groups,
+            self.open_group.as_deref(),
+        )
     }
 
     fn provider_names(&self) -> Vec<String> {
-        provider_summaries(&self.data)
+        self.provider_items()
             .into_iter()
-            .map(|summary| summary.provider)
+            .map(|item| item.summary.provider)
             .collect()
     }
 
+    /// Providers a list row stands for: a group's configured members, or
+    /// the provider itself.
+    fn members_of<'a>(&'a self, name: &'a str) -> Vec<&'a str> {
+        match self.config.provider_groups.get(name) {
+            Some(members) => members.iter().map(String::as_str).collect(),
+            None => vec![name],
+        }
+    }
+
+    /// Providers the dials describe: every member of the marked set when
+    /// anything is marked, otherwise the selected provider or group.
+    pub(crate) fn selected_members(&self) -> Vec<&str> {
+        if !self.marked_providers.is_empty() {
+            return self
+                .marked_providers
+                .iter()
+                .flat_map(|name| self.members_of(name))
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect();
+        }
+        self.selected_provider
+            .as_deref()
+            .map(|selected| self.members_of(selected))
+            .unwrap_or_default()
+    }
+
+    /// Stats behind the Info line and gauges: the marked set when anything
+    /// is marked, otherwise the selected provider or group.
+    pub(crate) fn selected_stats(&self) -> Option<ProviderStats> {
+        if !self.marked_providers.is_empty() {
+            let labels = self
+                .marked_providers
+                .iter()
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join(" + ");
+            return self
+                .summary
+                .combined_stats(self.selected_members())
+                .map(|stats| ProviderStats {
+                    provider: labels,
+                    ..stats
+                });
+        }
+
+        let selected = self.selected_provider.as_deref()?;
+        if self.config.provider_groups.contains_key(selected) {
+            self.summary
+                .combined_stats(self.members_of(selected))
+                .map(|stats| ProviderStats {
+                    provider: selected.to_string(),
+                    ..stats
+                })
+        } else {
+            self.summary.provider_stats(selected)
+        }
+    }
+
     fn sync_selected_provider(&mut self) {
+        if let Some(group) = self.open_group.as_deref()
+            && !self.config.provider_groups.contains_key(group)
+        {
+            self.open_group = None;
+        }
+        let present = self
+            .summary
+            .provider_summaries()
+            .into_iter()
+            .map(|summary| summary.provider)
+            .collect::<BTreeSet<_>>();
+        let groups = &self.config.provider_groups;
+        self.marked_providers
+            .retain(|marked| present.contains(marked) || groups.contains_key(marked));
+
         let providers = self.provider_names();
         if providers.is_empty() {
             self.selected_provider = None;
@@ -137,119 +671,1382 @@ impl App {
         self.selected_provider = providers.get(prev).cloned();
     }
 
+    /// Drills into the selected group, selecting its first member.
+    fn open_selected_group(&mut self) -> bool {
+        let Some(selected) = self.selected_provider.clone() else {
+            return false;
+        };
+        if !self.config.provider_groups.contains_key(&selected) {
+            return false;
+        }
+        self.open_group = Some(selected);
+        self.selected_provider = self.provider_names().into_iter().next();
+        true
+    }
+
+    /// Lists the selected provider's models, one level below the group.
+    fn open_provider_models(&mut self) -> bool {
+        if self.selected_provider.is_none() {
+            return false;
+        }
+        self.show_provider_models = true;
+        self.show_pricing_breakdown = false;
+        self.table_row = 0;
+        true
+    }
+
+    fn close_provider_models(&mut self) -> bool {
+        if !self.show_provider_models {
+            return false;
+        }
+        self.show_provider_models = false;
+        self.show_pricing_breakdown = false;
+        self.table_row = 0;
+        true
+    }
+
+    /// Leaves the open group and reselects it in the top-level list.
+    fn close_group(&mut self) -> bool {
+        let Some(group) = self.open_group.take() else {
+            return false;
+        };
+        self.selected_provider = Some(group);
+        true
+    }
+
+    fn toggle_marked_provider(&mut self) -> Option<bool> {
+        let provider = self.selected_provider.clone()?;
+        if self.marked_providers.remove(&provider) {
+            Some(false)
+        } else {
+            self.marked_providers.insert(provider);
+            Some(true)
+        }
+    }
+
+    /// Selects the provider at `index` in summary order (0-based).
+    fn select_provider_at(&mut self, index: usize) -> bool {
+        match self.provider_names().into_iter().nth(index) {
+            Some(name) => {
+                self.selected_provider = Some(name);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Jumps to the next provider whose name starts with `letter`, cycling
+    /// through all matches on repeated jumps.
+    fn jump_to_provider_letter(&mut self, letter: char) -> bool {
+        let letter = letter.to_ascii_lowercase();
+        let providers = self.provider_names();
+        let matches = providers
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| {
+                name.chars()
+                    .next()
+                    .is_some_and(|first| first.to_ascii_lowercase() == letter)
+            })
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        let current = self
+            .selected_provider
+            .as_ref()
+            .and_then(|name| providers.iter().position(|p| p == name));
+        let target = matches
+            .iter()
+            .copied()
+            .find(|index| current.is_some_and(|current| *index > current))
+            .or_else(|| matches.first().copied());
+        match target {
+            Some(index) => {
+                self.selected_provider = providers.get(index).cloned();
+                true
+            }
+            None => false,
+        }
+    }
+
     fn toggle_help(&mut self) {
         self.show_help = !self.show_help;
-        self.status = if self.show_help {
-            "Help opened".to_string()
+        self.set_status(if self.show_help {
+            "Help opened"
+        } else {
+            "Help closed"
+        });
+    }
+
+    fn toggle_status_history(&mut self) {
+        self.show_status_history = !self.show_status_history;
+        self.history_row = 0;
+    }
+
+    /// Leaving the panel drops the search, so no alert stays hidden by
+    /// it unnoticed.
+    fn toggle_alerts_focus(&mut self) {
+        self.alerts_focused = !self.alerts_focused;
+        self.alert_row = 0;
+        if !self.alerts_focused {
+            self.alert_search.clear();
+        }
+    }
+
+    /// Notes when each alert came up or changed severity, and forgets
+    /// those that have cleared, so a condition that fires again counts as
+    /// new.
+    fn note_alerts(&mut self, now: SystemTime) {
+        let alerts = collect_alerts(self, &AppState::new(self, clock::now_epoch_secs()));
+        let mut raised = HashMap::with_capacity(alerts.len());
+        for alert in alerts {
+            let at = match self.alert_raised.get(&alert.key) {
+                Some((severity, at)) if *severity == alert.severity => *at,
+                _ => now,
+            };
+            // A key reported twice keeps its worst severity.
+            raised
+                .entry(alert.key)
+                .and_modify(|entry: &mut (Severity, SystemTime)| {
+                    if alert.severity > entry.0 {
+                        *entry = (alert.severity, at);
+                    }
+                })
+                .or_insert((alert.severity, at));
+        }
+        self.alert_raised = raised;
+    }
+
+    fn toggle_limit_replay(&mut self) {
+        if self.limit_replay.take().is_some() {
+            return;
+        }
+        if self.codex_limit_history.is_empty() {
+            self.set_status("No Codex rate-limit readings in the last day");
         } else {
-            "Help closed".to_string()
+            self.limit_replay = Some(self.codex_limit_history.len() - 1);
+        }
+    }
+
+    /// Moves the replay to the first reading `secs` after the current one,
+    /// or the last one `secs` before it when negative.
+    fn replay_jump(&mut self, secs: i64) {
+        let (Some(index), Some(last)) = (
+            self.limit_replay,
+            self.codex_limit_history.len().checked_sub(1),
+        ) else {
+            return;
         };
+        let target = self.codex_limit_history[index.min(last)].0 + secs;
+        let history = &self.codex_limit_history;
+        self.limit_replay = Some(if secs >= 0 {
+            history
+                .iter()
+                .position(|(at, _)| *at >= target)
+                .unwrap_or(last)
+        } else {
+            history
+                .iter()
+                .rposition(|(at, _)| *at <= target)
+                .unwrap_or(0)
+        });
+    }
+
+    fn search_alerts(&mut self) {
+        self.alerts_focused = true;
+        self.alert_search_open = true;
+        self.alert_search.clear();
+        self.alert_row = 0;
+    }
+
+    fn cycle_alert_filter(&mut self) {
+        let (filter, label) = match self.alert_filter {
+            Severity::Ok => (Severity::Warn, "! and up"),
+            Severity::Warn => (Severity::Alert, "‼ only"),
+            Severity::Alert => (Severity::Ok, "all"),
+        };
+        self.alert_filter = filter;
+        self.alert_row = 0;
+        self.set_status(format!("Alerts shown: {label}"));
+    }
+
+    /// The list the row keys act on: the status history while it is open,
+    /// the Alerts panel while it has the keys, else the table view on
+    /// screen.
+    fn row_list(&self) -> Option<RowList> {
+        if self.show_status_history {
+            Some(RowList::StatusHistory)
+        } else if self.alerts_focused {
+            Some(RowList::Alerts)
+        } else if self.view.is_table() || self.show_provider_models {
+            Some(RowList::Table)
+        } else {
+            None
+        }
+    }
+
+    /// The rows of `list` as plain text, top first.
+    fn row_texts(&self, list: RowList) -> Vec<String> {
+        match list {
+            RowList::StatusHistory => self
+                .status_history
+                .iter()
+                .rev()
+                .map(|message| message.text.clone())
+                .collect(),
+            RowList::Alerts => alert_lines(self, &AppState::new(self, clock::now_epoch_secs()))
+                .iter()
+                .map(|alert| alert.text())
+                .collect(),
+            RowList::Table => table_rows(&AppState::new(self, clock::now_epoch_secs()), self.view)
+                .iter()
+                .map(|row| row.to_string().trim().to_string())
+                .collect(),
+        }
+    }
+
+    /// Moves the row cursor to `to(current row)`, kept on the list.
+    fn move_row(&mut self, to: impl FnOnce(usize) -> usize) {
+        let Some(list) = self.row_list() else {
+            return;
+        };
+        let last = self.row_texts(list).len().saturating_sub(1);
+        let row = match list {
+            RowList::StatusHistory => &mut self.history_row,
+            RowList::Alerts => &mut self.alert_row,
+            RowList::Table => &mut self.table_row,
+        };
+        *row = to((*row).min(last)).min(last);
+    }
+
+    /// Yanks `count` rows from the cursor down into the register, or adds
+    /// them to it with `append`, and returns the register's text for the
+    /// clipboard.
+    fn yank_rows(&mut self, count: usize, append: bool) -> Option<String> {
+        let list = self.row_list()?;
+        let row = match list {
+            RowList::StatusHistory => self.history_row,
+            RowList::Alerts => self.alert_row,
+            RowList::Table => self.table_row,
+        };
+        let rows = self
+            .row_texts(list)
+            .into_iter()
+            .skip(row)
+            .take(count)
+            .collect::<Vec<_>>();
+        if rows.is_empty() {
+            self.set_status("Nothing to yank");
+            return None;
+        }
+        let yanked = rows.len();
+        if !append {
+            self.register.clear();
+        }
+        self.register.extend(rows);
+        let plural = |n: usize| if n == 1 { "row" } else { "rows" };
+        self.set_status(if append {
+            format!(
+                "Added {yanked} {} to the register ({} {}, copied)",
+                plural(yanked),
+                self.register.len(),
+                plural(self.register.len())
+            )
+        } else {
+            format!("Yanked {yanked} {} (copied)", plural(yanked))
+        });
+        Some(self.register.join("\n"))
+    }
+
+    fn toggle_unpriced(&mut self) {
+        self.show_unpriced = !self.show_unpriced;
+    }
+
+    fn toggle_diagnostics(&mut self) {
+        self.show_diagnostics = !self.show_diagnostics;
+    }
+
+    fn toggle_context_growth(&mut self) {
+        self.show_context_growth = !self.show_context_growth;
+    }
+
+    fn toggle_period_preview(&mut self) {
+        self.show_period_preview = !self.show_period_preview;
+    }
+
+    fn toggle_estimate_accuracy(&mut self) {
+        self.show_estimate_accuracy = !self.show_estimate_accuracy;
+    }
+
+    /// The provider and model of the row under the cursor, in the model
+    /// view or a provider's model list.
+    pub(crate) fn model_under_cursor(&self) -> Option<(String, String)> {
+        if self.show_provider_models {
+            let models = self.summary.model_stats(self.selected_members());
+            let model = models.get(self.table_row.min(models.len().saturating_sub(1)))?;
+            return Some((model.provider.clone(), model.model.clone()));
+        }
+        if self.view != DashboardView::Models {
+            return None;
+        }
+        let ranked = model_leaderboard(
+            &self.data,
+            self.leaderboard_window.since(clock::now_epoch_secs()),
+        );
+        let model = ranked.get(self.table_row.min(ranked.len().saturating_sub(1)))?;
+        Some((model.provider.clone(), model.model.clone()))
+    }
+
+    fn edit_pricing(&mut self) {
+        match self.model_under_cursor() {
+            Some((provider, model)) => {
+                self.show_pricing_breakdown = false;
+                self.pricing_editor = Some(PricingEditor::new(
+                    &self.config,
+                    &self.data,
+                    &provider,
+                    &model,
+                ));
+            }
+            None => {
+                self.set_status("Edit pricing: switch to the model view (Tab) and pick a row");
+            }
+        }
+    }
+
+    /// Only the model view has rows to explain; the popover follows its
+    /// row cursor.
+    fn toggle_pricing_breakdown(&mut self) {
+        if self.show_pricing_breakdown {
+            self.show_pricing_breakdown = false;
+        } else if self.view == DashboardView::Models || self.show_provider_models {
+            self.show_pricing_breakdown = true;
+        } else {
+            self.set_status("Pricing breakdown: switch to the model view (Tab) and pick a row");
+        }
+    }
+
+    fn select_importer(&mut self, offset: isize) {
+        if self.importers.is_empty() {
+            return;
+        }
+        self.selected_importer = self
+            .selected_importer
+            .saturating_add_signed(offset)
+            .min(self.importers.len() - 1);
+    }
+
+    /// The selected importer and the state a toggle would switch it to.
+    fn importer_toggle(&self) -> Option<(Importer, bool)> {
+        self.importers
+            .get(self.selected_importer)
+            .map(|status| (status.importer, !status.enabled))
+    }
+
+    fn toggle_sidebar(&mut self) {
+        self.show_sidebar = !self.show_sidebar;
     }
 }
 
-pub(crate) fn run(
+/// Render task. Input and refresh work run in their own tasks and feed this
+/// loop over channels, so drawing never waits on filesystem scans.
+pub(crate) async fn run(
     mut terminal: DefaultTerminal,
-    app: &mut App,
-    refresh_interval: Duration,
+    worker: RefreshWorker,
+    display: DisplayOptions,
 ) -> Result<()> {
-    let mut last_refresh = Instant::now();
-    loop {
-        terminal.draw(|frame| draw(frame, app))?;
+    let mut app = App::new(worker.snapshot());
+    app.kiosk = display.kiosk;
+    app.no_canvas = display.no_canvas;
+    if display.tour && display.kiosk.is_none() {
+        app.start_tour();
+    }
+    let cycle_every = display.kiosk.unwrap_or(DEFAULT_KIOSK_INTERVAL);
+    let mut kiosk_cycle = time::interval_at(time::Instant::now() + cycle_every, cycle_every);
+    let (command_tx, command_rx) = mpsc::unbounded_channel();
+    let (snapshot_tx, mut snapshot_rx) = mpsc::unbounded_channel();
+    let (input_tx, mut input_rx) = mpsc::unbounded_channel();
+    let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
+    if worker.initial_import_pending() {
+        app.import_progress = Some(ImportProgress::default());
+    }
+    let worker = worker.with_import_progress(progress_tx);
 
-        let elapsed = last_refresh.elapsed();
-        let timeout = if elapsed >= refresh_interval {
-            Duration::from_millis(0)
-        } else {
-            refresh_interval - elapsed
-        };
+    let worker_task = tokio::spawn(run_refresh_worker(worker, command_rx, snapshot_tx));
+    let input_task = tokio::task::spawn_blocking(move || read_input_events(input_tx));
+    let mut job_control = JobControlSignals::new()?;
+
+    // Drawing the canvas dials is the costliest thing the TUI does, so a
+    // frame is only drawn after something it shows has changed, and no
+    // sooner than `max_fps` allows after the last one. Changes in between
+    // wait for the next frame together.
+    let mut dirty = true;
+    let mut last_frame: Option<time::Instant> = None;
+    // Set from stopping for `Ctrl+Z` until the SIGCONT that takes the
+    // terminal back; nothing is drawn while the shell has it.
+    let mut suspended = false;
+    let result = loop {
+        let next_frame = last_frame.map(|last| last + frame_interval(&app.config));
+        if !suspended && dirty && next_frame.is_none_or(|next| next <= time::Instant::now()) {
+            app.note_alerts(SystemTime::now());
+            if let Err(err) = terminal.draw(|frame| {
+                draw(frame, &app);
+                if display.monochrome {
+                    to_monochrome(frame.buffer_mut());
+                }
+            }) {
+                break Err(err.into());
+            }
+            dirty = false;
+            last_frame = Some(time::Instant::now());
+        }
+        let frame_due = next_frame.filter(|_| dirty && !suspended);
 
-        if event::poll(timeout)? {
-            match event::read()? {
-                Event::Key(key) if key.code == KeyCode::Char('q') => break,
-                Event::Key(key) if key.code == KeyCode::Char('r') => {
-                    app.reload();
-                    last_refresh = Instant::now();
+        let action = tokio::select! {
+            event = input_rx.recv() => match event {
+                Some(Ok(event)) => handle_event(&mut app, event, &command_tx),
+                Some(Err(err)) => break Err(err.into()),
+                None => LoopAction::Quit,
+            },
+            Some(snapshot) = snapshot_rx.recv() => {
+                app.apply_snapshot(snapshot);
+                LoopAction::Continue
+            }
+            _ = kiosk_cycle.tick(), if app.kiosk.is_some() => {
+                app.select_next_provider();
+                LoopAction::Continue
+            }
+            Some(progress) = progress_rx.recv() => {
+                if app.import_progress.is_some_and(|shown| shown != progress) {
+                    app.import_progress = Some(progress);
+                    LoopAction::Continue
+                } else {
+                    LoopAction::Unchanged
                 }
-                Event::Key(key)
-                    if matches!(
-                        key.code,
-                        KeyCode::Left | KeyCode::Char('h') | KeyCode::Char('k')
-                    ) =>
-                {
-                    app.select_prev_provider();
-                    app.status = "Selected previous provider".to_string();
+            }
+            _ = time::sleep_until(frame_due.unwrap_or_else(time::Instant::now)), if frame_due.is_some() => {
+                LoopAction::Continue
+            }
+            signal = job_control.recv() => match signal {
+                JobControl::Suspend => LoopAction::Suspend,
+                JobControl::Resume => {
+                    if let Err(err) = resume_terminal(&mut terminal) {
+                        break Err(err);
+                    }
+                    if std::mem::take(&mut suspended) {
+                        app.set_status("Resumed");
+                    }
+                    LoopAction::Continue
                 }
-                Event::Key(key)
-                    if matches!(
-                        key.code,
-                        KeyCode::Right | KeyCode::Char('l') | KeyCode::Char('j')
-                    ) =>
-                {
-                    app.select_next_provider();
-                    app.status = "Selected next provider".to_string();
+            },
+        };
+
+        dirty |= !matches!(action, LoopAction::Unchanged);
+        match action {
+            LoopAction::Continue | LoopAction::Unchanged => {}
+            LoopAction::Quit => break Ok(()),
+            LoopAction::Copy(text) => {
+                if let Err(err) = copy_to_clipboard(&mut terminal, &text) {
+                    break Err(err);
                 }
-                Event::Key(key) if key.code == KeyCode::Char('?') => {
-                    app.toggle_help();
+            }
+            LoopAction::Suspend => {
+                if let Err(err) = suspend() {
+                    break Err(err);
                 }
-                _ => {}
+                // Only Unix stops; elsewhere no SIGCONT would end this.
+                suspended = cfg!(unix);
             }
-            continue;
         }
+    };
+
+    drop(command_tx);
+    drop(input_rx);
+    let _ = input_task.await;
+    worker_task.await??;
+    result
+}
 
-        if last_refresh.elapsed() >= refresh_interval {
-            app.reload();
-            last_refresh = Instant::now();
+/// Shortest time between two frames under the config's `max_fps`.
+fn frame_interval(config: &AppConfig) -> Duration {
+    Duration::from_secs(1) / config.max_fps.max(1)
+}
+
+/// Sets the clipboard with an OSC 52 escape, which most terminals honour,
+/// over SSH and inside tmux too (with `set-clipboard on`).
+fn copy_to_clipboard(terminal: &mut DefaultTerminal, text: &str) -> Result<()> {
+    let backend = terminal.backend_mut();
+    write!(backend, "\x1b]52;c;{}\x07", base64_encode(text.as_bytes()))?;
+    backend.flush()?;
+    Ok(())
+}
+
+/// Input task: forwards terminal events until the render loop hangs up.
+fn read_input_events(events: UnboundedSender<io::Result<Event>>) {
+    while !events.is_closed() {
+        match event::poll(INPUT_POLL_INTERVAL) {
+            Ok(false) => continue,
+            Ok(true) => {
+                if events.send(event::read()).is_err() {
+                    return;
+                }
+            }
+            Err(err) => {
+                let _ = events.send(Err(err));
+                return;
+            }
         }
     }
+}
+
+/// Hands the terminal back to the shell and stops the process. Execution
+/// continues here after SIGCONT (`fg`); the terminal is taken back where
+/// every SIGCONT is handled, so the TUI is redrawn with its state intact.
+#[cfg(unix)]
+fn suspend() -> Result<()> {
+    restore_terminal()?;
+    // SAFETY: raise only delivers a signal to the current process. SIGSTOP
+    // cannot be caught, so this returns once the shell resumes us.
+    unsafe {
+        libc::raise(libc::SIGSTOP);
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn suspend() -> Result<()> {
     Ok(())
 }
 
+fn resume_terminal(terminal: &mut DefaultTerminal) -> Result<()> {
+    enable_raw_mode()?;
+    RAW_MODE.store(true, Ordering::SeqCst);
+    execute!(io::stdout(), EnterAlternateScreen, Hide)?;
+    terminal.clear()?;
+    Ok(())
+}
+
+/// Applies one input event to the UI state.
+fn handle_event(
+    app: &mut App,
+    event: Event,
+    commands: &UnboundedSender<WorkerCommand>,
+) -> LoopAction {
+    // Windows consoles also report key releases (and repeats); acting on
+    // those would make every toggle fire twice.
+    if let Event::Key(key) = &event
+        && key.kind != KeyEventKind::Press
+    {
+        return LoopAction::Unchanged;
+    }
+    let Event::Key(key) = event else {
+        // The next frame is laid out for the new size.
+        return match event {
+            Event::Resize(..) => LoopAction::Continue,
+            _ => LoopAction::Unchanged,
+        };
+    };
+    // The config was checked when it was loaded.
+    let keymap = Keymap::new(&app.config).unwrap_or_default();
+    let row_list = app.row_list();
+    let focused = [
+        app.show_diagnostics.then_some(KeyContext::Diagnostics),
+        row_list.map(|_| KeyContext::Rows),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+    let action = keymap.action(&key, &focused);
+    if app.kiosk.is_some() {
+        return match action {
+            Some(Action::Quit) => LoopAction::Quit,
+            _ => LoopAction::Unchanged,
+        };
+    }
+    if let Some(step) = app.tour {
+        match key.code {
+            _ if action == Some(Action::Quit) => return LoopAction::Quit,
+            KeyCode::Right | KeyCode::Enter | KeyCode::Char(' ' | 'l' | 'j') => {
+                app.advance_tour();
+            }
+            KeyCode::Left | KeyCode::Char('h' | 'k') => app.tour = Some(step.prev()),
+            KeyCode::Esc => app.end_tour(),
+            _ => {}
+        }
+        return LoopAction::Continue;
+    }
+    if let Some(index) = app.limit_replay {
+        let last = app.codex_limit_history.len().saturating_sub(1);
+        match key.code {
+            _ if action == Some(Action::Quit) => return LoopAction::Quit,
+            KeyCode::Left | KeyCode::Char('h') => app.limit_replay = Some(index.saturating_sub(1)),
+            KeyCode::Right | KeyCode::Char('l') => app.limit_replay = Some((index + 1).min(last)),
+            KeyCode::PageUp => app.replay_jump(-3600),
+            KeyCode::PageDown => app.replay_jump(3600),
+            KeyCode::Home => app.limit_replay = Some(0),
+            KeyCode::End => app.limit_replay = Some(last),
+            KeyCode::Esc => app.limit_replay = None,
+            _ if action == Some(Action::LimitReplay) => app.limit_replay = None,
+            _ => {}
+        }
+        return LoopAction::Continue;
+    }
+    if let Some(editor) = app.pricing_editor.as_mut() {
+        match editor.handle_key(key) {
+            PricingEditOutcome::Continue => {}
+            PricingEditOutcome::Cancelled => app.pricing_editor = None,
+            PricingEditOutcome::Save(change) => {
+                app.pricing_editor = None;
+                app.set_status(format!("Saving pricing for {}...", change.key));
+                let _ = commands.send(WorkerCommand::SetPricing(change));
+            }
+        }
+        return LoopAction::Continue;
+    }
+    if app.alert_search_open {
+        match key.code {
+            KeyCode::Enter => app.alert_search_open = false,
+            KeyCode::Esc => {
+                app.alert_search_open = false;
+                app.alert_search.clear();
+            }
+            KeyCode::Backspace => {
+                app.alert_search.pop();
+            }
+            KeyCode::Char(ch) => app.alert_search.push(ch),
+            _ => {}
+        }
+        app.alert_row = 0;
+        return LoopAction::Continue;
+    }
+    if app.pending_jump {
+        app.pending_jump = false;
+        if let KeyCode::Char(letter) = key.code {
+            if app.jump_to_provider_letter(letter) {
+                app.set_status(format!("Jumped to provider starting with '{letter}'"));
+            } else {
+                app.set_status(format!("No provider starts with '{letter}'"));
+            }
+        } else {
+            app.set_status("Jump cancelled");
+        }
+        return LoopAction::Continue;
+    }
+    // Counts and `gg` only mean something in a list; elsewhere digits
+    // still pick providers and `g` is context growth.
+    let count = app.pending_count.take();
+    let pending_g = std::mem::take(&mut app.pending_g);
+    if row_list.is_some() && key.modifiers.difference(KeyModifiers::SHIFT).is_empty() {
+        match key.code {
+            KeyCode::Char(digit @ '0'..='9') if digit != '0' || count.is_some() => {
+                let count = count.unwrap_or(0) * 10 + (digit as usize - '0' as usize);
+                app.pending_count = Some(count.min(MAX_COUNT));
+                return LoopAction::Continue;
+            }
+            KeyCode::Char('g') if pending_g => {
+                app.move_row(|_| count.map_or(0, |row| row - 1));
+                return LoopAction::Continue;
+            }
+            KeyCode::Char('g') => {
+                app.pending_count = count;
+                app.pending_g = true;
+                return LoopAction::Continue;
+            }
+            _ => {}
+        }
+    }
+
+    let Some(action) = action else {
+        if let KeyCode::Char(digit @ '1'..='9') = key.code {
+            let index = digit as usize - '1' as usize;
+            if app.select_provider_at(index) {
+                app.set_status(format!("Selected provider {digit}"));
+            } else {
+                app.set_status(format!("No provider {digit}"));
+            }
+        }
+        return LoopAction::Continue;
+    };
+    match action {
+        Action::Quit => return LoopAction::Quit,
+        Action::Suspend => return LoopAction::Suspend,
+        Action::Reload => {
+            let _ = commands.send(WorkerCommand::Reload);
+            app.set_status("Reloading...");
+        }
+        Action::PrevProvider => {
+            app.select_prev_provider();
+            app.set_status("Selected previous provider");
+        }
+        Action::NextProvider => {
+            app.select_next_provider();
+            app.set_status("Selected next provider");
+        }
+        Action::Help => app.toggle_help(),
+        Action::StatusHistory => app.toggle_status_history(),
+        Action::Unpriced => app.toggle_unpriced(),
+        Action::Sidebar => app.toggle_sidebar(),
+        Action::Diagnostics => app.toggle_diagnostics(),
+        Action::ContextGrowth => app.toggle_context_growth(),
+        Action::LimitReplay => app.toggle_limit_replay(),
+        Action::PeriodPreview => app.toggle_period_preview(),
+        Action::EstimateAccuracy => app.toggle_estimate_accuracy(),
+        Action::PricingBreakdown => app.toggle_pricing_breakdown(),
+        Action::EditPricing => app.edit_pricing(),
+        Action::Freeze => {
+            let frozen = app.config.spending_frozen_since.is_none();
+            let _ = commands.send(WorkerCommand::SetFrozen(frozen));
+            app.set_status(if frozen {
+                "Freezing spending..."
+            } else {
+                "Lifting the spending freeze..."
+            });
+        }
+        Action::FocusAlerts => app.toggle_alerts_focus(),
+        Action::AlertFilter => app.cycle_alert_filter(),
+        Action::SearchAlerts => app.search_alerts(),
+        Action::RetryQuarantined => {
+            if app.codex_diagnostics.quarantined.is_empty() {
+                app.set_status("No quarantined session files");
+            } else {
+                let _ = commands.send(WorkerCommand::RetryQuarantined);
+                app.set_status("Retrying quarantined session files...");
+            }
+        }
+        Action::PrevImporter => app.select_importer(-1),
+        Action::NextImporter => app.select_importer(1),
+        Action::ToggleImporter => {
+            if let Some((importer, enabled)) = app.importer_toggle() {
+                let _ = commands.send(WorkerCommand::SetImporter { importer, enabled });
+                app.set_status(format!(
+                    "{} importer {}",
+                    importer.name(),
+                    if enabled { "enabled" } else { "disabled" }
+                ));
+            }
+        }
+        Action::OpenGroup => {
+            if app.open_selected_group() {
+                app.set_status("Opened provider group (Esc to go back)");
+            } else if app.open_provider_models() {
+                app.set_status("Listing the provider's models (Esc to go back)");
+            }
+        }
+        Action::Zoom => app.zoom_next_dial(),
+        Action::Tour => app.start_tour(),
+        Action::Inspect => app.inspect_next_dial(),
+        Action::Back => {
+            if app.show_pricing_breakdown {
+                app.show_pricing_breakdown = false;
+            } else if app.inspected_dial.is_some() {
+                app.inspected_dial = None;
+            } else if app.zoomed_dial.is_some() {
+                app.zoomed_dial = None;
+            } else if app.alerts_focused {
+                app.toggle_alerts_focus();
+            } else if app.close_provider_models() {
+                app.set_status("Closed the model list");
+            } else if app.close_group() {
+                app.set_status("Closed provider group");
+            }
+        }
+        Action::Undo => {
+            let _ = commands.send(WorkerCommand::Undo);
+            app.set_status("Undoing last data change...");
+        }
+        Action::NextView => {
+            app.show_provider_models = false;
+            app.view = app.view.next();
+            app.table_row = 0;
+            app.show_pricing_breakdown = false;
+        }
+        Action::RowDown => app.move_row(|row| row.saturating_add(count.unwrap_or(1))),
+        Action::RowUp => app.move_row(|row| row.saturating_sub(count.unwrap_or(1))),
+        Action::FirstRow => app.move_row(|_| count.map_or(0, |row| row - 1)),
+        Action::LastRow => app.move_row(|_| count.map_or(usize::MAX, |row| row - 1)),
+        Action::Yank | Action::YankAppend => {
+            if let Some(text) = app.yank_rows(count.unwrap_or(1), action == Action::YankAppend) {
+                return LoopAction::Copy(text);
+            }
+        }
+        Action::LeaderboardWindow => {
+            if app.shows_model_leaderboard() {
+                app.leaderboard_window = app.leaderboard_window.next();
+                app.set_status(format!(
+                    "Model leaderboard: {}",
+                    app.leaderboard_window.label()
+                ));
+            }
+        }
+        Action::MarkProvider => match app.toggle_marked_provider() {
+            Some(true) => app.set_status(format!(
+                "Marked provider ({} marked)",
+                app.marked_providers.len()
+            )),
+            Some(false) => app.set_status(format!(
+                "Unmarked provider ({} marked)",
+                app.marked_providers.len()
+            )),
+            None => {}
+        },
+        Action::JumpToLetter => {
+            app.pending_jump = true;
+            app.set_status("Jump to provider: press its first letter");
+        }
+    }
+    LoopAction::Continue
+}
+
+/// Set while the TUI has the terminal in raw mode, so the panic hook only
+/// restores a terminal there is something to restore on; a panic in a
+/// piped command (`summary | jq`) leaves stdout alone.
+static RAW_MODE: AtomicBool = AtomicBool::new(false);
+
 pub(crate) fn init_terminal() -> Result<DefaultTerminal> {
     enable_raw_mode()?;
+    RAW_MODE.store(true, Ordering::SeqCst);
     execute!(io::stdout(), EnterAlternateScreen)?;
     Ok(ratatui::init())
 }
 
 pub(crate) fn restore_terminal() -> Result<()> {
+    RAW_MODE.store(false, Ordering::SeqCst);
     disable_raw_mode()?;
-    execute!(io::stdout(), LeaveAlternateScreen)?;
+    execute!(io::stdout(), LeaveAlternateScreen, Show)?;
     ratatui::restore();
     Ok(())
 }
 
-pub(crate) fn bootstrap_app(
-    data_file: Option<PathBuf>,
-    config_file: Option<PathBuf>,
-) -> Result<App> {
-    let data_file = match data_file {
-        Some(path) => path,
-        None => default_data_file()?,
-    };
-    let config_file = match config_file {
-        Some(path) => path,
-        None => default_config_file()?,
-    };
-    App::new(data_file, config_file)
+/// The secrets of the config in use, once it is known, for the panic hook
+/// to take out of its report.
+static CRASH_REDACTOR: OnceLock<Redactor> = OnceLock::new();
+
+/// Replaces `color_eyre::install`. A panic anywhere (render loop or a
+/// background task) restores the terminal if the TUI had it, appends the report and a full
+/// backtrace to `log_file`, prints the report, and exits the process.
+pub(crate) fn install_panic_hook(log_file: PathBuf) -> Result<()> {
+    let (panic_hook, eyre_hook) = color_eyre::config::HookBuilder::default().into_hooks();
+    eyre_hook.install()?;
+    std::panic::set_hook(Box::new(move |info| {
+        if RAW_MODE.load(Ordering::SeqCst) {
+            let _ = restore_terminal();
+        }
+        let report = scrub_crash_report(&panic_hook.panic_report(info).to_string());
+        let logged = append_crash_log(&log_file, &report, &Backtrace::force_capture());
+        eprintln!("{report}");
+        if logged.is_ok() {
+            eprintln!("Crash details written to {}", log_file.display());
+        }
+        std::process::exit(101);
+    }));
+    Ok(())
 }
 
-fn build_status_line(config: &AppConfig, cache: &CodexImportCache) -> String {
-    if !config.codex_import.enabled {
-        return "Ready".to_string();
+/// Has the panic hook also take the keys and URLs of the config at
+/// `config_file` out of its report. Without a readable config it keeps to
+/// the patterns of [`redact::text`].
+pub(crate) fn redact_crash_reports(config_file: &Path) {
+    let config = std::fs::read_to_string(config_file)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<AppConfig>(&contents).ok());
+    if let Some(config) = config {
+        let _ = CRASH_REDACTOR.set(Redactor::new(&config));
     }
-    let diagnostics = codex_import_diagnostics(cache);
-    let imported_ago_secs = diagnostics
-        .last_import_at
-        .and_then(|t| SystemTime::now().duration_since(t).ok())
+}
+
+fn scrub_crash_report(report: &str) -> String {
+    match CRASH_REDACTOR.get() {
+        Some(redactor) => redactor.text(report),
+        None => redact::text(report),
+    }
+}
+
+fn append_crash_log(log_file: &Path, report: &str, backtrace: &Backtrace) -> io::Result<()> {
+    let epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
         .map(|d| d.as_secs())
         .unwrap_or(0);
-    format!(
-        "Codex import files:{} refreshed:{} parse:{} no_usage:{} unreadable:{} scan:{}s updated:{}s",
-        diagnostics.active_files,
-        diagnostics.refreshed_files,
-        diagnostics.parse_error_files,
-        diagnostics.no_usage_or_limits_files,
-        diagnostics.unreadable_files,
-        diagnostics.discovery_interval.as_secs(),
-        imported_ago_secs
-    )
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_file)?;
+    writeln!(file, "=== panic at unix {epoch} ===")?;
+    writeln!(file, "{report}")?;
+    writeln!(file, "backtrace:\n{backtrace}")?;
+    Ok(())
+}
+
+/// Runs the first-run setup flow on an already initialised terminal.
+/// Returns `None` when the user backs out without saving.
+pub(crate) fn run_setup_wizard(
+    terminal: &mut DefaultTerminal,
+    mut wizard: SetupWizard,
+    monochrome: bool,
+) -> Result<Option<SetupChoices>> {
+    loop {
+        terminal.draw(|frame| {
+            draw_setup_wizard(frame, &wizard);
+            if monochrome {
+                to_monochrome(frame.buffer_mut());
+            }
+        })?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return Ok(None);
+        }
+        match wizard.handle_key(key) {
+            WizardOutcome::Continue => {}
+            WizardOutcome::Finished(choices) => return Ok(Some(choices)),
+            WizardOutcome::Cancelled => return Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyEvent;
+
+    use crate::models::{UsageEntry, load_or_bootstrap_config};
+
+    use super::*;
+    use crate::test_support::make_temp_dir;
+
+    fn app_with_providers(providers: &[(&str, f64)]) -> App {
+        let entries = providers
+            .iter()
+            .map(|(provider, cost_usd)| UsageEntry {
+                timestamp: "2026-02-10T03:15:00Z".to_string(),
+                provider: (*provider).into(),
+                model: "m".into(),
+                input_tokens: 10,
+                output_tokens: 10,
+                cost_usd: *cost_usd,
+                pricing_match: None,
+                cost_source: None,
+                original_cost: None,
+                api_key: None,
+                project_id: None,
+                tag: None,
+                data_file: None,
+            })
+            .collect();
+        App::new(Snapshot::for_test(UsageData {
+            budget_usd: None,
+            entries,
+        }))
+    }
+
+    #[test]
+    fn jumps_by_position_and_cycles_through_letter_matches() {
+        let mut app = app_with_providers(&[("anthropic", 3.0), ("codex", 2.0), ("azure", 1.0)]);
+        assert_eq!(app.selected_provider.as_deref(), Some("anthropic"));
+
+        assert!(app.select_provider_at(1));
+        assert_eq!(app.selected_provider.as_deref(), Some("codex"));
+        assert!(!app.select_provider_at(5));
+
+        assert!(app.jump_to_provider_letter('A'));
+        assert_eq!(app.selected_provider.as_deref(), Some("azure"));
+        assert!(app.jump_to_provider_letter('a'));
+        assert_eq!(app.selected_provider.as_deref(), Some("anthropic"));
+        assert!(!app.jump_to_provider_letter('z'));
+        assert_eq!(app.selected_provider.as_deref(), Some("anthropic"));
+    }
+
+    #[test]
+    fn opens_groups_and_reports_combined_group_stats() {
+        let mut app =
+            app_with_providers(&[("anthropic", 3.0), ("claude-code", 2.0), ("openai", 4.0)]);
+        app.config.provider_groups.insert(
+            "Anthropic".to_string(),
+            vec!["anthropic".to_string(), "claude-code".to_string()],
+        );
+        app.sync_selected_provider();
+        assert_eq!(app.provider_names(), vec!["Anthropic", "openai"]);
+        assert_eq!(app.selected_provider.as_deref(), Some("openai"));
+
+        app.select_provider_at(0);
+        let stats = app.selected_stats().expect("group stats");
+        assert_eq!(stats.provider, "Anthropic");
+        assert_eq!(stats.total_cost_usd, 5.0);
+
+        assert!(app.open_selected_group());
+        assert_eq!(app.provider_names(), vec!["anthropic", "claude-code"]);
+        assert_eq!(app.selected_provider.as_deref(), Some("anthropic"));
+        assert!(app.close_group());
+        assert_eq!(app.selected_provider.as_deref(), Some("Anthropic"));
+        assert!(!app.close_group());
+    }
+
+    #[test]
+    fn enter_lists_the_selected_providers_models_and_esc_goes_back() {
+        let mut app = app_with_providers(&[("anthropic", 3.0), ("openai", 1.0), ("openai", 2.0)]);
+        app.data.entries[1].model = "gpt-5".into();
+        app.data.entries[1].output_tokens = 90;
+        app.summary = UsageSummary::from_data(&app.data);
+        let (commands, _) = mpsc::unbounded_channel();
+        let press = |app: &mut App, code| {
+            handle_event(
+                app,
+                Event::Key(KeyEvent::new(code, KeyModifiers::NONE)),
+                &commands,
+            )
+        };
+
+        app.selected_provider = Some("openai".to_string());
+        press(&mut app, KeyCode::Enter);
+        assert!(app.show_provider_models);
+        let state = AppState::new(&app, clock::now_epoch_secs());
+        let (label, models) = state.provider_models.expect("model list");
+        assert_eq!(label, "openai");
+        // By cost, each with its own tokens and requests.
+        assert_eq!(
+            models
+                .iter()
+                .map(|model| (model.model.as_str(), model.requests, model.total_tokens))
+                .collect::<Vec<_>>(),
+            vec![("m", 1, 20), ("gpt-5", 1, 100)]
+        );
+        press(&mut app, KeyCode::Char('j'));
+        assert_eq!(
+            app.model_under_cursor(),
+            Some(("openai".to_string(), "gpt-5".to_string()))
+        );
+
+        press(&mut app, KeyCode::Esc);
+        assert!(!app.show_provider_models);
+        assert_eq!(app.status, "Closed the model list");
+        assert_eq!(app.model_under_cursor(), None);
+    }
+
+    #[test]
+    fn zoom_cycles_dials_and_keeps_a_trend_per_selection() {
+        let mut app = app_with_providers(&[("anthropic", 3.0), ("openai", 1.0)]);
+        app.data.budget_usd = Some(10.0);
+        app.record_dials();
+        app.select_next_provider();
+        app.record_dials();
+        assert_eq!(app.dial_trend(Dial::Fuel), vec![900]);
+        app.select_prev_provider();
+        assert_eq!(app.dial_trend(Dial::Fuel), vec![1000, 700]);
+
+        for dial in Dial::ALL {
+            app.zoom_next_dial();
+            assert_eq!(app.zoomed_dial, Some(dial));
+        }
+        app.zoom_next_dial();
+        assert_eq!(app.zoomed_dial, None);
+    }
+
+    #[test]
+    fn tour_steps_through_the_panels_and_holds_the_keys_while_open() {
+        let mut app = app_with_providers(&[("anthropic", 3.0), ("openai", 1.0)]);
+        let (commands, _) = mpsc::unbounded_channel();
+        let press = |app: &mut App, code| {
+            handle_event(
+                app,
+                Event::Key(KeyEvent::new(code, KeyModifiers::NONE)),
+                &commands,
+            )
+        };
+
+        app.zoomed_dial = Some(Dial::Rpm);
+        press(&mut app, KeyCode::Char('o'));
+        assert_eq!(app.tour, Some(TourStep::Providers));
+        assert_eq!(app.zoomed_dial, None);
+        press(&mut app, KeyCode::Left);
+        assert_eq!(app.tour, Some(TourStep::Providers));
+        let selected = app.selected_provider.clone();
+        press(&mut app, KeyCode::Char('l'));
+        assert_eq!(app.tour, Some(TourStep::Info));
+        assert_eq!(app.selected_provider, selected, "the tour had the key");
+        press(&mut app, KeyCode::Char('h'));
+        assert_eq!(app.tour, Some(TourStep::Providers));
+
+        for step in &TourStep::ALL[1..] {
+            press(&mut app, KeyCode::Enter);
+            assert_eq!(app.tour, Some(*step));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.tour, None);
+
+        press(&mut app, KeyCode::Char('o'));
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.tour, None);
+        assert_eq!(app.status, "Tour closed (o to take it again)");
+    }
+
+    #[test]
+    fn only_events_that_change_the_screen_ask_for_a_redraw() {
+        let mut app = app_with_providers(&[("anthropic", 3.0), ("openai", 1.0)]);
+        let (commands, _) = mpsc::unbounded_channel();
+        let mut release = KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE);
+        release.kind = KeyEventKind::Release;
+        let redraws = |app: &mut App, event| {
+            !matches!(handle_event(app, event, &commands), LoopAction::Unchanged)
+        };
+
+        assert!(!redraws(&mut app, Event::Key(release)));
+        assert!(!redraws(&mut app, Event::FocusGained));
+        assert!(redraws(&mut app, Event::Resize(100, 40)));
+        assert!(redraws(
+            &mut app,
+            Event::Key(KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE))
+        ));
+
+        app.kiosk = Some(DEFAULT_KIOSK_INTERVAL);
+        assert!(!redraws(
+            &mut app,
+            Event::Key(KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE))
+        ));
+    }
+
+    #[test]
+    fn frames_are_spaced_by_the_configured_rate() {
+        let mut config = AppConfig::default();
+        assert_eq!(frame_interval(&config), Duration::from_secs(1) / 15);
+        config.max_fps = 60;
+        assert_eq!(frame_interval(&config), Duration::from_secs(1) / 60);
+
+        let path = make_temp_dir("max-fps").join("config.json");
+        std::fs::write(&path, r#"{"max_fps": 0}"#).expect("config");
+        let err = load_or_bootstrap_config(&path).expect_err("zero fps");
+        assert_eq!(err.to_string(), "max_fps must be at least 1");
+        let _ = std::fs::remove_dir_all(path.parent().expect("dir"));
+    }
+
+    #[test]
+    fn counts_and_gg_move_through_the_status_history_and_y_yanks_rows() {
+        let mut app = app_with_providers(&[("anthropic", 3.0), ("openai", 1.0)]);
+        let (commands, _) = mpsc::unbounded_channel();
+        let press = |app: &mut App, ch| {
+            handle_event(
+                app,
+                Event::Key(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE)),
+                &commands,
+            )
+        };
+        let keys = |app: &mut App, keys: &str| {
+            for ch in keys.chars() {
+                press(app, ch);
+            }
+        };
+        app.status_history.clear();
+        for n in 0..12 {
+            app.set_status(format!("message {n}"));
+        }
+
+        keys(&mut app, "m3j");
+        assert_eq!(app.history_row, 3);
+        assert_eq!(app.selected_provider.as_deref(), Some("anthropic"));
+        keys(&mut app, "k");
+        assert_eq!(app.history_row, 2);
+        keys(&mut app, "G");
+        assert_eq!(app.history_row, 11);
+        keys(&mut app, "gg");
+        assert_eq!(app.history_row, 0);
+        keys(&mut app, "12j");
+        assert_eq!(app.history_row, 11, "kept on the list");
+        keys(&mut app, "5G");
+        assert_eq!(app.history_row, 4);
+        assert!(!app.show_context_growth);
+
+        let LoopAction::Copy(text) = press(&mut app, 'y') else {
+            panic!("y copies");
+        };
+        assert_eq!(text, "message 7");
+        assert_eq!(app.status, "Yanked 1 row (copied)");
+        keys(&mut app, "gg3j");
+        let LoopAction::Copy(text) = press(&mut app, 'Y') else {
+            panic!("Y copies");
+        };
+        assert_eq!(text, "message 7\nmessage 9");
+
+        // Outside a list digits still pick providers and g is context
+        // growth.
+        keys(&mut app, "m2g");
+        assert_eq!(app.selected_provider.as_deref(), Some("openai"));
+        assert!(app.show_context_growth);
+    }
+
+    #[test]
+    fn alerts_panel_sorts_by_severity_and_filters_and_searches() {
+        let mut app = app_with_providers(&[("anthropic", 3.0), ("openai", 1.0)]);
+        app.importers.push(ImporterStatus {
+            importer: Importer::Zed,
+            enabled: true,
+            last_run: None,
+            entries: 0,
+            error: Some("threads.db is locked".to_string()),
+        });
+        app.plan_quotas.push(PlanQuota {
+            provider: "cursor".to_string(),
+            unit: "requests".to_string(),
+            used: 475.0,
+            limit: 500.0,
+            resets_at: None,
+        });
+        let (commands, _) = mpsc::unbounded_channel();
+        let keys = |app: &mut App, keys: &str| {
+            for ch in keys.chars() {
+                let code = if ch == '\n' {
+                    KeyCode::Enter
+                } else {
+                    KeyCode::Char(ch)
+                };
+                handle_event(
+                    app,
+                    Event::Key(KeyEvent::new(code, KeyModifiers::NONE)),
+                    &commands,
+                );
+            }
+        };
+
+        let all = app.row_texts(RowList::Alerts);
+        assert!(all.len() > 4, "{all:?}");
+        assert!(all[0].starts_with("PLAN QUOTA"), "{all:?}");
+        let severities = alert_lines(&app, &AppState::new(&app, clock::now_epoch_secs()))
+            .iter()
+            .map(|alert| alert.severity)
+            .collect::<Vec<_>>();
+        assert!(severities.is_sorted_by(|a, b| a >= b), "{severities:?}");
+
+        keys(&mut app, "aG");
+        assert_eq!(app.alert_row, all.len() - 1);
+        keys(&mut app, "AA");
+        assert_eq!(app.alert_filter, Severity::Alert);
+        assert!(
+            app.row_texts(RowList::Alerts)
+                .iter()
+                .all(|text| text.contains("‼ ALERT"))
+        );
+        keys(&mut app, "A/LOCKED\n");
+        assert_eq!(
+            app.row_texts(RowList::Alerts),
+            ["IMPORTER     ! WARN     zed: threads.db is locked (d for details)"]
+        );
+        assert!(app.alerts_focused && !app.alert_search_open);
+
+        handle_event(
+            &mut app,
+            Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)),
+            &commands,
+        );
+        assert!(!app.alerts_focused);
+        assert_eq!(app.row_texts(RowList::Alerts).len(), all.len());
+
+        // A query keeps the lines about what it matches.
+        keys(&mut app, "/provider=cursor\n");
+        assert_eq!(
+            app.row_texts(RowList::Alerts),
+            ["PLAN QUOTA   ‼ ALERT    cursor 475 of 500 requests"]
+        );
+        keys(&mut app, "/provider=anthropic and provider!=openai\n");
+        let dials = app.row_texts(RowList::Alerts);
+        assert!(!dials.is_empty() && dials.len() < all.len(), "{dials:?}");
+        assert!(dials.iter().all(|text| !text.starts_with("PLAN QUOTA")));
+    }
+
+    #[test]
+    fn alerts_rank_the_most_recent_first_within_a_severity_and_show_once() {
+        let mut app = app_with_providers(&[("anthropic", 3.0), ("openai", 1.0)]);
+        let quota = |provider: &str, used: f64| PlanQuota {
+            provider: provider.to_string(),
+            unit: "requests".to_string(),
+            used,
+            limit: 100.0,
+            resets_at: None,
+        };
+        let quota_lines = |app: &App| {
+            app.row_texts(RowList::Alerts)
+                .into_iter()
+                .filter(|text| text.starts_with("PLAN QUOTA"))
+                .collect::<Vec<_>>()
+        };
+        let start = UNIX_EPOCH + Duration::from_secs(1_800_000_000);
+        app.plan_quotas.push(quota("cursor", 95.0));
+        app.note_alerts(start);
+        app.plan_quotas.push(quota("windsurf", 92.0));
+        app.note_alerts(start + Duration::from_secs(60));
+        assert_eq!(
+            quota_lines(&app),
+            [
+                "PLAN QUOTA   ‼ ALERT    windsurf 92 of 100 requests",
+                "PLAN QUOTA   ‼ ALERT    cursor 95 of 100 requests",
+            ]
+        );
+
+        // A second report of cursor's quota is the same condition.
+        app.plan_quotas.push(quota("cursor", 80.0));
+        app.note_alerts(start + Duration::from_secs(120));
+        assert_eq!(quota_lines(&app).len(), 2);
+        assert_eq!(
+            app.alert_raised["plan_quota.cursor"],
+            (Severity::Alert, start)
+        );
+
+        // Cleared and raised again, cursor is the newest.
+        app.plan_quotas.retain(|quota| quota.provider != "cursor");
+        app.note_alerts(start + Duration::from_secs(180));
+        assert!(!app.alert_raised.contains_key("plan_quota.cursor"));
+        app.plan_quotas.push(quota("cursor", 95.0));
+        app.note_alerts(start + Duration::from_secs(240));
+        assert!(quota_lines(&app)[0].contains("cursor"));
+    }
+
+    #[test]
+    fn p_replays_the_codex_limits_and_the_arrows_step_through_them() {
+        let mut app = app_with_providers(&[("openai", 1.0)]);
+        let (commands, _) = mpsc::unbounded_channel();
+        let press = |app: &mut App, code| {
+            handle_event(
+                app,
+                Event::Key(KeyEvent::new(code, KeyModifiers::NONE)),
+                &commands,
+            )
+        };
+        press(&mut app, KeyCode::Char('p'));
+        assert_eq!(app.limit_replay, None);
+        assert_eq!(app.status, "No Codex rate-limit readings in the last day");
+
+        let start = 1_800_000_000;
+        app.codex_limit_history = [0, 600, 1200, 5400]
+            .into_iter()
+            .map(|offset| {
+                let limits = serde_json::json!({
+                    "timestamp": clock::format_timestamp(start + offset),
+                    "primary": null,
+                    "secondary": null,
+                });
+                (
+                    start + offset,
+                    serde_json::from_value(limits).expect("limits"),
+                )
+            })
+            .collect();
+        press(&mut app, KeyCode::Char('p'));
+        assert_eq!(app.limit_replay, Some(3));
+        press(&mut app, KeyCode::Left);
+        assert_eq!(app.limit_replay, Some(2));
+        press(&mut app, KeyCode::Char('h'));
+        press(&mut app, KeyCode::Right);
+        assert_eq!(app.limit_replay, Some(2));
+        // An hour on from 20 minutes in is past the third reading.
+        press(&mut app, KeyCode::PageDown);
+        assert_eq!(app.limit_replay, Some(3));
+        press(&mut app, KeyCode::PageUp);
+        assert_eq!(app.limit_replay, Some(2));
+        press(&mut app, KeyCode::Home);
+        assert_eq!(app.limit_replay, Some(0));
+        press(&mut app, KeyCode::Left);
+        assert_eq!(app.limit_replay, Some(0));
+        // The provider keys are the replay's while it is open.
+        assert_eq!(app.selected_provider.as_deref(), Some("openai"));
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.limit_replay, None);
+    }
 }
@@ -0,0 +1,74 @@
+/// Rows in a big glyph.
+pub(crate) const BIG_TEXT_HEIGHT: usize = 5;
+
+/// Block-letter glyphs for the characters figures are made of: digits,
+/// the symbols `format_money` uses and a few separators.
+fn glyph(ch: char) -> Option<[&'static str; BIG_TEXT_HEIGHT]> {
+    Some(match ch {
+        '0' => ["███", "█ █", "█ █", "█ █", "███"],
+        '1' => [" █ ", "██ ", " █ ", " █ ", "███"],
+        '2' => ["███", "  █", "███", "█  ", "███"],
+        '3' => ["███", "  █", "███", "  █", "███"],
+        '4' => ["█ █", "█ █", "███", "  █", "  █"],
+        '5' => ["███", "█  ", "███", "  █", "███"],
+        '6' => ["███", "█  ", "███", "█ █", "███"],
+        '7' => ["███", "  █", "  █", "  █", "  █"],
+        '8' => ["███", "█ █", "███", "█ █", "███"],
+        '9' => ["███", "█ █", "███", "  █", "███"],
+        '.' => [" ", " ", " ", " ", "█"],
+        ',' => [" ", " ", " ", "▄", "▌"],
+        '%' => ["█ █", "  █", " █ ", "█  ", "█ █"],
+        '-' => ["   ", "   ", "███", "   ", "   "],
+        '+' => ["   ", " █ ", "███", " █ ", "   "],
+        ' ' => ["  ", "  ", "  ", "  ", "  "],
+        '$' => [" █ ", "█▀▀", "▀▀█", "▀█▀", "   "],
+        '€' => ["▄▀▀▀", "█▄▄ ", "█▄▄ ", "█   ", "▀▄▄▄"],
+        '£' => [" ▄▀▀", " █  ", "▀█▀ ", " █  ", "▀▀▀▀"],
+        '¥' => ["█ █", "▀▄▀", "▀█▀", "▀█▀", " █ "],
+        _ => return None,
+    })
+}
+
+/// `text` in block letters, one string per row, or `None` when it has a
+/// character the font lacks (a currency code, say).
+pub(crate) fn big_text(text: &str) -> Option<[String; BIG_TEXT_HEIGHT]> {
+    let mut rows: [String; BIG_TEXT_HEIGHT] = Default::default();
+    for (index, ch) in text.chars().enumerate() {
+        let glyph = glyph(ch)?;
+        for (row, part) in rows.iter_mut().zip(glyph) {
+            if index > 0 {
+                row.push(' ');
+            }
+            row.push_str(part);
+        }
+    }
+    Some(rows)
+}
+
+/// Columns `big_text(text)` takes up.
+pub(crate) fn big_text_width(text: &str) -> Option<usize> {
+    big_text(text).map(|rows| rows[0].chars().count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn figures_render_as_aligned_block_letters() {
+        let rows = big_text("$1.50").expect("known glyphs");
+        assert_eq!(
+            rows,
+            [
+                " █   █    ███ ███",
+                "█▀▀ ██    █   █ █",
+                "▀▀█  █    ███ █ █",
+                "▀█▀  █      █ █ █",
+                "    ███ █ ███ ███",
+            ]
+            .map(str::to_string)
+        );
+        assert_eq!(big_text_width("$1.50"), Some(17));
+        assert_eq!(big_text("12.000 CHF"), None);
+    }
+}
@@ -0,0 +1,371 @@
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+use color_eyre::eyre::{Result, bail};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::clock;
+use crate::models::{AppConfig, UsageData, month_spend};
+
+/// How close to the end of the month the Alerts panel points at the
+/// preview of a staged budget change.
+const PREVIEW_LEAD_SECS: i64 = 7 * clock::SECS_PER_DAY;
+
+/// What budget left unspent at the end of a UTC month does, e.g.
+/// `"carry"` or `{"cap": 25}`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum BudgetRollover {
+    /// Every month starts from its base budget.
+    Reset,
+    /// What is left is added to the next month's budget, and so on.
+    Carry,
+    /// As `Carry`, up to this percent of the next month's base budget.
+    Cap(f64),
+}
+
+impl BudgetRollover {
+    /// How much of `left` moves into a month whose base budget is `base`.
+    fn carried(self, left: f64, base: f64) -> f64 {
+        match self {
+            BudgetRollover::Reset => 0.0,
+            BudgetRollover::Carry => left,
+            BudgetRollover::Cap(percent) => left.min(base * percent / 100.0),
+        }
+    }
+}
+
+/// What the coming UTC month will start with, for reviewing staged
+/// changes before they apply.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PeriodPreview {
+    pub(crate) current: (i32, u32),
+    pub(crate) next: (i32, u32),
+    pub(crate) secs_left: i64,
+    pub(crate) spent_usd: f64,
+    pub(crate) current_budget_usd: Option<f64>,
+    /// `budget_schedule` entry the current budget comes from, if any.
+    pub(crate) current_source: Option<String>,
+    /// Scheduled changes after the current month, in order.
+    pub(crate) staged: Vec<(String, f64)>,
+    pub(crate) next_budget_usd: Option<f64>,
+    /// The base budget, carry-over aside, differs next month.
+    pub(crate) base_changes: bool,
+    pub(crate) rollover: Option<BudgetRollover>,
+    /// Unspent budget brought into this month, included in
+    /// `current_budget_usd`.
+    pub(crate) carried_in_usd: f64,
+    /// What would move into next month if nothing more were spent,
+    /// included in `next_budget_usd`.
+    pub(crate) carry_out_usd: f64,
+}
+
+impl PeriodPreview {
+    /// A staged change takes effect at the next rollover and that rollover
+    /// is near.
+    pub(crate) fn is_due(&self) -> bool {
+        self.secs_left <= PREVIEW_LEAD_SECS && self.base_changes
+    }
+}
+
+/// `YYYY-MM` as a year and month.
+fn parse_month(key: &str) -> Option<(i32, u32)> {
+    let (year, month) = key.split_once('-')?;
+    if year.len() != 4 || month.len() != 2 {
+        return None;
+    }
+    let month = month
+        .parse::<u32>()
+        .ok()
+        .filter(|month| (1..=12).contains(month))?;
+    Some((year.parse().ok()?, month))
+}
+
+fn month_key((year, month): (i32, u32)) -> String {
+    format!("{year:04}-{month:02}")
+}
+
+pub(crate) fn validate_budget_schedule(config: &AppConfig) -> Result<()> {
+    for (key, budget) in &config.budget_schedule {
+        if parse_month(key).is_none() {
+            bail!("budget_schedule: {key} is not a month (expected YYYY-MM)");
+        }
+        if *budget < 0.0 {
+            bail!("budget_schedule: {key} has a negative budget");
+        }
+    }
+    if let Some(BudgetRollover::Cap(percent)) = config.budget_rollover
+        && percent < 0.0
+    {
+        bail!("budget_rollover: cap must be a percentage of 0 or more");
+    }
+    Ok(())
+}
+
+/// The latest `budget_schedule` entry at or before `month`.
+fn scheduled_budget(config: &AppConfig, month: (i32, u32)) -> Option<(&str, f64)> {
+    config
+        .budget_schedule
+        .range(..=month_key(month))
+        .next_back()
+        .map(|(key, budget)| (key.as_str(), *budget))
+}
+
+fn next_month((year, month): (i32, u32)) -> (i32, u32) {
+    if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    }
+}
+
+/// Unspent budget `rollover` brings into `month`, walking forward from
+/// the first month with spend. A month's base budget is the scheduled one,
+/// else `file_budget`; overspending a month carries nothing, debt included.
+fn carry_into(
+    config: &AppConfig,
+    data: &UsageData,
+    file_budget: Option<f64>,
+    rollover: BudgetRollover,
+    month: (i32, u32),
+) -> f64 {
+    let mut spent: BTreeMap<(i32, u32), f64> = BTreeMap::new();
+    for entry in &data.entries {
+        if let Some(at) = clock::parse_timestamp(&entry.timestamp) {
+            let (_, year, month) = clock::month_start(at);
+            *spent.entry((year, month)).or_default() += entry.cost_usd;
+        }
+    }
+    let Some(mut current) = spent.keys().next().copied() else {
+        return 0.0;
+    };
+    let base = |month| {
+        scheduled_budget(config, month)
+            .map(|(_, budget)| budget)
+            .or(file_budget)
+    };
+    let mut carried = 0.0;
+    while current < month {
+        let left = base(current).map_or(0.0, |budget| {
+            (budget + carried - spent.get(&current).copied().unwrap_or_default()).max(0.0)
+        });
+        current = next_month(current);
+        carried = base(current).map_or(0.0, |budget| rollover.carried(left, budget));
+    }
+    carried
+}
+
+/// Replaces the data file's budget with the scheduled one for the month
+/// containing `now_epoch_secs`, once the schedule has started, and adds
+/// what `budget_rollover` carries over from earlier months. Returns the
+/// amount carried.
+pub(crate) fn apply_budget_schedule(
+    data: &mut UsageData,
+    config: &AppConfig,
+    now_epoch_secs: i64,
+) -> f64 {
+    let (_, year, month) = clock::month_start(now_epoch_secs);
+    let carried = match config.budget_rollover {
+        Some(rollover) => carry_into(config, data, data.budget_usd, rollover, (year, month)),
+        None => 0.0,
+    };
+    if let Some((_, budget)) = scheduled_budget(config, (year, month)) {
+        data.budget_usd = Some(budget);
+    }
+    if let Some(budget) = data.budget_usd.as_mut() {
+        *budget += carried;
+    }
+    carried
+}
+
+/// Where the fuel gauge starts counting spend: the current UTC month once
+/// `budget_rollover` makes the budget a monthly one, else all of it.
+pub(crate) fn period_start(config: &AppConfig, now_epoch_secs: i64) -> Option<i64> {
+    config
+        .budget_rollover
+        .map(|_| clock::month_start(now_epoch_secs).0)
+}
+
+/// What `members` spent from `since_epoch_secs` on.
+pub(crate) fn period_spend(data: &UsageData, members: &[&str], since_epoch_secs: i64) -> f64 {
+    data.entries
+        .iter()
+        .filter(|entry| members.contains(&&*entry.provider))
+        .filter(|entry| {
+            clock::parse_timestamp(&entry.timestamp).is_some_and(|at| at >= since_epoch_secs)
+        })
+        .map(|entry| entry.cost_usd)
+        .sum()
+}
+
+/// What counts against `data.budget_usd`: this month's spend when
+/// `budget_rollover` resets it monthly, everything recorded otherwise.
+pub(crate) fn budget_spend(data: &UsageData, config: &AppConfig, now_epoch_secs: i64) -> f64 {
+    match period_start(config, now_epoch_secs) {
+        Some(_) => month_spend(data, now_epoch_secs).spent_usd(),
+        None => data.entries.iter().map(|entry| entry.cost_usd).sum(),
+    }
+}
+
+/// `data` has the schedule applied already, so its budget is the current
+/// month's, `carried_in_usd` from [`apply_budget_schedule`] included.
+pub(crate) fn period_preview(
+    config: &AppConfig,
+    data: &UsageData,
+    carried_in_usd: f64,
+    now_epoch_secs: i64,
+) -> PeriodPreview {
+    let (_, year, month) = clock::month_start(now_epoch_secs);
+    let next = next_month((year, month));
+    let next_start = clock::days_from_civil(next.0, next.1, 1) * clock::SECS_PER_DAY;
+    let current_key = month_key((year, month));
+    let spent_usd = month_spend(data, now_epoch_secs).spent_usd();
+    let current_base = scheduled_budget(config, (year, month))
+        .map(|(_, budget)| budget)
+        .or(data.budget_usd.map(|budget| budget - carried_in_usd));
+    let next_base = scheduled_budget(config, next)
+        .map(|(_, budget)| budget)
+        .or(current_base);
+    let carry_out_usd = match (config.budget_rollover, data.budget_usd, next_base) {
+        (Some(rollover), Some(budget), Some(base)) => {
+            rollover.carried((budget - spent_usd).max(0.0), base)
+        }
+        _ => 0.0,
+    };
+    PeriodPreview {
+        current: (year, month),
+        next,
+        secs_left: next_start - now_epoch_secs,
+        spent_usd,
+        current_budget_usd: data.budget_usd,
+        current_source: scheduled_budget(config, (year, month)).map(|(key, _)| key.to_string()),
+        staged: config
+            .budget_schedule
+            .range((Bound::Excluded(current_key), Bound::Unbounded))
+            .map(|(key, budget)| (key.clone(), *budget))
+            .collect(),
+        next_budget_usd: next_base.map(|base| base + carry_out_usd),
+        base_changes: next_base != current_base,
+        rollover: config.budget_rollover,
+        carried_in_usd,
+        carry_out_usd,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scheduled_budgets_apply_from_their_month_and_show_in_the_preview() {
+        let mut config = AppConfig::default();
+        config.budget_schedule.insert("2026-09".to_string(), 60.0);
+        config.budget_schedule.insert("2026-11".to_string(), 80.0);
+        config.budget_schedule.insert("2027-01".to_string(), 100.0);
+        validate_budget_schedule(&config).expect("valid");
+
+        let now = clock::parse_timestamp("2026-10-28T00:00:00Z").expect("timestamp");
+        let mut data = UsageData {
+            budget_usd: Some(50.0),
+            entries: Vec::new(),
+        };
+        apply_budget_schedule(&mut data, &config, now);
+        assert_eq!(data.budget_usd, Some(60.0));
+
+        let preview = period_preview(&config, &data, 0.0, now);
+        assert_eq!((preview.current, preview.next), ((2026, 10), (2026, 11)));
+        assert_eq!(preview.secs_left, 4 * clock::SECS_PER_DAY);
+        assert_eq!(preview.current_source.as_deref(), Some("2026-09"));
+        assert_eq!(
+            preview.staged,
+            vec![
+                ("2026-11".to_string(), 80.0),
+                ("2027-01".to_string(), 100.0)
+            ]
+        );
+        assert_eq!(preview.next_budget_usd, Some(80.0));
+        assert!(preview.is_due());
+
+        let december = clock::parse_timestamp("2026-12-02T00:00:00Z").expect("timestamp");
+        let preview = period_preview(&config, &data, 0.0, december);
+        assert_eq!(preview.next, (2027, 1));
+        assert!(!preview.is_due());
+
+        config.budget_schedule.insert("2026-13".to_string(), 1.0);
+        assert!(validate_budget_schedule(&config).is_err());
+    }
+
+    #[test]
+    fn budget_spend_counts_this_month_under_a_rollover() {
+        let data = serde_json::from_str::<UsageData>(
+            r#"{"budget_usd": 50, "entries": [
+                {"timestamp": "2026-09-28T00:00:00Z", "provider": "openai", "model": "m",
+                 "input_tokens": 1, "output_tokens": 1, "cost_usd": 30.0},
+                {"timestamp": "2026-10-03T00:00:00Z", "provider": "anthropic", "model": "m",
+                 "input_tokens": 1, "output_tokens": 1, "cost_usd": 4.5}
+            ]}"#,
+        )
+        .expect("data");
+        let now = clock::parse_timestamp("2026-10-05T00:00:00Z").expect("timestamp");
+        let mut config = AppConfig::default();
+        assert_eq!(budget_spend(&data, &config, now), 34.5);
+        // What the team budget is told, too: September stays out of it.
+        config.budget_rollover = Some(BudgetRollover::Reset);
+        assert_eq!(budget_spend(&data, &config, now), 4.5);
+    }
+
+    #[test]
+    fn unspent_budget_rolls_over_by_policy() {
+        let mut config = AppConfig::default();
+        config.budget_schedule.insert("2026-09".to_string(), 100.0);
+        let data = serde_json::from_str::<UsageData>(
+            r#"{"budget_usd": 50, "entries": [
+                {"timestamp": "2026-08-10T00:00:00Z", "provider": "openai", "model": "m",
+                 "input_tokens": 1, "output_tokens": 1, "cost_usd": 20.0},
+                {"timestamp": "2026-09-10T00:00:00Z", "provider": "openai", "model": "m",
+                 "input_tokens": 1, "output_tokens": 1, "cost_usd": 90.0},
+                {"timestamp": "2026-10-03T00:00:00Z", "provider": "openai", "model": "m",
+                 "input_tokens": 1, "output_tokens": 1, "cost_usd": 10.0}
+            ]}"#,
+        )
+        .expect("data");
+        let now = clock::parse_timestamp("2026-10-05T00:00:00Z").expect("timestamp");
+        let mut budget = |rollover| {
+            config.budget_rollover = rollover;
+            let mut data = data.clone();
+            let carried = apply_budget_schedule(&mut data, &config, now);
+            (carried, data.budget_usd)
+        };
+
+        // August leaves 30 of the file's 50, September 40 of 100 + 30.
+        assert_eq!(budget(None), (0.0, Some(100.0)));
+        assert_eq!(budget(Some(BudgetRollover::Reset)), (0.0, Some(100.0)));
+        assert_eq!(budget(Some(BudgetRollover::Carry)), (40.0, Some(140.0)));
+        // At most 25% of September's 100 comes in, and of October's.
+        assert_eq!(budget(Some(BudgetRollover::Cap(25.0))), (25.0, Some(125.0)));
+
+        config.budget_rollover = Some(BudgetRollover::Carry);
+        let mut carried_data = data.clone();
+        let carried = apply_budget_schedule(&mut carried_data, &config, now);
+        let preview = period_preview(&config, &carried_data, carried, now);
+        assert_eq!(preview.carried_in_usd, 40.0);
+        assert_eq!(preview.carry_out_usd, 130.0);
+        assert_eq!(preview.next_budget_usd, Some(230.0));
+        assert!(!preview.base_changes);
+        assert_eq!(
+            period_start(&config, now),
+            Some(now - 4 * clock::SECS_PER_DAY)
+        );
+        assert_eq!(
+            period_spend(&carried_data, &["openai"], now - 86_400 * 4),
+            10.0
+        );
+
+        config.budget_rollover = Some(BudgetRollover::Cap(-1.0));
+        assert!(validate_budget_schedule(&config).is_err());
+        let parsed = serde_json::from_str::<AppConfig>(r#"{"budget_rollover": {"cap": 25}}"#)
+            .expect("config");
+        assert_eq!(parsed.budget_rollover, Some(BudgetRollover::Cap(25.0)));
+    }
+}
@@ -0,0 +1,246 @@
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+pub(crate) const SECS_PER_DAY: i64 = 86_400;
+
+/// Seconds since the Unix epoch, or 0 if the system clock is before it.
+pub(crate) fn now_epoch_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// When something happened, by both the wall clock and the monotonic one,
+/// so its age survives the wall clock being set back (NTP, a manual
+/// change) and still counts the time a laptop spent asleep, which the
+/// monotonic clock leaves out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Stamp {
+    wall: SystemTime,
+    monotonic: Instant,
+}
+
+impl Stamp {
+    pub(crate) fn now() -> Stamp {
+        Stamp {
+            wall: SystemTime::now(),
+            monotonic: Instant::now(),
+        }
+    }
+
+    pub(crate) fn age(&self) -> Duration {
+        stamp_age(
+            self.monotonic.elapsed(),
+            SystemTime::now().duration_since(self.wall).ok(),
+        )
+    }
+
+    /// How much further the wall clock has moved than the monotonic one
+    /// since the stamp: about the time spent suspended, or how far the
+    /// wall clock was set forward.
+    pub(crate) fn unaccounted(&self) -> Duration {
+        stamp_gap(
+            self.monotonic.elapsed(),
+            SystemTime::now().duration_since(self.wall).ok(),
+        )
+    }
+}
+
+/// The longer of the two clocks' readings: the wall clock's unless it was
+/// set back past the stamp or by more than the time since.
+fn stamp_age(monotonic: Duration, wall: Option<Duration>) -> Duration {
+    wall.map_or(monotonic, |wall| wall.max(monotonic))
+}
+
+fn stamp_gap(monotonic: Duration, wall: Option<Duration>) -> Duration {
+    wall.unwrap_or_default().saturating_sub(monotonic)
+}
+
+/// Formats Unix seconds as an RFC 3339 UTC timestamp
+/// (`2026-02-10T03:15:00Z`).
+pub(crate) fn format_timestamp(epoch_secs: i64) -> String {
+    let days = epoch_secs.div_euclid(SECS_PER_DAY);
+    let secs_of_day = epoch_secs.rem_euclid(SECS_PER_DAY);
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60
+    )
+}
+
+/// Parses the RFC 3339 timestamps used in usage files
+/// (`2026-02-10T03:15:00Z`, `2026-02-10T03:15:00.123+02:00`) into Unix
+/// seconds. A bare `YYYY-MM-DD` is taken as midnight UTC. Fractional
+/// seconds are dropped.
+pub(crate) fn parse_timestamp(input: &str) -> Option<i64> {
+    let input = input.trim();
+    let date = input.get(..10)?;
+    let days = parse_date(date)?;
+    let rest = &input[10..];
+    if rest.is_empty() {
+        return Some(days * SECS_PER_DAY);
+    }
+
+    let rest = rest.strip_prefix(['T', 't', ' '])?;
+    let hour = parse_number(rest.get(..2)?)?;
+    let minute = parse_number(rest.get(3..5)?)?;
+    let second = parse_number(rest.get(6..8)?)?;
+    if rest.get(2..3)? != ":" || rest.get(5..6)? != ":" || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let mut zone = &rest[8..];
+    if let Some(fraction) = zone.strip_prefix('.') {
+        let digits = fraction
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(fraction.len());
+        if digits == 0 {
+            return None;
+        }
+        zone = &fraction[digits..];
+    }
+    let offset = match zone {
+        "Z" | "z" | "" => 0,
+        _ => {
+            let sign = match zone.get(..1)? {
+                "+" => 1,
+                "-" => -1,
+                _ => return None,
+            };
+            let offset_hours = parse_number(zone.get(1..3)?)?;
+            let offset_minutes = parse_number(zone.get(4..6)?)?;
+            if zone.get(3..4)? != ":" || zone.len() != 6 {
+                return None;
+            }
+            sign * (offset_hours * 3600 + offset_minutes * 60)
+        }
+    };
+
+    Some(days * SECS_PER_DAY + hour * 3600 + minute * 60 + second - offset)
+}
+
+fn parse_date(date: &str) -> Option<i64> {
+    if date.get(4..5)? != "-" || date.get(7..8)? != "-" {
+        return None;
+    }
+    let year = parse_number(date.get(..4)?)?;
+    let month = parse_number(date.get(5..7)?)?;
+    let day = parse_number(date.get(8..10)?)?;
+    if !(1..=12).contains(&month) {
+        return None;
+    }
+    let (year, month) = (year as i32, month as u32);
+    if day < 1 || day as u32 > days_in_month(year, month) {
+        return None;
+    }
+    Some(days_from_civil(year, month, day as u32))
+}
+
+fn parse_number(digits: &str) -> Option<i64> {
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date.
+pub(crate) fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let year = i64::from(year) - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = i64::from(month);
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Inverse of [`days_from_civil`]: `(year, month, day)` for a day number.
+pub(crate) fn civil_from_days(days: i64) -> (i32, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year as i32, month, day)
+}
+
+pub(crate) fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        _ if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 => 29,
+        _ => 28,
+    }
+}
+
+/// Unix seconds at 00:00 UTC on the first day of the month containing
+/// `epoch_secs`, along with that month's `(year, month)`.
+pub(crate) fn month_start(epoch_secs: i64) -> (i64, i32, u32) {
+    let (year, month, _) = civil_from_days(epoch_secs.div_euclid(SECS_PER_DAY));
+    (days_from_civil(year, month, 1) * SECS_PER_DAY, year, month)
+}
+
+/// The month before `(year, month)`.
+pub(crate) fn previous_month(year: i32, month: u32) -> (i32, u32) {
+    if month == 1 {
+        (year - 1, 12)
+    } else {
+        (year, month - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rfc3339_variants_to_unix_seconds() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_timestamp("2026-02-10T03:15:00Z"), Some(1_770_693_300));
+        assert_eq!(
+            parse_timestamp("2026-02-10T05:15:00.250+02:00"),
+            Some(1_770_693_300)
+        );
+        assert_eq!(parse_timestamp("2026-02-10"), Some(1_770_681_600));
+        assert_eq!(parse_timestamp("2026-02-30T00:00:00Z"), None);
+        assert_eq!(parse_timestamp("yesterday"), None);
+        assert_eq!(parse_timestamp("2026-02-10T03:15"), None);
+        assert_eq!(format_timestamp(1_770_693_300), "2026-02-10T03:15:00Z");
+    }
+
+    #[test]
+    fn civil_day_round_trip_and_month_helpers() {
+        for days in [-1, 0, 59, 11_016, 20_494, 20_513] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+        assert_eq!(civil_from_days(20_513), (2026, 3, 1));
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(2100, 2), 28);
+        assert_eq!(month_start(1_770_693_300), (1_769_904_000, 2026, 2));
+        assert_eq!(previous_month(2026, 1), (2025, 12));
+    }
+
+    #[test]
+    fn stamp_age_survives_the_wall_clock_going_back_and_counts_sleep() {
+        let secs = Duration::from_secs;
+        assert_eq!(stamp_age(secs(5), Some(secs(5))), secs(5));
+        // Suspended for an hour: only the wall clock kept counting.
+        assert_eq!(stamp_age(secs(5), Some(secs(3605))), secs(3605));
+        // Set back a minute, past the stamp or partly.
+        assert_eq!(stamp_age(secs(5), None), secs(5));
+        assert_eq!(stamp_age(secs(90), Some(secs(30))), secs(90));
+        assert!(Stamp::now().age() < secs(60));
+
+        assert_eq!(stamp_gap(secs(5), Some(secs(3605))), secs(3600));
+        assert_eq!(stamp_gap(secs(90), Some(secs(30))), Duration::ZERO);
+        assert_eq!(stamp_gap(secs(5), None), Duration::ZERO);
+    }
+}
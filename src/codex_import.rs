@@ -1,19 +1,31 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::env;
 use std::fs;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, Cursor};
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime};
 
-use serde::Deserialize;
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
 
-use crate::models::{AppConfig, UsageData, UsageEntry, estimate_cost_usd};
+use crate::clock;
+use crate::intern::intern;
+use crate::models::{
+    AppConfig, CostSource, OversizeStrategy, UsageData, UsageEntry, cost_multiplier, estimate_cost,
+    write_atomically_with,
+};
 
 const MIN_DISCOVERY_INTERVAL: Duration = Duration::from_secs(10);
 const MAX_DISCOVERY_INTERVAL: Duration = Duration::from_secs(120);
 const DISCOVERY_BACKOFF_STEP: Duration = Duration::from_secs(10);
+/// Consecutive parse failures of an unchanged file before it is skipped
+/// until it changes on disk or the user asks for a retry.
+const QUARANTINE_AFTER_FAILURES: u32 = 3;
+const BYTES_PER_MB: u64 = 1024 * 1024;
+const PROGRESS_EVERY_FILES: usize = 64;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct CachedCodexSession {
     modified: SystemTime,
     file_len: u64,
@@ -22,6 +34,12 @@ struct CachedCodexSession {
     output_tokens: u64,
     has_token_usage: bool,
     limits: Option<CodexRateLimits>,
+    /// Input tokens sent with each model call, in order.
+    #[serde(default)]
+    turn_input_tokens: Vec<u64>,
+    /// The rate limits each time they changed, oldest first.
+    #[serde(default)]
+    limit_history: Vec<CodexRateLimits>,
 }
 
 #[derive(Debug, Clone)]
@@ -31,8 +49,44 @@ pub(crate) struct CodexImportDiagnostics {
     pub(crate) parse_error_files: usize,
     pub(crate) no_usage_or_limits_files: usize,
     pub(crate) unreadable_files: usize,
-    pub(crate) last_import_at: Option<SystemTime>,
+    /// Files over the size cap that were tailed or skipped this pass.
+    pub(crate) oversized_files: usize,
+    pub(crate) last_import_at: Option<clock::Stamp>,
     pub(crate) discovery_interval: Duration,
+    pub(crate) quarantined: Vec<QuarantinedSession>,
+}
+
+/// Where a session file first stopped parsing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ParseFailure {
+    line: usize,
+    offset: u64,
+    message: String,
+}
+
+/// A session file that kept failing to parse and is no longer retried on
+/// every refresh.
+#[derive(Debug, Clone)]
+pub(crate) struct QuarantinedSession {
+    pub(crate) path: PathBuf,
+    pub(crate) failures: u32,
+    pub(crate) line: usize,
+    pub(crate) offset: u64,
+    pub(crate) error: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SessionFailure {
+    modified: SystemTime,
+    file_len: u64,
+    failures: u32,
+    first: ParseFailure,
+}
+
+impl SessionFailure {
+    fn is_quarantined(&self) -> bool {
+        self.failures >= QUARANTINE_AFTER_FAILURES
+    }
 }
 
 impl Default for CodexImportDiagnostics {
@@ -43,8 +97,10 @@ impl Default for CodexImportDiagnostics {
             parse_error_files: 0,
             no_usage_or_limits_files: 0,
             unreadable_files: 0,
+            oversized_files: 0,
             last_import_at: None,
             discovery_interval: MIN_DISCOVERY_INTERVAL,
+            quarantined: Vec::new(),
         }
     }
 }
@@ -52,30 +108,63 @@ impl Default for CodexImportDiagnostics {
 enum ParsedSessionFile {
     Parsed(CachedCodexSession),
     NoUsageOrLimits,
-    ParseError,
+    ParseError(ParseFailure),
     Unreadable,
 }
 
+/// Timestamp, input and output token totals, whether any usage was seen,
+/// the latest rate limits, the input tokens of each turn and the rate
+/// limits each time they changed.
+type ParsedSession = (
+    String,
+    u64,
+    u64,
+    bool,
+    Option<CodexRateLimits>,
+    Vec<u64>,
+    Vec<CodexRateLimits>,
+);
+
 enum ParsedSessionContents {
-    Parsed((String, u64, u64, bool, Option<CodexRateLimits>)),
+    Parsed(ParsedSession),
     NoUsageOrLimits,
-    ParseError,
+    ParseError(ParseFailure),
 }
 
+/// One line of a session file, read in a single pass: the `type` tags
+/// decode straight into enums and the fields no line kind needs are
+/// skipped without being copied.
 #[derive(Debug, Deserialize)]
 struct CodexSessionLine {
     #[serde(default)]
     timestamp: Option<String>,
     #[serde(rename = "type")]
-    event_type: String,
+    event_type: CodexLineType,
     #[serde(default)]
     payload: Option<CodexSessionLinePayload>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum CodexLineType {
+    SessionMeta,
+    EventMsg,
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum CodexPayloadType {
+    TokenCount,
+    #[serde(other)]
+    Other,
+}
+
 #[derive(Debug, Deserialize)]
 struct CodexSessionLinePayload {
     #[serde(rename = "type", default)]
-    payload_type: Option<String>,
+    payload_type: Option<CodexPayloadType>,
     #[serde(default)]
     timestamp: Option<String>,
     #[serde(default)]
@@ -88,6 +177,8 @@ struct CodexSessionLinePayload {
 struct CodexTokenInfo {
     #[serde(default)]
     total_token_usage: Option<CodexTotalTokenUsage>,
+    #[serde(default)]
+    last_token_usage: Option<CodexTotalTokenUsage>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -128,61 +219,283 @@ impl CodexRateLimitPercent {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub(crate) struct CodexRateLimit {
     pub(crate) used_percent: f64,
     pub(crate) window_minutes: u64,
     pub(crate) resets_at: Option<u64>,
 }
 
-#[derive(Debug, Clone)]
+impl CodexRateLimit {
+    pub(crate) fn has_reset(&self, now_epoch_secs: i64) -> bool {
+        self.resets_at
+            .is_some_and(|resets_at| now_epoch_secs >= resets_at as i64)
+    }
+
+    /// The reading as it stands at `now_epoch_secs`. Codex only reports
+    /// limits alongside a request, so after a quiet spell or a suspend the
+    /// last reading can be windows old: once its reset has passed nothing
+    /// of the window is used and the reset moves on by whole windows.
+    pub(crate) fn as_of(&self, now_epoch_secs: i64) -> CodexRateLimit {
+        let Some(resets_at) = self.resets_at.filter(|_| self.has_reset(now_epoch_secs)) else {
+            return self.clone();
+        };
+        let window = self.window_minutes * 60;
+        let resets_at = (window > 0).then(|| {
+            let passed = (now_epoch_secs as u64 - resets_at) / window + 1;
+            resets_at + passed * window
+        });
+        CodexRateLimit {
+            used_percent: 0.0,
+            window_minutes: self.window_minutes,
+            resets_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct CodexRateLimits {
     timestamp: String,
     pub(crate) primary: Option<CodexRateLimit>,
     pub(crate) secondary: Option<CodexRateLimit>,
 }
 
+impl CodexRateLimits {
+    fn same_reading(&self, other: &CodexRateLimits) -> bool {
+        self.primary == other.primary && self.secondary == other.secondary
+    }
+
+    /// Both windows as of `now_epoch_secs`; see [`CodexRateLimit::as_of`].
+    pub(crate) fn as_of(&self, now_epoch_secs: i64) -> CodexRateLimits {
+        CodexRateLimits {
+            timestamp: self.timestamp.clone(),
+            primary: self
+                .primary
+                .as_ref()
+                .map(|limit| limit.as_of(now_epoch_secs)),
+            secondary: self
+                .secondary
+                .as_ref()
+                .map(|limit| limit.as_of(now_epoch_secs)),
+        }
+    }
+}
+
+/// What is left of a session evicted from the hot cache: enough to skip
+/// re-parsing it and to keep its tokens in the per-day rollup.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct RolledUpSession {
+    modified: SystemTime,
+    file_len: u64,
+    day: i64,
+    input_tokens: u64,
+    output_tokens: u64,
+}
+
+/// Token totals of evicted sessions, pre-aggregated per UTC day.
+#[derive(Debug, Default)]
+struct SessionRollup {
+    files: HashMap<PathBuf, RolledUpSession>,
+    days: BTreeMap<i64, (u64, u64)>,
+}
+
+impl SessionRollup {
+    fn insert(&mut self, path: PathBuf, session: &CachedCodexSession) {
+        let day = clock::parse_timestamp(&session.timestamp)
+            .unwrap_or_else(|| {
+                session
+                    .modified
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .map(|d| d.as_secs() as i64)
+                    .unwrap_or(0)
+            })
+            .div_euclid(clock::SECS_PER_DAY);
+        let (input_tokens, output_tokens) = if session.has_token_usage {
+            (session.input_tokens, session.output_tokens)
+        } else {
+            (0, 0)
+        };
+        self.insert_rolled_up(
+            path,
+            RolledUpSession {
+                modified: session.modified,
+                file_len: session.file_len,
+                day,
+                input_tokens,
+                output_tokens,
+            },
+        );
+    }
+
+    fn insert_rolled_up(&mut self, path: PathBuf, session: RolledUpSession) {
+        if session.input_tokens + session.output_tokens > 0 {
+            let totals = self.days.entry(session.day).or_default();
+            totals.0 += session.input_tokens;
+            totals.1 += session.output_tokens;
+        }
+        self.files.insert(path, session);
+    }
+
+    fn remove(&mut self, path: &Path) {
+        let Some(session) = self.files.remove(path) else {
+            return;
+        };
+        if let Some(totals) = self.days.get_mut(&session.day) {
+            totals.0 -= session.input_tokens;
+            totals.1 -= session.output_tokens;
+            if *totals == (0, 0) {
+                self.days.remove(&session.day);
+            }
+        }
+    }
+
+    fn is_current(&self, path: &Path, modified: SystemTime, file_len: u64) -> bool {
+        self.files
+            .get(path)
+            .is_some_and(|session| session.modified == modified && session.file_len == file_len)
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct CodexImportCache {
     sessions: HashMap<PathBuf, CachedCodexSession>,
+    rollup: SessionRollup,
     latest_limits: Option<CodexRateLimits>,
     session_files: Vec<PathBuf>,
     last_discovery_at: Option<SystemTime>,
     session_discovery_interval: Duration,
     idle_discovery_cycles: u32,
     diagnostics: CodexImportDiagnostics,
+    failures: HashMap<PathBuf, SessionFailure>,
 }
 
 impl Default for CodexImportCache {
     fn default() -> Self {
         Self {
             sessions: HashMap::new(),
+            rollup: SessionRollup::default(),
             latest_limits: None,
             session_files: Vec::new(),
             last_discovery_at: None,
             session_discovery_interval: MIN_DISCOVERY_INTERVAL,
             idle_discovery_cycles: 0,
             diagnostics: CodexImportDiagnostics::default(),
+            failures: HashMap::new(),
         }
     }
 }
 
+/// Bumped whenever the persisted layout changes; older files are ignored.
+const IMPORT_CACHE_VERSION: u32 = 3;
+
+/// On-disk form of the parsed-session state, so a restart only re-parses
+/// files that changed while PromptPetrol was not running.
+#[derive(Debug, Serialize, Deserialize)]
+struct PersistedImportCache {
+    version: u32,
+    sessions: Vec<(PathBuf, CachedCodexSession)>,
+    rolled_up: Vec<(PathBuf, RolledUpSession)>,
+    failures: Vec<(PathBuf, SessionFailure)>,
+}
+
+/// Loads a cache written by [`save_import_cache`]. A missing, unreadable
+/// or outdated file just means starting cold.
+pub(crate) fn load_import_cache(path: &Path) -> CodexImportCache {
+    let mut cache = CodexImportCache::default();
+    let Some(persisted) = fs::read(path)
+        .ok()
+        .and_then(|contents| serde_json::from_slice::<PersistedImportCache>(&contents).ok())
+        .filter(|persisted| persisted.version == IMPORT_CACHE_VERSION)
+    else {
+        return cache;
+    };
+    cache.sessions = persisted.sessions.into_iter().collect();
+    for (path, session) in persisted.rolled_up {
+        cache.rollup.insert_rolled_up(path, session);
+    }
+    cache.failures = persisted.failures.into_iter().collect();
+    cache.latest_limits = find_latest_limits(&cache.sessions);
+    cache
+}
+
+pub(crate) fn save_import_cache(cache: &CodexImportCache, path: &Path) -> Result<()> {
+    let persisted = PersistedImportCache {
+        version: IMPORT_CACHE_VERSION,
+        sessions: cache
+            .sessions
+            .iter()
+            .map(|(path, session)| (path.clone(), session.clone()))
+            .collect(),
+        rolled_up: cache
+            .rollup
+            .files
+            .iter()
+            .map(|(path, session)| (path.clone(), *session))
+            .collect(),
+        failures: cache
+            .failures
+            .iter()
+            .map(|(path, failure)| (path.clone(), failure.clone()))
+            .collect(),
+    };
+    write_atomically_with(path, |out| Ok(serde_json::to_writer(out, &persisted)?))
+}
+
+#[cfg(test)]
 pub(crate) fn merge_codex_usage(
     data: &mut UsageData,
     config: &AppConfig,
     cache: &mut CodexImportCache,
+) {
+    refresh_codex_sessions(config, cache);
+    append_codex_entries(data, config, cache);
+}
+
+/// How far an import pass has got through the discovered session files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct ImportProgress {
+    pub(crate) checked: usize,
+    pub(crate) total: usize,
+    pub(crate) parsed: usize,
+}
+
+impl ImportProgress {
+    pub(crate) fn ratio(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.checked as f64 / self.total as f64
+        }
+    }
+}
+
+pub(crate) fn refresh_codex_sessions(config: &AppConfig, cache: &mut CodexImportCache) {
+    refresh_codex_sessions_with_progress(config, cache, |_| {});
+}
+
+/// [`refresh_codex_sessions`], calling `on_progress` before discovery,
+/// every [`PROGRESS_EVERY_FILES`] files, and once at the end.
+pub(crate) fn refresh_codex_sessions_with_progress(
+    config: &AppConfig,
+    cache: &mut CodexImportCache,
+    mut on_progress: impl FnMut(ImportProgress),
 ) {
     if !config.codex_import.enabled {
         return;
     }
 
-    let sessions_dir = codex_sessions_dir(config);
+    on_progress(ImportProgress::default());
+    let sessions_dirs = codex_sessions_dirs(config);
     let mut changes_detected = false;
     let mut discovery_ran = false;
     if should_refresh_file_discovery(cache) {
         discovery_ran = true;
         let previous_count = cache.session_files.len();
-        cache.session_files = collect_codex_session_files(&sessions_dir).unwrap_or_default();
+        cache.session_files = sessions_dirs
+            .iter()
+            .filter_map(|dir| collect_codex_session_files(dir))
+            .flatten()
+            .collect();
         cache.last_discovery_at = Some(SystemTime::now());
         changes_detected = changes_detected || cache.session_files.len() != previous_count;
     }
@@ -192,7 +505,17 @@ pub(crate) fn merge_codex_usage(
     let mut parse_error_files = 0_usize;
     let mut no_usage_or_limits_files = 0_usize;
     let mut unreadable_files = 0_usize;
-    for file in &cache.session_files {
+    let mut oversized_files = 0_usize;
+    let max_file_len = config.codex_import.max_session_file_mb * BYTES_PER_MB;
+    let total = cache.session_files.len();
+    for (index, file) in cache.session_files.iter().enumerate() {
+        if index % PROGRESS_EVERY_FILES == 0 {
+            on_progress(ImportProgress {
+                checked: index,
+                total,
+                parsed: refreshed_files,
+            });
+        }
         active.insert(file.clone());
         let (modified, file_len) = match fs::metadata(file) {
             Ok(metadata) => match metadata.modified() {
@@ -200,6 +523,7 @@ pub(crate) fn merge_codex_usage(
                 Err(_) => {
                     unreadable_files += 1;
                     cache.sessions.remove(file);
+                    cache.rollup.remove(file);
                     continue;
                 }
             },
@@ -207,9 +531,32 @@ pub(crate) fn merge_codex_usage(
                 changes_detected = true;
                 unreadable_files += 1;
                 cache.sessions.remove(file);
+                cache.rollup.remove(file);
                 continue;
             }
         };
+        if cache.rollup.is_current(file, modified, file_len) {
+            continue;
+        }
+        cache.rollup.remove(file);
+
+        if let Some(failure) = cache.failures.get(file) {
+            let unchanged = failure.modified == modified && failure.file_len == file_len;
+            if !unchanged {
+                cache.failures.remove(file);
+            } else if failure.is_quarantined() {
+                continue;
+            }
+        }
+
+        let oversized = max_file_len > 0 && file_len > max_file_len;
+        if oversized {
+            oversized_files += 1;
+            if config.codex_import.oversize_sessions == OversizeStrategy::Skip {
+                cache.sessions.remove(file);
+                continue;
+            }
+        }
 
         let needs_refresh = cache
             .sessions
@@ -223,7 +570,8 @@ pub(crate) fn merge_codex_usage(
         changes_detected = true;
         refreshed_files += 1;
 
-        match parse_codex_session_file(file, modified, file_len) {
+        let tail_len = oversized.then_some(max_file_len);
+        match parse_codex_session_file(file, modified, file_len, tail_len) {
             ParsedSessionFile::Parsed(parsed) => {
                 cache.sessions.insert(file.clone(), parsed);
             }
@@ -231,9 +579,10 @@ pub(crate) fn merge_codex_usage(
                 no_usage_or_limits_files += 1;
                 cache.sessions.remove(file);
             }
-            ParsedSessionFile::ParseError => {
+            ParsedSessionFile::ParseError(error) => {
                 parse_error_files += 1;
                 cache.sessions.remove(file);
+                record_parse_failure(&mut cache.failures, file, modified, file_len, error);
             }
             ParsedSessionFile::Unreadable => {
                 unreadable_files += 1;
@@ -242,9 +591,27 @@ pub(crate) fn merge_codex_usage(
         }
     }
 
+    on_progress(ImportProgress {
+        checked: total,
+        total,
+        parsed: refreshed_files,
+    });
+
     cache.sessions.retain(|path, _| active.contains(path));
+    let gone = cache
+        .rollup
+        .files
+        .keys()
+        .filter(|path| !active.contains(*path))
+        .cloned()
+        .collect::<Vec<_>>();
+    for path in gone {
+        cache.rollup.remove(&path);
+    }
+    cache.failures.retain(|path, _| active.contains(path));
     cache.session_files.retain(|path| active.contains(path));
     cache.latest_limits = find_latest_limits(&cache.sessions);
+    evict_cold_sessions(cache, config.codex_import.max_cached_sessions);
     if discovery_ran {
         tune_discovery_interval(cache, changes_detected);
     }
@@ -254,9 +621,101 @@ pub(crate) fn merge_codex_usage(
         parse_error_files,
         no_usage_or_limits_files,
         unreadable_files,
-        last_import_at: Some(SystemTime::now()),
+        oversized_files,
+        last_import_at: Some(clock::Stamp::now()),
         discovery_interval: cache.session_discovery_interval,
+        quarantined: quarantined_sessions(&cache.failures),
     };
+}
+
+/// Moves the least recently modified sessions beyond `max_sessions` into
+/// the rollup. 0 keeps everything.
+fn evict_cold_sessions(cache: &mut CodexImportCache, max_sessions: usize) {
+    if max_sessions == 0 || cache.sessions.len() <= max_sessions {
+        return;
+    }
+    let mut by_age = cache
+        .sessions
+        .iter()
+        .map(|(path, session)| (session.modified, path.clone()))
+        .collect::<Vec<_>>();
+    by_age.sort();
+    let excess = by_age.len() - max_sessions;
+    for (_, path) in by_age.into_iter().take(excess) {
+        if let Some(session) = cache.sessions.remove(&path) {
+            cache.rollup.insert(path, &session);
+        }
+    }
+}
+
+fn record_parse_failure(
+    failures: &mut HashMap<PathBuf, SessionFailure>,
+    file: &Path,
+    modified: SystemTime,
+    file_len: u64,
+    error: ParseFailure,
+) {
+    let failure = failures
+        .entry(file.to_path_buf())
+        .or_insert_with(|| SessionFailure {
+            modified,
+            file_len,
+            failures: 0,
+            first: error,
+        });
+    failure.failures += 1;
+}
+
+fn quarantined_sessions(failures: &HashMap<PathBuf, SessionFailure>) -> Vec<QuarantinedSession> {
+    session_failures(failures, SessionFailure::is_quarantined)
+}
+
+/// Every session file that failed to parse, quarantined yet or not, by
+/// path.
+pub(crate) fn failing_sessions(cache: &CodexImportCache) -> Vec<QuarantinedSession> {
+    session_failures(&cache.failures, |_| true)
+}
+
+fn session_failures(
+    failures: &HashMap<PathBuf, SessionFailure>,
+    keep: impl Fn(&SessionFailure) -> bool,
+) -> Vec<QuarantinedSession> {
+    let mut sessions = failures
+        .iter()
+        .filter(|(_, failure)| keep(failure))
+        .map(|(path, failure)| QuarantinedSession {
+            path: path.clone(),
+            failures: failure.failures,
+            line: failure.first.line,
+            offset: failure.first.offset,
+            error: failure.first.message.clone(),
+        })
+        .collect::<Vec<_>>();
+    sessions.sort_by(|a, b| a.path.cmp(&b.path));
+    sessions
+}
+
+/// Lifts the quarantine so every failing file is parsed again on the next
+/// import pass. Returns how many files were released.
+pub(crate) fn retry_quarantined(cache: &mut CodexImportCache) -> usize {
+    let released = cache
+        .failures
+        .values()
+        .filter(|failure| failure.is_quarantined())
+        .count();
+    cache.failures.clear();
+    cache.diagnostics.quarantined.clear();
+    released
+}
+
+pub(crate) fn append_codex_entries(
+    data: &mut UsageData,
+    config: &AppConfig,
+    cache: &CodexImportCache,
+) {
+    if !config.codex_import.enabled {
+        return;
+    }
 
     let mut imported = cache
         .sessions
@@ -264,22 +723,61 @@ pub(crate) fn merge_codex_usage(
         .filter(|session| session.has_token_usage)
         .map(|session| {
             let model = &config.codex_import.model;
+            let estimate = estimate_cost(
+                "codex",
+                model,
+                session.input_tokens,
+                session.output_tokens,
+                &config.pricing,
+            );
             UsageEntry {
                 timestamp: session.timestamp.clone(),
-                provider: "codex".to_string(),
-                model: model.clone(),
+                provider: intern("codex"),
+                model: intern(model),
                 input_tokens: session.input_tokens,
                 output_tokens: session.output_tokens,
-                cost_usd: estimate_cost_usd(
-                    "codex",
-                    model,
-                    session.input_tokens,
-                    session.output_tokens,
-                    &config.pricing,
-                ),
+                cost_usd: estimate.cost_usd,
+                pricing_match: estimate.pricing_match,
+                cost_source: Some(CostSource::Estimated),
+                original_cost: None,
+                api_key: None,
+                project_id: None,
+                tag: None,
+                data_file: None,
             }
         })
         .collect::<Vec<_>>();
+    imported.extend(
+        cache
+            .rollup
+            .days
+            .iter()
+            .map(|(day, (input_tokens, output_tokens))| {
+                let model = &config.codex_import.model;
+                let estimate = estimate_cost(
+                    "codex",
+                    model,
+                    *input_tokens,
+                    *output_tokens,
+                    &config.pricing,
+                );
+                UsageEntry {
+                    timestamp: clock::format_timestamp(day * clock::SECS_PER_DAY),
+                    provider: intern("codex"),
+                    model: intern(model),
+                    input_tokens: *input_tokens,
+                    output_tokens: *output_tokens,
+                    cost_usd: estimate.cost_usd,
+                    pricing_match: estimate.pricing_match,
+                    cost_source: Some(CostSource::Estimated),
+                    original_cost: None,
+                    api_key: None,
+                    project_id: None,
+                    tag: None,
+                    data_file: None,
+                }
+            }),
+    );
 
     data.entries.append(&mut imported);
     data.entries.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
@@ -295,6 +793,14 @@ fn should_refresh_file_discovery(cache: &CodexImportCache) -> bool {
     }
 }
 
+/// Makes the next pass list the sessions directory again, with the
+/// discovery backoff back at its shortest.
+pub(crate) fn rediscover_sessions(cache: &mut CodexImportCache) {
+    cache.last_discovery_at = None;
+    cache.session_discovery_interval = MIN_DISCOVERY_INTERVAL;
+    cache.idle_discovery_cycles = 0;
+}
+
 fn tune_discovery_interval(cache: &mut CodexImportCache, changes_detected: bool) {
     if changes_detected {
         cache.session_discovery_interval = MIN_DISCOVERY_INTERVAL;
@@ -312,6 +818,103 @@ fn tune_discovery_interval(cache: &mut CodexImportCache, changes_detected: bool)
     cache.session_discovery_interval = std::cmp::min(next, MAX_DISCOVERY_INTERVAL);
 }
 
+/// A session file and what it is estimated to have cost so far.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SessionCost {
+    pub(crate) path: PathBuf,
+    pub(crate) cost_usd: f64,
+}
+
+/// Sessions whose estimated cost (after any cost multiplier) exceeds
+/// `ceiling_usd`, most expensive first. Rolled-up sessions are left out:
+/// they are too old to still be running.
+pub(crate) fn runaway_sessions(
+    config: &AppConfig,
+    cache: &CodexImportCache,
+    ceiling_usd: f64,
+) -> Vec<SessionCost> {
+    let multiplier = cost_multiplier(config, "codex");
+    let mut sessions = cache
+        .sessions
+        .iter()
+        .filter(|(_, session)| session.has_token_usage)
+        .map(|(path, session)| SessionCost {
+            path: path.clone(),
+            cost_usd: estimate_cost(
+                "codex",
+                &config.codex_import.model,
+                session.input_tokens,
+                session.output_tokens,
+                &config.pricing,
+            )
+            .cost_usd
+                * multiplier,
+        })
+        .filter(|session| session.cost_usd > ceiling_usd)
+        .collect::<Vec<_>>();
+    sessions.sort_by(|a, b| {
+        b.cost_usd
+            .partial_cmp(&a.cost_usd)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    sessions
+}
+
+/// Per-turn input tokens of every session still in the hot cache.
+pub(crate) fn session_turns(cache: &CodexImportCache) -> impl Iterator<Item = (&Path, &[u64])> {
+    cache
+        .sessions
+        .iter()
+        .map(|(path, session)| (path.as_path(), session.turn_input_tokens.as_slice()))
+}
+
+/// The most recently modified session with any turns, with when it last
+/// changed.
+pub(crate) fn latest_session_turns(
+    cache: &CodexImportCache,
+) -> Option<(&Path, SystemTime, &[u64])> {
+    cache
+        .sessions
+        .iter()
+        .filter(|(_, session)| !session.turn_input_tokens.is_empty())
+        .max_by_key(|(path, session)| (session.modified, *path))
+        .map(|(path, session)| {
+            (
+                path.as_path(),
+                session.modified,
+                session.turn_input_tokens.as_slice(),
+            )
+        })
+}
+
+/// Entries `append_codex_entries` contributes: one per session with token
+/// usage plus one per rolled-up day.
+pub(crate) fn imported_entry_count(cache: &CodexImportCache) -> usize {
+    cache
+        .sessions
+        .values()
+        .filter(|session| session.has_token_usage)
+        .count()
+        + cache.rollup.days.len()
+}
+
+/// Total tokens across all cached sessions; changes whenever an import
+/// picks up new usage.
+pub(crate) fn imported_token_total(cache: &CodexImportCache) -> u64 {
+    let hot = cache
+        .sessions
+        .values()
+        .map(|session| session.input_tokens + session.output_tokens)
+        .sum::<u64>();
+    let rolled_up = cache
+        .rollup
+        .days
+        .values()
+        .map(|(input, output)| input + output)
+        .sum::<u64>();
+    hot + rolled_up
+}
+
 pub(crate) fn latest_codex_limits(cache: &CodexImportCache) -> Option<CodexRateLimits> {
     cache
         .latest_limits
@@ -319,19 +922,122 @@ pub(crate) fn latest_codex_limits(cache: &CodexImportCache) -> Option<CodexRateL
         .or_else(|| find_latest_limits(&cache.sessions))
 }
 
+/// Rate-limit readings from `since` (Unix seconds) on, across sessions,
+/// oldest first, with when each was taken. Sessions running side by side
+/// report the same account limits, so repeats are dropped.
+pub(crate) fn codex_limit_history(
+    cache: &CodexImportCache,
+    since: i64,
+) -> Vec<(i64, CodexRateLimits)> {
+    let mut history = cache
+        .sessions
+        .values()
+        .flat_map(|session| &session.limit_history)
+        .filter_map(|limits| Some((clock::parse_timestamp(&limits.timestamp)?, limits)))
+        .filter(|(at, _)| *at >= since)
+        .collect::<Vec<_>>();
+    history.sort_by_key(|(at, _)| *at);
+    history.dedup_by(|(_, later), (_, earlier)| later.same_reading(earlier));
+    history
+        .into_iter()
+        .map(|(at, limits)| (at, limits.clone()))
+        .collect()
+}
+
 pub(crate) fn codex_import_diagnostics(cache: &CodexImportCache) -> CodexImportDiagnostics {
     cache.diagnostics.clone()
 }
 
 fn codex_sessions_dir(config: &AppConfig) -> PathBuf {
+    let home = home_dir();
     if let Some(path) = config.codex_import.sessions_dir.as_ref() {
-        return PathBuf::from(path);
+        return expand_home(path, &home);
+    }
+    if let Some(codex_home) = env::var_os("CODEX_HOME").filter(|value| !value.is_empty()) {
+        return PathBuf::from(codex_home).join("sessions");
     }
+    home.join(".codex").join("sessions")
+}
+
+/// The configured sessions directory plus, with `codex_import.cross_wsl`,
+/// any Codex session directories on the other side of the WSL boundary.
+fn codex_sessions_dirs(config: &AppConfig) -> Vec<PathBuf> {
+    let mut dirs = vec![codex_sessions_dir(config)];
+    if config.codex_import.cross_wsl {
+        for dir in wsl_counterpart_sessions_dirs() {
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+    }
+    dirs
+}
+
+/// Inside WSL: the Windows profiles under `/mnt/c/Users`.
+#[cfg(target_os = "linux")]
+fn wsl_counterpart_sessions_dirs() -> Vec<PathBuf> {
+    let in_wsl = env::var_os("WSL_DISTRO_NAME").is_some()
+        || fs::read_to_string("/proc/sys/kernel/osrelease")
+            .is_ok_and(|release| release.to_ascii_lowercase().contains("microsoft"));
+    if !in_wsl {
+        return Vec::new();
+    }
+    sessions_dirs_under_homes(Path::new("/mnt/c/Users"))
+}
+
+/// On Windows: the home directories of every installed WSL distribution.
+#[cfg(windows)]
+fn wsl_counterpart_sessions_dirs() -> Vec<PathBuf> {
+    let Ok(distros) = fs::read_dir(r"\\wsl.localhost\") else {
+        return Vec::new();
+    };
+    distros
+        .flatten()
+        .flat_map(|distro| sessions_dirs_under_homes(&distro.path().join("home")))
+        .collect()
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn wsl_counterpart_sessions_dirs() -> Vec<PathBuf> {
+    Vec::new()
+}
+
+/// `<home>/.codex/sessions` for each home directory under `homes_root`
+/// that has one.
+fn sessions_dirs_under_homes(homes_root: &Path) -> Vec<PathBuf> {
+    let Ok(homes) = fs::read_dir(homes_root) else {
+        return Vec::new();
+    };
+    let mut dirs = homes
+        .flatten()
+        .map(|home| home.path().join(".codex").join("sessions"))
+        .filter(|dir| dir.is_dir())
+        .collect::<Vec<_>>();
+    dirs.sort();
+    dirs
+}
 
+/// The user's home directory. `dirs` covers the usual cases; `USERPROFILE`
+/// and `HOME` are fallbacks for Windows shells (and MSYS/Git Bash) where the
+/// known-folder lookup fails.
+pub(crate) fn home_dir() -> PathBuf {
     dirs::home_dir()
+        .or_else(|| env::var_os("USERPROFILE").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(PathBuf::from))
+        .filter(|path| !path.as_os_str().is_empty())
         .unwrap_or_else(|| PathBuf::from("."))
-        .join(".codex")
-        .join("sessions")
+}
+
+/// Expands a leading `~`, `~/` or `~\` in a configured path, since neither
+/// the config file nor `cmd.exe` does that for us.
+pub(crate) fn expand_home(path: &str, home: &Path) -> PathBuf {
+    if path == "~" {
+        return home.to_path_buf();
+    }
+    match path.strip_prefix("~/").or_else(|| path.strip_prefix("~\\")) {
+        Some(rest) => home.join(rest),
+        None => PathBuf::from(path),
+    }
 }
 
 fn collect_codex_session_files(dir: &Path) -> Option<Vec<PathBuf>> {
@@ -344,7 +1050,10 @@ fn collect_codex_session_files(dir: &Path) -> Option<Vec<PathBuf>> {
     Some(files)
 }
 
-fn collect_jsonl_files_recursive(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+pub(crate) fn collect_jsonl_files_recursive(
+    dir: &Path,
+    files: &mut Vec<PathBuf>,
+) -> io::Result<()> {
     for entry in fs::read_dir(dir)? {
         let entry = entry?;
         let path = entry.path();
@@ -352,19 +1061,43 @@ fn collect_jsonl_files_recursive(dir: &Path, files: &mut Vec<PathBuf>) -> io::Re
             collect_jsonl_files_recursive(&path, files)?;
             continue;
         }
-        if path.extension().and_then(|ext| ext.to_str()) == Some("jsonl") {
+        if path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("jsonl"))
+        {
             files.push(path);
         }
     }
     Ok(())
 }
 
-fn parse_codex_session_file(path: &Path, modified: SystemTime, file_len: u64) -> ParsedSessionFile {
-    let file = match File::open(path) {
-        Ok(file) => file,
-        Err(_) => return ParsedSessionFile::Unreadable,
+/// Parses one session file. With `tail_len`, only the last `tail_len`
+/// bytes are read, starting at the first complete line inside them.
+fn parse_codex_session_file(
+    path: &Path,
+    modified: SystemTime,
+    file_len: u64,
+    tail_len: Option<u64>,
+) -> ParsedSessionFile {
+    let Ok(mut file) = File::open(path) else {
+        return ParsedSessionFile::Unreadable;
     };
-    let reader = BufReader::new(file);
+    let mut start = 0;
+    if let Some(tail_len) = tail_len {
+        start = file_len.saturating_sub(tail_len);
+        if file.seek(SeekFrom::Start(start)).is_err() {
+            return ParsedSessionFile::Unreadable;
+        }
+    }
+    let mut reader = BufReader::new(file);
+    if start > 0 {
+        let mut partial = Vec::new();
+        match reader.read_until(b'\n', &mut partial) {
+            Ok(skipped) => start += skipped as u64,
+            Err(_) => return ParsedSessionFile::Unreadable,
+        }
+    }
 
     match parse_codex_session_reader(reader) {
         ParsedSessionContents::Parsed((
@@ -373,31 +1106,45 @@ fn parse_codex_session_file(path: &Path, modified: SystemTime, file_len: u64) ->
             output_tokens,
             has_token_usage,
             limits,
-        )) => ParsedSessionFile::Parsed(CachedCodexSession {
-            modified,
-            file_len,
-            timestamp,
-            input_tokens,
-            output_tokens,
-            has_token_usage,
-            limits,
-        }),
+            mut turn_input_tokens,
+            limit_history,
+        )) => {
+            // The first turn of a tail may only be the difference to totals
+            // from before the cut.
+            if start > 0 && !turn_input_tokens.is_empty() {
+                turn_input_tokens.remove(0);
+            }
+            ParsedSessionFile::Parsed(CachedCodexSession {
+                modified,
+                file_len,
+                timestamp,
+                input_tokens,
+                output_tokens,
+                has_token_usage,
+                limits,
+                turn_input_tokens,
+                limit_history,
+            })
+        }
         ParsedSessionContents::NoUsageOrLimits => ParsedSessionFile::NoUsageOrLimits,
-        ParsedSessionContents::ParseError => ParsedSessionFile::ParseError,
+        ParsedSessionContents::ParseError(mut error) => {
+            error.offset += start;
+            ParsedSessionFile::ParseError(error)
+        }
     }
 }
 
-fn parse_codex_session_contents(
-    contents: &str,
-) -> Option<(String, u64, u64, bool, Option<CodexRateLimits>)> {
+#[cfg(test)]
+fn parse_codex_session_contents(contents: &str) -> Option<ParsedSession> {
     match parse_codex_session_contents_with_status(contents) {
         ParsedSessionContents::Parsed(parsed) => Some(parsed),
-        ParsedSessionContents::NoUsageOrLimits | ParsedSessionContents::ParseError => None,
+        ParsedSessionContents::NoUsageOrLimits | ParsedSessionContents::ParseError(_) => None,
     }
 }
 
+#[cfg(test)]
 fn parse_codex_session_contents_with_status(contents: &str) -> ParsedSessionContents {
-    parse_codex_session_reader(Cursor::new(contents.as_bytes()))
+    parse_codex_session_reader(io::Cursor::new(contents.as_bytes()))
 }
 
 fn parse_codex_session_reader<R: BufRead>(mut reader: R) -> ParsedSessionContents {
@@ -407,30 +1154,52 @@ fn parse_codex_session_reader<R: BufRead>(mut reader: R) -> ParsedSessionContent
     let mut input_tokens: u64 = 0;
     let mut output_tokens: u64 = 0;
     let mut has_token_usage = false;
+    let mut turn_input_tokens = Vec::new();
     let mut latest_limits: Option<CodexRateLimits> = None;
+    let mut limit_history: Vec<CodexRateLimits> = Vec::new();
+    let mut first_failure: Option<ParseFailure> = None;
+    let mut line_number = 0_usize;
+    let mut offset = 0_u64;
     let mut line = String::new();
 
     loop {
         line.clear();
+        line_number += 1;
+        let line_offset = offset;
         let bytes_read = match reader.read_line(&mut line) {
             Ok(count) => count,
-            Err(_) => return ParsedSessionContents::ParseError,
+            Err(err) => {
+                return ParsedSessionContents::ParseError(ParseFailure {
+                    line: line_number,
+                    offset: line_offset,
+                    message: err.to_string(),
+                });
+            }
         };
         if bytes_read == 0 {
             break;
         }
+        offset += bytes_read as u64;
 
         let line = line.trim_end_matches(['\n', '\r']);
         if line.is_empty() {
             continue;
         }
 
-        let Ok(parsed_line) = serde_json::from_str::<CodexSessionLine>(line) else {
-            continue;
+        let parsed_line = match serde_json::from_str::<CodexSessionLine>(line) {
+            Ok(parsed_line) => parsed_line,
+            Err(err) => {
+                first_failure.get_or_insert_with(|| ParseFailure {
+                    line: line_number,
+                    offset: line_offset,
+                    message: err.to_string(),
+                });
+                continue;
+            }
         };
         parsed_json_lines += 1;
 
-        if parsed_line.event_type == "session_meta" {
+        if parsed_line.event_type == CodexLineType::SessionMeta {
             let meta_timestamp = parsed_line
                 .payload
                 .as_ref()
@@ -442,12 +1211,12 @@ fn parse_codex_session_reader<R: BufRead>(mut reader: R) -> ParsedSessionContent
             continue;
         }
 
-        let is_token_count = parsed_line.event_type == "event_msg"
+        let is_token_count = parsed_line.event_type == CodexLineType::EventMsg
             && parsed_line
                 .payload
                 .as_ref()
-                .and_then(|payload| payload.payload_type.as_deref())
-                == Some("token_count");
+                .and_then(|payload| payload.payload_type)
+                == Some(CodexPayloadType::TokenCount);
         if !is_token_count {
             continue;
         }
@@ -478,20 +1247,39 @@ fn parse_codex_session_reader<R: BufRead>(mut reader: R) -> ParsedSessionContent
                 .or_else(|| latest_event_timestamp.clone())
                 .or_else(|| session_timestamp.clone())
                 .unwrap_or_else(|| "unknown".to_string());
-            latest_limits = Some(CodexRateLimits {
+            let limits = CodexRateLimits {
                 timestamp: limit_timestamp,
                 primary,
                 secondary,
-            });
+            };
+            // Most events repeat the last reading.
+            if limit_history
+                .last()
+                .is_none_or(|last| !last.same_reading(&limits))
+            {
+                limit_history.push(limits.clone());
+            }
+            latest_limits = Some(limits);
         }
 
-        let maybe_total_usage = parsed_line
+        let token_info = parsed_line
             .payload
             .as_ref()
-            .and_then(|payload| payload.info.as_ref())
-            .and_then(|info| info.total_token_usage.as_ref());
+            .and_then(|payload| payload.info.as_ref());
+        let maybe_total_usage = token_info.and_then(|info| info.total_token_usage.as_ref());
 
         if let Some(total_usage) = maybe_total_usage {
+            // Totals are repeated when only the rate limits changed; a turn
+            // is a call that added input.
+            if total_usage.input_tokens > input_tokens {
+                turn_input_tokens.push(
+                    token_info
+                        .and_then(|info| info.last_token_usage.as_ref())
+                        .map_or(total_usage.input_tokens - input_tokens, |last| {
+                            last.input_tokens
+                        }),
+                );
+            }
             input_tokens = total_usage.input_tokens;
             output_tokens = total_usage.output_tokens;
             has_token_usage = true;
@@ -499,7 +1287,11 @@ fn parse_codex_session_reader<R: BufRead>(mut reader: R) -> ParsedSessionContent
     }
 
     if parsed_json_lines == 0 {
-        return ParsedSessionContents::ParseError;
+        return ParsedSessionContents::ParseError(first_failure.unwrap_or(ParseFailure {
+            line: 1,
+            offset: 0,
+            message: "no JSON lines".to_string(),
+        }));
     }
 
     let timestamp = match latest_event_timestamp.or(session_timestamp) {
@@ -517,6 +1309,8 @@ fn parse_codex_session_reader<R: BufRead>(mut reader: R) -> ParsedSessionContent
         output_tokens,
         has_token_usage,
         latest_limits,
+        turn_input_tokens,
+        limit_history,
     ))
 }
 
@@ -549,6 +1343,7 @@ mod tests {
 
     use super::*;
     use crate::models::{AppConfig, UsageData};
+    use crate::test_support::make_temp_dir;
 
     #[test]
     fn parses_codex_session_usage_from_token_count_events() {
@@ -561,6 +1356,17 @@ mod tests {
         assert_eq!(parsed.2, 326);
         assert!(parsed.3);
         assert!(parsed.4.is_none());
+        assert_eq!(parsed.5, vec![8582, 8856]);
+    }
+
+    #[test]
+    fn records_turn_input_from_last_token_usage_and_skips_repeated_totals() {
+        let payload = r#"{"timestamp":"2026-02-16T09:45:53.237Z","type":"event_msg","payload":{"type":"token_count","info":{"total_token_usage":{"input_tokens":8582,"output_tokens":210},"last_token_usage":{"input_tokens":8582,"output_tokens":210}}}}
+{"timestamp":"2026-02-16T09:45:54.000Z","type":"event_msg","payload":{"type":"token_count","info":{"total_token_usage":{"input_tokens":8582,"output_tokens":210},"last_token_usage":{"input_tokens":8582,"output_tokens":210}}}}
+{"timestamp":"2026-02-16T09:45:56.220Z","type":"event_msg","payload":{"type":"token_count","info":{"total_token_usage":{"input_tokens":19000,"output_tokens":326},"last_token_usage":{"input_tokens":10418,"output_tokens":116}}}}"#;
+        let parsed = parse_codex_session_contents(payload).expect("expected codex usage");
+        assert_eq!(parsed.1, 19000);
+        assert_eq!(parsed.5, vec![8582, 10418]);
     }
 
     #[test]
@@ -633,6 +1439,8 @@ mod tests {
                     }),
                     secondary: None,
                 }),
+                turn_input_tokens: Vec::new(),
+                limit_history: Vec::new(),
             },
         );
 
@@ -654,6 +1462,8 @@ mod tests {
                     }),
                     secondary: None,
                 }),
+                turn_input_tokens: Vec::new(),
+                limit_history: Vec::new(),
             },
         );
 
@@ -661,6 +1471,129 @@ mod tests {
         assert_eq!(limits.primary.expect("primary").used_percent, 4.0);
     }
 
+    #[test]
+    fn limits_past_their_reset_roll_forward_by_whole_windows() {
+        let limit = CodexRateLimit {
+            used_percent: 70.0,
+            window_minutes: 300,
+            resets_at: Some(100_000),
+        };
+        assert_eq!(limit.as_of(99_999), limit);
+        let rolled = |now| limit.as_of(now).resets_at;
+        assert_eq!(rolled(100_000), Some(118_000));
+        assert_eq!(rolled(117_999), Some(118_000));
+        // Asleep over several windows.
+        assert_eq!(rolled(160_000), Some(172_000));
+        assert_eq!(limit.as_of(160_000).used_percent, 0.0);
+
+        let unknown_window = CodexRateLimit {
+            window_minutes: 0,
+            ..limit.clone()
+        };
+        assert_eq!(unknown_window.as_of(100_000).resets_at, None);
+    }
+
+    #[test]
+    fn limit_history_keeps_each_change_once_across_sessions() {
+        let event = |at: &str, used: f64| {
+            format!(
+                r#"{{"timestamp":"{at}","type":"event_msg","payload":{{"type":"token_count","info":null,"rate_limits":{{"primary":{{"used_percent":{used},"window_minutes":300,"resets_at":1771348283}}}}}}}}"#
+            )
+        };
+        let payload = [
+            event("2026-02-17T09:00:00Z", 7.0),
+            event("2026-02-17T09:05:00Z", 7.0),
+            event("2026-02-17T09:10:00Z", 9.0),
+        ]
+        .join("\n");
+        let parsed = parse_codex_session_contents(&payload).expect("expected codex limits");
+        let history = parsed.6;
+        assert_eq!(
+            history
+                .iter()
+                .map(|limits| limits.timestamp.as_str())
+                .collect::<Vec<_>>(),
+            vec!["2026-02-17T09:00:00Z", "2026-02-17T09:10:00Z"]
+        );
+
+        let mut cache = CodexImportCache::default();
+        for (name, limit_history) in [
+            ("a.jsonl", history.clone()),
+            // A second session sees the same account limits a moment later.
+            ("b.jsonl", {
+                let mut repeat = history[1].clone();
+                repeat.timestamp = "2026-02-17T09:11:00Z".to_string();
+                vec![repeat]
+            }),
+        ] {
+            cache.sessions.insert(
+                PathBuf::from(name),
+                CachedCodexSession {
+                    modified: UNIX_EPOCH,
+                    file_len: 1,
+                    timestamp: "2026-02-17T09:00:00Z".to_string(),
+                    input_tokens: 0,
+                    output_tokens: 0,
+                    has_token_usage: false,
+                    limits: limit_history.last().cloned(),
+                    turn_input_tokens: Vec::new(),
+                    limit_history,
+                },
+            );
+        }
+        let since = clock::parse_timestamp("2026-02-17T09:00:00Z").expect("timestamp");
+        let replay = codex_limit_history(&cache, since);
+        assert_eq!(
+            replay
+                .iter()
+                .map(|(at, limits)| (*at - since, limits.primary.as_ref().map(|l| l.used_percent)))
+                .collect::<Vec<_>>(),
+            vec![(0, Some(7.0)), (600, Some(9.0))]
+        );
+        assert_eq!(codex_limit_history(&cache, since + 1).len(), 1);
+    }
+
+    #[test]
+    fn runaway_sessions_lists_sessions_over_the_ceiling() {
+        let mut config = AppConfig::default();
+        config.pricing.insert(
+            "codex/gpt-5-codex".to_string(),
+            crate::models::ModelPricing {
+                input_per_million_usd: 1.0,
+                output_per_million_usd: 10.0,
+            },
+        );
+        config.codex_import.model = "gpt-5-codex".to_string();
+        let mut cache = CodexImportCache::default();
+        for (name, input_tokens) in [("small", 1_000_000), ("loop", 9_000_000)] {
+            cache.sessions.insert(
+                PathBuf::from(format!("{name}.jsonl")),
+                CachedCodexSession {
+                    modified: UNIX_EPOCH,
+                    file_len: 1,
+                    timestamp: "2026-02-18T00:00:00Z".to_string(),
+                    input_tokens,
+                    output_tokens: 0,
+                    has_token_usage: true,
+                    limits: None,
+                    turn_input_tokens: Vec::new(),
+                    limit_history: Vec::new(),
+                },
+            );
+        }
+
+        let runaway = runaway_sessions(&config, &cache, 5.0);
+        assert_eq!(
+            runaway,
+            vec![SessionCost {
+                path: PathBuf::from("loop.jsonl"),
+                cost_usd: 9.0
+            }]
+        );
+        config.cost_multipliers.insert("codex".to_string(), 6.0);
+        assert_eq!(runaway_sessions(&config, &cache, 5.0).len(), 2);
+    }
+
     #[test]
     fn parses_fixture_with_malformed_and_mixed_events() {
         let payload = fixture_contents("mixed_usage_and_limits.jsonl");
@@ -699,7 +1632,7 @@ mod tests {
         let codex_entries = data
             .entries
             .iter()
-            .filter(|entry| entry.provider == "codex")
+            .filter(|entry| &*entry.provider == "codex")
             .collect::<Vec<_>>();
         assert_eq!(codex_entries.len(), 1);
         assert_eq!(codex_entries[0].input_tokens, 180);
@@ -751,6 +1684,34 @@ mod tests {
         let _ = fs::remove_dir_all(temp_root);
     }
 
+    #[test]
+    #[ignore = "performance probe for local profiling"]
+    fn benchmark_parse_large_codex_session() {
+        let message = "x".repeat(2_000);
+        let mut contents = String::from(
+            "{\"timestamp\":\"2026-02-18T09:59:00.000Z\",\"type\":\"session_meta\",\"payload\":{\"id\":\"s\",\"timestamp\":\"2026-02-18T09:59:00.000Z\",\"cwd\":\"/work\"}}\n",
+        );
+        for turn in 1..=20_000_u64 {
+            contents.push_str(&format!(
+                "{{\"timestamp\":\"2026-02-18T10:00:00.000Z\",\"type\":\"response_item\",\"payload\":{{\"type\":\"message\",\"role\":\"assistant\",\"content\":[{{\"type\":\"output_text\",\"text\":\"{message}\"}}]}}}}\n\
+                 {{\"timestamp\":\"2026-02-18T10:00:01.000Z\",\"type\":\"event_msg\",\"payload\":{{\"type\":\"agent_message\",\"message\":\"{message}\"}}}}\n\
+                 {{\"timestamp\":\"2026-02-18T10:00:02.000Z\",\"type\":\"event_msg\",\"payload\":{{\"type\":\"token_count\",\"info\":{{\"total_token_usage\":{{\"input_tokens\":{},\"output_tokens\":{turn}}},\"last_token_usage\":{{\"input_tokens\":100,\"output_tokens\":1}}}},\"rate_limits\":{{\"primary\":{{\"used_percent\":12.0,\"window_minutes\":300,\"resets_at\":1771412400}},\"secondary\":{{\"used_percent\":3.0,\"window_minutes\":10080,\"resets_at\":1771934400}}}}}}}}\n",
+                turn * 100
+            ));
+        }
+
+        let started = Instant::now();
+        let parsed = parse_codex_session_contents(&contents).expect("parsed");
+        let elapsed = started.elapsed();
+        assert_eq!(parsed.1, 2_000_000);
+        eprintln!(
+            "parsed {} lines ({} MiB) in {:?}",
+            contents.lines().count(),
+            contents.len() / (1024 * 1024),
+            elapsed
+        );
+    }
+
     #[test]
     fn discovery_backoff_increases_when_idle_and_resets_on_change() {
         let temp_root = make_temp_dir("codex-backoff");
@@ -789,7 +1750,11 @@ mod tests {
     fn parser_classifies_malformed_only_payload_as_parse_error() {
         let payload = "not-json\nthis is also invalid\n";
         let classification = parse_codex_session_contents_with_status(payload);
-        assert!(matches!(classification, ParsedSessionContents::ParseError));
+        let ParsedSessionContents::ParseError(failure) = classification else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(failure.line, 1);
+        assert_eq!(failure.offset, 0);
     }
 
     #[test]
@@ -820,13 +1785,228 @@ mod tests {
             .join(name)
     }
 
-    fn make_temp_dir(prefix: &str) -> PathBuf {
-        let nanos = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("clock")
-            .as_nanos();
-        let path = std::env::temp_dir().join(format!("promptpetrol-{prefix}-{nanos}"));
-        fs::create_dir_all(&path).expect("create temp dir");
-        path
+    #[test]
+    fn session_discovery_matches_jsonl_case_insensitively() {
+        let temp_root = make_temp_dir("codex-case");
+        fs::write(temp_root.join("rollout-a.JSONL"), "").expect("write upper");
+        fs::write(temp_root.join("rollout-b.jsonl"), "").expect("write lower");
+        fs::write(temp_root.join("notes.json"), "").expect("write other");
+
+        let mut files = collect_codex_session_files(&temp_root).expect("files");
+        files.sort();
+        let names = files
+            .iter()
+            .filter_map(|path| path.file_name()?.to_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["rollout-a.JSONL", "rollout-b.jsonl"]);
+        let _ = fs::remove_dir_all(temp_root);
+    }
+
+    #[test]
+    fn expands_tilde_in_configured_sessions_dir() {
+        let home = Path::new("/home/pat");
+        assert_eq!(expand_home("~", home), home);
+        assert_eq!(
+            expand_home("~/.codex/sessions", home),
+            home.join(".codex/sessions")
+        );
+        assert_eq!(expand_home("/srv/codex", home), PathBuf::from("/srv/codex"));
+        assert_eq!(expand_home("~pat/x", home), PathBuf::from("~pat/x"));
+    }
+
+    #[test]
+    fn quarantines_files_that_keep_failing_until_retried() {
+        let temp_root = make_temp_dir("codex-quarantine");
+        let broken = temp_root.join("broken.jsonl");
+        fs::write(&broken, "{\"type\":\n").expect("write broken");
+
+        let mut config = AppConfig::default();
+        config.codex_import.enabled = true;
+        config.codex_import.sessions_dir = Some(temp_root.to_string_lossy().to_string());
+        let mut cache = CodexImportCache::default();
+
+        for _ in 0..QUARANTINE_AFTER_FAILURES {
+            refresh_codex_sessions(&config, &mut cache);
+            assert_eq!(cache.diagnostics.parse_error_files, 1);
+        }
+        let quarantined = &cache.diagnostics.quarantined;
+        assert_eq!(quarantined.len(), 1);
+        assert_eq!(quarantined[0].path, broken);
+        assert_eq!((quarantined[0].line, quarantined[0].offset), (1, 0));
+
+        refresh_codex_sessions(&config, &mut cache);
+        assert_eq!(cache.diagnostics.refreshed_files, 0);
+        assert_eq!(cache.diagnostics.quarantined.len(), 1);
+
+        assert_eq!(retry_quarantined(&mut cache), 1);
+        refresh_codex_sessions(&config, &mut cache);
+        assert_eq!(cache.diagnostics.parse_error_files, 1);
+        assert!(cache.diagnostics.quarantined.is_empty());
+        let _ = fs::remove_dir_all(temp_root);
+    }
+
+    #[test]
+    fn oversized_sessions_are_tailed_or_skipped() {
+        let temp_root = make_temp_dir("codex-oversize");
+        let token_count = |input: u64| {
+            format!(
+                r#"{{"timestamp":"2026-02-18T10:00:00.000Z","type":"event_msg","payload":{{"type":"token_count","info":{{"total_token_usage":{{"input_tokens":{input},"output_tokens":1}}}}}}}}"#
+            )
+        };
+        let padding = format!(
+            "{{\"type\":\"response_item\",\"pad\":\"{}\"}}\n",
+            "x".repeat(1000)
+        );
+        let mut contents = token_count(10) + "\n";
+        contents.push_str(&padding.repeat(1500));
+        contents.push_str(&token_count(500));
+        contents.push('\n');
+        fs::write(temp_root.join("huge.jsonl"), contents).expect("write huge");
+
+        let mut config = AppConfig::default();
+        config.codex_import.enabled = true;
+        config.codex_import.sessions_dir = Some(temp_root.to_string_lossy().to_string());
+        config.codex_import.max_session_file_mb = 1;
+
+        let mut cache = CodexImportCache::default();
+        refresh_codex_sessions(&config, &mut cache);
+        assert_eq!(cache.diagnostics.oversized_files, 1);
+        assert_eq!(imported_token_total(&cache), 501);
+
+        config.codex_import.oversize_sessions = OversizeStrategy::Skip;
+        let mut cache = CodexImportCache::default();
+        refresh_codex_sessions(&config, &mut cache);
+        assert_eq!(cache.diagnostics.oversized_files, 1);
+        assert_eq!(imported_token_total(&cache), 0);
+        let _ = fs::remove_dir_all(temp_root);
+    }
+
+    #[test]
+    fn evicted_sessions_keep_their_tokens_in_the_daily_rollup() {
+        let temp_root = make_temp_dir("codex-rollup");
+        let session = |day: u32, input: u64| {
+            format!(
+                r#"{{"timestamp":"2026-02-{day:02}T10:00:00.000Z","type":"event_msg","payload":{{"type":"token_count","info":{{"total_token_usage":{{"input_tokens":{input},"output_tokens":5}}}}}}}}"#
+            )
+        };
+        let old = temp_root.join("old.jsonl");
+        fs::write(&old, session(1, 100)).expect("write old");
+        std::thread::sleep(Duration::from_millis(20));
+        fs::write(temp_root.join("new.jsonl"), session(2, 200)).expect("write new");
+
+        let mut config = AppConfig::default();
+        config.codex_import.enabled = true;
+        config.codex_import.sessions_dir = Some(temp_root.to_string_lossy().to_string());
+        config.codex_import.max_cached_sessions = 1;
+        let mut cache = CodexImportCache::default();
+        refresh_codex_sessions(&config, &mut cache);
+
+        assert_eq!(cache.sessions.len(), 1);
+        assert!(cache.rollup.files.contains_key(&old));
+        assert_eq!(imported_token_total(&cache), 310);
+        let mut data = UsageData {
+            budget_usd: None,
+            entries: vec![],
+        };
+        append_codex_entries(&mut data, &config, &cache);
+        assert_eq!(data.entries[0].timestamp, "2026-02-01T00:00:00Z");
+        assert_eq!(data.entries[0].input_tokens, 100);
+
+        refresh_codex_sessions(&config, &mut cache);
+        assert_eq!(cache.diagnostics.refreshed_files, 0);
+
+        fs::write(&old, session(1, 150)).expect("rewrite old");
+        refresh_codex_sessions(&config, &mut cache);
+        assert_eq!(cache.diagnostics.refreshed_files, 1);
+        assert_eq!(imported_token_total(&cache), 360);
+        let _ = fs::remove_dir_all(temp_root);
+    }
+
+    #[test]
+    fn persisted_cache_skips_reparsing_unchanged_files() {
+        let temp_root = make_temp_dir("codex-persist");
+        let session_dir = temp_root.join("sessions");
+        fs::create_dir_all(&session_dir).expect("create session dir");
+        write_fixture(&session_dir, "mixed_usage_and_limits.jsonl");
+        fs::write(session_dir.join("broken.jsonl"), "nope\n").expect("write broken");
+
+        let mut config = AppConfig::default();
+        config.codex_import.enabled = true;
+        config.codex_import.sessions_dir = Some(session_dir.to_string_lossy().to_string());
+        let mut cache = CodexImportCache::default();
+        refresh_codex_sessions(&config, &mut cache);
+        let tokens = imported_token_total(&cache);
+        assert!(tokens > 0);
+
+        let cache_file = temp_root.join("codex-cache.json");
+        save_import_cache(&cache, &cache_file).expect("save cache");
+        let mut restored = load_import_cache(&cache_file);
+        assert_eq!(imported_token_total(&restored), tokens);
+        assert!(latest_codex_limits(&restored).is_some());
+
+        refresh_codex_sessions(&config, &mut restored);
+        assert_eq!(restored.diagnostics.refreshed_files, 1);
+        assert_eq!(restored.diagnostics.parse_error_files, 1);
+
+        fs::write(&cache_file, "{\"version\":0}").expect("write stale cache");
+        assert_eq!(imported_token_total(&load_import_cache(&cache_file)), 0);
+        let _ = fs::remove_dir_all(temp_root);
+    }
+
+    #[test]
+    fn reports_progress_through_the_import_pass() {
+        let temp_root = make_temp_dir("codex-progress");
+        for index in 0..(PROGRESS_EVERY_FILES + 1) {
+            fs::write(temp_root.join(format!("rollout-{index}.jsonl")), "").expect("write");
+        }
+        let mut config = AppConfig::default();
+        config.codex_import.enabled = true;
+        config.codex_import.sessions_dir = Some(temp_root.to_string_lossy().to_string());
+
+        let mut updates = Vec::new();
+        let mut cache = CodexImportCache::default();
+        refresh_codex_sessions_with_progress(&config, &mut cache, |progress| {
+            updates.push(progress)
+        });
+        let total = PROGRESS_EVERY_FILES + 1;
+        assert_eq!(updates.first(), Some(&ImportProgress::default()));
+        assert_eq!(updates.len(), 4);
+        assert_eq!(updates[2].checked, PROGRESS_EVERY_FILES);
+        let last = updates.last().expect("final update");
+        assert_eq!(
+            (last.checked, last.total, last.parsed),
+            (total, total, total)
+        );
+        assert_eq!(last.ratio(), 1.0);
+        let _ = fs::remove_dir_all(temp_root);
+    }
+
+    #[test]
+    fn finds_sessions_dirs_under_each_home() {
+        let temp_root = make_temp_dir("codex-homes");
+        fs::create_dir_all(temp_root.join("pat/.codex/sessions")).expect("pat");
+        fs::create_dir_all(temp_root.join("Public")).expect("public");
+        fs::create_dir_all(temp_root.join("sam/.codex/sessions")).expect("sam");
+
+        assert_eq!(
+            sessions_dirs_under_homes(&temp_root),
+            [
+                temp_root.join("pat/.codex/sessions"),
+                temp_root.join("sam/.codex/sessions"),
+            ]
+        );
+        assert!(sessions_dirs_under_homes(&temp_root.join("missing")).is_empty());
+        let _ = fs::remove_dir_all(temp_root);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn expands_backslash_tilde_against_userprofile_style_home() {
+        let home = Path::new(r"C:\Users\pat");
+        assert_eq!(
+            expand_home(r"~\.codex\sessions", home),
+            PathBuf::from(r"C:\Users\pat\.codex\sessions")
+        );
+        assert!(home_dir().is_absolute());
     }
 }
@@ -0,0 +1,259 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::process::Command;
+
+use color_eyre::eyre::{Result, bail, eyre};
+
+use crate::clock;
+use crate::codex_import::{expand_home, home_dir};
+use crate::models::{CommitCostConfig, UsageData};
+use crate::query::Query;
+use crate::ui::format_money;
+
+/// One UTC day of a repository's tagged spend and commits.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CommitDay {
+    pub(crate) day: i64,
+    pub(crate) commits: u32,
+    pub(crate) cost_usd: f64,
+}
+
+/// Spend tagged for one repository next to the commits made in it.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RepoCommitCost {
+    pub(crate) tag: String,
+    pub(crate) path: String,
+    /// Days with commits or spend, oldest first.
+    pub(crate) days: Vec<CommitDay>,
+}
+
+impl RepoCommitCost {
+    pub(crate) fn commits(&self) -> u32 {
+        self.days.iter().map(|day| day.commits).sum()
+    }
+
+    pub(crate) fn cost_usd(&self) -> f64 {
+        self.days.iter().map(|day| day.cost_usd).sum()
+    }
+
+    /// `None` when nothing was committed in the window.
+    pub(crate) fn cost_per_commit(&self) -> Option<f64> {
+        match self.commits() {
+            0 => None,
+            commits => Some(self.cost_usd() / f64::from(commits)),
+        }
+    }
+}
+
+/// Cost per commit for every repository in `commit_cost.repos` over the
+/// last `lookback_days` UTC days, today included. With a `filter`, only
+/// the tagged entries it matches count as spend.
+pub(crate) fn commit_costs(
+    config: &CommitCostConfig,
+    data: &UsageData,
+    filter: Option<&Query>,
+    now_epoch_secs: i64,
+) -> Result<Vec<RepoCommitCost>> {
+    let since_day =
+        now_epoch_secs.div_euclid(clock::SECS_PER_DAY) - i64::from(config.lookback_days.max(1)) + 1;
+    config
+        .repos
+        .iter()
+        .map(|(tag, path)| {
+            let commits = commit_days(
+                &expand_home(path, &home_dir()),
+                since_day * clock::SECS_PER_DAY,
+            )?;
+            Ok(correlate(tag, path, data, filter, &commits, since_day))
+        })
+        .collect()
+}
+
+/// Non-merge commits reachable from `HEAD` per UTC day of their commit
+/// time, from `since_epoch_secs` on.
+fn commit_days(repo: &Path, since_epoch_secs: i64) -> Result<BTreeMap<i64, u32>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["log", "--no-merges", "--format=%ct"])
+        .arg(format!("--since=@{since_epoch_secs}"))
+        .output()
+        .map_err(|err| eyre!("git: {err}"))?;
+    if !output.status.success() {
+        bail!(
+            "git log in {}: {}",
+            repo.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let mut days = BTreeMap::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Ok(committed) = line.trim().parse::<i64>() else {
+            continue;
+        };
+        *days
+            .entry(committed.div_euclid(clock::SECS_PER_DAY))
+            .or_default() += 1;
+    }
+    Ok(days)
+}
+
+fn correlate(
+    tag: &str,
+    path: &str,
+    data: &UsageData,
+    filter: Option<&Query>,
+    commits: &BTreeMap<i64, u32>,
+    since_day: i64,
+) -> RepoCommitCost {
+    let mut days = commits
+        .range(since_day..)
+        .map(|(day, commits)| (*day, (*commits, 0.0)))
+        .collect::<BTreeMap<_, _>>();
+    for entry in data
+        .entries
+        .iter()
+        .filter(|entry| entry.tag.as_deref() == Some(tag))
+        .filter(|entry| filter.is_none_or(|filter| filter.matches(*entry)))
+    {
+        let Some(day) = clock::parse_timestamp(&entry.timestamp)
+            .map(|at| at.div_euclid(clock::SECS_PER_DAY))
+            .filter(|day| *day >= since_day)
+        else {
+            continue;
+        };
+        days.entry(day).or_insert((0, 0.0)).1 += entry.cost_usd;
+    }
+    RepoCommitCost {
+        tag: tag.to_string(),
+        path: path.to_string(),
+        days: days
+            .into_iter()
+            .map(|(day, (commits, cost_usd))| CommitDay {
+                day,
+                commits,
+                cost_usd,
+            })
+            .collect(),
+    }
+}
+
+/// The report `promptpetrol commit-cost` prints: a total per repository,
+/// then one row per day.
+pub(crate) fn format_commit_costs(
+    reports: &[RepoCommitCost],
+    lookback_days: u32,
+    currency: &str,
+) -> String {
+    let per_commit = |cost: Option<f64>| {
+        cost.map_or_else(|| "-".to_string(), |cost| format_money(cost, currency))
+    };
+    let mut out = String::new();
+    for report in reports {
+        let _ = writeln!(
+            out,
+            "{} ({}), last {lookback_days} days: {} over {} commits, {} per commit",
+            report.tag,
+            report.path,
+            format_money(report.cost_usd(), currency),
+            report.commits(),
+            per_commit(report.cost_per_commit())
+        );
+        let _ = writeln!(
+            out,
+            "  {:<10} {:>7} {:>10} {:>10}",
+            "day", "commits", "cost", "per commit"
+        );
+        for day in &report.days {
+            let (year, month, date) = clock::civil_from_days(day.day);
+            let _ = writeln!(
+                out,
+                "  {year:04}-{month:02}-{date:02} {:>7} {:>10} {:>10}",
+                day.commits,
+                format_money(day.cost_usd, currency),
+                per_commit((day.commits > 0).then(|| day.cost_usd / f64::from(day.commits)))
+            );
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::UsageEntry;
+
+    fn entry(timestamp: &str, tag: &str, cost_usd: f64) -> UsageEntry {
+        UsageEntry {
+            timestamp: timestamp.to_string(),
+            provider: "openai".into(),
+            model: "gpt-4.1-mini".into(),
+            input_tokens: 0,
+            output_tokens: 0,
+            cost_usd,
+            pricing_match: None,
+            cost_source: None,
+            original_cost: None,
+            api_key: None,
+            project_id: None,
+            tag: Some(tag.to_string()),
+            data_file: None,
+        }
+    }
+
+    #[test]
+    fn tagged_spend_is_set_against_commits_per_day() {
+        let day = |timestamp: &str| {
+            clock::parse_timestamp(timestamp).expect("timestamp") / clock::SECS_PER_DAY
+        };
+        let data = UsageData {
+            budget_usd: None,
+            entries: vec![
+                entry("2026-10-13T09:00:00Z", "search-api", 9.0),
+                entry("2026-10-14T09:00:00Z", "search-api", 2.0),
+                entry("2026-10-14T15:00:00Z", "search-api", 1.0),
+                entry("2026-10-14T16:00:00Z", "billing", 5.0),
+                entry("2026-10-15T10:00:00Z", "search-api", 1.5),
+            ],
+        };
+        let commits = BTreeMap::from([
+            (day("2026-10-13T00:00:00Z"), 4),
+            (day("2026-10-14T00:00:00Z"), 4),
+            (day("2026-10-16T00:00:00Z"), 2),
+        ]);
+        let report = correlate(
+            "search-api",
+            "~/src/search-api",
+            &data,
+            None,
+            &commits,
+            day("2026-10-14T00:00:00Z"),
+        );
+        assert_eq!(report.commits(), 6);
+        assert_eq!(report.cost_usd(), 4.5);
+        assert_eq!(report.cost_per_commit(), Some(0.75));
+        assert_eq!(
+            format_commit_costs(&[report], 3, "USD"),
+            "search-api (~/src/search-api), last 3 days: $4.500 over 6 commits, $0.750 per commit
+  day        commits       cost per commit
+  2026-10-14       4     $3.000     $0.750
+  2026-10-15       0     $1.500          -
+  2026-10-16       2     $0.000     $0.000
+"
+        );
+
+        // A filter narrows the spend, not the commits.
+        let filter = Query::parse("cost<2 and ts<2026-10-15").expect("query");
+        let report = correlate(
+            "search-api",
+            "~/src/search-api",
+            &data,
+            Some(&filter),
+            &commits,
+            day("2026-10-14T00:00:00Z"),
+        );
+        assert_eq!(report.commits(), 6);
+        assert_eq!(report.cost_usd(), 1.0);
+    }
+}
@@ -0,0 +1,173 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::codex_import::{CodexImportCache, latest_session_turns, session_turns};
+use crate::models::{AppConfig, cost_multiplier, estimate_cost};
+
+/// Turns a session needs before its growth says anything.
+const MIN_TURNS: usize = 4;
+/// Share of a session's input spent re-sending earlier conversation above
+/// which compacting it would have paid off.
+const BLOATED_SHARE: f64 = 0.5;
+/// A session whose file changed this recently is taken to be still running.
+const ACTIVE_SESSION_WINDOW: Duration = Duration::from_secs(30 * 60);
+
+/// How a session's input grew turn by turn as the conversation was sent
+/// back to the model with every call.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ContextGrowth {
+    pub(crate) path: PathBuf,
+    /// Input tokens sent with each turn, in order.
+    pub(crate) turn_input_tokens: Vec<u64>,
+    /// Input beyond what the first turn sent, summed over all turns: what
+    /// carrying the conversation along cost in tokens.
+    pub(crate) carried_tokens: u64,
+    pub(crate) input_cost_usd: f64,
+    pub(crate) carried_cost_usd: f64,
+}
+
+impl ContextGrowth {
+    pub(crate) fn input_tokens(&self) -> u64 {
+        self.turn_input_tokens.iter().sum()
+    }
+
+    /// Share of the session's input that was carried context.
+    pub(crate) fn carried_share(&self) -> f64 {
+        match self.input_tokens() {
+            0 => 0.0,
+            input => self.carried_tokens as f64 / input as f64,
+        }
+    }
+
+    /// Carried context makes up most of the input: time to compact.
+    pub(crate) fn is_bloated(&self) -> bool {
+        self.carried_share() >= BLOATED_SHARE
+    }
+}
+
+/// Growth of every cached Codex session with enough turns, the most
+/// carried context first.
+pub(crate) fn context_growth(config: &AppConfig, cache: &CodexImportCache) -> Vec<ContextGrowth> {
+    if !config.codex_import.enabled {
+        return Vec::new();
+    }
+    let usd_per_token = estimate_cost(
+        "codex",
+        &config.codex_import.model,
+        1_000_000,
+        0,
+        &config.pricing,
+    )
+    .cost_usd
+        * cost_multiplier(config, "codex")
+        / 1_000_000.0;
+    let mut sessions = session_turns(cache)
+        .filter_map(|(path, turns)| analyze_turns(path, turns, usd_per_token))
+        .collect::<Vec<_>>();
+    sessions.sort_by(|a, b| {
+        b.carried_tokens
+            .cmp(&a.carried_tokens)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    sessions
+}
+
+/// The running Codex session's latest turn sent more input than
+/// `heavy_load_turn_tokens`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct HeavyLoad {
+    pub(crate) path: PathBuf,
+    pub(crate) turn_input_tokens: u64,
+    pub(crate) threshold: u64,
+}
+
+/// Checks the most recently active Codex session against
+/// `heavy_load_turn_tokens`. Sessions idle for more than half an hour are
+/// left alone: advice to compact only helps while the agent still runs.
+pub(crate) fn heavy_load(
+    config: &AppConfig,
+    cache: &CodexImportCache,
+    now: SystemTime,
+) -> Option<HeavyLoad> {
+    let threshold = config.heavy_load_turn_tokens?;
+    if !config.codex_import.enabled {
+        return None;
+    }
+    let (path, modified, turns) = latest_session_turns(cache)?;
+    check_heavy_load(path, modified, turns, threshold, now)
+}
+
+fn check_heavy_load(
+    path: &Path,
+    modified: SystemTime,
+    turns: &[u64],
+    threshold: u64,
+    now: SystemTime,
+) -> Option<HeavyLoad> {
+    let last = *turns.last()?;
+    // A file modified "in the future" is from a skewed clock, not idle.
+    let active = now
+        .duration_since(modified)
+        .map_or(true, |idle| idle <= ACTIVE_SESSION_WINDOW);
+    (active && last > threshold).then(|| HeavyLoad {
+        path: path.to_path_buf(),
+        turn_input_tokens: last,
+        threshold,
+    })
+}
+
+fn analyze_turns(path: &Path, turns: &[u64], usd_per_token: f64) -> Option<ContextGrowth> {
+    let first = *turns.first()?;
+    if turns.len() < MIN_TURNS {
+        return None;
+    }
+    let input = turns.iter().sum::<u64>();
+    let carried_tokens = turns
+        .iter()
+        .map(|tokens| tokens.saturating_sub(first))
+        .sum::<u64>();
+    Some(ContextGrowth {
+        path: path.to_path_buf(),
+        turn_input_tokens: turns.to_vec(),
+        carried_tokens,
+        input_cost_usd: input as f64 * usd_per_token,
+        carried_cost_usd: carried_tokens as f64 * usd_per_token,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn carried_context_is_measured_against_the_first_turn() {
+        let path = Path::new("rollout.jsonl");
+        assert_eq!(analyze_turns(path, &[8_000, 9_000, 10_000], 0.0), None);
+
+        let steady = analyze_turns(path, &[10_000, 10_500, 9_800, 10_200], 1e-6).expect("steady");
+        assert_eq!(steady.carried_tokens, 700);
+        assert!(!steady.is_bloated());
+
+        let growing =
+            analyze_turns(path, &[8_000, 20_000, 40_000, 70_000, 110_000], 1e-6).expect("growing");
+        assert_eq!(growing.input_tokens(), 248_000);
+        assert_eq!(growing.carried_tokens, 208_000);
+        assert!(growing.is_bloated());
+        assert!((growing.carried_cost_usd - 0.208).abs() < 1e-9);
+        assert!((growing.input_cost_usd - 0.248).abs() < 1e-9);
+    }
+
+    #[test]
+    fn heavy_load_looks_at_the_latest_turn_of_a_running_session() {
+        let path = Path::new("rollout.jsonl");
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(100_000);
+        let turns = [8_000, 60_000, 140_000];
+        let load = check_heavy_load(path, now, &turns, 120_000, now).expect("heavy");
+        assert_eq!(load.turn_input_tokens, 140_000);
+        assert_eq!(check_heavy_load(path, now, &turns, 150_000, now), None);
+        assert_eq!(check_heavy_load(path, now, &[], 0, now), None);
+
+        let idle = now - ACTIVE_SESSION_WINDOW - Duration::from_secs(1);
+        assert_eq!(check_heavy_load(path, idle, &turns, 120_000, now), None);
+    }
+}
@@ -0,0 +1,172 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use serde::de::IgnoredAny;
+
+use crate::clock;
+use crate::codex_import::{collect_jsonl_files_recursive, expand_home, home_dir};
+use crate::editor_import::{EditorRecord, ParsedEditorFile};
+use crate::models::AppConfig;
+
+/// Continue writes one line per model call to this file under each
+/// dev-data schema directory.
+const TOKENS_FILE_NAME: &str = "tokensGenerated.jsonl";
+
+/// One line of the log. Only these fields are decoded; anything else on
+/// the line is skipped as it is read, never copied out.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TokensGenerated {
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    provider: Option<String>,
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    generated_tokens: u64,
+    #[serde(default)]
+    timestamp: Option<EventTimestamp>,
+    /// Newer Continue versions wrap the event's fields in here.
+    #[serde(default)]
+    data: Option<Box<TokensGenerated>>,
+}
+
+/// RFC 3339 strings are kept; numbers are taken as Unix milliseconds.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum EventTimestamp {
+    Text(String),
+    Millis(i64),
+    Other(IgnoredAny),
+}
+
+impl EventTimestamp {
+    fn into_timestamp(self) -> Option<String> {
+        match self {
+            EventTimestamp::Text(timestamp) => {
+                clock::parse_timestamp(&timestamp).map(|_| timestamp)
+            }
+            EventTimestamp::Millis(millis) => Some(clock::format_timestamp(millis / 1000)),
+            EventTimestamp::Other(_) => None,
+        }
+    }
+}
+
+/// `storage_dir` from the config, else Continue's default `~/.continue/dev_data`.
+pub(crate) fn continue_storage_dir(config: &AppConfig) -> Option<PathBuf> {
+    let continue_dev = config.continue_dev.as_ref()?;
+    let home = home_dir();
+    Some(match continue_dev.storage_dir.as_deref() {
+        Some(dir) => expand_home(dir, &home),
+        None => home.join(".continue").join("dev_data"),
+    })
+}
+
+pub(crate) fn continue_log_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    collect_jsonl_files_recursive(dir, &mut files)?;
+    files.retain(|path| {
+        path.file_name()
+            .is_some_and(|name| name == TOKENS_FILE_NAME)
+    });
+    Ok(files)
+}
+
+/// Parses a `tokensGenerated.jsonl` file. Newer Continue versions wrap each
+/// event as `{"name": ..., "data": {...}, "timestamp": ...}`; older ones
+/// log the fields at the top level. Lines without a timestamp are dated by
+/// the file's modification time.
+pub(crate) fn parse_continue_file(path: &Path, modified_epoch_secs: i64) -> ParsedEditorFile {
+    let Ok(contents) = fs::read_to_string(path) else {
+        return ParsedEditorFile::Unreadable;
+    };
+    let mut records = Vec::new();
+    let mut skipped_lines = 0;
+    for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+        let Ok(mut event) = serde_json::from_str::<TokensGenerated>(line) else {
+            skipped_lines += 1;
+            continue;
+        };
+        let outer_timestamp = event.timestamp.take();
+        let tokens = match event.data.take() {
+            Some(data) => *data,
+            None => event,
+        };
+        let Some(model) = tokens.model else {
+            skipped_lines += 1;
+            continue;
+        };
+        let timestamp = outer_timestamp
+            .and_then(EventTimestamp::into_timestamp)
+            .or_else(|| tokens.timestamp.and_then(EventTimestamp::into_timestamp))
+            .unwrap_or_else(|| clock::format_timestamp(modified_epoch_secs));
+        let pricing_provider = tokens
+            .provider
+            .unwrap_or_else(|| "continue".to_string())
+            .to_lowercase();
+        records.push(EditorRecord {
+            timestamp,
+            model,
+            tag: Some(pricing_provider.clone()),
+            pricing_provider: Some(pricing_provider),
+            input_tokens: tokens.prompt_tokens,
+            output_tokens: tokens.generated_tokens,
+            prompts: 1,
+        });
+    }
+    ParsedEditorFile::Parsed {
+        records,
+        skipped_lines,
+        quota: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::make_temp_dir;
+
+    #[test]
+    fn parses_wrapped_and_flat_token_events() {
+        let dir = make_temp_dir("continue").join("0.2.0");
+        fs::create_dir_all(&dir).expect("create dir");
+        let path = dir.join(TOKENS_FILE_NAME);
+        fs::write(
+            &path,
+            concat!(
+                r#"{"name":"tokensGenerated","data":{"model":"gpt-4.1-mini","provider":"OpenAI","promptTokens":1200,"generatedTokens":300},"timestamp":"2026-03-02T10:00:00.000Z","schema":"0.2.0"}"#,
+                "\n",
+                r#"{"model":"codestral","provider":"mistral","promptTokens":400,"generatedTokens":80,"timestamp":1772445600000}"#,
+                "\n",
+                r#"{"model":"qwen2.5-coder","promptTokens":10,"generatedTokens":5}"#,
+                "\n",
+                "not json\n",
+                r#"{"name":"chatFeedback","data":{"feedback":true}}"#,
+                "\n",
+            ),
+        )
+        .expect("write");
+        fs::write(dir.join("chatInteraction.jsonl"), "{}\n").expect("write");
+
+        let root = dir.parent().expect("root");
+        assert_eq!(continue_log_files(root).expect("files"), vec![path.clone()]);
+        let ParsedEditorFile::Parsed {
+            records,
+            skipped_lines,
+            ..
+        } = parse_continue_file(&path, 0)
+        else {
+            panic!("expected parsed file");
+        };
+        assert_eq!(skipped_lines, 2);
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].pricing_provider.as_deref(), Some("openai"));
+        assert_eq!(records[0].input_tokens, 1200);
+        assert_eq!(records[1].timestamp, "2026-03-02T10:00:00Z");
+        assert_eq!(records[2].timestamp, "1970-01-01T00:00:00Z");
+        assert_eq!(records[2].tag.as_deref(), Some("continue"));
+        let _ = fs::remove_dir_all(root);
+    }
+}
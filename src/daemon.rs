@@ -0,0 +1,466 @@
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use color_eyre::Result;
+use serde::Serialize;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, watch};
+
+use crate::alerts::{Alert, evaluate_alerts, new_alerts};
+use crate::clock;
+use crate::codex_import::CodexRateLimits;
+use crate::freeze::daily_allowance;
+use crate::grafana::{self, QueryRequest, SearchRequest};
+use crate::models::{ProviderSummary, provider_summaries, save_usage_data};
+use crate::reconcile::Reconciliation;
+use crate::retry::PollerHealth;
+use crate::team_budget::TeamBudgetState;
+use crate::worker::{RefreshWorker, Snapshot, run_refresh_worker};
+
+pub(crate) const DEFAULT_LISTEN_ADDR: &str = "127.0.0.1:9464";
+const MAX_REQUEST_BYTES: usize = 64 * 1024;
+/// A client that has not sent its whole request by then is dropped, so
+/// idle connections do not pile up.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// Pause after a failed accept (such as running out of file descriptors)
+/// before trying again.
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Headless collector: keeps the importers running, mirrors the merged
+/// usage into `store_file` after every refresh, logs alerts as they start
+/// firing, and serves `/status` (JSON), `/metrics` (Prometheus text) and
+/// the Grafana simple-json datasource endpoints on `listen`. Runs until
+/// SIGINT or SIGTERM.
+pub(crate) async fn run_daemon(
+    worker: RefreshWorker,
+    store_file: PathBuf,
+    listen: SocketAddr,
+) -> Result<()> {
+    let initial = worker.snapshot();
+    save_usage_data(&store_file, &initial.data)?;
+    let mut active_alerts = evaluate_alerts(&initial);
+    log_new_alerts(&[], &active_alerts);
+
+    let listener = TcpListener::bind(listen).await?;
+    log_line(&format!(
+        "listening on http://{}, writing {}",
+        listener.local_addr()?,
+        store_file.display()
+    ));
+    let (state_tx, state_rx) = watch::channel(Arc::new(initial));
+    let server = tokio::spawn(serve(listener, state_rx));
+
+    let (command_tx, command_rx) = mpsc::unbounded_channel();
+    let (snapshot_tx, mut snapshot_rx) = mpsc::unbounded_channel();
+    let worker_task = tokio::spawn(run_refresh_worker(worker, command_rx, snapshot_tx));
+
+    loop {
+        tokio::select! {
+            snapshot = snapshot_rx.recv() => {
+                let Some(snapshot) = snapshot else {
+                    break;
+                };
+                if let Err(err) = save_usage_data(&store_file, &snapshot.data) {
+                    log_line(&format!("failed to write store: {err}"));
+                }
+                let alerts = evaluate_alerts(&snapshot);
+                log_new_alerts(&active_alerts, &alerts);
+                active_alerts = alerts;
+                state_tx.send_replace(Arc::new(snapshot));
+            }
+            _ = shutdown_signal() => {
+                log_line("shutting down");
+                break;
+            }
+        }
+    }
+
+    server.abort();
+    drop(command_tx);
+    worker_task.await?
+}
+
+#[cfg(unix)]
+pub(crate) async fn shutdown_signal() {
+    use tokio::signal::unix::{SignalKind, signal};
+    let Ok(mut terminate) = signal(SignalKind::terminate()) else {
+        let _ = tokio::signal::ctrl_c().await;
+        return;
+    };
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = terminate.recv() => {}
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) async fn shutdown_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+fn log_line(message: &str) {
+    println!(
+        "{} {message}",
+        clock::format_timestamp(clock::now_epoch_secs())
+    );
+}
+
+fn log_new_alerts(previous: &[Alert], current: &[Alert]) {
+    for alert in new_alerts(previous, current) {
+        log_line(&format!(
+            "alert {} [{:?}] {}",
+            alert.key, alert.level, alert.message
+        ));
+    }
+}
+
+async fn serve(listener: TcpListener, state: watch::Receiver<Arc<Snapshot>>) {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(err) => {
+                log_line(&format!("accept failed: {err}"));
+                tokio::time::sleep(ACCEPT_BACKOFF).await;
+                continue;
+            }
+        };
+        let snapshot = state.borrow().clone();
+        tokio::spawn(async move {
+            let _ = handle_connection(stream, &snapshot, REQUEST_TIMEOUT).await;
+        });
+    }
+}
+
+/// Minimal HTTP/1.1 handling: reads the request head and any
+/// `Content-Length` body, answers the known paths and closes the
+/// connection. A request not read within `timeout` gets no answer.
+async fn handle_connection(
+    mut stream: TcpStream,
+    snapshot: &Snapshot,
+    timeout: Duration,
+) -> Result<()> {
+    let Ok(request) = tokio::time::timeout(timeout, read_request(&mut stream)).await else {
+        return Ok(());
+    };
+    let (request, head_end) = request?;
+    let head = String::from_utf8_lossy(&request[..head_end]);
+    let body = &request[head_end..];
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default();
+    let path = request_line.next().unwrap_or_default();
+    let path = path.split('?').next().unwrap_or_default();
+
+    let (status, content_type, body) = match (method, path) {
+        // Grafana's datasource health check.
+        ("GET", "/") => ("200 OK", "text/plain", "ok\n".to_string()),
+        ("POST", "/search") => {
+            let request = serde_json::from_slice::<SearchRequest>(body).unwrap_or_default();
+            (
+                "200 OK",
+                "application/json",
+                serde_json::to_string(&grafana::search(&snapshot.data, &request))?,
+            )
+        }
+        ("POST", "/query") => match serde_json::from_slice::<QueryRequest>(body) {
+            Ok(request) => (
+                "200 OK",
+                "application/json",
+                serde_json::to_string(&grafana::query(&snapshot.data, &request))?,
+            ),
+            Err(err) => ("400 Bad Request", "text/plain", format!("{err}\n")),
+        },
+        ("POST", "/annotations") => ("200 OK", "application/json", "[]".to_string()),
+        ("GET", "/status") => (
+            "200 OK",
+            "application/json",
+            serde_json::to_string_pretty(&status_report(snapshot))?,
+        ),
+        ("GET", "/metrics") => (
+            "200 OK",
+            "text/plain; version=0.0.4",
+            render_metrics(snapshot),
+        ),
+        ("GET", _) => ("404 Not Found", "text/plain", "not found\n".to_string()),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "method not allowed\n".to_string(),
+        ),
+    };
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Reads one request, head and `Content-Length` body, up to
+/// `MAX_REQUEST_BYTES`. Returns it with where the head ends.
+async fn read_request(stream: &mut TcpStream) -> Result<(Vec<u8>, usize)> {
+    let mut request = Vec::with_capacity(1024);
+    let mut buffer = [0_u8; 1024];
+    let mut head_end = None;
+    let mut content_length = 0;
+    loop {
+        if head_end.is_none()
+            && let Some(end) = request.windows(4).position(|window| window == b"\r\n\r\n")
+        {
+            head_end = Some(end + 4);
+            content_length = parse_content_length(&String::from_utf8_lossy(&request[..end]));
+        }
+        if head_end.is_some_and(|end| request.len() >= end + content_length) {
+            break;
+        }
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 || request.len() + read > MAX_REQUEST_BYTES {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+    let head_end = head_end.unwrap_or(request.len());
+    Ok((request, head_end))
+}
+
+#[derive(Debug, Serialize)]
+struct StatusReport<'a> {
+    status: &'a str,
+    budget_usd: Option<f64>,
+    total_cost_usd: f64,
+    total_tokens: u64,
+    entries: usize,
+    providers: Vec<ProviderSummary>,
+    codex_limits: Option<&'a CodexRateLimits>,
+    alerts: Vec<Alert>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    team_budget: Option<&'a TeamBudgetState>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    pollers: &'a [PollerHealth],
+    /// Estimates left out as duplicates of billed usage.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reconciliation: Option<&'a Reconciliation>,
+    /// Unspent budget carried into this month, part of `budget_usd`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    carried_over_usd: Option<f64>,
+    /// What can be spent per day for the rest of the month; 0 while
+    /// spending is frozen.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    daily_allowance_usd: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    spending_frozen_since: Option<&'a str>,
+}
+
+fn status_report(snapshot: &Snapshot) -> StatusReport<'_> {
+    let providers = provider_summaries(&snapshot.data);
+    StatusReport {
+        status: &snapshot.status,
+        budget_usd: snapshot.data.budget_usd,
+        total_cost_usd: providers.iter().map(|p| p.total_cost_usd).sum(),
+        total_tokens: providers.iter().map(|p| p.total_tokens).sum(),
+        entries: snapshot.data.entries.len(),
+        providers,
+        codex_limits: snapshot.codex_limits.as_ref(),
+        alerts: evaluate_alerts(snapshot),
+        team_budget: snapshot.team_budget.as_ref(),
+        pollers: &snapshot.pollers,
+        reconciliation: Some(&snapshot.reconciliation).filter(|done| done.matched > 0),
+        carried_over_usd: Some(snapshot.carried_over_usd).filter(|carried| *carried > 0.0),
+        daily_allowance_usd: daily_allowance(
+            &snapshot.config,
+            &snapshot.data,
+            clock::now_epoch_secs(),
+        ),
+        spending_frozen_since: snapshot.config.spending_frozen_since.as_deref(),
+    }
+}
+
+fn render_metrics(snapshot: &Snapshot) -> String {
+    let mut out = String::new();
+    let providers = provider_summaries(&snapshot.data);
+
+    let _ = writeln!(
+        out,
+        "# HELP promptpetrol_cost_usd Recorded spend per provider."
+    );
+    let _ = writeln!(out, "# TYPE promptpetrol_cost_usd gauge");
+    for provider in &providers {
+        let _ = writeln!(
+            out,
+            "promptpetrol_cost_usd{{provider=\"{}\"}} {}",
+            escape_label(&provider.provider),
+            provider.total_cost_usd
+        );
+    }
+    let _ = writeln!(
+        out,
+        "# HELP promptpetrol_tokens Recorded tokens per provider."
+    );
+    let _ = writeln!(out, "# TYPE promptpetrol_tokens gauge");
+    for provider in &providers {
+        let _ = writeln!(
+            out,
+            "promptpetrol_tokens{{provider=\"{}\"}} {}",
+            escape_label(&provider.provider),
+            provider.total_tokens
+        );
+    }
+    if let Some(budget) = snapshot.data.budget_usd {
+        let _ = writeln!(out, "# HELP promptpetrol_budget_usd Configured budget.");
+        let _ = writeln!(out, "# TYPE promptpetrol_budget_usd gauge");
+        let _ = writeln!(out, "promptpetrol_budget_usd {budget}");
+    }
+    let _ = writeln!(
+        out,
+        "# HELP promptpetrol_entries Usage entries in the store."
+    );
+    let _ = writeln!(out, "# TYPE promptpetrol_entries gauge");
+    let _ = writeln!(out, "promptpetrol_entries {}", snapshot.data.entries.len());
+    if let Some(team) = snapshot.team_budget.as_ref() {
+        let _ = writeln!(
+            out,
+            "# HELP promptpetrol_team_spent_usd Spend recorded by every member of the shared team budget."
+        );
+        let _ = writeln!(out, "# TYPE promptpetrol_team_spent_usd gauge");
+        let _ = writeln!(out, "promptpetrol_team_spent_usd {}", team.spent_usd());
+    }
+
+    if let Some(limits) = snapshot.codex_limits.as_ref() {
+        let _ = writeln!(
+            out,
+            "# HELP promptpetrol_codex_limit_used_percent Codex rate-limit window usage."
+        );
+        let _ = writeln!(out, "# TYPE promptpetrol_codex_limit_used_percent gauge");
+        for (window, limit) in [
+            ("primary", &limits.primary),
+            ("secondary", &limits.secondary),
+        ] {
+            if let Some(limit) = limit {
+                let _ = writeln!(
+                    out,
+                    "promptpetrol_codex_limit_used_percent{{window=\"{window}\"}} {}",
+                    limit.used_percent
+                );
+            }
+        }
+    }
+
+    let alerts = evaluate_alerts(snapshot);
+    let _ = writeln!(
+        out,
+        "# HELP promptpetrol_alerts_active Alerts currently firing."
+    );
+    let _ = writeln!(out, "# TYPE promptpetrol_alerts_active gauge");
+    let _ = writeln!(out, "promptpetrol_alerts_active {}", alerts.len());
+    out
+}
+
+fn parse_content_length(head: &str) -> usize {
+    head.lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::UsageData;
+
+    use super::*;
+
+    fn sample_snapshot() -> Snapshot {
+        Snapshot::for_test(UsageData::default())
+    }
+
+    #[test]
+    fn metrics_list_each_provider_and_budget() {
+        let metrics = render_metrics(&sample_snapshot());
+        assert!(metrics.contains("promptpetrol_cost_usd{provider=\"anthropic\"} 0.361"));
+        assert!(metrics.contains("promptpetrol_tokens{provider=\"openai\"} 10000"));
+        assert!(metrics.contains("promptpetrol_budget_usd 50"));
+        assert!(metrics.contains("promptpetrol_entries 3"));
+        assert_eq!(escape_label("a\"b"), "a\\\"b");
+    }
+
+    #[tokio::test]
+    async fn serves_status_json_over_http() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let (_state_tx, state_rx) = watch::channel(Arc::new(sample_snapshot()));
+        let server = tokio::spawn(serve(listener, state_rx));
+
+        let mut stream = TcpStream::connect(addr).await.expect("connect");
+        stream
+            .write_all(b"GET /status HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .expect("write");
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.expect("read");
+        server.abort();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        let body = response.split("\r\n\r\n").nth(1).expect("body");
+        let status: serde_json::Value = serde_json::from_str(body).expect("json");
+        assert_eq!(status["entries"], 3);
+        assert_eq!(status["providers"][0]["provider"], "anthropic");
+    }
+
+    #[tokio::test]
+    async fn drops_clients_that_send_nothing() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let mut client = TcpStream::connect(addr).await.expect("connect");
+        let (stream, _) = listener.accept().await.expect("accept");
+
+        let snapshot = sample_snapshot();
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            handle_connection(stream, &snapshot, Duration::from_millis(50)),
+        )
+        .await
+        .expect("gave up on the idle client")
+        .expect("handled");
+        let mut response = Vec::new();
+        client.read_to_end(&mut response).await.expect("read");
+        assert!(response.is_empty());
+    }
+
+    #[tokio::test]
+    async fn answers_grafana_search_with_a_body() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let (_state_tx, state_rx) = watch::channel(Arc::new(sample_snapshot()));
+        let server = tokio::spawn(serve(listener, state_rx));
+
+        let body = r#"{"target": "open"}"#;
+        let mut stream = TcpStream::connect(addr).await.expect("connect");
+        stream
+            .write_all(
+                format!(
+                    "POST /search HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+                    body.len()
+                )
+                .as_bytes(),
+            )
+            .await
+            .expect("write");
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.expect("read");
+        server.abort();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with(r#"["openai"]"#));
+    }
+}
@@ -0,0 +1,263 @@
+use std::fs;
+use std::path::PathBuf;
+
+use color_eyre::eyre::Result;
+use serde::Serialize;
+
+use crate::clock;
+use crate::reconcile::Reconciliation;
+use crate::redact::Redactor;
+use crate::retry::PollerHealth;
+use crate::worker::RefreshWorker;
+
+/// What `diagnose --json` prints: one import pass, run headless, and
+/// everything the diagnostics overlay would show about it. Errors go
+/// through the config's [`Redactor`], so the output can go into a bug
+/// report as is.
+#[derive(Debug, Serialize)]
+pub(crate) struct Diagnostics {
+    pub(crate) version: &'static str,
+    pub(crate) platform: String,
+    pub(crate) generated_at: String,
+    pub(crate) files: Vec<FileDiagnostics>,
+    /// Each import task of the pass, in the order it ran.
+    pub(crate) tasks: Vec<TaskDiagnostics>,
+    pub(crate) importers: Vec<ImporterDiagnostics>,
+    pub(crate) codex: CodexDiagnostics,
+    pub(crate) editors: Vec<EditorDiagnostics>,
+    pub(crate) pollers: Vec<PollerHealth>,
+    pub(crate) reconciliation: Reconciliation,
+    /// Entries after the pass, imports included.
+    pub(crate) entries: usize,
+    pub(crate) status: String,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct FileDiagnostics {
+    pub(crate) role: &'static str,
+    pub(crate) path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct TaskDiagnostics {
+    /// Its key under `refresh_intervals`.
+    pub(crate) task: &'static str,
+    pub(crate) duration_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct ImporterDiagnostics {
+    pub(crate) importer: &'static str,
+    pub(crate) enabled: bool,
+    pub(crate) entries: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub(crate) struct CodexDiagnostics {
+    pub(crate) active_files: usize,
+    pub(crate) refreshed_files: usize,
+    pub(crate) parse_error_files: usize,
+    pub(crate) no_usage_or_limits_files: usize,
+    pub(crate) unreadable_files: usize,
+    pub(crate) oversized_files: usize,
+    pub(crate) failing_files: Vec<FailingFile>,
+}
+
+/// A session file that failed to parse, and where it first did.
+#[derive(Debug, Serialize)]
+pub(crate) struct FailingFile {
+    pub(crate) path: String,
+    pub(crate) failures: u32,
+    pub(crate) line: usize,
+    pub(crate) offset: u64,
+    pub(crate) error: String,
+    /// No longer retried on every refresh.
+    pub(crate) quarantined: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct EditorDiagnostics {
+    pub(crate) source: &'static str,
+    pub(crate) files: usize,
+    pub(crate) records: usize,
+    pub(crate) skipped_lines: usize,
+    pub(crate) unreadable_files: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+}
+
+/// Runs every enabled importer once through `worker`, timing each, and
+/// gathers how it went. `files` are described by role, size and whether
+/// they can be read.
+pub(crate) fn diagnose(
+    worker: &mut RefreshWorker,
+    files: &[(&'static str, PathBuf)],
+) -> Diagnostics {
+    worker.import_now();
+    let snapshot = worker.snapshot();
+    let redactor = Redactor::new(&snapshot.config);
+    let scrub = |error: &str| redactor.text(error);
+
+    let files = files
+        .iter()
+        .map(|(role, path)| {
+            let metadata = fs::metadata(path);
+            FileDiagnostics {
+                role,
+                path: path.display().to_string(),
+                bytes: metadata.as_ref().ok().map(fs::Metadata::len),
+                error: metadata.err().map(|err| err.to_string()),
+            }
+        })
+        .collect();
+    let tasks = worker
+        .task_runs()
+        .iter()
+        .map(|run| TaskDiagnostics {
+            task: run.task.config_key(),
+            duration_ms: run.took.as_secs_f64() * 1000.0,
+            error: run.error.as_deref().map(scrub),
+        })
+        .collect();
+    let importers = snapshot
+        .importers
+        .iter()
+        .map(|status| ImporterDiagnostics {
+            importer: status.importer.name(),
+            enabled: status.enabled,
+            entries: status.entries,
+            error: status.error.clone(),
+        })
+        .collect();
+    let codex = &snapshot.codex_diagnostics;
+    let codex = CodexDiagnostics {
+        active_files: codex.active_files,
+        refreshed_files: codex.refreshed_files,
+        parse_error_files: codex.parse_error_files,
+        no_usage_or_limits_files: codex.no_usage_or_limits_files,
+        unreadable_files: codex.unreadable_files,
+        oversized_files: codex.oversized_files,
+        failing_files: worker
+            .failing_sessions()
+            .into_iter()
+            .map(|session| FailingFile {
+                quarantined: codex
+                    .quarantined
+                    .iter()
+                    .any(|quarantined| quarantined.path == session.path),
+                path: session.path.display().to_string(),
+                failures: session.failures,
+                line: session.line,
+                offset: session.offset,
+                error: scrub(&session.error),
+            })
+            .collect(),
+    };
+    let editors = snapshot
+        .editor_imports
+        .iter()
+        .map(|(source, status)| EditorDiagnostics {
+            source: source.provider(),
+            files: status.files,
+            records: status.records,
+            skipped_lines: status.skipped_lines,
+            unreadable_files: status.unreadable_files,
+            error: status.error.as_deref().map(scrub),
+        })
+        .collect();
+
+    Diagnostics {
+        version: env!("CARGO_PKG_VERSION"),
+        platform: format!("{}/{}", std::env::consts::OS, std::env::consts::ARCH),
+        generated_at: clock::format_timestamp(clock::now_epoch_secs()),
+        files,
+        tasks,
+        importers,
+        codex,
+        editors,
+        pollers: snapshot.pollers,
+        reconciliation: snapshot.reconciliation,
+        entries: snapshot.data.entries.len(),
+        status: snapshot.status,
+    }
+}
+
+pub(crate) fn format_diagnostics_json(diagnostics: &Diagnostics) -> Result<String> {
+    Ok(serde_json::to_string_pretty(diagnostics)? + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::Value;
+
+    use super::*;
+    use crate::test_support::make_temp_dir;
+
+    #[test]
+    fn reports_each_import_task_and_the_files_that_failed() {
+        let dir = make_temp_dir("diagnose");
+        let sessions = dir.join("sessions");
+        fs::create_dir_all(&sessions).expect("create dir");
+        let broken = sessions.join("broken.jsonl");
+        fs::write(&broken, "{\"type\":\n").expect("write session");
+        let config_file = dir.join("config.json");
+        fs::write(
+            &config_file,
+            serde_json::json!({
+                "codex_import": {"enabled": true, "sessions_dir": sessions},
+            })
+            .to_string(),
+        )
+        .expect("write config");
+        let data_file = dir.join("usage.json");
+        let mut worker = RefreshWorker::new(
+            data_file.clone(),
+            Vec::new(),
+            config_file.clone(),
+            Duration::from_secs(5),
+        )
+        .expect("worker");
+
+        let diagnostics = diagnose(
+            &mut worker,
+            &[
+                ("config", config_file),
+                ("data", data_file),
+                ("data", dir.join("missing.json")),
+            ],
+        );
+        let json = format_diagnostics_json(&diagnostics).expect("json");
+        let report = serde_json::from_str::<Value>(&json).expect("parse");
+        assert_eq!(
+            report["tasks"]
+                .as_array()
+                .expect("tasks")
+                .iter()
+                .map(|task| task["task"].as_str().expect("task"))
+                .collect::<Vec<_>>(),
+            ["codex", "langfuse", "editors"]
+        );
+        assert_eq!(report["importers"].as_array().expect("importers").len(), 5);
+        assert_eq!(report["importers"][0]["enabled"], true);
+        assert_eq!(report["codex"]["parse_error_files"], 1);
+        let failing = &report["codex"]["failing_files"][0];
+        assert_eq!(failing["path"], broken.display().to_string());
+        assert_eq!(
+            (&failing["line"], &failing["quarantined"]),
+            (&1.into(), &false.into())
+        );
+        assert!(report["files"][0]["bytes"].as_u64().expect("bytes") > 0);
+        assert!(report["files"][2]["error"].is_string());
+        let _ = fs::remove_dir_all(dir);
+    }
+}
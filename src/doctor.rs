@@ -0,0 +1,182 @@
+use std::fmt::Write as _;
+use std::time::{Duration, Instant};
+
+use crate::http;
+use crate::models::{AppConfig, NetworkConfig, is_placeholder_key};
+
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What a cheap authenticated call made of one configured key.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum KeyHealth {
+    Ok,
+    /// The provider answered with this HTTP status, e.g. 401 for a revoked
+    /// key.
+    Rejected(u16),
+    Unreachable(String),
+    /// Still the placeholder the default config ships with, or empty.
+    NotSet,
+    /// No list-models call is known for this provider.
+    Unsupported,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct KeyCheck {
+    pub(crate) provider: String,
+    pub(crate) health: KeyHealth,
+    pub(crate) latency: Option<Duration>,
+}
+
+impl KeyCheck {
+    pub(crate) fn failed(&self) -> bool {
+        matches!(
+            self.health,
+            KeyHealth::Rejected(_) | KeyHealth::Unreachable(_)
+        )
+    }
+}
+
+/// A request that lists models, which every provider here answers for free.
+struct ModelsRequest {
+    url: String,
+    headers: Vec<(&'static str, String)>,
+}
+
+fn models_request(provider: &str, key: &str) -> Option<ModelsRequest> {
+    // Codex uses an OpenAI key, and `opus` is the Anthropic model family.
+    let request = match provider {
+        "openai" | "codex" => ModelsRequest {
+            url: "https://api.openai.com/v1/models".to_string(),
+            headers: vec![("Authorization", format!("Bearer {key}"))],
+        },
+        "anthropic" | "opus" => ModelsRequest {
+            url: "https://api.anthropic.com/v1/models".to_string(),
+            headers: vec![
+                ("x-api-key", key.to_string()),
+                ("anthropic-version", "2023-06-01".to_string()),
+            ],
+        },
+        "gemini" => ModelsRequest {
+            url: "https://generativelanguage.googleapis.com/v1beta/models".to_string(),
+            headers: vec![("x-goog-api-key", key.to_string())],
+        },
+        _ => return None,
+    };
+    Some(request)
+}
+
+/// Checks every key in `api_keys`, in provider order.
+pub(crate) fn check_api_keys(config: &AppConfig) -> Vec<KeyCheck> {
+    let mut providers = config.api_keys.keys().collect::<Vec<_>>();
+    providers.sort();
+    providers
+        .into_iter()
+        .map(|provider| {
+            let key = &config.api_keys[provider];
+            let (health, latency) = if is_placeholder_key(key) {
+                (KeyHealth::NotSet, None)
+            } else {
+                match models_request(provider, key) {
+                    Some(request) => {
+                        let (health, latency) = send(&config.network, &request);
+                        (health, Some(latency))
+                    }
+                    None => (KeyHealth::Unsupported, None),
+                }
+            };
+            KeyCheck {
+                provider: provider.clone(),
+                health,
+                latency,
+            }
+        })
+        .collect()
+}
+
+fn send(network: &NetworkConfig, request: &ModelsRequest) -> (KeyHealth, Duration) {
+    let agent = match http::agent(network, CHECK_TIMEOUT) {
+        Ok(agent) => agent,
+        Err(err) => return (KeyHealth::Unreachable(err.to_string()), Duration::ZERO),
+    };
+    let mut call = agent.get(&request.url);
+    for (name, value) in &request.headers {
+        call = call.header(*name, value);
+    }
+    let started = Instant::now();
+    let health = match call.call() {
+        Ok(_) => KeyHealth::Ok,
+        Err(ureq::Error::StatusCode(status)) => KeyHealth::Rejected(status),
+        Err(err) => KeyHealth::Unreachable(err.to_string()),
+    };
+    (health, started.elapsed())
+}
+
+/// One line per key for `promptpetrol doctor`.
+pub(crate) fn format_key_checks(checks: &[KeyCheck]) -> String {
+    if checks.is_empty() {
+        return "No API keys configured.\n".to_string();
+    }
+    let width = checks
+        .iter()
+        .map(|check| check.provider.len())
+        .max()
+        .unwrap_or(0);
+    let mut out = String::new();
+    for check in checks {
+        let latency = check
+            .latency
+            .map(|latency| format!(" ({} ms)", latency.as_millis()))
+            .unwrap_or_default();
+        let verdict = match &check.health {
+            KeyHealth::Ok => format!("ok{latency}"),
+            KeyHealth::Rejected(401 | 403) => {
+                format!("REJECTED{latency}: key is invalid or lacks access")
+            }
+            KeyHealth::Rejected(status) => format!("FAILED{latency}: HTTP {status}"),
+            KeyHealth::Unreachable(err) => format!("UNREACHABLE{latency}: {err}"),
+            KeyHealth::NotSet => "not set".to_string(),
+            KeyHealth::Unsupported => "no check available for this provider".to_string(),
+        };
+        let _ = writeln!(out, "{:width$}  {verdict}", check.provider);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_and_unknown_providers_are_not_called() {
+        let mut config = AppConfig::default();
+        config
+            .api_keys
+            .insert("mistral".to_string(), "live-key".to_string());
+        let checks = check_api_keys(&config);
+        assert!(checks.iter().all(|check| !check.failed()));
+        assert_eq!(checks[0].provider, "anthropic");
+        assert_eq!(checks[0].health, KeyHealth::NotSet);
+        let mistral = checks
+            .iter()
+            .find(|check| check.provider == "mistral")
+            .expect("mistral");
+        assert_eq!(mistral.health, KeyHealth::Unsupported);
+
+        let report = format_key_checks(&[
+            KeyCheck {
+                provider: "openai".to_string(),
+                health: KeyHealth::Rejected(401),
+                latency: Some(Duration::from_millis(182)),
+            },
+            KeyCheck {
+                provider: "gemini".to_string(),
+                health: KeyHealth::Ok,
+                latency: Some(Duration::from_millis(95)),
+            },
+        ]);
+        assert_eq!(
+            report,
+            "openai  REJECTED (182 ms): key is invalid or lacks access\ngemini  ok (95 ms)\n"
+        );
+    }
+}
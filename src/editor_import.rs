@@ -0,0 +1,463 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::clock;
+use crate::continue_import::{continue_log_files, continue_storage_dir, parse_continue_file};
+use crate::intern::intern;
+use crate::jetbrains_import::{jetbrains_config_dir, jetbrains_quota_files, parse_jetbrains_quota};
+use crate::models::{AppConfig, CostSource, UsageData, UsageEntry, estimate_cost};
+use crate::zed_import::{parse_zed_thread, zed_thread_files, zed_threads_dir};
+
+/// Editor integrations whose local logs are imported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum EditorSource {
+    Continue,
+    Zed,
+    JetBrains,
+}
+
+impl EditorSource {
+    pub(crate) const ALL: [EditorSource; 3] = [
+        EditorSource::Continue,
+        EditorSource::Zed,
+        EditorSource::JetBrains,
+    ];
+
+    /// Provider the source's entries are recorded under.
+    pub(crate) fn provider(self) -> &'static str {
+        match self {
+            EditorSource::Continue => "continue",
+            EditorSource::Zed => "zed",
+            EditorSource::JetBrains => "jetbrains",
+        }
+    }
+
+    /// Directory to read, or `None` when the source is not enabled.
+    fn dir(self, config: &AppConfig) -> Option<PathBuf> {
+        match self {
+            EditorSource::Continue => config
+                .continue_dev
+                .as_ref()
+                .filter(|c| c.enabled)
+                .and_then(|_| continue_storage_dir(config)),
+            EditorSource::Zed => config
+                .zed
+                .as_ref()
+                .filter(|zed| zed.enabled)
+                .map(zed_threads_dir),
+            EditorSource::JetBrains => config
+                .jetbrains
+                .as_ref()
+                .filter(|jetbrains| jetbrains.enabled)
+                .map(jetbrains_config_dir),
+        }
+    }
+
+    fn files(self, dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        match self {
+            EditorSource::Continue => continue_log_files(dir),
+            EditorSource::Zed => zed_thread_files(dir),
+            EditorSource::JetBrains => jetbrains_quota_files(dir),
+        }
+    }
+
+    fn parse(self, path: &Path, modified_epoch_secs: i64, metadata_only: bool) -> ParsedEditorFile {
+        match self {
+            EditorSource::Continue => parse_continue_file(path, modified_epoch_secs),
+            EditorSource::Zed => parse_zed_thread(path, modified_epoch_secs, metadata_only),
+            EditorSource::JetBrains => parse_jetbrains_quota(path, modified_epoch_secs),
+        }
+    }
+}
+
+/// Usage read from an editor log: one model call, or one thread when the
+/// editor only keeps running totals. `pricing_provider` is the upstream
+/// provider used to look up pricing; `None` means the usage is covered by
+/// the editor's plan and costs nothing per call. The entry itself is
+/// recorded under the editor's provider.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct EditorRecord {
+    pub(crate) timestamp: String,
+    pub(crate) model: String,
+    pub(crate) pricing_provider: Option<String>,
+    pub(crate) input_tokens: u64,
+    pub(crate) output_tokens: u64,
+    /// Prompts sent, for plans that meter prompts rather than tokens.
+    pub(crate) prompts: u64,
+    pub(crate) tag: Option<String>,
+}
+
+/// A plan allowance that is metered in something other than dollars
+/// (prompts, AI credits).
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PlanQuota {
+    pub(crate) provider: String,
+    pub(crate) unit: String,
+    pub(crate) used: f64,
+    pub(crate) limit: f64,
+    pub(crate) resets_at: Option<String>,
+}
+
+impl PlanQuota {
+    pub(crate) fn used_ratio(&self) -> f64 {
+        if self.limit > 0.0 {
+            (self.used / self.limit).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+pub(crate) enum ParsedEditorFile {
+    Parsed {
+        records: Vec<EditorRecord>,
+        skipped_lines: usize,
+        quota: Option<PlanQuota>,
+    },
+    Unreadable,
+}
+
+#[derive(Debug)]
+struct CachedEditorFile {
+    modified: SystemTime,
+    len: u64,
+    records: Vec<EditorRecord>,
+    skipped_lines: usize,
+    quota: Option<PlanQuota>,
+}
+
+/// Per-source outcome of the last import pass.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct EditorSourceStatus {
+    pub(crate) files: usize,
+    pub(crate) records: usize,
+    pub(crate) skipped_lines: usize,
+    pub(crate) unreadable_files: usize,
+    pub(crate) error: Option<String>,
+    pub(crate) last_run: Option<clock::Stamp>,
+}
+
+/// Parsed editor logs, re-read only when a file's size or mtime changes.
+#[derive(Debug, Default)]
+pub(crate) struct EditorImportCache {
+    files: HashMap<EditorSource, HashMap<PathBuf, CachedEditorFile>>,
+    status: HashMap<EditorSource, EditorSourceStatus>,
+    /// `metadata_only` as the cached files were parsed under.
+    metadata_only: bool,
+}
+
+impl EditorImportCache {
+    pub(crate) fn status(&self, source: EditorSource) -> Option<&EditorSourceStatus> {
+        self.status.get(&source)
+    }
+
+    /// Status of every configured source, in `EditorSource::ALL` order.
+    pub(crate) fn statuses(&self) -> Vec<(EditorSource, EditorSourceStatus)> {
+        EditorSource::ALL
+            .into_iter()
+            .filter_map(|source| Some((source, self.status(source)?.clone())))
+            .collect()
+    }
+}
+
+pub(crate) fn refresh_editor_imports(config: &AppConfig, cache: &mut EditorImportCache) {
+    // Files parsed under the other setting would keep or lack labels until
+    // they next change.
+    if cache.metadata_only != config.metadata_only {
+        cache.files.clear();
+        cache.metadata_only = config.metadata_only;
+    }
+    for source in EditorSource::ALL {
+        let Some(dir) = source.dir(config) else {
+            cache.files.remove(&source);
+            cache.status.remove(&source);
+            continue;
+        };
+        let listing = source.files(&dir);
+        let mut status = EditorSourceStatus {
+            last_run: Some(clock::Stamp::now()),
+            ..EditorSourceStatus::default()
+        };
+        let paths = match listing {
+            Ok(paths) => paths,
+            Err(err) => {
+                status.error = Some(err.to_string());
+                cache.status.insert(source, status);
+                continue;
+            }
+        };
+
+        let cached = cache.files.entry(source).or_default();
+        cached.retain(|path, _| paths.contains(path));
+        for path in paths {
+            let Ok(metadata) = fs::metadata(&path) else {
+                cached.remove(&path);
+                status.unreadable_files += 1;
+                continue;
+            };
+            let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+            let len = metadata.len();
+            if cached
+                .get(&path)
+                .is_some_and(|file| file.modified == modified && file.len == len)
+            {
+                continue;
+            }
+            let modified_epoch_secs = modified
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs() as i64);
+            match source.parse(&path, modified_epoch_secs, config.metadata_only) {
+                ParsedEditorFile::Parsed {
+                    records,
+                    skipped_lines,
+                    quota,
+                } => {
+                    cached.insert(
+                        path,
+                        CachedEditorFile {
+                            modified,
+                            len,
+                            records,
+                            skipped_lines,
+                            quota,
+                        },
+                    );
+                }
+                ParsedEditorFile::Unreadable => {
+                    cached.remove(&path);
+                    status.unreadable_files += 1;
+                }
+            }
+        }
+        status.files = cached.len();
+        status.records = cached.values().map(|file| file.records.len()).sum();
+        status.skipped_lines = cached.values().map(|file| file.skipped_lines).sum();
+        cache.status.insert(source, status);
+    }
+}
+
+pub(crate) fn append_editor_entries(
+    data: &mut UsageData,
+    config: &AppConfig,
+    cache: &EditorImportCache,
+) {
+    for (source, files) in &cache.files {
+        let provider = source.provider();
+        for record in files.values().flat_map(|file| &file.records) {
+            let (cost_usd, pricing_match, cost_source) = match record.pricing_provider.as_deref() {
+                Some(pricing_provider) => {
+                    let estimate = estimate_cost(
+                        pricing_provider,
+                        &record.model,
+                        record.input_tokens,
+                        record.output_tokens,
+                        &config.pricing,
+                    );
+                    (
+                        estimate.cost_usd,
+                        estimate.pricing_match,
+                        CostSource::Estimated,
+                    )
+                }
+                None => (0.0, None, CostSource::Payload),
+            };
+            data.entries.push(UsageEntry {
+                timestamp: record.timestamp.clone(),
+                provider: intern(provider),
+                model: intern(&record.model),
+                input_tokens: record.input_tokens,
+                output_tokens: record.output_tokens,
+                cost_usd,
+                pricing_match,
+                cost_source: Some(cost_source),
+                original_cost: None,
+                api_key: None,
+                project_id: None,
+                tag: record.tag.clone(),
+                data_file: None,
+            });
+        }
+    }
+}
+
+/// Plan quotas for the editor sources: JetBrains' AI credits as read from
+/// the most recently written quota file, and Zed's plan prompts this month
+/// against `zed.monthly_prompt_quota`.
+pub(crate) fn plan_quotas(
+    config: &AppConfig,
+    cache: &EditorImportCache,
+    now_epoch_secs: i64,
+) -> Vec<PlanQuota> {
+    let mut quotas = Vec::new();
+    if let Some(files) = cache.files.get(&EditorSource::JetBrains)
+        && let Some(quota) = files
+            .values()
+            .filter(|file| file.quota.is_some())
+            .max_by_key(|file| file.modified)
+            .and_then(|file| file.quota.clone())
+    {
+        quotas.push(quota);
+    }
+    if let Some(limit) = config.zed.as_ref().and_then(|zed| zed.monthly_prompt_quota)
+        && let Some(files) = cache.files.get(&EditorSource::Zed)
+    {
+        let (month_start, year, month) = clock::month_start(now_epoch_secs);
+        let used = files
+            .values()
+            .flat_map(|file| &file.records)
+            .filter(|record| record.pricing_provider.is_none())
+            .filter(|record| {
+                clock::parse_timestamp(&record.timestamp).is_some_and(|at| at >= month_start)
+            })
+            .map(|record| record.prompts)
+            .sum::<u64>();
+        let (next_year, next_month) = if month == 12 {
+            (year + 1, 1)
+        } else {
+            (year, month + 1)
+        };
+        quotas.push(PlanQuota {
+            provider: EditorSource::Zed.provider().to_string(),
+            unit: "prompts".to_string(),
+            used: used as f64,
+            limit: limit as f64,
+            resets_at: Some(clock::format_timestamp(
+                clock::days_from_civil(next_year, next_month, 1) * clock::SECS_PER_DAY,
+            )),
+        });
+    }
+    quotas
+}
+
+pub(crate) fn imported_editor_tokens(cache: &EditorImportCache) -> u64 {
+    cache
+        .files
+        .values()
+        .flat_map(|files| files.values())
+        .flat_map(|file| &file.records)
+        .map(|record| record.input_tokens + record.output_tokens)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ContinueConfig, ZedConfig};
+    use crate::test_support::make_temp_dir;
+
+    #[test]
+    fn imports_continue_logs_and_prices_by_upstream_provider() {
+        let dir = make_temp_dir("editors");
+        fs::create_dir_all(dir.join("0.2.0")).expect("create dir");
+        fs::write(
+            dir.join("0.2.0").join("tokensGenerated.jsonl"),
+            r#"{"model":"gpt-4.1-mini","provider":"openai","promptTokens":1000000,"generatedTokens":0,"timestamp":"2026-03-02T10:00:00Z"}"#,
+        )
+        .expect("write");
+
+        let config = AppConfig {
+            continue_dev: Some(ContinueConfig {
+                enabled: true,
+                storage_dir: Some(dir.to_string_lossy().into_owned()),
+            }),
+            ..AppConfig::default()
+        };
+        let mut cache = EditorImportCache::default();
+        refresh_editor_imports(&config, &mut cache);
+        let status = cache.status(EditorSource::Continue).expect("status");
+        assert_eq!((status.files, status.records), (1, 1));
+
+        let mut data = UsageData {
+            budget_usd: None,
+            entries: Vec::new(),
+        };
+        append_editor_entries(&mut data, &config, &cache);
+        assert_eq!(data.entries.len(), 1);
+        assert_eq!(&*data.entries[0].provider, "continue");
+        assert_eq!(data.entries[0].tag.as_deref(), Some("openai"));
+        assert!((data.entries[0].cost_usd - 0.40).abs() < 1e-9);
+        assert_eq!(imported_editor_tokens(&cache), 1_000_000);
+
+        refresh_editor_imports(&AppConfig::default(), &mut cache);
+        assert!(cache.status(EditorSource::Continue).is_none());
+        assert_eq!(imported_editor_tokens(&cache), 0);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn counts_plan_prompts_this_month_against_the_zed_quota() {
+        let dir = make_temp_dir("zed-quota");
+        let thread = |updated_at: &str, provider: &str, prompts: usize| {
+            let messages = vec![r#"{"role":"user"}"#; prompts].join(",");
+            format!(
+                r#"{{"updated_at":"{updated_at}","model":{{"provider":"{provider}","model":"claude-3.7-sonnet"}},"messages":[{messages}],"cumulative_token_usage":{{"input_tokens":1000,"output_tokens":100}}}}"#
+            )
+        };
+        fs::write(
+            dir.join("a.json"),
+            thread("2026-03-02T10:00:00Z", "zed.dev", 3),
+        )
+        .expect("write");
+        fs::write(
+            dir.join("b.json"),
+            thread("2026-03-20T10:00:00Z", "zed.dev", 2),
+        )
+        .expect("write");
+        fs::write(
+            dir.join("c.json"),
+            thread("2026-02-27T10:00:00Z", "zed.dev", 9),
+        )
+        .expect("write");
+        fs::write(
+            dir.join("d.json"),
+            thread("2026-03-05T10:00:00Z", "anthropic", 4),
+        )
+        .expect("write");
+
+        let mut config = AppConfig {
+            zed: Some(ZedConfig {
+                enabled: true,
+                threads_dir: Some(dir.to_string_lossy().into_owned()),
+                monthly_prompt_quota: Some(500),
+            }),
+            ..AppConfig::default()
+        };
+        let mut cache = EditorImportCache::default();
+        refresh_editor_imports(&config, &mut cache);
+        let now = clock::parse_timestamp("2026-03-25T00:00:00Z").expect("now");
+        let quotas = plan_quotas(&config, &cache, now);
+        assert_eq!(
+            quotas,
+            vec![PlanQuota {
+                provider: "zed".to_string(),
+                unit: "prompts".to_string(),
+                used: 5.0,
+                limit: 500.0,
+                resets_at: Some("2026-04-01T00:00:00Z".to_string()),
+            }]
+        );
+
+        // Plan-covered threads cost nothing; bring-your-own-key ones are priced.
+        let mut data = UsageData {
+            budget_usd: None,
+            entries: Vec::new(),
+        };
+        append_editor_entries(&mut data, &config, &cache);
+        assert_eq!(data.entries.len(), 4);
+        let priced = data
+            .entries
+            .iter()
+            .filter(|entry| entry.cost_usd > 0.0)
+            .count();
+        assert_eq!(priced, 1);
+
+        config.zed.as_mut().expect("zed").monthly_prompt_quota = None;
+        assert!(plan_quotas(&config, &cache, now).is_empty());
+        let _ = fs::remove_dir_all(dir);
+    }
+}
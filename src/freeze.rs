@@ -0,0 +1,158 @@
+use std::path::Path;
+
+use color_eyre::eyre::{Result, bail};
+use serde_json::Value;
+
+use crate::budget_period::period_start;
+use crate::clock;
+use crate::models::{AppConfig, UsageData, UsageEntry, edit_config_json};
+
+pub(crate) fn validate_freeze(config: &AppConfig) -> Result<()> {
+    if let Some(since) = config.spending_frozen_since.as_deref()
+        && clock::parse_timestamp(since).is_none()
+    {
+        bail!("spending_frozen_since: {since} is not an RFC 3339 timestamp");
+    }
+    Ok(())
+}
+
+/// When spending was frozen, if it is.
+pub(crate) fn frozen_since(config: &AppConfig) -> Option<i64> {
+    config
+        .spending_frozen_since
+        .as_deref()
+        .and_then(clock::parse_timestamp)
+}
+
+/// Freezes spending from `since` on, or lifts the freeze for `None`, in
+/// the config file; the config reload that follows applies it.
+pub(crate) fn set_spending_frozen(path: &Path, since: Option<&str>) -> Result<()> {
+    edit_config_json(path, |root| {
+        match since {
+            Some(since) => {
+                root.insert(
+                    "spending_frozen_since".to_string(),
+                    Value::String(since.to_string()),
+                );
+            }
+            None => {
+                root.shift_remove("spending_frozen_since");
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Entries recorded since spending was frozen, oldest first; none while
+/// it is not.
+pub(crate) fn spend_while_frozen<'a>(
+    data: &'a UsageData,
+    config: &AppConfig,
+) -> Vec<&'a UsageEntry> {
+    let Some(since) = frozen_since(config) else {
+        return Vec::new();
+    };
+    let mut entries = data
+        .entries
+        .iter()
+        .filter_map(|entry| {
+            clock::parse_timestamp(&entry.timestamp)
+                .filter(|at| *at >= since)
+                .map(|at| (at, entry))
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|(at, _)| *at);
+    entries.into_iter().map(|(_, entry)| entry).collect()
+}
+
+/// The governor: what can be spent per day for the rest of the UTC month
+/// and stay within budget, today included. Nothing while spending is
+/// frozen; `None` without a budget.
+pub(crate) fn daily_allowance(
+    config: &AppConfig,
+    data: &UsageData,
+    now_epoch_secs: i64,
+) -> Option<f64> {
+    if frozen_since(config).is_some() {
+        return Some(0.0);
+    }
+    let budget = data.budget_usd?;
+    // Against the same spend as the budget alerts.
+    let since = period_start(config, now_epoch_secs).unwrap_or(i64::MIN);
+    let spent = data
+        .entries
+        .iter()
+        .filter(|entry| {
+            clock::parse_timestamp(&entry.timestamp)
+                .is_some_and(|at| (since..=now_epoch_secs).contains(&at))
+        })
+        .map(|entry| entry.cost_usd)
+        .sum::<f64>();
+    let (month_start, year, month) = clock::month_start(now_epoch_secs);
+    let days_gone = (now_epoch_secs - month_start) / clock::SECS_PER_DAY;
+    let days_left = i64::from(clock::days_in_month(year, month)) - days_gone;
+    Some((budget - spent).max(0.0) / days_left.max(1) as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::models::load_or_bootstrap_config;
+    use crate::test_support::make_temp_dir;
+
+    #[test]
+    fn freezing_zeroes_the_allowance_and_singles_out_later_spend() {
+        let dir = make_temp_dir("freeze");
+        let path = dir.join("config.json");
+        fs::write(&path, r#"{"currency": "USD", "api_keys": {}}"#).expect("write config");
+        let data = serde_json::from_str::<UsageData>(
+            r#"{"budget_usd": 50, "entries": [
+                {"timestamp": "2026-10-11T09:00:00Z", "provider": "openai", "model": "m",
+                 "input_tokens": 1, "output_tokens": 1, "cost_usd": 1.5},
+                {"timestamp": "2026-10-02T09:00:00Z", "provider": "openai", "model": "m",
+                 "input_tokens": 1, "output_tokens": 1, "cost_usd": 10.0},
+                {"timestamp": "2026-10-10T12:00:00Z", "provider": "anthropic", "model": "m",
+                 "input_tokens": 1, "output_tokens": 1, "cost_usd": 0.5}
+            ]}"#,
+        )
+        .expect("data");
+        let now = clock::parse_timestamp("2026-10-22T06:00:00Z").expect("timestamp");
+
+        let config = load_or_bootstrap_config(&path).expect("config");
+        assert!(spend_while_frozen(&data, &config).is_empty());
+        // 38 left over the 22nd to the 31st.
+        let allowance = daily_allowance(&config, &data, now).expect("allowance");
+        assert!((allowance - 3.8).abs() < 1e-9);
+
+        set_spending_frozen(&path, Some("2026-10-10T00:00:00Z")).expect("freeze");
+        let config = load_or_bootstrap_config(&path).expect("config");
+        assert_eq!(config.currency, "USD");
+        assert_eq!(daily_allowance(&config, &data, now), Some(0.0));
+        let frozen = spend_while_frozen(&data, &config);
+        assert_eq!(
+            frozen
+                .iter()
+                .map(|entry| entry.cost_usd)
+                .collect::<Vec<_>>(),
+            vec![0.5, 1.5]
+        );
+
+        set_spending_frozen(&path, None).expect("unfreeze");
+        assert_eq!(
+            load_or_bootstrap_config(&path)
+                .expect("config")
+                .spending_frozen_since,
+            None
+        );
+        // The rest of the file keeps its keys and their order.
+        assert_eq!(
+            fs::read_to_string(&path).expect("read config"),
+            "{\n  \"currency\": \"USD\",\n  \"api_keys\": {}\n}"
+        );
+        fs::write(&path, r#"{"spending_frozen_since": "soon"}"#).expect("write config");
+        assert!(load_or_bootstrap_config(&path).is_err());
+        let _ = fs::remove_dir_all(dir);
+    }
+}
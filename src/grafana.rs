@@ -0,0 +1,175 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::clock;
+use crate::models::UsageData;
+
+/// Target that sums every provider.
+const TOTAL_TARGET: &str = "total";
+/// Upper bound on points per series when the request does not set one.
+const DEFAULT_MAX_POINTS: u64 = 1000;
+
+/// Body of a simple-json datasource `/search` request.
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct SearchRequest {
+    #[serde(default)]
+    target: String,
+}
+
+/// Body of a simple-json datasource `/query` request. Fields Grafana sends
+/// that PromptPetrol has no use for (`interval`, `adhocFilters`, ...) are
+/// ignored.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct QueryRequest {
+    range: QueryRange,
+    #[serde(default)]
+    interval_ms: Option<u64>,
+    #[serde(default)]
+    max_data_points: Option<u64>,
+    #[serde(default)]
+    targets: Vec<QueryTarget>,
+}
+
+#[derive(Debug, Deserialize)]
+struct QueryRange {
+    from: String,
+    to: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct QueryTarget {
+    #[serde(default)]
+    target: String,
+}
+
+/// One series in a `/query` response: `[value, unix_millis]` pairs.
+#[derive(Debug, Serialize)]
+pub(crate) struct TimeSeries {
+    target: String,
+    datapoints: Vec<(f64, i64)>,
+}
+
+/// Series names for the query editor: every provider plus `total`,
+/// filtered by what the user has typed so far.
+pub(crate) fn search(data: &UsageData, request: &SearchRequest) -> Vec<String> {
+    let needle = request.target.to_ascii_lowercase();
+    let providers = data
+        .entries
+        .iter()
+        .map(|entry| &*entry.provider)
+        .collect::<BTreeSet<_>>();
+    std::iter::once(TOTAL_TARGET)
+        .chain(providers)
+        .filter(|name| name.to_ascii_lowercase().contains(&needle))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Spend per time bucket for each requested target over the request's
+/// range. Buckets are `intervalMs` wide, widened so no series exceeds
+/// `maxDataPoints`; empty buckets report 0 so lines do not interpolate
+/// across idle periods.
+pub(crate) fn query(data: &UsageData, request: &QueryRequest) -> Vec<TimeSeries> {
+    let (Some(from), Some(to)) = (
+        clock::parse_timestamp(&request.range.from),
+        clock::parse_timestamp(&request.range.to),
+    ) else {
+        return Vec::new();
+    };
+    let (from_ms, to_ms) = (from * 1000, to * 1000);
+    if to_ms <= from_ms {
+        return Vec::new();
+    }
+    let span_ms = (to_ms - from_ms) as u64;
+    let max_points = request
+        .max_data_points
+        .filter(|points| *points > 0)
+        .unwrap_or(DEFAULT_MAX_POINTS);
+    let step_ms = request
+        .interval_ms
+        .unwrap_or(60_000)
+        .max(1000)
+        .max(span_ms.div_ceil(max_points));
+    let buckets = span_ms.div_ceil(step_ms) as usize;
+
+    request
+        .targets
+        .iter()
+        .filter(|target| !target.target.is_empty())
+        .map(|target| {
+            let mut values = vec![0.0; buckets];
+            for entry in &data.entries {
+                if target.target != TOTAL_TARGET && *entry.provider != *target.target {
+                    continue;
+                }
+                let Some(at) = clock::parse_timestamp(&entry.timestamp) else {
+                    continue;
+                };
+                let at_ms = at * 1000;
+                if (from_ms..to_ms).contains(&at_ms) {
+                    values[((at_ms - from_ms) as u64 / step_ms) as usize] += entry.cost_usd;
+                }
+            }
+            TimeSeries {
+                target: target.target.clone(),
+                datapoints: values
+                    .into_iter()
+                    .enumerate()
+                    .map(|(bucket, value)| (value, from_ms + (bucket as u64 * step_ms) as i64))
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_lists_providers_and_total() {
+        let data = UsageData::default();
+        let all = search(&data, &SearchRequest::default());
+        assert_eq!(all, ["total", "anthropic", "gemini", "openai"]);
+        let filtered = search(
+            &data,
+            &SearchRequest {
+                target: "AN".to_string(),
+            },
+        );
+        assert_eq!(filtered, ["anthropic"]);
+    }
+
+    #[test]
+    fn query_buckets_spend_across_the_range() {
+        let data = UsageData::default();
+        let request = serde_json::from_str::<QueryRequest>(
+            r#"{
+                "range": {"from": "2026-02-09T00:00:00.000Z", "to": "2026-02-11T00:00:00.000Z"},
+                "intervalMs": 3600000,
+                "maxDataPoints": 24,
+                "targets": [{"target": "total", "refId": "A"}, {"target": "anthropic", "refId": "B"}]
+            }"#,
+        )
+        .expect("request");
+        let series = query(&data, &request);
+
+        assert_eq!(series.len(), 2);
+        // 24 points over two days means two-hour buckets.
+        let total = &series[0].datapoints;
+        assert_eq!(total.len(), 24);
+        assert_eq!(total[1].1 - total[0].1, 7_200_000);
+        let sum = total.iter().map(|(value, _)| value).sum::<f64>();
+        assert!((sum - (0.084 + 0.361 + 0.056)).abs() < 1e-9);
+
+        // Anthropic's 13:30 entry lands in the 12:00-14:00 bucket.
+        let anthropic = &series[1].datapoints;
+        assert_eq!(anthropic[6].0, 0.361);
+        assert_eq!(
+            anthropic.iter().filter(|(value, _)| *value > 0.0).count(),
+            1
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
+
+use color_eyre::eyre::{Result, bail, eyre};
+use ureq::Proxy;
+use ureq::tls::{PemItem, RootCerts, TlsConfig, parse_pem};
+
+use crate::codex_import::{expand_home, home_dir};
+use crate::models::NetworkConfig;
+
+/// An agent for every outbound request, honouring the configured proxy and
+/// CA bundle. Without a configured proxy, `HTTPS_PROXY`, `ALL_PROXY` and
+/// `NO_PROXY` from the environment apply as usual.
+pub(crate) fn agent(network: &NetworkConfig, timeout: Duration) -> Result<ureq::Agent> {
+    let mut builder = ureq::Agent::config_builder().timeout_global(Some(timeout));
+    if let Some(proxy) = network.proxy.as_deref() {
+        let proxy = Proxy::new(proxy).map_err(|err| eyre!("proxy {proxy}: {err}"))?;
+        builder = builder.proxy(Some(proxy));
+    }
+    if let Some(bundle) = network.ca_bundle.as_deref() {
+        builder = builder.tls_config(
+            TlsConfig::builder()
+                .root_certs(RootCerts::Specific(Arc::new(ca_bundle(bundle)?)))
+                .build(),
+        );
+    }
+    Ok(builder.build().new_agent())
+}
+
+fn ca_bundle(path: &str) -> Result<Vec<ureq::tls::Certificate<'static>>> {
+    let path = expand_home(path, &home_dir());
+    let pem = fs::read(&path).map_err(|err| eyre!("ca_bundle {}: {err}", path.display()))?;
+    let mut certificates = Vec::new();
+    for item in parse_pem(&pem) {
+        if let PemItem::Certificate(certificate) =
+            item.map_err(|err| eyre!("ca_bundle {}: {err}", path.display()))?
+        {
+            certificates.push(certificate);
+        }
+    }
+    if certificates.is_empty() {
+        bail!("ca_bundle {}: no PEM certificates found", path.display());
+    }
+    Ok(certificates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::make_temp_dir;
+
+    const TEST_CA: &str = "-----BEGIN CERTIFICATE-----
+MIIBkjCCATmgAwIBAgIUQMP2rn4VyQdU7qxXeOhis6tESQIwCgYIKoZIzj0EAwIw
+HzEdMBsGA1UEAwwUUHJvbXB0UGV0cm9sIFRlc3QgQ0EwHhcNMjYxMDE2MTYzNTEy
+WhcNMzYxMDEzMTYzNTEyWjAfMR0wGwYDVQQDDBRQcm9tcHRQZXRyb2wgVGVzdCBD
+QTBZMBMGByqGSM49AgEGCCqGSM49AwEHA0IABCAO8EFMoGk2TscbeW9s2PJOu7K4
+dpOO12WTAaMEQcizUdpv2TuKDkGXiqcsq0pPmdBeppkd+kj5Qb/YRR772OmjUzBR
+MB0GA1UdDgQWBBQNHeBQWzD4SDhqmHCuRZn8UrbprzAfBgNVHSMEGDAWgBQNHeBQ
+WzD4SDhqmHCuRZn8UrbprzAPBgNVHRMBAf8EBTADAQH/MAoGCCqGSM49BAMCA0cA
+MEQCIHnpg0MVG9KUx5XtMMPTcvVi1jA2nuUKM4fh/9Mu9l+TAiA4I+5raLiHutJG
+5ozVybJJ1RWcKeCyj8QYMTLPq7XCHA==
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn agent_uses_the_configured_proxy_and_ca_bundle() {
+        let bundle = make_temp_dir("ca").join("ca.pem");
+        fs::write(&bundle, TEST_CA).expect("write");
+        let network = NetworkConfig {
+            proxy: Some("http://proxy.corp.example:3128".to_string()),
+            ca_bundle: Some(bundle.to_string_lossy().into_owned()),
+        };
+        let agent = agent(&network, Duration::from_secs(1)).expect("agent");
+        let proxy = agent.config().proxy().expect("proxy");
+        assert_eq!((proxy.host(), proxy.port()), ("proxy.corp.example", 3128));
+        let RootCerts::Specific(roots) = agent.config().tls_config().root_certs() else {
+            panic!("expected the bundle's roots");
+        };
+        assert_eq!(roots.len(), 1);
+
+        fs::write(&bundle, "not a certificate").expect("write");
+        let err = super::agent(&network, Duration::from_secs(1)).expect_err("empty bundle");
+        assert!(err.to_string().contains("no PEM certificates"));
+        let _ = fs::remove_dir_all(bundle.parent().expect("dir"));
+    }
+}
@@ -0,0 +1,387 @@
+use std::fmt::Write as _;
+use std::path::Path;
+
+use color_eyre::eyre::{Result, bail};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use crate::clock;
+use crate::codex_import::{
+    CodexImportCache, append_codex_entries, codex_import_diagnostics, refresh_codex_sessions,
+};
+use crate::editor_import::{
+    EditorImportCache, EditorSource, append_editor_entries, refresh_editor_imports,
+};
+use crate::langfuse_import::{LangfuseCache, append_langfuse_entries, refresh_langfuse};
+use crate::models::{AppConfig, CostSource, UsageData, edit_config_json, unpriced_models};
+use crate::rate_limit::RequestBudget;
+use crate::ui::format_money;
+
+/// Rows shown under "sample entries" in a dry-run report.
+const DRY_RUN_SAMPLE_ROWS: usize = 5;
+
+/// Every importer the diagnostics overlay can switch on and off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Importer {
+    Codex,
+    Langfuse,
+    Continue,
+    Zed,
+    JetBrains,
+}
+
+impl Importer {
+    pub(crate) const ALL: [Importer; 5] = [
+        Importer::Codex,
+        Importer::Langfuse,
+        Importer::Continue,
+        Importer::Zed,
+        Importer::JetBrains,
+    ];
+
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|importer| importer.name().eq_ignore_ascii_case(name))
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Importer::Codex => "codex",
+            Importer::Langfuse => "langfuse",
+            Importer::Continue => "continue",
+            Importer::Zed => "zed",
+            Importer::JetBrains => "jetbrains",
+        }
+    }
+
+    /// Top-level config section holding the importer's `enabled` flag.
+    fn config_key(self) -> &'static str {
+        match self {
+            Importer::Codex => "codex_import",
+            Importer::Langfuse => "langfuse",
+            Importer::Continue => "continue_dev",
+            Importer::Zed => "zed",
+            Importer::JetBrains => "jetbrains",
+        }
+    }
+
+    pub(crate) fn is_enabled(self, config: &AppConfig) -> bool {
+        match self {
+            Importer::Codex => config.codex_import.enabled,
+            Importer::Langfuse => config.langfuse.as_ref().is_some_and(|c| c.enabled),
+            Importer::Continue => config.continue_dev.as_ref().is_some_and(|c| c.enabled),
+            Importer::Zed => config.zed.as_ref().is_some_and(|c| c.enabled),
+            Importer::JetBrains => config.jetbrains.as_ref().is_some_and(|c| c.enabled),
+        }
+    }
+
+    /// Switches the importer in `config`, filling in defaults when turning
+    /// on a section the config does not have yet.
+    fn set_enabled(self, config: &mut AppConfig, enabled: bool) -> Result<()> {
+        match self {
+            Importer::Codex => {
+                config.codex_import.enabled = enabled;
+                Ok(())
+            }
+            Importer::Langfuse => {
+                set_section_enabled(&mut config.langfuse, enabled, |c| &mut c.enabled)
+            }
+            Importer::Continue => {
+                set_section_enabled(&mut config.continue_dev, enabled, |c| &mut c.enabled)
+            }
+            Importer::Zed => set_section_enabled(&mut config.zed, enabled, |c| &mut c.enabled),
+            Importer::JetBrains => {
+                set_section_enabled(&mut config.jetbrains, enabled, |c| &mut c.enabled)
+            }
+        }
+    }
+
+    pub(crate) fn editor_source(self) -> Option<EditorSource> {
+        match self {
+            Importer::Continue => Some(EditorSource::Continue),
+            Importer::Zed => Some(EditorSource::Zed),
+            Importer::JetBrains => Some(EditorSource::JetBrains),
+            Importer::Codex | Importer::Langfuse => None,
+        }
+    }
+}
+
+/// Sets the `enabled` flag of an optional config section. A missing
+/// section is created from its serde defaults when switching on and left
+/// missing when switching off.
+fn set_section_enabled<T: DeserializeOwned>(
+    section: &mut Option<T>,
+    enabled: bool,
+    flag: fn(&mut T) -> &mut bool,
+) -> Result<()> {
+    if section.is_none() && enabled {
+        *section = Some(serde_json::from_value(Value::Object(Map::new()))?);
+    }
+    if let Some(section) = section.as_mut() {
+        *flag(section) = enabled;
+    }
+    Ok(())
+}
+
+/// One row of the importer list: whether it is on, how its last pass went
+/// and how many entries it currently contributes.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ImporterStatus {
+    pub(crate) importer: Importer,
+    pub(crate) enabled: bool,
+    pub(crate) last_run: Option<clock::Stamp>,
+    pub(crate) entries: usize,
+    pub(crate) error: Option<String>,
+}
+
+/// Sets `<section>.enabled` in the config file, creating the section when
+/// an importer that was never configured is switched on.
+pub(crate) fn set_importer_enabled(path: &Path, importer: Importer, enabled: bool) -> Result<()> {
+    edit_config_json(path, |root| {
+        let section = root
+            .entry(importer.config_key())
+            .or_insert_with(|| Value::Object(Map::new()));
+        if section.is_null() {
+            *section = Value::Object(Map::new());
+        }
+        let Some(section) = section.as_object_mut() else {
+            bail!(
+                "{}: {} is not an object",
+                path.display(),
+                importer.config_key()
+            );
+        };
+        section.insert("enabled".to_string(), Value::Bool(enabled));
+        Ok(())
+    })
+}
+
+/// What one importer would contribute, run against fresh caches so
+/// nothing already imported (or persisted) is involved.
+pub(crate) struct DryRun {
+    pub(crate) importer: Importer,
+    pub(crate) data: UsageData,
+    /// Files or lines the importer had to skip.
+    pub(crate) warnings: Vec<String>,
+}
+
+/// Runs `importer` on its own, enabled even if the config has it off, and
+/// keeps the entries it produces in memory. Nothing is written: not the
+/// data file, not the import cache, not the config.
+pub(crate) fn dry_run_import(config: &AppConfig, importer: Importer) -> Result<DryRun> {
+    let mut config = config.clone();
+    for other in Importer::ALL {
+        other.set_enabled(&mut config, other == importer)?;
+    }
+    let mut data = UsageData {
+        budget_usd: None,
+        entries: Vec::new(),
+    };
+    let mut warnings = Vec::new();
+    match importer {
+        Importer::Codex => {
+            let mut cache = CodexImportCache::default();
+            refresh_codex_sessions(&config, &mut cache);
+            let diagnostics = codex_import_diagnostics(&cache);
+            if diagnostics.parse_error_files > 0 {
+                warnings.push(format!(
+                    "{} session files failed to parse",
+                    diagnostics.parse_error_files
+                ));
+            }
+            if diagnostics.unreadable_files > 0 {
+                warnings.push(format!(
+                    "{} session files unreadable",
+                    diagnostics.unreadable_files
+                ));
+            }
+            append_codex_entries(&mut data, &config, &cache);
+        }
+        Importer::Langfuse => {
+            let mut cache = LangfuseCache::default();
+            refresh_langfuse(
+                &config,
+                &mut cache,
+                &RequestBudget::new("langfuse", &config),
+            )?;
+            append_langfuse_entries(&mut data, &config, &cache);
+        }
+        Importer::Continue | Importer::Zed | Importer::JetBrains => {
+            let mut cache = EditorImportCache::default();
+            refresh_editor_imports(&config, &mut cache);
+            if let Some(status) = importer
+                .editor_source()
+                .and_then(|source| cache.status(source))
+            {
+                if let Some(error) = status.error.as_ref() {
+                    bail!("{}: {error}", importer.name());
+                }
+                if status.unreadable_files > 0 {
+                    warnings.push(format!("{} files unreadable", status.unreadable_files));
+                }
+                if status.skipped_lines > 0 {
+                    warnings.push(format!("{} lines skipped", status.skipped_lines));
+                }
+            }
+            append_editor_entries(&mut data, &config, &cache);
+        }
+    }
+    Ok(DryRun {
+        importer,
+        data,
+        warnings,
+    })
+}
+
+/// Plain-text summary of a dry run: counts, totals, the most recent rows
+/// and any models pricing did not cover.
+pub(crate) fn format_dry_run(dry_run: &DryRun, currency: &str) -> String {
+    let entries = &dry_run.data.entries;
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{} dry run: {} entries (nothing written)",
+        dry_run.importer.name(),
+        entries.len()
+    );
+    for warning in &dry_run.warnings {
+        let _ = writeln!(out, "warning: {warning}");
+    }
+    if entries.is_empty() {
+        return out;
+    }
+
+    let input_tokens = entries.iter().map(|entry| entry.input_tokens).sum::<u64>();
+    let output_tokens = entries.iter().map(|entry| entry.output_tokens).sum::<u64>();
+    let cost = entries.iter().map(|entry| entry.cost_usd).sum::<f64>();
+    let from_payload = entries
+        .iter()
+        .filter(|entry| entry.cost_source != Some(CostSource::Estimated))
+        .count();
+    let (first, last) = entries.iter().map(|entry| entry.timestamp.as_str()).fold(
+        (entries[0].timestamp.as_str(), entries[0].timestamp.as_str()),
+        |(first, last), timestamp| (first.min(timestamp), last.max(timestamp)),
+    );
+    let _ = writeln!(out, "span: {first} .. {last}");
+    let _ = writeln!(out, "tokens: {input_tokens} in / {output_tokens} out");
+    let _ = writeln!(
+        out,
+        "cost: {} ({from_payload} entries with a reported cost, {} estimated)",
+        format_money(cost, currency),
+        entries.len() - from_payload
+    );
+
+    let mut recent = entries.iter().collect::<Vec<_>>();
+    recent.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    let _ = writeln!(out, "\nmost recent entries:");
+    for entry in recent.into_iter().take(DRY_RUN_SAMPLE_ROWS) {
+        let _ = writeln!(
+            out,
+            "  {}  {}/{}  {} in / {} out  {}{}",
+            entry.timestamp,
+            entry.provider,
+            entry.model,
+            entry.input_tokens,
+            entry.output_tokens,
+            format_money(entry.cost_usd, currency),
+            entry
+                .tag
+                .as_deref()
+                .map(|tag| format!("  [{tag}]"))
+                .unwrap_or_default()
+        );
+    }
+
+    let unpriced = unpriced_models(&dry_run.data);
+    if !unpriced.is_empty() {
+        let _ = writeln!(out, "\nunpriced models (costed at zero):");
+        for model in unpriced {
+            let _ = writeln!(
+                out,
+                "  {}/{}: {} entries, {} tokens",
+                model.provider, model.model, model.entries, model.total_tokens
+            );
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::test_support::make_temp_dir;
+
+    #[test]
+    fn toggles_only_the_importer_section() {
+        let path = make_temp_dir("importers").join("config.json");
+        fs::write(
+            &path,
+            r#"{"include": ["team.json"], "codex_import": {"enabled": true, "model": "gpt-5"}}"#,
+        )
+        .expect("write");
+
+        set_importer_enabled(&path, Importer::Codex, false).expect("codex");
+        set_importer_enabled(&path, Importer::Zed, true).expect("zed");
+        let saved =
+            serde_json::from_str::<Value>(&fs::read_to_string(&path).expect("read")).expect("json");
+        assert_eq!(saved["codex_import"]["enabled"], false);
+        assert_eq!(saved["codex_import"]["model"], "gpt-5");
+        assert_eq!(saved["zed"]["enabled"], true);
+        assert!(saved.get("pricing").is_none());
+        let keys = saved
+            .as_object()
+            .expect("object")
+            .keys()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        assert_eq!(keys, ["include", "codex_import", "zed"]);
+
+        let config = serde_json::from_value::<AppConfig>(saved).expect("config");
+        assert!(!Importer::Codex.is_enabled(&config));
+        assert!(Importer::Zed.is_enabled(&config));
+        assert!(!Importer::Langfuse.is_enabled(&config));
+        let _ = fs::remove_dir_all(path.parent().expect("dir"));
+    }
+
+    #[test]
+    fn dry_run_reports_one_importer_without_enabling_it_in_config() {
+        let dir = make_temp_dir("dry-run");
+        fs::create_dir_all(dir.join("0.2.0")).expect("create dir");
+        fs::write(
+            dir.join("0.2.0").join("tokensGenerated.jsonl"),
+            concat!(
+                r#"{"model":"gpt-4.1-mini","provider":"openai","promptTokens":1000,"generatedTokens":10,"timestamp":"2026-03-02T10:00:00Z"}"#,
+                "\n",
+                r#"{"model":"mystery-coder","provider":"local","promptTokens":50,"generatedTokens":5,"timestamp":"2026-03-03T10:00:00Z"}"#,
+                "\n",
+                "not json\n",
+            ),
+        )
+        .expect("write");
+        let mut config = AppConfig::default();
+        config.codex_import.sessions_dir = Some(dir.join("missing").to_string_lossy().into_owned());
+        config.continue_dev = serde_json::from_value(serde_json::json!({
+            "enabled": false,
+            "storage_dir": dir.to_string_lossy(),
+        }))
+        .expect("continue");
+
+        let dry_run = dry_run_import(&config, Importer::Continue).expect("dry run");
+        assert_eq!(dry_run.data.entries.len(), 2);
+        assert_eq!(dry_run.warnings, ["1 lines skipped"]);
+        assert!(!Importer::Continue.is_enabled(&config));
+
+        let report = format_dry_run(&dry_run, "USD");
+        assert!(report.starts_with("continue dry run: 2 entries (nothing written)\n"));
+        assert!(report.contains("span: 2026-03-02T10:00:00Z .. 2026-03-03T10:00:00Z"));
+        assert!(report.contains("tokens: 1050 in / 15 out"));
+        assert!(report.contains("  2026-03-03T10:00:00Z  continue/mystery-coder"));
+        assert!(report.contains(
+            "unpriced models (costed at zero):\n  continue/mystery-coder: 1 entries, 55 tokens"
+        ));
+        let _ = fs::remove_dir_all(dir);
+    }
+}
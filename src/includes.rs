@@ -0,0 +1,270 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use color_eyre::eyre::{Result, bail, eyre};
+use ring::digest::{SHA256, digest};
+use serde::Deserialize;
+
+use crate::http;
+use crate::models::{
+    AppConfig, ModelPricing, NetworkConfig, PricingOrigin, ProviderBadge, write_atomically,
+};
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+/// How much of the host goes into a cache file name, which keeps it well
+/// under the 255 bytes filesystems allow.
+const MAX_HOST_CHARS: usize = 64;
+
+/// The parts of a config an `include` may contribute. Anything else in the
+/// included document is ignored, so shared files cannot set API keys.
+#[derive(Debug, Default, Deserialize)]
+struct IncludedConfig {
+    #[serde(default)]
+    pricing: HashMap<String, ModelPricing>,
+    #[serde(default)]
+    provider_groups: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    provider_badges: HashMap<String, ProviderBadge>,
+}
+
+/// Merges every `include` into `config`, in order. Keys already present
+/// win, so the local file can override whatever a shared include says.
+/// Relative paths resolve against the directory of `config_path`; `https://`
+/// URLs are fetched through a disk cache refreshed after
+/// `include_max_age_secs`.
+pub(crate) fn apply_includes(config: &mut AppConfig, config_path: &Path) -> Result<()> {
+    let own = PricingOrigin {
+        source: config_path.display().to_string(),
+        updated_at: modified_at(config_path),
+    };
+    config.pricing_origins = config
+        .pricing
+        .keys()
+        .map(|key| (key.clone(), own.clone()))
+        .collect();
+    let base_dir = config_path.parent().unwrap_or(Path::new("."));
+    let max_age = Duration::from_secs(config.include_max_age_secs);
+    for source in config.include.clone() {
+        let (contents, copy) = if is_url(&source) {
+            let cache_dir = include_cache_dir();
+            let contents = fetch_cached(&source, &config.network, &cache_dir, max_age)?;
+            (contents, cache_dir.join(cache_file_name(&source)))
+        } else {
+            let path = base_dir.join(&source);
+            let contents = fs::read_to_string(&path)
+                .map_err(|err| eyre!("include {}: {err}", path.display()))?;
+            (contents, path)
+        };
+        let included = serde_json::from_str::<IncludedConfig>(&contents)
+            .map_err(|err| eyre!("include {source}: {err}"))?;
+        let origin = PricingOrigin {
+            source,
+            updated_at: modified_at(&copy),
+        };
+        merge(config, included, &origin);
+    }
+    Ok(())
+}
+
+fn modified_at(path: &Path) -> Option<i64> {
+    let modified = fs::metadata(path).and_then(|metadata| metadata.modified());
+    let secs = modified.ok()?.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    i64::try_from(secs).ok()
+}
+
+fn merge(config: &mut AppConfig, included: IncludedConfig, origin: &PricingOrigin) {
+    for (key, pricing) in included.pricing {
+        if !config.pricing.contains_key(&key) {
+            config.pricing_origins.insert(key.clone(), origin.clone());
+            config.pricing.insert(key, pricing);
+        }
+    }
+    for (group, members) in included.provider_groups {
+        config.provider_groups.entry(group).or_insert(members);
+    }
+    for (provider, badge) in included.provider_badges {
+        config.provider_badges.entry(provider).or_insert(badge);
+    }
+}
+
+fn is_url(source: &str) -> bool {
+    source.contains("://")
+}
+
+fn include_cache_dir() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("promptpetrol")
+        .join("includes")
+}
+
+/// Returns the cached copy of `url` while it is younger than `max_age`,
+/// otherwise fetches it again. A failed fetch falls back to a stale copy
+/// so a flaky network does not take the dashboard down.
+fn fetch_cached(
+    url: &str,
+    network: &NetworkConfig,
+    cache_dir: &Path,
+    max_age: Duration,
+) -> Result<String> {
+    if !url.starts_with("https://") {
+        bail!("include {url}: only https:// URLs are supported");
+    }
+    let cache_file = cache_dir.join(cache_file_name(url));
+    let cached_age = fs::metadata(&cache_file)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .map(|modified| {
+            SystemTime::now()
+                .duration_since(modified)
+                .unwrap_or_default()
+        });
+    if cached_age.is_some_and(|age| age < max_age)
+        && let Ok(contents) = fs::read_to_string(&cache_file)
+    {
+        return Ok(contents);
+    }
+
+    match fetch(url, network) {
+        Ok(contents) => {
+            fs::create_dir_all(cache_dir)?;
+            write_atomically(&cache_file, contents.as_bytes())?;
+            Ok(contents)
+        }
+        Err(err) => fs::read_to_string(&cache_file)
+            .map_err(|_| eyre!("include {url}: {err} (and no cached copy)")),
+    }
+}
+
+fn fetch(url: &str, network: &NetworkConfig) -> Result<String> {
+    let agent = http::agent(network, FETCH_TIMEOUT)?;
+    let mut response = agent.get(url).call()?;
+    Ok(response.body_mut().read_to_string()?)
+}
+
+/// The name of the cached copy of `url`: its host, to tell the files
+/// apart, and a SHA-256 of the whole URL, so every URL gets its own short
+/// name and no path or query (tokens included) ends up on disk.
+fn cache_file_name(url: &str) -> String {
+    let authority = url
+        .split_once("://")
+        .map_or(url, |(_, rest)| rest)
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default();
+    let host = authority
+        .rsplit('@')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .take(MAX_HOST_CHARS)
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    let hash = digest(&SHA256, url.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+    format!("{host}-{hash}.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::make_temp_dir;
+
+    #[test]
+    fn local_includes_fill_gaps_without_overriding() {
+        let temp_root = make_temp_dir("includes");
+        fs::write(
+            temp_root.join("team.json"),
+            r#"{
+                "pricing": {
+                    "openai/gpt-4.1-mini": {"input_per_million_usd": 9, "output_per_million_usd": 9},
+                    "mistral/*": {"input_per_million_usd": 1, "output_per_million_usd": 2}
+                },
+                "provider_groups": {"Vendors": ["openai"]},
+                "api_keys": {"openai": "ignored"}
+            }"#,
+        )
+        .expect("write include");
+
+        let mut config = AppConfig {
+            include: vec!["team.json".to_string()],
+            ..AppConfig::default()
+        };
+        let openai_key = config.api_keys["openai"].clone();
+        apply_includes(&mut config, &temp_root.join("config.json")).expect("includes");
+
+        assert_eq!(
+            config.pricing["openai/gpt-4.1-mini"].input_per_million_usd,
+            0.40
+        );
+        assert_eq!(config.pricing["mistral/*"].output_per_million_usd, 2.0);
+        assert_eq!(config.provider_groups["Vendors"], ["openai"]);
+        assert_eq!(config.api_keys["openai"], openai_key);
+        assert_eq!(config.pricing_origins["mistral/*"].source, "team.json");
+        assert!(config.pricing_origins["mistral/*"].updated_at.is_some());
+        assert!(
+            config.pricing_origins["openai/gpt-4.1-mini"]
+                .source
+                .ends_with("config.json")
+        );
+        // The config file itself is only seeded later.
+        assert_eq!(
+            config.pricing_origins["openai/gpt-4.1-mini"].updated_at,
+            None
+        );
+
+        config.include = vec!["missing.json".to_string()];
+        assert!(apply_includes(&mut config, &temp_root.join("config.json")).is_err());
+        let _ = fs::remove_dir_all(temp_root);
+    }
+
+    #[test]
+    fn fresh_cached_copy_is_used_without_fetching() {
+        let cache_dir = make_temp_dir("include-cache");
+        let url = "https://config.example.invalid/promptpetrol/pricing.json";
+        fs::write(cache_dir.join(cache_file_name(url)), r#"{"pricing": {}}"#).expect("seed");
+
+        let contents = fetch_cached(
+            url,
+            &NetworkConfig::default(),
+            &cache_dir,
+            Duration::from_secs(3600),
+        )
+        .expect("cached copy");
+        assert_eq!(contents, r#"{"pricing": {}}"#);
+        let name = cache_file_name(url);
+        assert!(name.starts_with("config.example.invalid-"), "{name}");
+        assert_eq!(
+            name.len(),
+            "config.example.invalid-".len() + 64 + ".json".len()
+        );
+        assert_ne!(
+            cache_file_name("https://example.invalid/a/b.json"),
+            cache_file_name("https://example.invalid/a_b.json")
+        );
+        let with_token = cache_file_name("https://user:pw@example.invalid/p.json?token=hush");
+        assert!(with_token.starts_with("example.invalid-"), "{with_token}");
+        assert!(!with_token.contains("hush") && !with_token.contains("pw"));
+        assert!(
+            fetch_cached(
+                "http://example.invalid/x",
+                &NetworkConfig::default(),
+                &cache_dir,
+                Duration::ZERO,
+            )
+            .is_err()
+        );
+        let _ = fs::remove_dir_all(cache_dir);
+    }
+}
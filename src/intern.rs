@@ -0,0 +1,77 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::{Arc, LazyLock, Mutex};
+
+use serde::Deserializer;
+use serde::de::{self, Visitor};
+
+/// Every string handed out by [`intern`]. It only grows, so it holds
+/// provider and model names alone, which are few next to the entries
+/// repeating them; free text such as tags is never interned.
+static INTERNED: LazyLock<Mutex<HashSet<Arc<str>>>> = LazyLock::new(Mutex::default);
+
+/// A shared copy of `value`, so a provider or model name repeated across
+/// hundreds of thousands of entries is stored once.
+pub(crate) fn intern(value: &str) -> Arc<str> {
+    let mut interned = INTERNED
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(shared) = interned.get(value) {
+        return shared.clone();
+    }
+    let shared = Arc::<str>::from(value);
+    interned.insert(shared.clone());
+    shared
+}
+
+/// `deserialize_with` for interned fields; the string is looked up without
+/// being copied first.
+pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Arc<str>, D::Error> {
+    deserializer.deserialize_str(InternVisitor)
+}
+
+struct InternVisitor;
+
+impl Visitor<'_> for InternVisitor {
+    type Value = Arc<str>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a string")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Arc<str>, E> {
+        Ok(intern(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::UsageData;
+
+    #[test]
+    fn repeated_names_share_one_allocation() {
+        let data: UsageData = serde_json::from_str(
+            r#"{"entries": [
+                {"timestamp": "2026-02-09T08:00:00Z", "provider": "openai", "model": "gpt-4.1",
+                 "input_tokens": 1, "output_tokens": 1, "cost_usd": 0.1, "tag": "search"},
+                {"timestamp": "2026-02-09T09:00:00Z", "provider": "openai", "model": "gpt-4.1",
+                 "input_tokens": 1, "output_tokens": 1, "cost_usd": 0.1, "tag": null}
+            ]}"#,
+        )
+        .expect("data");
+        let [first, second] = &data.entries[..] else {
+            panic!("two entries");
+        };
+        assert!(Arc::ptr_eq(&first.provider, &second.provider));
+        assert!(Arc::ptr_eq(&first.model, &intern("gpt-4.1")));
+        assert_eq!(first.tag.as_deref(), Some("search"));
+        assert_eq!(second.tag, None);
+        assert_eq!(
+            serde_json::to_value(first).expect("entry")["provider"],
+            "openai"
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::clock;
+use crate::codex_import::{expand_home, home_dir};
+use crate::editor_import::{EditorRecord, ParsedEditorFile, PlanQuota};
+use crate::models::JetBrainsConfig;
+
+/// Where each IDE keeps the AI Assistant quota it last fetched.
+const QUOTA_FILE_NAME: &str = "AIAssistantQuotaManager2.xml";
+
+/// `config_dir` from the config, else `JetBrains` under the platform
+/// config directory.
+pub(crate) fn jetbrains_config_dir(config: &JetBrainsConfig) -> PathBuf {
+    let home = home_dir();
+    match config.config_dir.as_deref() {
+        Some(dir) => expand_home(dir, &home),
+        None => dirs::config_dir()
+            .unwrap_or_else(|| home.join(".config"))
+            .join("JetBrains"),
+    }
+}
+
+/// `<product>/options/AIAssistantQuotaManager2.xml` for every installed
+/// IDE version.
+pub(crate) fn jetbrains_quota_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path().join("options").join(QUOTA_FILE_NAME);
+        if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// Reads the quota an IDE cached. JetBrains AI is billed by plan, so the
+/// product gets a single zero-cost record to put `jetbrains` in the
+/// provider list, and the credits go to the plan-quota gauge.
+pub(crate) fn parse_jetbrains_quota(path: &Path, modified_epoch_secs: i64) -> ParsedEditorFile {
+    let Some(info) = fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| quota_info(&contents))
+    else {
+        return ParsedEditorFile::Unreadable;
+    };
+    let (Some(used), Some(limit)) = (number(info.get("current")), number(info.get("maximum")))
+    else {
+        return ParsedEditorFile::Unreadable;
+    };
+    let product = path
+        .ancestors()
+        .nth(2)
+        .and_then(Path::file_name)
+        .map_or_else(
+            || "ai-assistant".to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+    ParsedEditorFile::Parsed {
+        records: vec![EditorRecord {
+            timestamp: clock::format_timestamp(modified_epoch_secs),
+            model: product.clone(),
+            pricing_provider: None,
+            input_tokens: 0,
+            output_tokens: 0,
+            prompts: 0,
+            tag: Some(product),
+        }],
+        skipped_lines: 0,
+        quota: Some(PlanQuota {
+            provider: "jetbrains".to_string(),
+            unit: "credits".to_string(),
+            used,
+            limit,
+            resets_at: info
+                .get("until")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+        }),
+    }
+}
+
+/// The JSON held in `<option name="quotaInfo" value="..."/>`.
+fn quota_info(xml: &str) -> Option<Value> {
+    let start = xml.find(r#"name="quotaInfo""#)?;
+    let rest = &xml[start..];
+    let value_start = rest.find(r#"value=""#)? + r#"value=""#.len();
+    let rest = &rest[value_start..];
+    let escaped = &rest[..rest.find('"')?];
+    serde_json::from_str(&unescape_xml(escaped)).ok()
+}
+
+fn unescape_xml(escaped: &str) -> String {
+    let mut out = String::with_capacity(escaped.len());
+    let mut rest = escaped;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..semi];
+        let decoded = match entity {
+            "quot" => Some('"'),
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(ch) => {
+                out.push(ch);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Quota amounts are sent as strings by current IDEs and as numbers by
+/// older ones.
+fn number(value: Option<&Value>) -> Option<f64> {
+    match value? {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::make_temp_dir;
+
+    #[test]
+    fn reads_cached_quota_per_product() {
+        let root = make_temp_dir("jetbrains");
+        let options = root.join("IntelliJIdea2025.2").join("options");
+        fs::create_dir_all(&options).expect("create dir");
+        fs::create_dir_all(root.join("consentOptions")).expect("create dir");
+        let path = options.join(QUOTA_FILE_NAME);
+        fs::write(
+            &path,
+            r#"<application>
+  <component name="AIAssistantQuotaManager2">
+    <option name="quotaInfo" value="{&#10;  &quot;type&quot;: &quot;Available&quot;,&#10;  &quot;current&quot;: &quot;7478.25&quot;,&#10;  &quot;maximum&quot;: 10000,&#10;  &quot;until&quot;: &quot;2026-04-01T00:00:00Z&quot;&#10;}" />
+  </component>
+</application>"#,
+        )
+        .expect("write");
+
+        assert_eq!(
+            jetbrains_quota_files(&root).expect("files"),
+            vec![path.clone()]
+        );
+        let ParsedEditorFile::Parsed { records, quota, .. } = parse_jetbrains_quota(&path, 0)
+        else {
+            panic!("expected parsed quota");
+        };
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].model, "IntelliJIdea2025.2");
+        assert_eq!(records[0].pricing_provider, None);
+        let quota = quota.expect("quota");
+        assert_eq!((quota.used, quota.limit), (7478.25, 10000.0));
+        assert_eq!(quota.resets_at.as_deref(), Some("2026-04-01T00:00:00Z"));
+        assert!((quota.used_ratio() - 0.747825).abs() < 1e-9);
+
+        fs::write(&path, "<application/>").expect("write");
+        assert!(matches!(
+            parse_jetbrains_quota(&path, 0),
+            ParsedEditorFile::Unreadable
+        ));
+        let _ = fs::remove_dir_all(root);
+    }
+}
@@ -0,0 +1,172 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use color_eyre::eyre::{Result, eyre};
+use serde_json::Value;
+
+use crate::clock;
+use crate::lock;
+use crate::models::write_atomically;
+
+/// Versions of the data file kept for `undo`; older ones are dropped.
+const JOURNAL_LIMIT: usize = 20;
+/// Holds the contents the last undo restored, so the reload that follows
+/// is not journaled as a fresh change.
+const UNDO_MARKER: &str = "last-undo";
+
+/// A version of the data file from just before a change.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct JournalEntry {
+    path: PathBuf,
+    pub(crate) recorded_at: i64,
+    pub(crate) operation: String,
+}
+
+impl JournalEntry {
+    /// Usage entries in the saved version, if it parses far enough to tell.
+    pub(crate) fn entry_count(&self) -> Option<usize> {
+        let contents = fs::read_to_string(&self.path).ok()?;
+        let value = serde_json::from_str::<Value>(&contents).ok()?;
+        value.get("entries")?.as_array().map(Vec::len)
+    }
+}
+
+/// `usage.json` keeps its journal in `usage.json.journal` next to it.
+fn journal_dir(data_file: &Path) -> PathBuf {
+    let mut name = data_file.file_name().unwrap_or_default().to_os_string();
+    name.push(".journal");
+    data_file.with_file_name(name)
+}
+
+/// Saves `previous` — the data file as it was before `operation` — and
+/// drops the oldest versions beyond the journal limit.
+pub(crate) fn record(data_file: &Path, previous: &str, operation: &str) -> Result<()> {
+    let dir = journal_dir(data_file);
+    fs::create_dir_all(&dir)?;
+    let _ = fs::remove_file(dir.join(UNDO_MARKER));
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_nanos());
+    write_atomically(
+        &dir.join(format!("{nanos:020}-{operation}.json")),
+        previous.as_bytes(),
+    )?;
+    let journal = entries(data_file)?;
+    for stale in &journal[..journal.len().saturating_sub(JOURNAL_LIMIT)] {
+        fs::remove_file(&stale.path)?;
+    }
+    Ok(())
+}
+
+/// Journaled versions, oldest first.
+pub(crate) fn entries(data_file: &Path) -> Result<Vec<JournalEntry>> {
+    let dir = journal_dir(data_file);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut journal = Vec::new();
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        let Some((nanos, operation)) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".json"))
+            .and_then(|stem| stem.split_once('-'))
+            .and_then(|(nanos, operation)| Some((nanos.parse::<u128>().ok()?, operation)))
+        else {
+            continue;
+        };
+        journal.push((
+            nanos,
+            JournalEntry {
+                recorded_at: (nanos / 1_000_000_000) as i64,
+                operation: operation.to_string(),
+                path,
+            },
+        ));
+    }
+    journal.sort_by_key(|(nanos, _)| *nanos);
+    Ok(journal.into_iter().map(|(_, entry)| entry).collect())
+}
+
+/// Puts the newest journaled version back in place of the data file and
+/// removes it from the journal. Returns the entry and the restored
+/// contents, or `None` when there is nothing to undo.
+pub(crate) fn undo(data_file: &Path) -> Result<Option<(JournalEntry, String)>> {
+    let Some(entry) = entries(data_file)?.pop() else {
+        return Ok(None);
+    };
+    let contents =
+        fs::read_to_string(&entry.path).map_err(|err| eyre!("{}: {err}", entry.path.display()))?;
+    let lock = lock::exclusive(data_file)?;
+    write_atomically(data_file, contents.as_bytes())?;
+    drop(lock);
+    fs::remove_file(&entry.path)?;
+    write_atomically(
+        &journal_dir(data_file).join(UNDO_MARKER),
+        contents.as_bytes(),
+    )?;
+    Ok(Some((entry, contents)))
+}
+
+/// True when `contents` is what the last undo wrote, i.e. the change is
+/// the undo itself rather than an edit worth journaling.
+pub(crate) fn is_undo_result(data_file: &Path, contents: &str) -> bool {
+    fs::read_to_string(journal_dir(data_file).join(UNDO_MARKER))
+        .is_ok_and(|restored| restored == contents)
+}
+
+pub(crate) fn describe(entry: &JournalEntry) -> String {
+    let entries = entry
+        .entry_count()
+        .map(|count| format!(", {count} entries"))
+        .unwrap_or_default();
+    format!(
+        "{}  before {}{entries}",
+        clock::format_timestamp(entry.recorded_at),
+        entry.operation
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::make_temp_dir;
+
+    #[test]
+    fn undo_restores_the_newest_version_and_trims_old_ones() {
+        let dir = make_temp_dir("journal");
+        let data_file = dir.join("usage.json");
+        fs::write(&data_file, r#"{"entries": [{}, {}, {}]}"#).expect("write");
+
+        assert_eq!(undo(&data_file).expect("undo"), None);
+        for version in 0..JOURNAL_LIMIT + 2 {
+            record(
+                &data_file,
+                &format!(r#"{{"entries": [], "v": {version}}}"#),
+                "edit",
+            )
+            .expect("record");
+        }
+        let journal = entries(&data_file).expect("entries");
+        assert_eq!(journal.len(), JOURNAL_LIMIT);
+        assert_eq!(journal[0].entry_count(), Some(0));
+        assert!(describe(&journal[0]).contains("before edit, 0 entries"));
+
+        let (entry, contents) = undo(&data_file).expect("undo").expect("entry");
+        assert_eq!(entry.operation, "edit");
+        let expected = format!(r#"{{"entries": [], "v": {}}}"#, JOURNAL_LIMIT + 1);
+        assert_eq!(contents, expected);
+        assert_eq!(fs::read_to_string(&data_file).expect("read"), expected);
+        assert!(is_undo_result(&data_file, &expected));
+        assert_eq!(
+            entries(&data_file).expect("entries").len(),
+            JOURNAL_LIMIT - 1
+        );
+
+        record(&data_file, "{}", "edit").expect("record");
+        assert!(!is_undo_result(&data_file, &expected));
+        let _ = fs::remove_dir_all(dir);
+    }
+}
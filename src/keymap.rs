@@ -0,0 +1,534 @@
+use std::fmt;
+
+use color_eyre::eyre::{Result, bail};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::models::AppConfig;
+
+/// Something a key does. `keymap` in the config rebinds these by name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Action {
+    Quit,
+    Reload,
+    PrevProvider,
+    NextProvider,
+    JumpToLetter,
+    MarkProvider,
+    OpenGroup,
+    Back,
+    Sidebar,
+    NextView,
+    LeaderboardWindow,
+    Zoom,
+    Inspect,
+    StatusHistory,
+    Unpriced,
+    Undo,
+    Diagnostics,
+    ContextGrowth,
+    LimitReplay,
+    PeriodPreview,
+    EstimateAccuracy,
+    PricingBreakdown,
+    EditPricing,
+    Freeze,
+    FocusAlerts,
+    AlertFilter,
+    SearchAlerts,
+    Tour,
+    Help,
+    Suspend,
+    PrevImporter,
+    NextImporter,
+    ToggleImporter,
+    RetryQuarantined,
+    RowDown,
+    RowUp,
+    FirstRow,
+    LastRow,
+    Yank,
+    YankAppend,
+}
+
+/// Where a binding applies. Diagnostics keys win while that overlay is
+/// open, and row keys while the status history or a table view is, so
+/// they may reuse dashboard keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum KeyContext {
+    Dashboard,
+    Diagnostics,
+    Rows,
+}
+
+impl Action {
+    /// In the order the help overlay lists them.
+    pub(crate) const ALL: [Action; 40] = [
+        Action::Quit,
+        Action::Reload,
+        Action::PrevProvider,
+        Action::NextProvider,
+        Action::JumpToLetter,
+        Action::MarkProvider,
+        Action::OpenGroup,
+        Action::Back,
+        Action::Sidebar,
+        Action::NextView,
+        Action::LeaderboardWindow,
+        Action::Zoom,
+        Action::Inspect,
+        Action::StatusHistory,
+        Action::Unpriced,
+        Action::Undo,
+        Action::Diagnostics,
+        Action::ContextGrowth,
+        Action::LimitReplay,
+        Action::PeriodPreview,
+        Action::EstimateAccuracy,
+        Action::PricingBreakdown,
+        Action::EditPricing,
+        Action::Freeze,
+        Action::FocusAlerts,
+        Action::AlertFilter,
+        Action::SearchAlerts,
+        Action::Tour,
+        Action::Help,
+        Action::Suspend,
+        Action::PrevImporter,
+        Action::NextImporter,
+        Action::ToggleImporter,
+        Action::RetryQuarantined,
+        Action::RowDown,
+        Action::RowUp,
+        Action::FirstRow,
+        Action::LastRow,
+        Action::Yank,
+        Action::YankAppend,
+    ];
+
+    /// The action's name in `keymap`.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Reload => "reload",
+            Action::PrevProvider => "prev_provider",
+            Action::NextProvider => "next_provider",
+            Action::JumpToLetter => "jump_to_letter",
+            Action::MarkProvider => "mark_provider",
+            Action::OpenGroup => "open_group",
+            Action::Back => "back",
+            Action::Sidebar => "sidebar",
+            Action::NextView => "next_view",
+            Action::LeaderboardWindow => "leaderboard_window",
+            Action::Zoom => "zoom",
+            Action::Inspect => "inspect",
+            Action::StatusHistory => "status_history",
+            Action::Unpriced => "unpriced",
+            Action::Undo => "undo",
+            Action::Diagnostics => "diagnostics",
+            Action::ContextGrowth => "context_growth",
+            Action::LimitReplay => "limit_replay",
+            Action::PeriodPreview => "period_preview",
+            Action::EstimateAccuracy => "estimate_accuracy",
+            Action::PricingBreakdown => "pricing_breakdown",
+            Action::EditPricing => "edit_pricing",
+            Action::Freeze => "freeze",
+            Action::FocusAlerts => "focus_alerts",
+            Action::AlertFilter => "alert_filter",
+            Action::SearchAlerts => "search_alerts",
+            Action::Tour => "tour",
+            Action::Help => "help",
+            Action::Suspend => "suspend",
+            Action::PrevImporter => "prev_importer",
+            Action::NextImporter => "next_importer",
+            Action::ToggleImporter => "toggle_importer",
+            Action::RetryQuarantined => "retry_quarantined",
+            Action::RowDown => "row_down",
+            Action::RowUp => "row_up",
+            Action::FirstRow => "first_row",
+            Action::LastRow => "last_row",
+            Action::Yank => "yank",
+            Action::YankAppend => "yank_append",
+        }
+    }
+
+    pub(crate) fn description(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Reload => "reload usage/config",
+            Action::PrevProvider => "previous provider",
+            Action::NextProvider => "next provider",
+            Action::JumpToLetter => "jump to provider by first letter (then the letter)",
+            Action::MarkProvider => "mark/unmark provider for combined stats",
+            Action::OpenGroup => "open provider group, or list the provider's models",
+            Action::Back => {
+                "close the inspected dial, the zoom, the model list or the provider group"
+            }
+            Action::Sidebar => "toggle provider sidebar",
+            Action::NextView => "switch dials / forecast / API key / project / model view",
+            Action::LeaderboardWindow => "model view window (7 days / 30 days / all time)",
+            Action::Zoom => "zoom a dial to full screen, again for the next",
+            Action::Inspect => "explain a dial's reading, again for the next",
+            Action::StatusHistory => "toggle status message history",
+            Action::Unpriced => "list unpriced provider/models",
+            Action::Undo => "undo the last change to the usage file",
+            Action::Diagnostics => "import diagnostics",
+            Action::ContextGrowth => "context growth per turn of Codex sessions",
+            Action::LimitReplay => "replay the Codex limits over the last day",
+            Action::PeriodPreview => "preview the next budget period",
+            Action::EstimateAccuracy => "estimates vs billed usage, per vendor and month",
+            Action::PricingBreakdown => "how the model row's cost was computed (model view)",
+            Action::EditPricing => "edit the model row's pricing and recost its entries",
+            Action::Freeze => "freeze spending (again to lift the freeze)",
+            Action::FocusAlerts => "scroll the alerts with the row keys (again or Esc to leave)",
+            Action::AlertFilter => "alerts shown: all / ! and up / ‼ only",
+            Action::SearchAlerts => "search the alerts (Enter keeps, Esc clears)",
+            Action::Tour => "take the onboarding tour again",
+            Action::Help => "toggle help",
+            Action::Suspend => "suspend to shell (resume with fg)",
+            Action::PrevImporter => "select the previous importer",
+            Action::NextImporter => "select the next importer",
+            Action::ToggleImporter => "enable/disable the selected importer",
+            Action::RetryQuarantined => "retry quarantined session files",
+            Action::RowDown => "next row (3j: three rows)",
+            Action::RowUp => "previous row",
+            Action::FirstRow => "first row (also gg; 5gg: row 5)",
+            Action::LastRow => "last row (5G: row 5)",
+            Action::Yank => "yank the row into the register and clipboard (3y: three rows)",
+            Action::YankAppend => "add the row to the register and copy it all",
+        }
+    }
+
+    pub(crate) fn context(self) -> KeyContext {
+        match self {
+            Action::PrevImporter
+            | Action::NextImporter
+            | Action::ToggleImporter
+            | Action::RetryQuarantined => KeyContext::Diagnostics,
+            Action::RowDown
+            | Action::RowUp
+            | Action::FirstRow
+            | Action::LastRow
+            | Action::Yank
+            | Action::YankAppend => KeyContext::Rows,
+            _ => KeyContext::Dashboard,
+        }
+    }
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["q"],
+            Action::Reload => &["r"],
+            Action::PrevProvider => &["left", "h", "k"],
+            Action::NextProvider => &["right", "l", "j"],
+            Action::JumpToLetter => &["f"],
+            Action::MarkProvider => &["space"],
+            Action::OpenGroup => &["enter"],
+            Action::Back => &["esc"],
+            Action::Sidebar => &["s"],
+            Action::NextView => &["tab"],
+            Action::LeaderboardWindow => &["w"],
+            Action::Zoom => &["z"],
+            Action::Inspect => &["i"],
+            Action::StatusHistory => &["m"],
+            Action::Unpriced => &["u"],
+            Action::Undo => &["U"],
+            Action::Diagnostics => &["d"],
+            Action::ContextGrowth => &["g"],
+            Action::LimitReplay => &["p"],
+            Action::PeriodPreview => &["n"],
+            Action::EstimateAccuracy => &["c"],
+            Action::PricingBreakdown => &["b"],
+            Action::EditPricing => &["e"],
+            Action::Freeze => &["F"],
+            Action::FocusAlerts => &["a"],
+            Action::AlertFilter => &["A"],
+            Action::SearchAlerts => &["/"],
+            Action::Tour => &["o"],
+            Action::Help => &["?"],
+            Action::Suspend => &["ctrl+z"],
+            Action::PrevImporter => &["up"],
+            Action::NextImporter => &["down"],
+            Action::ToggleImporter => &["space"],
+            Action::RetryQuarantined => &["t"],
+            Action::RowDown => &["down", "j"],
+            Action::RowUp => &["up", "k"],
+            Action::FirstRow => &["home"],
+            Action::LastRow => &["G", "end"],
+            Action::Yank => &["y"],
+            Action::YankAppend => &["Y"],
+        }
+    }
+}
+
+/// A key as written in `keymap`: a character (`q`, `?`, `U`), a named key
+/// (`left`, `enter`, `esc`, `tab`, `space`, `f5`, ...), optionally with
+/// `ctrl+` or `alt+` in front.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct KeySpec {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeySpec {
+    fn parse(text: &str) -> Option<KeySpec> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = text;
+        loop {
+            let lower = rest.to_ascii_lowercase();
+            if let Some(key) = lower.strip_prefix("ctrl+").filter(|key| !key.is_empty()) {
+                modifiers |= KeyModifiers::CONTROL;
+                rest = &rest[rest.len() - key.len()..];
+            } else if let Some(key) = lower.strip_prefix("alt+").filter(|key| !key.is_empty()) {
+                modifiers |= KeyModifiers::ALT;
+                rest = &rest[rest.len() - key.len()..];
+            } else {
+                break;
+            }
+        }
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(ch), None) => KeyCode::Char(ch),
+            _ => match rest.to_ascii_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "enter" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backspace" => KeyCode::Backspace,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => KeyCode::F(
+                    name.strip_prefix('f')?
+                        .parse()
+                        .ok()
+                        .filter(|n| (1..=12).contains(n))?,
+                ),
+            },
+        };
+        Some(KeySpec { code, modifiers })
+    }
+
+    /// Shift is ignored: it is already in the character (`U`, `?`).
+    fn matches(&self, key: &KeyEvent) -> bool {
+        key.code == self.code && key.modifiers.difference(KeyModifiers::SHIFT) == self.modifiers
+    }
+}
+
+impl fmt::Display for KeySpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(ch) => write!(f, "{ch}"),
+            KeyCode::F(n) => write!(f, "F{n}"),
+            KeyCode::PageUp => write!(f, "PageUp"),
+            KeyCode::PageDown => write!(f, "PageDown"),
+            code => write!(f, "{code}"),
+        }
+    }
+}
+
+/// The keys bound to each action: the defaults with `keymap` laid over
+/// them.
+#[derive(Debug, Clone)]
+pub(crate) struct Keymap {
+    bindings: Vec<(Action, Vec<KeySpec>)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap {
+            bindings: Action::ALL
+                .iter()
+                .map(|action| {
+                    let keys = action
+                        .default_keys()
+                        .iter()
+                        .map(|key| KeySpec::parse(key).expect("default key"))
+                        .collect();
+                    (*action, keys)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Keymap {
+    /// Rejects unknown actions, keys that don't parse and keys bound twice
+    /// in one context.
+    pub(crate) fn new(config: &AppConfig) -> Result<Keymap> {
+        let mut keymap = Keymap::default();
+        for (name, keys) in &config.keymap {
+            let Some(action) = Action::ALL.iter().find(|action| action.name() == name) else {
+                bail!(
+                    "keymap: unknown action {name} (expected one of {})",
+                    Action::ALL
+                        .iter()
+                        .map(|action| action.name())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            };
+            let keys = keys
+                .iter()
+                .map(|key| match KeySpec::parse(key) {
+                    Some(spec) => Ok(spec),
+                    None => bail!("keymap: {name}: {key} is not a key"),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            keymap.bindings[*action as usize].1 = keys;
+        }
+        for (index, (action, keys)) in keymap.bindings.iter().enumerate() {
+            for (other, other_keys) in &keymap.bindings[index + 1..] {
+                if action.context() != other.context() {
+                    continue;
+                }
+                if let Some(key) = keys.iter().find(|key| other_keys.contains(key)) {
+                    bail!(
+                        "keymap: {key} is bound to both {} and {}",
+                        action.name(),
+                        other.name()
+                    );
+                }
+            }
+        }
+        Ok(keymap)
+    }
+
+    /// The action `key` triggers, looking at the keys of the `focused`
+    /// contexts first, in order, then the dashboard's.
+    pub(crate) fn action(&self, key: &KeyEvent, focused: &[KeyContext]) -> Option<Action> {
+        let find = |context: KeyContext| {
+            self.bindings
+                .iter()
+                .find(|(action, keys)| {
+                    action.context() == context && keys.iter().any(|spec| spec.matches(key))
+                })
+                .map(|(action, _)| *action)
+        };
+        focused
+            .iter()
+            .find_map(|context| find(*context))
+            .or_else(|| find(KeyContext::Dashboard))
+    }
+
+    /// Actions with the keys bound to them, e.g. `Left/h/k`, in help
+    /// order. Unbound actions are left out.
+    pub(crate) fn help_entries(&self, context: KeyContext) -> Vec<(String, &'static str)> {
+        self.bindings
+            .iter()
+            .filter(|(action, keys)| action.context() == context && !keys.is_empty())
+            .map(|(action, keys)| {
+                let keys = keys
+                    .iter()
+                    .map(KeySpec::to_string)
+                    .collect::<Vec<_>>()
+                    .join("/");
+                (keys, action.description())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remaps_apply_over_the_defaults_and_conflicts_are_rejected() {
+        let press = |code, modifiers| KeyEvent::new(code, modifiers);
+        let keymap = Keymap::new(&AppConfig::default()).expect("defaults");
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('U'), KeyModifiers::SHIFT), &[]),
+            Some(Action::Undo)
+        );
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('z'), KeyModifiers::CONTROL), &[]),
+            Some(Action::Suspend)
+        );
+        let space = press(KeyCode::Char(' '), KeyModifiers::NONE);
+        assert_eq!(keymap.action(&space, &[]), Some(Action::MarkProvider));
+        assert_eq!(
+            keymap.action(&space, &[KeyContext::Diagnostics]),
+            Some(Action::ToggleImporter)
+        );
+        let j = press(KeyCode::Char('j'), KeyModifiers::NONE);
+        assert_eq!(keymap.action(&j, &[]), Some(Action::NextProvider));
+        assert_eq!(
+            keymap.action(&j, &[KeyContext::Diagnostics, KeyContext::Rows]),
+            Some(Action::RowDown)
+        );
+        let down = press(KeyCode::Down, KeyModifiers::NONE);
+        assert_eq!(
+            keymap.action(&down, &[KeyContext::Diagnostics, KeyContext::Rows]),
+            Some(Action::NextImporter)
+        );
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('t'), KeyModifiers::NONE), &[]),
+            None
+        );
+
+        let mut config = AppConfig::default();
+        config
+            .keymap
+            .insert("zoom".to_string(), vec!["Z".to_string(), "F5".to_string()]);
+        config.keymap.insert("undo".to_string(), Vec::new());
+        let keymap = Keymap::new(&config).expect("remapped");
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('Z'), KeyModifiers::SHIFT), &[]),
+            Some(Action::Zoom)
+        );
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('z'), KeyModifiers::NONE), &[]),
+            None
+        );
+        let help = keymap.help_entries(KeyContext::Dashboard);
+        assert!(help.contains(&(
+            "Z/F5".to_string(),
+            "zoom a dial to full screen, again for the next"
+        )));
+        assert!(help.contains(&("Left/h/k".to_string(), "previous provider")));
+        assert!(
+            !help
+                .iter()
+                .any(|(_, description)| description.contains("undo"))
+        );
+        assert_eq!(
+            keymap.help_entries(KeyContext::Diagnostics)[3],
+            ("t".to_string(), "retry quarantined session files")
+        );
+
+        config
+            .keymap
+            .insert("quit".to_string(), vec!["F5".to_string()]);
+        assert_eq!(
+            Keymap::new(&config).expect_err("conflict").to_string(),
+            "keymap: F5 is bound to both quit and zoom"
+        );
+        config
+            .keymap
+            .insert("quit".to_string(), vec!["ctrl+".to_string()]);
+        assert_eq!(
+            Keymap::new(&config).expect_err("bad key").to_string(),
+            "keymap: quit: ctrl+ is not a key"
+        );
+        config.keymap.clear();
+        config.keymap.insert("fly".to_string(), Vec::new());
+        assert!(
+            Keymap::new(&config)
+                .expect_err("unknown action")
+                .to_string()
+                .starts_with("keymap: unknown action fly")
+        );
+    }
+}
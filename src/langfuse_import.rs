@@ -0,0 +1,415 @@
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use color_eyre::eyre::{Result, eyre};
+use serde::Deserialize;
+
+use crate::clock::{self, SECS_PER_DAY};
+use crate::http;
+use crate::intern::intern;
+use crate::models::{
+    AppConfig, CostSource, LangfuseConfig, NetworkConfig, UsageData, UsageEntry, estimate_cost,
+};
+use crate::rate_limit::RequestBudget;
+use crate::retry::{POLLER_RETRY, retry};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const PAGE_LIMIT: u32 = 100;
+/// Stop paging after this many pages in one pass; the rest is picked up by
+/// the next one.
+const MAX_PAGES_PER_PASS: u32 = 50;
+/// Generations can finish well after they start, so each pass re-reads
+/// this much before the previous one's start.
+const RESYNC_OVERLAP_SECS: i64 = 3600;
+
+/// Generations pulled from Langfuse so far, keyed by observation id so
+/// overlapping passes replace rather than duplicate them.
+#[derive(Debug, Default)]
+pub(crate) struct LangfuseCache {
+    generations: BTreeMap<String, Generation>,
+    trace_names: HashMap<String, String>,
+    /// Start of the last successful pass, in Unix seconds.
+    synced_from: Option<i64>,
+    pub(crate) last_run: Option<clock::Stamp>,
+    pub(crate) last_error: Option<String>,
+}
+
+impl LangfuseCache {
+    pub(crate) fn generation_count(&self) -> usize {
+        self.generations.len()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Generation {
+    timestamp: String,
+    model: String,
+    trace_id: Option<String>,
+    input_tokens: u64,
+    output_tokens: u64,
+    cost_usd: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Page<T> {
+    #[serde(default = "Vec::new")]
+    data: Vec<T>,
+    meta: PageMeta,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageMeta {
+    page: u32,
+    total_pages: u32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ObservationRecord {
+    id: String,
+    #[serde(default)]
+    trace_id: Option<String>,
+    start_time: String,
+    #[serde(default)]
+    model: Option<String>,
+    /// Current API: token counts by usage type.
+    #[serde(default)]
+    usage_details: HashMap<String, u64>,
+    /// Older servers only send this.
+    #[serde(default)]
+    usage: Option<LegacyUsage>,
+    #[serde(default)]
+    cost_details: HashMap<String, f64>,
+    #[serde(default)]
+    calculated_total_cost: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LegacyUsage {
+    #[serde(default)]
+    input: Option<u64>,
+    #[serde(default)]
+    output: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TraceRecord {
+    id: String,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+impl ObservationRecord {
+    fn into_generation(self) -> (String, Generation) {
+        let legacy = self.usage.as_ref();
+        let input_tokens = self
+            .usage_details
+            .get("input")
+            .copied()
+            .or_else(|| legacy.and_then(|usage| usage.input))
+            .unwrap_or(0);
+        let output_tokens = self
+            .usage_details
+            .get("output")
+            .copied()
+            .or_else(|| legacy.and_then(|usage| usage.output))
+            .unwrap_or(0);
+        let cost_usd = self
+            .cost_details
+            .get("total")
+            .copied()
+            .or(self.calculated_total_cost);
+        (
+            self.id,
+            Generation {
+                timestamp: self.start_time,
+                model: self.model.unwrap_or_else(|| "unknown".to_string()),
+                trace_id: self.trace_id,
+                input_tokens,
+                output_tokens,
+                cost_usd,
+            },
+        )
+    }
+}
+
+/// Pulls generations started since the last pass (or within
+/// `lookback_days` on the first one) and the names of their traces.
+pub(crate) fn refresh_langfuse(
+    config: &AppConfig,
+    cache: &mut LangfuseCache,
+    budget: &RequestBudget,
+) -> Result<()> {
+    let Some(langfuse) = config.langfuse.as_ref().filter(|langfuse| langfuse.enabled) else {
+        return Ok(());
+    };
+    let result = pull_generations(langfuse, config, cache, budget);
+    cache.last_run = Some(clock::Stamp::now());
+    cache.last_error = result.as_ref().err().map(ToString::to_string);
+    result
+}
+
+fn pull_generations(
+    langfuse: &LangfuseConfig,
+    config: &AppConfig,
+    cache: &mut LangfuseCache,
+    budget: &RequestBudget,
+) -> Result<()> {
+    let client = Client::new(langfuse, &config.network, budget)?;
+    let now = clock::now_epoch_secs();
+    let from = match cache.synced_from {
+        Some(synced_from) => synced_from - RESYNC_OVERLAP_SECS,
+        None => now - i64::from(langfuse.lookback_days) * SECS_PER_DAY,
+    };
+    let from = clock::format_timestamp(from);
+
+    if config.metadata_only {
+        cache.trace_names.clear();
+    } else {
+        for trace in client
+            .fetch_all::<TraceRecord>("traces", &[("fromTimestamp", &from), ("fields", "core")])?
+        {
+            if let Some(name) = trace.name {
+                cache.trace_names.insert(trace.id, name);
+            }
+        }
+    }
+    for observation in client.fetch_all::<ObservationRecord>(
+        "observations",
+        &[("type", "GENERATION"), ("fromStartTime", &from)],
+    )? {
+        let (id, generation) = observation.into_generation();
+        cache.generations.insert(id, generation);
+    }
+    cache.synced_from = Some(now);
+    Ok(())
+}
+
+/// Adds one entry per imported generation, tagged with its trace name
+/// unless `metadata_only` is set.
+/// Langfuse's own cost is used when it computed one, configured pricing
+/// otherwise.
+pub(crate) fn append_langfuse_entries(
+    data: &mut UsageData,
+    config: &AppConfig,
+    cache: &LangfuseCache,
+) {
+    let Some(langfuse) = config.langfuse.as_ref().filter(|langfuse| langfuse.enabled) else {
+        return;
+    };
+    let provider = &langfuse.provider;
+    data.entries
+        .extend(cache.generations.values().map(|generation| {
+            let (cost_usd, pricing_match, cost_source) = match generation.cost_usd {
+                Some(cost_usd) => (cost_usd, None, CostSource::Payload),
+                None => {
+                    let estimate = estimate_cost(
+                        provider,
+                        &generation.model,
+                        generation.input_tokens,
+                        generation.output_tokens,
+                        &config.pricing,
+                    );
+                    (
+                        estimate.cost_usd,
+                        estimate.pricing_match,
+                        CostSource::Estimated,
+                    )
+                }
+            };
+            UsageEntry {
+                timestamp: generation.timestamp.clone(),
+                provider: intern(provider),
+                model: intern(&generation.model),
+                input_tokens: generation.input_tokens,
+                output_tokens: generation.output_tokens,
+                cost_usd,
+                pricing_match,
+                cost_source: Some(cost_source),
+                original_cost: None,
+                api_key: None,
+                project_id: None,
+                tag: generation
+                    .trace_id
+                    .as_ref()
+                    .filter(|_| !config.metadata_only)
+                    .and_then(|trace_id| cache.trace_names.get(trace_id))
+                    .cloned(),
+                data_file: None,
+            }
+        }));
+}
+
+pub(crate) fn imported_generation_tokens(cache: &LangfuseCache) -> u64 {
+    cache
+        .generations
+        .values()
+        .map(|generation| generation.input_tokens + generation.output_tokens)
+        .sum()
+}
+
+struct Client<'a> {
+    agent: ureq::Agent,
+    budget: &'a RequestBudget,
+    base_url: String,
+    authorization: String,
+}
+
+impl<'a> Client<'a> {
+    fn new(
+        config: &LangfuseConfig,
+        network: &NetworkConfig,
+        budget: &'a RequestBudget,
+    ) -> Result<Self> {
+        let key = |configured: &Option<String>, variable: &str| {
+            configured
+                .clone()
+                .or_else(|| std::env::var(variable).ok())
+                .ok_or_else(|| eyre!("langfuse: set {variable} or the key in config.json"))
+        };
+        let public_key = key(&config.public_key, "LANGFUSE_PUBLIC_KEY")?;
+        let secret_key = key(&config.secret_key, "LANGFUSE_SECRET_KEY")?;
+        Ok(Self {
+            agent: http::agent(network, REQUEST_TIMEOUT)?,
+            budget,
+            base_url: config.base_url.trim_end_matches('/').to_string(),
+            authorization: format!(
+                "Basic {}",
+                base64_encode(format!("{public_key}:{secret_key}").as_bytes())
+            ),
+        })
+    }
+
+    fn fetch_all<T: for<'de> Deserialize<'de>>(
+        &self,
+        resource: &str,
+        query: &[(&str, &str)],
+    ) -> Result<Vec<T>> {
+        let url = format!("{}/api/public/{resource}", self.base_url);
+        let mut records = Vec::new();
+        let mut page = 1;
+        loop {
+            let page_number = page.to_string();
+            let limit = PAGE_LIMIT.to_string();
+            let mut response = retry(&POLLER_RETRY, self.budget, || {
+                let mut request = self
+                    .agent
+                    .get(&url)
+                    .header("Authorization", &self.authorization)
+                    .query("page", &page_number)
+                    .query("limit", &limit);
+                for (name, value) in query {
+                    request = request.query(*name, *value);
+                }
+                request.call()
+            })
+            .map_err(|err| eyre!("langfuse {resource}: {err}"))?;
+            // Parsed as it streams in: generations carry their prompts and
+            // completions, which are skipped rather than buffered.
+            let parsed = serde_json::from_reader::<_, Page<T>>(response.body_mut().as_reader())
+                .map_err(|err| eyre!("langfuse {resource}: {err}"))?;
+            records.extend(parsed.data);
+            if parsed.meta.page >= parsed.meta.total_pages || page >= MAX_PAGES_PER_PASS {
+                return Ok(records);
+            }
+            page += 1;
+        }
+    }
+}
+
+pub(crate) fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        for (i, shift) in [18, 12, 6, 0].into_iter().enumerate() {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> shift & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_generations_into_tagged_entries() {
+        let page = serde_json::from_str::<Page<ObservationRecord>>(
+            r#"{
+                "data": [
+                    {
+                        "id": "obs-1", "traceId": "t-1", "type": "GENERATION",
+                        "startTime": "2026-03-02T10:00:00.000Z", "model": "gpt-4.1-mini",
+                        "usageDetails": {"input": 1000, "output": 250, "total": 1250},
+                        "costDetails": {"input": 0.0004, "output": 0.0004, "total": 0.0008},
+                        "input": [{"role": "user", "content": "summarise the plan"}],
+                        "output": {"role": "assistant", "content": "the plan is..."}
+                    },
+                    {
+                        "id": "obs-2", "traceId": "t-2",
+                        "startTime": "2026-03-02T11:00:00.000Z", "model": "claude-3.7-sonnet",
+                        "usage": {"input": 2000, "output": 500, "total": 2500, "unit": "TOKENS"}
+                    }
+                ],
+                "meta": {"page": 1, "limit": 100, "totalItems": 2, "totalPages": 1}
+            }"#,
+        )
+        .expect("page");
+
+        let mut cache = LangfuseCache::default();
+        cache
+            .trace_names
+            .insert("t-1".to_string(), "support-bot".to_string());
+        for observation in page.data {
+            let (id, generation) = observation.into_generation();
+            cache.generations.insert(id, generation);
+        }
+        let mut config = AppConfig {
+            langfuse: serde_json::from_str(r#"{"provider": "anthropic"}"#).expect("config"),
+            ..AppConfig::default()
+        };
+        let mut data = UsageData {
+            budget_usd: None,
+            entries: Vec::new(),
+        };
+        append_langfuse_entries(&mut data, &config, &cache);
+
+        assert_eq!(data.entries.len(), 2);
+        assert_eq!(data.entries[0].tag.as_deref(), Some("support-bot"));
+        assert_eq!(data.entries[0].cost_usd, 0.0008);
+        assert_eq!(data.entries[0].cost_source, Some(CostSource::Payload));
+        // No Langfuse cost: priced from config under the configured provider.
+        assert_eq!(data.entries[1].tag, None);
+        assert_eq!(data.entries[1].input_tokens, 2000);
+        assert!((data.entries[1].cost_usd - 0.0135).abs() < 1e-9);
+        assert_eq!(imported_generation_tokens(&cache), 3750);
+
+        config.metadata_only = true;
+        data.entries.clear();
+        append_langfuse_entries(&mut data, &config, &cache);
+        assert!(data.entries.iter().all(|entry| entry.tag.is_none()));
+
+        config.langfuse.as_mut().expect("langfuse").enabled = false;
+        data.entries.clear();
+        append_langfuse_entries(&mut data, &config, &cache);
+        assert!(data.entries.is_empty());
+    }
+
+    #[test]
+    fn encodes_basic_auth_credentials() {
+        assert_eq!(base64_encode(b"pk-lf:sk-lf"), "cGstbGY6c2stbGY=");
+        assert_eq!(base64_encode(b"ab"), "YWI=");
+        assert_eq!(base64_encode(b"abc"), "YWJj");
+    }
+}
@@ -0,0 +1,72 @@
+use color_eyre::eyre::{Result, bail};
+
+use crate::models::AppConfig;
+use crate::widgets::{WIDGETS, WidgetSpec, find_widget};
+
+/// `dashboard_layout` as rows of widgets, top to bottom, each row's
+/// widgets left to right. Empty when the fixed dials grid is in use.
+pub(crate) fn dashboard_layout(config: &AppConfig) -> Result<Vec<Vec<&'static WidgetSpec>>> {
+    config
+        .dashboard_layout
+        .iter()
+        .enumerate()
+        .map(|(index, row)| {
+            if row.is_empty() {
+                bail!("dashboard_layout: row {} is empty", index + 1);
+            }
+            row.iter()
+                .map(|name| match find_widget(name) {
+                    Some(widget) => Ok(widget),
+                    None => bail!(
+                        "dashboard_layout: unknown widget {name} (expected one of {})",
+                        WIDGETS
+                            .iter()
+                            .map(|widget| widget.name)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_rows_parse_into_widgets() {
+        let mut config = AppConfig::default();
+        assert!(dashboard_layout(&config).expect("default").is_empty());
+
+        config.dashboard_layout = vec![
+            vec!["gauge:fuel".to_string(), "codex:limits".to_string()],
+            vec!["chart:daily".to_string()],
+        ];
+        let names = dashboard_layout(&config)
+            .expect("valid")
+            .iter()
+            .map(|row| row.iter().map(|widget| widget.name).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![vec!["gauge:fuel", "codex:limits"], vec!["chart:daily"]]
+        );
+
+        config.dashboard_layout[1] = vec!["chart:hourly".to_string()];
+        let err = dashboard_layout(&config).expect_err("unknown widget");
+        assert!(
+            err.to_string()
+                .starts_with("dashboard_layout: unknown widget chart:hourly")
+        );
+
+        config.dashboard_layout[1].clear();
+        assert_eq!(
+            dashboard_layout(&config)
+                .expect_err("empty row")
+                .to_string(),
+            "dashboard_layout: row 2 is empty"
+        );
+    }
+}
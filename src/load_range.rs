@@ -0,0 +1,150 @@
+use color_eyre::eyre::{Result, bail};
+
+use crate::clock;
+use crate::models::{AppConfig, UsageData};
+
+/// The days of usage to load: `load_since`/`load_until` from the config,
+/// or `--since`/`--until`, which win over them. Each is a `YYYY-MM-DD`
+/// date or `<N>d` for N days before today, and both days are included.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct LoadRange {
+    pub(crate) since: Option<String>,
+    pub(crate) until: Option<String>,
+}
+
+impl LoadRange {
+    pub(crate) fn from_config(config: &AppConfig) -> LoadRange {
+        LoadRange {
+            since: config.load_since.clone(),
+            until: config.load_until.clone(),
+        }
+    }
+
+    /// `self`'s bounds, with `fallback`'s where `self` has none.
+    pub(crate) fn or(&self, fallback: LoadRange) -> LoadRange {
+        LoadRange {
+            since: self.since.clone().or(fallback.since),
+            until: self.until.clone().or(fallback.until),
+        }
+    }
+
+    /// Unix seconds of the first moment to keep and the first one past
+    /// the range. Relative days count back from `now_epoch_secs`.
+    pub(crate) fn bounds(&self, now_epoch_secs: i64) -> (Option<i64>, Option<i64>) {
+        let day = |value: &Option<String>| {
+            value
+                .as_deref()
+                .and_then(|value| parse_day(value, now_epoch_secs))
+        };
+        (
+            day(&self.since).map(|day| day * clock::SECS_PER_DAY),
+            day(&self.until).map(|day| (day + 1) * clock::SECS_PER_DAY),
+        )
+    }
+
+    /// Rejects values that are not days and a range that ends before it
+    /// starts. `what` names the source in the message.
+    pub(crate) fn validate(&self, what: &str) -> Result<()> {
+        for (name, value) in [("since", &self.since), ("until", &self.until)] {
+            if let Some(value) = value
+                && parse_day(value, 0).is_none()
+            {
+                bail!(
+                    "{what}{name}: {value} is not a day (expected YYYY-MM-DD or a number of days like 90d)"
+                );
+            }
+        }
+        if let (Some(start), Some(end)) = self.bounds(clock::now_epoch_secs())
+            && end <= start
+        {
+            bail!("{what}until is before {what}since");
+        }
+        Ok(())
+    }
+}
+
+/// Day number (days since 1970-01-01) of a `YYYY-MM-DD` date or of `<N>d`
+/// days before the day of `now_epoch_secs`.
+fn parse_day(value: &str, now_epoch_secs: i64) -> Option<i64> {
+    if let Some(days) = value.strip_suffix('d') {
+        let days = days.parse::<u32>().ok()?;
+        return Some(now_epoch_secs.div_euclid(clock::SECS_PER_DAY) - i64::from(days));
+    }
+    if value.len() != 10 {
+        return None;
+    }
+    clock::parse_timestamp(value).map(|epoch| epoch.div_euclid(clock::SECS_PER_DAY))
+}
+
+/// Drops the entries outside `bounds`, as given by [`LoadRange::bounds`].
+/// Entries whose time cannot be read are kept.
+pub(crate) fn retain_in_range(data: &mut UsageData, bounds: (Option<i64>, Option<i64>)) {
+    if bounds == (None, None) {
+        return;
+    }
+    let (start, end) = bounds;
+    data.entries.retain(|entry| {
+        clock::parse_timestamp(&entry.timestamp).is_none_or(|at| {
+            start.is_none_or(|start| at >= start) && end.is_none_or(|end| at < end)
+        })
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges_keep_whole_days_and_count_relative_days_from_today() {
+        let now = clock::parse_timestamp("2026-03-15T18:00:00Z").expect("timestamp");
+        let day = |date: &str| clock::parse_timestamp(date).expect("date");
+        let range = |since: Option<&str>, until: Option<&str>| LoadRange {
+            since: since.map(str::to_string),
+            until: until.map(str::to_string),
+        };
+
+        assert_eq!(
+            range(Some("2026-03-01"), Some("2026-03-10")).bounds(now),
+            (Some(day("2026-03-01")), Some(day("2026-03-11")))
+        );
+        assert_eq!(
+            range(Some("14d"), None).bounds(now),
+            (Some(day("2026-03-01")), None)
+        );
+        assert_eq!(
+            range(None, Some("2026-03-10")).or(range(Some("2026-01-01"), Some("0d"))),
+            range(Some("2026-01-01"), Some("2026-03-10"))
+        );
+
+        let mut data = UsageData::default();
+        let before = data.entries.len();
+        retain_in_range(&mut data, (None, None));
+        assert_eq!(data.entries.len(), before);
+        retain_in_range(
+            &mut data,
+            range(Some("2026-02-10"), Some("2026-02-10")).bounds(now),
+        );
+        assert!(!data.entries.is_empty());
+        assert!(
+            data.entries
+                .iter()
+                .all(|entry| entry.timestamp.starts_with("2026-02-10"))
+        );
+
+        assert!(range(Some("90d"), Some("0d")).validate("").is_ok());
+        assert_eq!(
+            range(Some("March"), None)
+                .validate("load_")
+                .expect_err("not a day")
+                .to_string(),
+            "load_since: March is not a day (expected YYYY-MM-DD or a number of days like 90d)"
+        );
+        assert_eq!(
+            range(Some("2026-03-10"), Some("2026-03-01"))
+                .validate("--")
+                .expect_err("backwards")
+                .to_string(),
+            "--until is before --since"
+        );
+    }
+}
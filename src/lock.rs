@@ -0,0 +1,88 @@
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use color_eyre::eyre::{Result, bail};
+
+/// How long to wait for another process (a daemon, dashboard or CLI
+/// command) to finish with the file before giving up.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+const LOCK_POLL: Duration = Duration::from_millis(25);
+
+/// An advisory lock on `<file>.lock`, released when dropped. The lock lives
+/// in a sidecar because atomic writes replace the file itself.
+#[derive(Debug)]
+pub(crate) struct FileLock {
+    _file: File,
+}
+
+/// Waits for sole access to `path`, for writes.
+pub(crate) fn exclusive(path: &Path) -> Result<FileLock> {
+    acquire(path, true, LOCK_TIMEOUT)
+}
+
+/// Waits until no one is writing `path`; other readers may hold it too.
+pub(crate) fn shared(path: &Path) -> Result<FileLock> {
+    acquire(path, false, LOCK_TIMEOUT)
+}
+
+fn lock_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".lock");
+    path.with_file_name(name)
+}
+
+fn acquire(path: &Path, exclusive: bool, timeout: Duration) -> Result<FileLock> {
+    let lock_path = lock_path(path);
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)?;
+    let deadline = Instant::now() + timeout;
+    loop {
+        let attempt = if exclusive {
+            file.try_lock()
+        } else {
+            file.try_lock_shared()
+        };
+        match attempt {
+            Ok(()) => return Ok(FileLock { _file: file }),
+            Err(TryLockError::WouldBlock) if Instant::now() < deadline => {
+                thread::sleep(LOCK_POLL);
+            }
+            Err(TryLockError::WouldBlock) => bail!(
+                "{} is locked by another PromptPetrol process ({})",
+                path.display(),
+                lock_path.display()
+            ),
+            Err(TryLockError::Error(err)) => return Err(err.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::make_temp_dir;
+
+    #[test]
+    fn writers_wait_for_readers_and_each_other() {
+        let dir = make_temp_dir("lock");
+        let path = dir.join("usage.json");
+        let short = Duration::from_millis(60);
+
+        let reader = acquire(&path, false, short).expect("shared");
+        let second_reader = acquire(&path, false, short).expect("second shared");
+        assert!(acquire(&path, true, short).is_err());
+        drop((reader, second_reader));
+
+        let writer = acquire(&path, true, short).expect("exclusive");
+        let err = acquire(&path, false, short).expect_err("reader must wait");
+        assert!(err.to_string().contains("locked by another"));
+        drop(writer);
+        assert!(acquire(&path, true, short).is_ok());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -1,32 +1,160 @@
+mod alert_routing;
+mod alerts;
 mod app;
+mod big_text;
+mod budget_period;
+mod bundle;
+mod clock;
 mod codex_import;
+mod commit_cost;
+mod context_growth;
+mod continue_import;
+mod daemon;
+mod diagnose;
+mod doctor;
+mod editor_import;
+mod freeze;
+mod grafana;
+mod http;
+mod importers;
+mod includes;
+mod intern;
+mod jetbrains_import;
+mod journal;
+mod keymap;
+mod langfuse_import;
+mod layout;
+mod load_range;
+mod lock;
 mod models;
+mod object_store;
+mod pacing;
+mod pricing_breakdown;
+mod pricing_editor;
+mod query;
+mod rate_limit;
+mod reconcile;
+mod redact;
+mod refresh;
+mod retry;
+mod schema;
+mod summary;
+mod summary_report;
+mod sync_conflicts;
+mod tail;
+mod team_budget;
+#[cfg(test)]
+mod test_support;
+mod theme;
+mod throttle;
 mod ui;
+mod widgets;
+mod wizard;
+mod worker;
+mod zed_import;
 
-use std::path::PathBuf;
+use std::fs;
+use std::io::{self, Write};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
-use std::{fs, io::Write};
 
 use color_eyre::eyre::{Result, bail};
+use ratatui::DefaultTerminal;
 
-use crate::app::{DEFAULT_REFRESH_INTERVAL, bootstrap_app, init_terminal, restore_terminal, run};
-use crate::models::provider_summaries;
+use crate::app::{
+    DEFAULT_KIOSK_INTERVAL, DEFAULT_REFRESH_INTERVAL, DisplayOptions, init_terminal,
+    install_panic_hook, redact_crash_reports, restore_terminal, run, run_setup_wizard,
+};
+use crate::bundle::{BundleSources, write_bundle};
+use crate::commit_cost::{commit_costs, format_commit_costs};
+use crate::daemon::{DEFAULT_LISTEN_ADDR, run_daemon};
+use crate::diagnose::{diagnose, format_diagnostics_json};
+use crate::doctor::{check_api_keys, format_key_checks};
+use crate::importers::{Importer, dry_run_import, format_dry_run};
+use crate::load_range::LoadRange;
+use crate::models::{
+    NetworkConfig, ProviderSummary, UsageData, default_config_file, default_data_file,
+    default_log_file, default_store_file, import_cache_file, load_or_bootstrap_config,
+    provider_summaries,
+};
+use crate::object_store::ObjectTarget;
+use crate::query::Query;
+use crate::schema::{SchemaKind, format_json_schema};
+use crate::summary_report::format_summary_report;
+use crate::tail::run_tail;
+use crate::theme::detect_monochrome;
+use crate::wizard::{SetupWizard, apply_setup_choices, needs_setup};
+use crate::worker::RefreshWorker;
 
 struct CliArgs {
-    data_file: Option<PathBuf>,
+    /// Every `--data-file`: the first is written to, the rest only read.
+    data_files: Vec<PathBuf>,
+    /// `--since`/`--until`, over the config's `load_since`/`load_until`.
+    load_range: LoadRange,
     config_file: Option<PathBuf>,
     refresh_interval: Duration,
     export_json: Option<PathBuf>,
     export_csv: Option<PathBuf>,
+    /// Destinations for `export --to`: local paths, `s3://` or `gs://`.
+    export_to: Vec<String>,
+    /// `--where <query>`: the entries exports are made from.
+    filter: Option<Query>,
+    daemon: bool,
+    viewer: bool,
+    listen: SocketAddr,
+    store_file: Option<PathBuf>,
+    /// `import --dry-run <source>`.
+    dry_run: Option<Importer>,
+    undo: bool,
+    /// `undo --list`: show the journal instead of restoring from it.
+    list: bool,
+    doctor: bool,
+    /// `doctor --bundle <path>`: write an error report bundle instead.
+    bundle: Option<PathBuf>,
+    commit_cost: bool,
+    summary: bool,
+    tail: bool,
+    /// `diagnose --json`.
+    diagnose: bool,
+    /// `schema <config|usage>`.
+    schema: Option<SchemaKind>,
+    display: DisplayOptions,
 }
 
 fn parse_cli_args() -> Result<CliArgs> {
     let mut args = std::env::args().skip(1);
-    let mut data_file = None;
+    let mut data_files = Vec::new();
+    let mut load_range = LoadRange::default();
     let mut config_file = None;
     let mut refresh_interval = DEFAULT_REFRESH_INTERVAL;
     let mut export_json = None;
     let mut export_csv = None;
+    let mut export = false;
+    let mut export_to = Vec::new();
+    let mut filter = None;
+    let mut daemon = false;
+    let mut viewer = false;
+    let mut listen = DEFAULT_LISTEN_ADDR.parse::<SocketAddr>()?;
+    let mut store_file = None;
+    let mut import = false;
+    let mut dry_run = false;
+    let mut import_source = None;
+    let mut undo = false;
+    let mut list = false;
+    let mut doctor = false;
+    let mut bundle = None;
+    let mut commit_cost = false;
+    let mut summary = false;
+    let mut tail = false;
+    let mut diagnose = false;
+    let mut json = false;
+    let mut schema = false;
+    let mut schema_kind = None;
+    let mut kiosk = false;
+    let mut kiosk_interval = None;
+    let mut no_canvas = false;
+    let mut monochrome = false;
 
     while let Some(arg) = args.next() {
         match arg.as_str() {
@@ -34,7 +162,19 @@ fn parse_cli_args() -> Result<CliArgs> {
                 let Some(value) = args.next() else {
                     bail!("missing value for --data-file");
                 };
-                data_file = Some(PathBuf::from(value));
+                data_files.push(PathBuf::from(value));
+            }
+            "--since" => {
+                let Some(value) = args.next() else {
+                    bail!("missing value for --since");
+                };
+                load_range.since = Some(value);
+            }
+            "--until" => {
+                let Some(value) = args.next() else {
+                    bail!("missing value for --until");
+                };
+                load_range.until = Some(value);
             }
             "--config-file" => {
                 let Some(value) = args.next() else {
@@ -66,64 +206,500 @@ fn parse_cli_args() -> Result<CliArgs> {
                 };
                 export_csv = Some(PathBuf::from(value));
             }
+            "export" => export = true,
+            "--to" => {
+                let Some(value) = args.next() else {
+                    bail!("missing value for --to");
+                };
+                export_to.push(value);
+            }
+            "--where" => {
+                let Some(value) = args.next() else {
+                    bail!("missing value for --where");
+                };
+                filter = Some(Query::parse(&value)?);
+            }
+            "daemon" => daemon = true,
+            "--viewer" => viewer = true,
+            "--kiosk" => kiosk = true,
+            "--no-canvas" => no_canvas = true,
+            "--monochrome" => monochrome = true,
+            "--kiosk-interval" => {
+                let Some(value) = args.next() else {
+                    bail!("missing value for --kiosk-interval");
+                };
+                let seconds = value
+                    .parse::<u64>()
+                    .map_err(|_| color_eyre::eyre::eyre!("invalid kiosk interval: {value}"))?;
+                if seconds == 0 {
+                    bail!("--kiosk-interval must be >= 1");
+                }
+                kiosk_interval = Some(Duration::from_secs(seconds));
+            }
+            "--listen" => {
+                let Some(value) = args.next() else {
+                    bail!("missing value for --listen");
+                };
+                listen = value
+                    .parse()
+                    .map_err(|_| color_eyre::eyre::eyre!("invalid listen address: {value}"))?;
+            }
+            "--store-file" => {
+                let Some(value) = args.next() else {
+                    bail!("missing value for --store-file");
+                };
+                store_file = Some(PathBuf::from(value));
+            }
+            "import" => import = true,
+            "--dry-run" => dry_run = true,
+            "undo" => undo = true,
+            "--list" => list = true,
+            "doctor" => doctor = true,
+            "--bundle" => {
+                let Some(value) = args.next() else {
+                    bail!("missing value for --bundle");
+                };
+                bundle = Some(PathBuf::from(value));
+            }
+            "commit-cost" => commit_cost = true,
+            "summary" => summary = true,
+            "tail" => tail = true,
+            "diagnose" => diagnose = true,
+            "--json" => json = true,
+            "schema" => schema = true,
+            source if import && import_source.is_none() && !source.starts_with('-') => {
+                import_source = Some(source.to_string());
+            }
+            kind if schema && schema_kind.is_none() && !kind.starts_with('-') => {
+                schema_kind = Some(kind.to_string());
+            }
             _ => {
                 bail!("unknown argument: {arg}");
             }
         }
     }
+    load_range.validate("--")?;
+    if daemon && viewer {
+        bail!("--viewer cannot be combined with daemon");
+    }
+    if tail && (daemon || viewer) {
+        bail!("tail cannot be combined with daemon or --viewer");
+    }
+    if filter.is_some()
+        && !export
+        && export_json.is_none()
+        && export_csv.is_none()
+        && !summary
+        && !commit_cost
+    {
+        bail!(
+            "--where only applies to export, --export-json, --export-csv, summary and commit-cost"
+        );
+    }
+    if export == export_to.is_empty() {
+        bail!("usage: promptpetrol export --to <path|s3://bucket/key|gs://bucket/key>");
+    }
+    if bundle.is_some() && !doctor {
+        bail!("--bundle only applies to doctor");
+    }
+    if json && !diagnose {
+        bail!("--json only applies to diagnose");
+    }
+    if diagnose && !json {
+        bail!("usage: promptpetrol diagnose --json");
+    }
+    if list && !undo {
+        bail!("--list only applies to undo");
+    }
+    if kiosk_interval.is_some() && !kiosk {
+        bail!("--kiosk-interval only applies to --kiosk");
+    }
+    if kiosk && daemon {
+        bail!("--kiosk cannot be combined with daemon");
+    }
+    let dry_run = match (import, dry_run, import_source) {
+        (false, false, _) => None,
+        (true, true, Some(source)) => Some(Importer::from_name(&source).ok_or_else(|| {
+            color_eyre::eyre::eyre!(
+                "unknown importer: {source} (expected one of {})",
+                Importer::ALL.map(Importer::name).join(", ")
+            )
+        })?),
+        _ => bail!(
+            "usage: promptpetrol import --dry-run <{}>",
+            Importer::ALL.map(Importer::name).join("|")
+        ),
+    };
+    let schema =
+        match (schema, schema_kind) {
+            (false, _) => None,
+            (true, kind) => Some(kind.as_deref().and_then(SchemaKind::from_name).ok_or_else(
+                || {
+                    color_eyre::eyre::eyre!(
+                        "usage: promptpetrol schema <{}>",
+                        SchemaKind::ALL.map(SchemaKind::name).join("|")
+                    )
+                },
+            )?),
+        };
 
     Ok(CliArgs {
-        data_file,
+        data_files,
+        load_range,
         config_file,
         refresh_interval,
         export_json,
         export_csv,
+        export_to,
+        filter,
+        daemon,
+        viewer,
+        listen,
+        store_file,
+        dry_run,
+        undo,
+        list,
+        doctor,
+        bundle,
+        commit_cost,
+        summary,
+        tail,
+        diagnose,
+        schema,
+        display: DisplayOptions {
+            kiosk: kiosk.then(|| kiosk_interval.unwrap_or(DEFAULT_KIOSK_INTERVAL)),
+            no_canvas,
+            monochrome: monochrome || detect_monochrome(|name| std::env::var(name).ok()),
+            tour: false,
+        },
     })
 }
 
-fn main() -> Result<()> {
-    color_eyre::install()?;
+#[tokio::main]
+async fn main() -> Result<()> {
+    install_panic_hook(default_log_file()?)?;
     let args = parse_cli_args()?;
-    let mut app = bootstrap_app(args.data_file, args.config_file)?;
-    if args.export_json.is_some() || args.export_csv.is_some() {
-        export_provider_summaries(&app, args.export_json, args.export_csv)?;
+    if let Some(kind) = args.schema {
+        print!("{}", format_json_schema(kind)?);
+        return Ok(());
+    }
+    let mut data_files = args.data_files.into_iter();
+    let data_file = match data_files.next() {
+        Some(path) => path,
+        None => default_data_file()?,
+    };
+    let extra_data_files = data_files.collect::<Vec<_>>();
+    let config_file = match args.config_file {
+        Some(path) => path,
+        None => default_config_file()?,
+    };
+    redact_crash_reports(&config_file);
+    let store_file = match args.store_file {
+        Some(path) => path,
+        None => default_store_file()?,
+    };
+
+    if args.undo {
+        return undo_data_change(&data_file, args.list);
+    }
+
+    if let Some(path) = args.bundle {
+        let files = write_bundle(
+            &path,
+            &BundleSources {
+                config_file: &config_file,
+                data_file: &data_file,
+                log_file: &default_log_file()?,
+            },
+        )?;
+        println!("Wrote {} ({})", path.display(), files.join(", "));
+        println!("API keys and credentials are redacted; look it over before attaching it.");
+        return Ok(());
+    }
+    if args.doctor {
+        return run_doctor(&config_file);
+    }
+
+    if args.commit_cost {
+        let mut worker = RefreshWorker::new(
+            data_file,
+            extra_data_files,
+            config_file,
+            args.refresh_interval,
+        )?
+        .with_load_range(args.load_range);
+        worker.import_now();
+        let snapshot = worker.snapshot();
+        let Some(config) = snapshot.config.commit_cost.as_ref() else {
+            bail!("commit-cost needs a \"commit_cost\" section mapping tags to repositories");
+        };
+        let reports = commit_costs(
+            config,
+            &snapshot.data,
+            args.filter.as_ref(),
+            clock::now_epoch_secs(),
+        )?;
+        print!(
+            "{}",
+            format_commit_costs(&reports, config.lookback_days, &snapshot.config.currency)
+        );
+        return worker.save_import_cache();
+    }
+
+    if args.summary {
+        let mut worker = RefreshWorker::new(
+            data_file,
+            extra_data_files,
+            config_file,
+            args.refresh_interval,
+        )?
+        .with_load_range(args.load_range);
+        worker.import_now();
+        print!(
+            "{}",
+            format_summary_report(
+                &worker.snapshot(),
+                args.filter.as_ref(),
+                clock::now_epoch_secs()
+            )
+        );
+        return worker.save_import_cache();
+    }
+
+    if args.diagnose {
+        let files = [
+            ("config", config_file.clone()),
+            ("import cache", import_cache_file(&config_file)),
+        ]
+        .into_iter()
+        .chain(
+            std::iter::once(&data_file)
+                .chain(&extra_data_files)
+                .map(|path| ("data", path.clone())),
+        )
+        .collect::<Vec<_>>();
+        let mut worker = RefreshWorker::new(
+            data_file,
+            extra_data_files,
+            config_file,
+            args.refresh_interval,
+        )?
+        .with_load_range(args.load_range);
+        print!(
+            "{}",
+            format_diagnostics_json(&diagnose(&mut worker, &files))?
+        );
         return Ok(());
     }
+
+    if let Some(importer) = args.dry_run {
+        let config = load_or_bootstrap_config(&config_file)?;
+        let dry_run = dry_run_import(&config, importer)?;
+        print!("{}", format_dry_run(&dry_run, &config.currency));
+        return Ok(());
+    }
+
+    if args.daemon {
+        let mut worker = RefreshWorker::new(
+            data_file,
+            extra_data_files,
+            config_file,
+            args.refresh_interval,
+        )?
+        .with_load_range(args.load_range);
+        worker.import_now();
+        return run_daemon(worker, store_file, args.listen).await;
+    }
+
+    if args.export_json.is_some() || args.export_csv.is_some() || !args.export_to.is_empty() {
+        let mut worker = RefreshWorker::new(
+            data_file,
+            extra_data_files,
+            config_file,
+            args.refresh_interval,
+        )?
+        .with_load_range(args.load_range);
+        worker.import_now();
+        let mut snapshot = worker.snapshot();
+        if let Some(query) = &args.filter {
+            query.retain(&mut snapshot.data);
+        }
+        export_provider_summaries(&snapshot.data, args.export_json, args.export_csv)?;
+        ship_provider_summaries(&snapshot.data, &snapshot.config.network, &args.export_to)?;
+        return worker.save_import_cache();
+    }
+
+    if args.tail {
+        let worker = RefreshWorker::viewer(store_file, config_file, args.refresh_interval)?
+            .with_load_range(args.load_range);
+        return run_tail(worker).await;
+    }
+
+    if args.viewer {
+        let worker = RefreshWorker::viewer(store_file, config_file, args.refresh_interval)?
+            .with_load_range(args.load_range);
+        let result = run(init_terminal()?, worker, args.display).await;
+        restore_terminal()?;
+        return result;
+    }
+
     let terminal = init_terminal()?;
-    let result = run(terminal, &mut app, args.refresh_interval);
+    let result = run_dashboard(
+        terminal,
+        data_file,
+        extra_data_files,
+        args.load_range,
+        config_file,
+        args.refresh_interval,
+        args.display,
+    )
+    .await;
     restore_terminal()?;
     result
 }
 
+async fn run_dashboard(
+    mut terminal: DefaultTerminal,
+    data_file: PathBuf,
+    extra_data_files: Vec<PathBuf>,
+    load_range: LoadRange,
+    config_file: PathBuf,
+    refresh_interval: Duration,
+    mut display: DisplayOptions,
+) -> Result<()> {
+    if needs_setup(&config_file) {
+        let wizard = SetupWizard::new(data_file.exists());
+        let Some(choices) = run_setup_wizard(&mut terminal, wizard, display.monochrome)? else {
+            return Ok(());
+        };
+        apply_setup_choices(&choices, &config_file, &data_file)?;
+        display.tour = true;
+    }
+    let worker = RefreshWorker::new(data_file, extra_data_files, config_file, refresh_interval)?
+        .with_load_range(load_range);
+    run(terminal, worker, display).await
+}
+
+/// `doctor`: checks each configured API key with a list-models call, so a
+/// $0 reading can be told apart from a key the provider no longer accepts.
+fn run_doctor(config_file: &Path) -> Result<()> {
+    if !config_file.exists() {
+        bail!("no config at {}", config_file.display());
+    }
+    let config = load_or_bootstrap_config(config_file)?;
+    let checks = check_api_keys(&config);
+    print!("{}", format_key_checks(&checks));
+    let failed = checks.iter().filter(|check| check.failed()).count();
+    if failed > 0 {
+        bail!("{failed} API key check(s) failed");
+    }
+    Ok(())
+}
+
+/// `undo`: restores the data file to before its last journaled change;
+/// `undo --list` shows what could be restored, newest first.
+fn undo_data_change(data_file: &Path, list: bool) -> Result<()> {
+    if list {
+        let journal = journal::entries(data_file)?;
+        if journal.is_empty() {
+            println!("No journaled changes for {}", data_file.display());
+        }
+        for entry in journal.iter().rev() {
+            println!("{}", journal::describe(entry));
+        }
+        return Ok(());
+    }
+    match journal::undo(data_file)? {
+        Some((entry, _)) => println!(
+            "Restored {} to {}",
+            data_file.display(),
+            journal::describe(&entry)
+        ),
+        None => println!("Nothing to undo for {}", data_file.display()),
+    }
+    Ok(())
+}
+
 fn export_provider_summaries(
-    app: &app::App,
+    data: &UsageData,
     export_json: Option<PathBuf>,
     export_csv: Option<PathBuf>,
 ) -> Result<()> {
-    let summaries = provider_summaries(&app.data);
+    let summaries = provider_summaries(data);
 
     if let Some(path) = export_json {
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        let payload = serde_json::to_string_pretty(&summaries)?;
-        fs::write(path, payload)?;
+        write_export(&path, |out| write_summaries_json(out, &summaries))?;
     }
 
     if let Some(path) = export_csv {
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        let mut file = fs::File::create(path)?;
-        writeln!(file, "provider,total_tokens,total_cost_usd")?;
-        for summary in &summaries {
-            writeln!(
-                file,
-                "{},{},{}",
-                summary.provider, summary.total_tokens, summary.total_cost_usd
-            )?;
+        write_export(&path, |out| write_summaries_csv(out, &summaries))?;
+    }
+
+    Ok(())
+}
+
+/// `export --to`: the format follows each destination's extension (`.csv`
+/// for CSV, JSON otherwise); `s3://` and `gs://` destinations are uploaded.
+fn ship_provider_summaries(
+    data: &UsageData,
+    network: &NetworkConfig,
+    destinations: &[String],
+) -> Result<()> {
+    let summaries = provider_summaries(data);
+    for destination in destinations {
+        let csv = destination.to_ascii_lowercase().ends_with(".csv");
+        let write = |out: &mut dyn Write| {
+            if csv {
+                write_summaries_csv(out, &summaries)
+            } else {
+                write_summaries_json(out, &summaries)
+            }
+        };
+        match ObjectTarget::parse(destination)? {
+            // Uploads go out in one request, so they are put together
+            // first.
+            Some(target) => {
+                let mut payload = Vec::new();
+                write(&mut payload)?;
+                let content_type = if csv { "text/csv" } else { "application/json" };
+                target.upload(network, &payload, content_type)?;
+            }
+            None => write_export(&PathBuf::from(destination), write)?,
         }
     }
+    Ok(())
+}
 
+/// Streams an export into `path` as `write` produces it.
+fn write_export(path: &Path, write: impl FnOnce(&mut dyn Write) -> Result<()>) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut out = io::BufWriter::new(fs::File::create(path)?);
+    write(&mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+fn write_summaries_json(out: &mut dyn Write, summaries: &[ProviderSummary]) -> Result<()> {
+    serde_json::to_writer_pretty(out, summaries)?;
+    Ok(())
+}
+
+fn write_summaries_csv(out: &mut dyn Write, summaries: &[ProviderSummary]) -> Result<()> {
+    writeln!(
+        out,
+        "provider,total_tokens,total_cost_usd,authoritative_cost_usd"
+    )?;
+    for summary in summaries {
+        writeln!(
+            out,
+            "{},{},{},{}",
+            summary.provider,
+            summary.total_tokens,
+            summary.total_cost_usd,
+            summary.authoritative_cost_usd
+        )?;
+    }
     Ok(())
 }
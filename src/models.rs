@@ -1,21 +1,118 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
+use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use color_eyre::Result;
+use color_eyre::eyre::{bail, eyre};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::alert_routing::{AlertChannel, AlertRoute};
+use crate::budget_period::{BudgetRollover, validate_budget_schedule};
+use crate::clock;
+use crate::freeze::validate_freeze;
+use crate::includes::apply_includes;
+use crate::intern::{self, intern};
+use crate::keymap::Keymap;
+use crate::layout::dashboard_layout;
+use crate::load_range::LoadRange;
+use crate::lock;
+use crate::redact::mask_api_key;
+use crate::summary::UsageSummary;
+use crate::theme::Theme;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct UsageEntry {
     pub(crate) timestamp: String,
-    pub(crate) provider: String,
-    pub(crate) model: String,
+    /// Interned, like `model`: the same few names repeat across every
+    /// entry.
+    #[serde(deserialize_with = "intern::deserialize")]
+    pub(crate) provider: Arc<str>,
+    #[serde(deserialize_with = "intern::deserialize")]
+    pub(crate) model: Arc<str>,
     pub(crate) input_tokens: u64,
     pub(crate) output_tokens: u64,
     pub(crate) cost_usd: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) pricing_match: Option<PricingMatch>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) cost_source: Option<CostSource>,
+    /// Free-form label from the source, e.g. the Langfuse trace name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) tag: Option<String>,
+    /// The cost as the source billed it, when that was in another currency
+    /// and `cost_usd` holds the converted amount.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) original_cost: Option<OriginalCost>,
+    /// The API key the usage was billed to, e.g. a LiteLLM virtual key
+    /// alias or an OpenAI project key name. Secrets are masked at load.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) api_key: Option<String>,
+    /// OpenAI project the cost was billed to; `project_names` in the config
+    /// gives it a friendly name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) project_id: Option<String>,
+    /// The data file the entry was loaded from, set when several are.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) data_file: Option<String>,
+}
+
+impl UsageEntry {
+    /// Where this entry's cost came from. Older data files carry no explicit
+    /// source, so fall back to whether a pricing lookup was involved.
+    pub(crate) fn effective_cost_source(&self) -> CostSource {
+        self.cost_source.unwrap_or(if self.pricing_match.is_some() {
+            CostSource::Estimated
+        } else {
+            CostSource::Payload
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct OriginalCost {
+    pub(crate) amount: f64,
+    pub(crate) currency: String,
+}
+
+/// Origin of a recorded cost. `Payload` and `Billing` are reported by the
+/// provider; `Estimated` was computed locally from configured pricing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum CostSource {
+    Payload,
+    Billing,
+    Estimated,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl CostSource {
+    pub(crate) fn is_authoritative(self) -> bool {
+        matches!(self, CostSource::Payload | CostSource::Billing)
+    }
+}
+
+/// How an estimated cost found its pricing entry. Absent when the cost came
+/// straight from the source payload; `Missing` means no pricing matched and
+/// the cost was recorded as zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum PricingMatch {
+    Exact,
+    Wildcard,
+    Fuzzy,
+    Missing,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CostEstimate {
+    pub(crate) cost_usd: f64,
+    pub(crate) pricing_match: Option<PricingMatch>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct UsageData {
     pub(crate) budget_usd: Option<f64>,
     pub(crate) entries: Vec<UsageEntry>,
@@ -28,40 +125,85 @@ impl Default for UsageData {
             entries: vec![
                 UsageEntry {
                     timestamp: "2026-02-09T08:45:00Z".to_string(),
-                    provider: "openai".to_string(),
-                    model: "gpt-4.1-mini".to_string(),
+                    provider: "openai".into(),
+                    model: "gpt-4.1-mini".into(),
                     input_tokens: 7_600,
                     output_tokens: 2_400,
                     cost_usd: 0.084,
+                    pricing_match: None,
+                    cost_source: None,
+                    original_cost: None,
+                    api_key: None,
+                    project_id: None,
+                    tag: None,
+                    data_file: None,
                 },
                 UsageEntry {
                     timestamp: "2026-02-09T13:30:00Z".to_string(),
-                    provider: "anthropic".to_string(),
-                    model: "claude-3.7-sonnet".to_string(),
+                    provider: "anthropic".into(),
+                    model: "claude-3.7-sonnet".into(),
                     input_tokens: 10_400,
                     output_tokens: 5_800,
                     cost_usd: 0.361,
+                    pricing_match: None,
+                    cost_source: None,
+                    original_cost: None,
+                    api_key: None,
+                    project_id: None,
+                    tag: None,
+                    data_file: None,
                 },
                 UsageEntry {
                     timestamp: "2026-02-10T03:15:00Z".to_string(),
-                    provider: "gemini".to_string(),
-                    model: "gemini-2.0-flash".to_string(),
+                    provider: "gemini".into(),
+                    model: "gemini-2.0-flash".into(),
                     input_tokens: 5_300,
                     output_tokens: 1_200,
                     cost_usd: 0.056,
+                    pricing_match: None,
+                    cost_source: None,
+                    original_cost: None,
+                    api_key: None,
+                    project_id: None,
+                    tag: None,
+                    data_file: None,
                 },
             ],
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct ModelPricing {
     pub(crate) input_per_million_usd: f64,
     pub(crate) output_per_million_usd: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Where a pricing key was read from, to explain the estimates it made.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PricingOrigin {
+    /// The config file, or the `include` path or URL.
+    pub(crate) source: String,
+    /// When that copy was written: the file's modification time, or when a
+    /// URL was last fetched.
+    pub(crate) updated_at: Option<i64>,
+}
+
+/// Settings for every outbound request: pollers, includes, webhooks,
+/// uploads and team budget sync.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct NetworkConfig {
+    /// e.g. `http://proxy.corp.example:3128`; without it the usual
+    /// `HTTPS_PROXY`/`ALL_PROXY`/`NO_PROXY` variables apply.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) proxy: Option<String>,
+    /// PEM file of root certificates trusted instead of the built-in ones,
+    /// for networks that intercept TLS.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) ca_bundle: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct AppConfig {
     #[serde(default)]
     pub(crate) api_keys: HashMap<String, String>,
@@ -69,6 +211,264 @@ pub(crate) struct AppConfig {
     pub(crate) pricing: HashMap<String, ModelPricing>,
     #[serde(default)]
     pub(crate) codex_import: CodexImportConfig,
+    #[serde(default)]
+    pub(crate) refresh_intervals: HashMap<String, u64>,
+    #[serde(default = "default_currency")]
+    pub(crate) currency: String,
+    /// Units of `currency` per unit of another currency, used to convert
+    /// entries that carry their own `currency`, e.g. `{"EUR": 1.08}`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub(crate) exchange_rates: HashMap<String, f64>,
+    /// Factor applied to each provider's costs for display and reporting,
+    /// e.g. `1.2` for VAT or a reseller markup; `"*"` covers the rest.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub(crate) cost_multipliers: HashMap<String, f64>,
+    /// Friendly names for OpenAI project IDs, e.g. `{"proj_abc": "Search"}`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub(crate) project_names: HashMap<String, String>,
+    #[serde(default)]
+    pub(crate) provider_badges: HashMap<String, ProviderBadge>,
+    #[serde(default)]
+    pub(crate) provider_groups: BTreeMap<String, Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) gauge_half_life_hours: Option<f64>,
+    /// Rows of named widgets (`gauge:fuel`, `chart:daily`, ...) shown in
+    /// place of the fixed dials grid; see `layout.rs`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) dashboard_layout: Vec<Vec<String>>,
+    /// Keys per action, e.g. `{"zoom": ["Z"]}`, replacing that action's
+    /// default keys; see `keymap.rs`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) keymap: BTreeMap<String, Vec<String>>,
+    /// Colour preset, e.g. `deuteranopia`; see `theme.rs`.
+    #[serde(default)]
+    pub(crate) theme: Theme,
+    /// Estimated cost at which a single agent session counts as runaway.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) session_cost_ceiling_usd: Option<f64>,
+    /// Input tokens in one turn of the running Codex session above which a
+    /// HEAVY LOAD advisory suggests compacting its context.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) heavy_load_turn_tokens: Option<u64>,
+    /// Tokens a Codex session of each kind is expected to take, e.g.
+    /// `{"heavy": 2000000}`, for the pacing under the Codex dials.
+    #[serde(default = "default_codex_session_tokens")]
+    pub(crate) codex_session_tokens: BTreeMap<String, u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) spend_rate_alert: Option<SpendRateAlertConfig>,
+    /// Matching of estimates against billed usage reported twice; see
+    /// `reconcile.rs`.
+    #[serde(default)]
+    pub(crate) reconcile: ReconcileConfig,
+    /// Budgets staged by UTC month (`YYYY-MM`), each replacing the data
+    /// file's `budget_usd` from that month until the next entry.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) budget_schedule: BTreeMap<String, f64>,
+    /// What unspent budget does at the end of a month; when set, the fuel
+    /// gauge counts the current month's spend only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) budget_rollover: Option<BudgetRollover>,
+    /// Set by `F`: spending is paused from this timestamp on; see
+    /// `freeze.rs`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) spending_frozen_since: Option<String>,
+    /// Repositories whose tagged spend `commit-cost` sets against their git
+    /// history.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) commit_cost: Option<CommitCostConfig>,
+    /// Named destinations for alerts besides the dashboard.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) alert_channels: BTreeMap<String, AlertChannel>,
+    /// Which alerts go to which channels; see [`AlertRoute`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) alert_routes: Vec<AlertRoute>,
+    /// File written while a budget or limit is breached, for agent
+    /// wrappers to poll; see `throttle.rs`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) throttle_file: Option<String>,
+    /// Outbound requests per minute for each network poller (`langfuse`,
+    /// `team_budget`), retries included.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub(crate) request_limits: HashMap<String, u32>,
+    #[serde(default, skip_serializing_if = "is_default_network")]
+    pub(crate) network: NetworkConfig,
+    #[serde(default = "default_true")]
+    pub(crate) adaptive_refresh: bool,
+    /// Most frames drawn per second, however fast changes come in.
+    #[serde(default = "default_max_fps")]
+    pub(crate) max_fps: u32,
+    /// Importers keep only counts, models and times: free-text labels a
+    /// source derives from conversations (Zed thread summaries, Langfuse
+    /// trace names) are skipped unread.
+    #[serde(default)]
+    pub(crate) metadata_only: bool,
+    /// More data files, e.g. tool-generated logs kept apart from a manual
+    /// one, merged into the main data file on load but never written to.
+    /// `~` is expanded.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) data_files: Vec<String>,
+    /// First and last day of usage to load, as `YYYY-MM-DD` or `<N>d` for
+    /// N days back; entries outside are dropped when read.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) load_since: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) load_until: Option<String>,
+    /// Extra config documents (paths or `https://` URLs) whose pricing,
+    /// provider groups and badges fill in keys this file leaves out.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) include: Vec<String>,
+    #[serde(default = "default_include_max_age_secs")]
+    pub(crate) include_max_age_secs: u64,
+    /// Where each `pricing` key came from, filled in on load.
+    #[serde(skip)]
+    pub(crate) pricing_origins: HashMap<String, PricingOrigin>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) team_budget: Option<TeamBudgetConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) langfuse: Option<LangfuseConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) continue_dev: Option<ContinueConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) zed: Option<ZedConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) jetbrains: Option<JetBrainsConfig>,
+}
+
+/// When an estimate and a billed entry count as the same usage.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct ReconcileConfig {
+    #[serde(default = "default_true")]
+    pub(crate) enabled: bool,
+    /// Furthest apart the two timestamps may be.
+    #[serde(default = "default_reconcile_window_minutes")]
+    pub(crate) window_minutes: u64,
+    /// Furthest apart the two token counts may be, as a share of the
+    /// larger.
+    #[serde(default = "default_reconcile_tolerance_percent")]
+    pub(crate) tolerance_percent: f64,
+}
+
+impl Default for ReconcileConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_minutes: default_reconcile_window_minutes(),
+            tolerance_percent: default_reconcile_tolerance_percent(),
+        }
+    }
+}
+
+/// Alarm on how fast money is going out, regardless of the budget.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct SpendRateAlertConfig {
+    pub(crate) usd_per_hour: f64,
+    /// How far back the rolling rate looks.
+    #[serde(default = "default_spend_rate_window_minutes")]
+    pub(crate) window_minutes: u64,
+}
+
+/// Usage tags mapped to the git repositories they were spent on.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct CommitCostConfig {
+    /// Tag to repository path; `~` is expanded.
+    pub(crate) repos: BTreeMap<String, String>,
+    #[serde(default = "default_commit_cost_lookback_days")]
+    pub(crate) lookback_days: u32,
+}
+
+/// Zed agent threads, counted as the `zed` provider.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct ZedConfig {
+    #[serde(default = "default_true")]
+    pub(crate) enabled: bool,
+    /// Directory of serialized thread JSON files; defaults to `threads`
+    /// under Zed's data directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) threads_dir: Option<String>,
+    /// Prompts per calendar month included in the Zed plan, shown as a
+    /// plan-quota gauge.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) monthly_prompt_quota: Option<u64>,
+}
+
+/// JetBrains AI Assistant's quota files, counted as the `jetbrains` provider.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct JetBrainsConfig {
+    #[serde(default = "default_true")]
+    pub(crate) enabled: bool,
+    /// JetBrains' per-user config root holding one directory per IDE;
+    /// defaults to `JetBrains` under the platform config directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) config_dir: Option<String>,
+}
+
+/// Continue.dev's dev-data logs, counted as the `continue` provider.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct ContinueConfig {
+    #[serde(default = "default_true")]
+    pub(crate) enabled: bool,
+    /// Where Continue keeps `dev_data`; defaults to `~/.continue/dev_data`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) storage_dir: Option<String>,
+}
+
+/// A Langfuse project to import generations from. Keys may be left out in
+/// favour of the `LANGFUSE_PUBLIC_KEY` / `LANGFUSE_SECRET_KEY` variables.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct LangfuseConfig {
+    #[serde(default = "default_true")]
+    pub(crate) enabled: bool,
+    #[serde(default = "default_langfuse_base_url")]
+    pub(crate) base_url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) public_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) secret_key: Option<String>,
+    /// Provider the imported entries are recorded under.
+    #[serde(default = "default_langfuse_provider")]
+    pub(crate) provider: String,
+    /// How far back the first import reaches.
+    #[serde(default = "default_langfuse_lookback_days")]
+    pub(crate) lookback_days: u32,
+}
+
+/// Where a team-shared budget lives. `url` is read and written with plain
+/// HTTP GET/PUT; for S3 or GCS, point `url` at a presigned PUT URL and
+/// `read_url` at a presigned GET URL for the same object.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct TeamBudgetConfig {
+    pub(crate) url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) read_url: Option<String>,
+    /// Name this machine's spend is recorded under; defaults to the login
+    /// name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) member: Option<String>,
+    /// Environment variable holding a bearer token for the backend.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) token_env: Option<String>,
+    #[serde(default = "default_team_sync_secs")]
+    pub(crate) sync_interval_secs: u64,
+}
+
+impl TeamBudgetConfig {
+    pub(crate) fn member_name(&self) -> String {
+        self.member
+            .clone()
+            .or_else(|| std::env::var("USER").ok())
+            .or_else(|| std::env::var("USERNAME").ok())
+            .unwrap_or_else(|| "local".to_string())
+    }
+}
+
+/// Per-provider override for the icon and accent colour shown in the
+/// provider strip. Either field may be left out to keep the built-in one.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct ProviderBadge {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) icon: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) color: Option<String>,
 }
 
 impl Default for AppConfig {
@@ -107,11 +507,50 @@ impl Default for AppConfig {
             api_keys,
             pricing,
             codex_import: CodexImportConfig::default(),
+            refresh_intervals: HashMap::new(),
+            currency: default_currency(),
+            exchange_rates: HashMap::new(),
+            cost_multipliers: HashMap::new(),
+            project_names: HashMap::new(),
+            provider_badges: HashMap::new(),
+            provider_groups: BTreeMap::new(),
+            gauge_half_life_hours: None,
+            dashboard_layout: Vec::new(),
+            keymap: BTreeMap::new(),
+            theme: Theme::default(),
+            session_cost_ceiling_usd: None,
+            heavy_load_turn_tokens: None,
+            codex_session_tokens: default_codex_session_tokens(),
+            spend_rate_alert: None,
+            reconcile: ReconcileConfig::default(),
+            budget_schedule: BTreeMap::new(),
+            budget_rollover: None,
+            spending_frozen_since: None,
+            commit_cost: None,
+            alert_channels: BTreeMap::new(),
+            alert_routes: Vec::new(),
+            throttle_file: None,
+            request_limits: HashMap::new(),
+            network: NetworkConfig::default(),
+            adaptive_refresh: true,
+            max_fps: default_max_fps(),
+            metadata_only: false,
+            data_files: Vec::new(),
+            load_since: None,
+            load_until: None,
+            include: Vec::new(),
+            include_max_age_secs: default_include_max_age_secs(),
+            pricing_origins: HashMap::new(),
+            team_budget: None,
+            langfuse: None,
+            continue_dev: None,
+            zed: None,
+            jetbrains: None,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct CodexImportConfig {
     #[serde(default = "default_true")]
     pub(crate) enabled: bool,
@@ -119,6 +558,32 @@ pub(crate) struct CodexImportConfig {
     pub(crate) sessions_dir: Option<String>,
     #[serde(default = "default_codex_model")]
     pub(crate) model: String,
+    /// Also scan the other side of a WSL boundary (`/mnt/c/Users/*` from
+    /// inside WSL, `\\wsl.localhost\*\home\*` from Windows).
+    #[serde(default)]
+    pub(crate) cross_wsl: bool,
+    /// Session files larger than this are handled per `oversize_sessions`
+    /// instead of being parsed in full. 0 disables the cap.
+    #[serde(default = "default_max_session_file_mb")]
+    pub(crate) max_session_file_mb: u64,
+    #[serde(default)]
+    pub(crate) oversize_sessions: OversizeStrategy,
+    /// Parsed sessions kept individually; older ones are folded into
+    /// per-day totals. 0 keeps every session.
+    #[serde(default = "default_max_cached_sessions")]
+    pub(crate) max_cached_sessions: usize,
+}
+
+/// What to do with a session file over `max_session_file_mb`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum OversizeStrategy {
+    /// Parse only the last `max_session_file_mb` of the file. Token counts
+    /// in Codex sessions are running totals, so the tail is enough.
+    #[default]
+    Tail,
+    /// Leave the file out of the import entirely.
+    Skip,
 }
 
 impl Default for CodexImportConfig {
@@ -127,6 +592,10 @@ impl Default for CodexImportConfig {
             enabled: true,
             sessions_dir: None,
             model: default_codex_model(),
+            cross_wsl: false,
+            max_session_file_mb: default_max_session_file_mb(),
+            oversize_sessions: OversizeStrategy::Tail,
+            max_cached_sessions: default_max_cached_sessions(),
         }
     }
 }
@@ -135,10 +604,76 @@ fn default_true() -> bool {
     true
 }
 
+fn is_default_network(network: &NetworkConfig) -> bool {
+    network.proxy.is_none() && network.ca_bundle.is_none()
+}
+
+fn default_spend_rate_window_minutes() -> u64 {
+    60
+}
+
+fn default_reconcile_window_minutes() -> u64 {
+    10
+}
+
+fn default_reconcile_tolerance_percent() -> f64 {
+    10.0
+}
+
+fn default_commit_cost_lookback_days() -> u32 {
+    30
+}
+
+fn default_max_fps() -> u32 {
+    15
+}
+
+fn default_include_max_age_secs() -> u64 {
+    3600
+}
+
+fn default_team_sync_secs() -> u64 {
+    60
+}
+
+fn default_langfuse_base_url() -> String {
+    "https://cloud.langfuse.com".to_string()
+}
+
+fn default_langfuse_provider() -> String {
+    "langfuse".to_string()
+}
+
+fn default_langfuse_lookback_days() -> u32 {
+    30
+}
+
+fn default_max_session_file_mb() -> u64 {
+    64
+}
+
+fn default_max_cached_sessions() -> usize {
+    5_000
+}
+
+fn default_codex_session_tokens() -> BTreeMap<String, u64> {
+    BTreeMap::from([("heavy".to_string(), 2_000_000)])
+}
+
 fn default_codex_model() -> String {
     "codex-cli".to_string()
 }
 
+/// A `<set-openai-key>` style placeholder, or no key at all.
+pub(crate) fn is_placeholder_key(key: &str) -> bool {
+    let key = key.trim();
+    key.is_empty() || (key.starts_with('<') && key.ends_with('>'))
+}
+
+pub(crate) fn default_currency() -> String {
+    "USD".to_string()
+}
+
 #[derive(Debug, Clone, Deserialize)]
 struct RawUsageData {
     budget_usd: Option<f64>,
@@ -172,13 +707,44 @@ struct RawUsageEntry {
     total_token_count: Option<u64>,
     #[serde(default)]
     cost_usd: Option<f64>,
+    #[serde(default)]
+    billed_cost_usd: Option<f64>,
+    /// Cost in `currency` rather than the configured one, as some provider
+    /// exports bill in EUR.
+    #[serde(default)]
+    cost: Option<f64>,
+    #[serde(default)]
+    currency: Option<String>,
+    #[serde(default, alias = "key_alias", alias = "api_key_name")]
+    api_key: Option<String>,
+    #[serde(default)]
+    project_id: Option<String>,
+    #[serde(default)]
+    tag: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub(crate) struct ProviderSummary {
     pub(crate) provider: String,
     pub(crate) total_tokens: u64,
     pub(crate) total_cost_usd: f64,
+    pub(crate) authoritative_cost_usd: f64,
+    pub(crate) fuzzy_priced_entries: usize,
+    /// Totals of converted entries in the currency they were billed in.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) original_costs: BTreeMap<String, f64>,
+}
+
+/// Spend billed to one API key or project, across every provider that
+/// used it.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SpendGroup {
+    /// `None` collects entries no importer could attribute.
+    pub(crate) name: Option<String>,
+    pub(crate) providers: Vec<String>,
+    pub(crate) requests: usize,
+    pub(crate) total_tokens: u64,
+    pub(crate) total_cost_usd: f64,
 }
 
 #[derive(Debug, Clone)]
@@ -186,67 +752,557 @@ pub(crate) struct ProviderStats {
     pub(crate) provider: String,
     pub(crate) total_tokens: u64,
     pub(crate) total_cost_usd: f64,
+    pub(crate) authoritative_cost_usd: f64,
     pub(crate) requests: usize,
+    pub(crate) fuzzy_priced_requests: usize,
 }
 
-pub(crate) fn provider_summaries(data: &UsageData) -> Vec<ProviderSummary> {
-    let mut grouped: HashMap<String, (u64, f64)> = HashMap::new();
+/// [`ProviderStats`] for one of a provider's models.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ModelStats {
+    pub(crate) provider: String,
+    pub(crate) model: String,
+    pub(crate) total_tokens: u64,
+    pub(crate) total_cost_usd: f64,
+    pub(crate) authoritative_cost_usd: f64,
+    pub(crate) requests: usize,
+    pub(crate) fuzzy_priced_requests: usize,
+}
+
+/// Percentage of `total_cost_usd` reported by the provider rather than
+/// estimated locally. `None` when there is no cost to weigh.
+pub(crate) fn authoritative_percent(
+    authoritative_cost_usd: f64,
+    total_cost_usd: f64,
+) -> Option<f64> {
+    (total_cost_usd > 0.0)
+        .then(|| (authoritative_cost_usd / total_cost_usd * 100.0).clamp(0.0, 100.0))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct UnpricedModel {
+    pub(crate) provider: String,
+    pub(crate) model: String,
+    pub(crate) entries: usize,
+    pub(crate) total_tokens: u64,
+}
+
+/// Distinct provider/model pairs whose cost fell back to zero because no
+/// pricing matched, most tokens first.
+pub(crate) fn unpriced_models(data: &UsageData) -> Vec<UnpricedModel> {
+    let mut grouped: HashMap<(&str, &str), (usize, u64)> = HashMap::new();
     for entry in &data.entries {
-        let current = grouped.entry(entry.provider.clone()).or_insert((0, 0.0));
-        current.0 += entry.input_tokens + entry.output_tokens;
-        current.1 += entry.cost_usd;
+        if entry.pricing_match != Some(PricingMatch::Missing) {
+            continue;
+        }
+        let current = grouped
+            .entry((&*entry.provider, &*entry.model))
+            .or_insert((0, 0));
+        current.0 += 1;
+        current.1 += entry.input_tokens + entry.output_tokens;
     }
 
-    let mut summaries = grouped
+    let mut unpriced = grouped
         .into_iter()
         .map(
-            |(provider, (total_tokens, total_cost_usd))| ProviderSummary {
-                provider,
+            |((provider, model), (entries, total_tokens))| UnpricedModel {
+                provider: provider.to_string(),
+                model: model.to_string(),
+                entries,
                 total_tokens,
-                total_cost_usd,
             },
         )
         .collect::<Vec<_>>();
-    summaries.sort_by(|a, b| {
-        b.total_cost_usd
-            .partial_cmp(&a.total_cost_usd)
-            .unwrap_or(std::cmp::Ordering::Equal)
-            .then_with(|| b.total_tokens.cmp(&a.total_tokens))
+    unpriced.sort_by(|a, b| {
+        b.total_tokens
+            .cmp(&a.total_tokens)
             .then_with(|| a.provider.cmp(&b.provider))
+            .then_with(|| a.model.cmp(&b.model))
     });
-    summaries
+    unpriced
+}
+
+/// The `cost_multipliers` factor for `provider`, else the `"*"` one, else 1.
+pub(crate) fn cost_multiplier(config: &AppConfig, provider: &str) -> f64 {
+    config
+        .cost_multipliers
+        .get(provider)
+        .or_else(|| config.cost_multipliers.get("*"))
+        .copied()
+        .unwrap_or(1.0)
+}
+
+/// Scales every entry's cost by its provider's multiplier, so totals,
+/// gauges and exports show what is actually paid.
+pub(crate) fn apply_cost_multipliers(data: &mut UsageData, config: &AppConfig) {
+    if config.cost_multipliers.is_empty() {
+        return;
+    }
+    for entry in &mut data.entries {
+        entry.cost_usd *= cost_multiplier(config, &entry.provider);
+    }
+}
+
+pub(crate) fn provider_summaries(data: &UsageData) -> Vec<ProviderSummary> {
+    UsageSummary::from_data(data).provider_summaries()
 }
 
-pub(crate) fn provider_stats(data: &UsageData, provider: &str) -> Option<ProviderStats> {
-    if provider.is_empty() {
-        return None;
+/// Spend per API key, most expensive first, with unattributed entries
+/// last.
+pub(crate) fn key_summaries(data: &UsageData) -> Vec<SpendGroup> {
+    spend_groups(data, |entry| entry.api_key.clone())
+}
+
+/// Spend per OpenAI project, named from `project_names` where configured.
+pub(crate) fn project_summaries(data: &UsageData, config: &AppConfig) -> Vec<SpendGroup> {
+    spend_groups(data, |entry| {
+        entry.project_id.as_ref().map(|id| {
+            config
+                .project_names
+                .get(id)
+                .cloned()
+                .unwrap_or_else(|| id.clone())
+        })
+    })
+}
+
+fn spend_groups(
+    data: &UsageData,
+    label: impl Fn(&UsageEntry) -> Option<String>,
+) -> Vec<SpendGroup> {
+    let mut grouped: HashMap<Option<String>, SpendGroup> = HashMap::new();
+    for entry in &data.entries {
+        let name = label(entry);
+        let current = grouped.entry(name.clone()).or_insert_with(|| SpendGroup {
+            name,
+            providers: Vec::new(),
+            requests: 0,
+            total_tokens: 0,
+            total_cost_usd: 0.0,
+        });
+        if !current
+            .providers
+            .iter()
+            .any(|name| **name == *entry.provider)
+        {
+            current.providers.push(entry.provider.to_string());
+        }
+        current.requests += 1;
+        current.total_tokens += entry.input_tokens + entry.output_tokens;
+        current.total_cost_usd += entry.cost_usd;
     }
 
-    let mut total_input_tokens = 0_u64;
-    let mut total_output_tokens = 0_u64;
-    let mut total_cost_usd = 0.0_f64;
-    let mut requests = 0_usize;
+    let mut summaries = grouped.into_values().collect::<Vec<_>>();
+    for summary in &mut summaries {
+        summary.providers.sort();
+    }
+    summaries.sort_by(|a, b| {
+        a.name
+            .is_none()
+            .cmp(&b.name.is_none())
+            .then_with(|| {
+                b.total_cost_usd
+                    .partial_cmp(&a.total_cost_usd)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    summaries
+}
+
+/// What one model cost per million output tokens, with whatever caching
+/// discounts the reported costs include.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ModelEfficiency {
+    pub(crate) provider: String,
+    pub(crate) model: String,
+    pub(crate) output_tokens: u64,
+    pub(crate) total_cost_usd: f64,
+    pub(crate) authoritative_cost_usd: f64,
+}
 
+impl ModelEfficiency {
+    /// Everything paid for the model, input included, per million tokens
+    /// it produced.
+    pub(crate) fn cost_per_million_output(&self) -> f64 {
+        self.total_cost_usd / self.output_tokens as f64 * 1_000_000.0
+    }
+}
+
+/// Models ranked by achieved cost per million output tokens, cheapest
+/// first, over entries from `since_epoch_secs` on (all of them for
+/// `None`). Models that produced no output are left out.
+pub(crate) fn model_leaderboard(
+    data: &UsageData,
+    since_epoch_secs: Option<i64>,
+) -> Vec<ModelEfficiency> {
+    let mut grouped: HashMap<(&str, &str), ModelEfficiency> = HashMap::new();
     for entry in &data.entries {
-        if entry.provider != provider {
+        if let Some(since) = since_epoch_secs
+            && clock::parse_timestamp(&entry.timestamp).is_none_or(|at| at < since)
+        {
             continue;
         }
-        total_input_tokens += entry.input_tokens;
-        total_output_tokens += entry.output_tokens;
-        total_cost_usd += entry.cost_usd;
-        requests += 1;
+        let current = grouped
+            .entry((&entry.provider, &entry.model))
+            .or_insert_with(|| ModelEfficiency {
+                provider: entry.provider.to_string(),
+                model: entry.model.to_string(),
+                output_tokens: 0,
+                total_cost_usd: 0.0,
+                authoritative_cost_usd: 0.0,
+            });
+        current.output_tokens += entry.output_tokens;
+        current.total_cost_usd += entry.cost_usd;
+        if entry.effective_cost_source().is_authoritative() {
+            current.authoritative_cost_usd += entry.cost_usd;
+        }
+    }
+    let mut ranked = grouped
+        .into_values()
+        .filter(|model| model.output_tokens > 0)
+        .collect::<Vec<_>>();
+    ranked.sort_by(|a, b| {
+        a.cost_per_million_output()
+            .partial_cmp(&b.cost_per_million_output())
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| (&a.provider, &a.model).cmp(&(&b.provider, &b.model)))
+    });
+    ranked
+}
+
+/// One row of the provider list: either a single provider or a vendor
+/// group from `provider_groups`, whose summary totals its members.
+#[derive(Debug, Clone)]
+pub(crate) struct ProviderItem {
+    pub(crate) summary: ProviderSummary,
+    pub(crate) members: Vec<String>,
+}
+
+impl ProviderItem {
+    pub(crate) fn name(&self) -> &str {
+        &self.summary.provider
+    }
+
+    pub(crate) fn is_group(&self) -> bool {
+        !self.members.is_empty()
+    }
+}
+
+/// A provider may belong to one group only: the list folds it into a single
+/// row, and selecting either group must cover the same providers.
+pub(crate) fn validate_provider_groups(config: &AppConfig) -> Result<()> {
+    let mut owners = HashMap::new();
+    for (group, members) in &config.provider_groups {
+        for member in members {
+            if let Some(owner) = owners.insert(member.as_str(), group.as_str())
+                && owner != group
+            {
+                bail!("provider_groups: {member} is in both {owner} and {group}");
+            }
+        }
     }
+    Ok(())
+}
 
-    if requests == 0 {
-        return None;
+/// Provider rows at one level of the group hierarchy. At the top level,
+/// grouped providers fold into their group; inside `open_group` only that
+/// group's members are listed. Rows keep the cost ordering of `summaries`,
+/// as from `provider_summaries`.
+pub(crate) fn provider_items(
+    summaries: Vec<ProviderSummary>,
+    groups: &BTreeMap<String, Vec<String>>,
+    open_group: Option<&str>,
+) -> Vec<ProviderItem> {
+    if let Some(group) = open_group {
+        let members = groups.get(group).map(Vec::as_slice).unwrap_or_default();
+        return summaries
+            .into_iter()
+            .filter(|summary| members.contains(&summary.provider))
+            .map(|summary| ProviderItem {
+                summary,
+                members: Vec::new(),
+            })
+            .collect();
     }
 
-    Some(ProviderStats {
-        provider: provider.to_string(),
-        total_tokens: total_input_tokens + total_output_tokens,
-        total_cost_usd,
-        requests,
-    })
+    let mut items: Vec<ProviderItem> = Vec::new();
+    for summary in summaries {
+        let group = groups
+            .iter()
+            .find(|(_, members)| members.contains(&summary.provider))
+            .map(|(name, _)| name);
+        let Some(group) = group else {
+            items.push(ProviderItem {
+                summary,
+                members: Vec::new(),
+            });
+            continue;
+        };
+        match items.iter_mut().find(|item| item.name() == group) {
+            Some(item) => {
+                item.summary.total_tokens += summary.total_tokens;
+                item.summary.total_cost_usd += summary.total_cost_usd;
+                item.summary.authoritative_cost_usd += summary.authoritative_cost_usd;
+                item.summary.fuzzy_priced_entries += summary.fuzzy_priced_entries;
+                for (currency, amount) in summary.original_costs {
+                    *item.summary.original_costs.entry(currency).or_default() += amount;
+                }
+                item.members.push(summary.provider);
+            }
+            None => items.push(ProviderItem {
+                members: vec![summary.provider.clone()],
+                summary: ProviderSummary {
+                    provider: group.clone(),
+                    ..summary
+                },
+            }),
+        }
+    }
+    items.sort_by(|a, b| {
+        b.summary
+            .total_cost_usd
+            .partial_cmp(&a.summary.total_cost_usd)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| b.summary.total_tokens.cmp(&a.summary.total_tokens))
+            .then_with(|| a.name().cmp(b.name()))
+    });
+    items
+}
+
+/// Selection's share of the RPM, Throttle and Traffic dials, each in 0..=1.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct GaugeRatios {
+    pub(crate) token: f64,
+    pub(crate) spend: f64,
+    pub(crate) activity: f64,
+}
+
+/// One provider's tokens, spend and requests, each entry weighted.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct WeightedTotals {
+    pub(crate) provider: Arc<str>,
+    pub(crate) tokens: f64,
+    pub(crate) cost_usd: f64,
+    pub(crate) requests: f64,
+}
+
+/// Weighted totals behind the RPM, Throttle and Traffic dials.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct GaugeTotals {
+    pub(crate) tokens: f64,
+    /// The busiest single provider's tokens, or the selection's if larger.
+    pub(crate) busiest_tokens: f64,
+    pub(crate) cost_usd: f64,
+    pub(crate) busiest_cost_usd: f64,
+    pub(crate) requests: f64,
+    pub(crate) all_requests: f64,
+}
+
+impl GaugeTotals {
+    /// Totals for `selection` from each provider's tokens, spend and
+    /// requests, weighted or not.
+    pub(crate) fn from_providers<'a>(
+        providers: impl IntoIterator<Item = (&'a str, f64, f64, f64)>,
+        selection: &[&str],
+    ) -> GaugeTotals {
+        let mut totals = GaugeTotals::default();
+        let mut busiest_tokens = 0.0_f64;
+        let mut busiest_cost_usd = 0.0_f64;
+        for (provider, tokens, cost_usd, requests) in providers {
+            busiest_tokens = busiest_tokens.max(tokens);
+            busiest_cost_usd = busiest_cost_usd.max(cost_usd);
+            totals.all_requests += requests;
+            if selection.contains(&provider) {
+                totals.tokens += tokens;
+                totals.cost_usd += cost_usd;
+                totals.requests += requests;
+            }
+        }
+        totals.busiest_tokens = busiest_tokens.max(totals.tokens);
+        totals.busiest_cost_usd = busiest_cost_usd.max(totals.cost_usd);
+        totals
+    }
+
+    /// [`GaugeTotals::from_providers`] over [`decayed_totals`].
+    pub(crate) fn from_weighted(weighted: &[WeightedTotals], selection: &[&str]) -> GaugeTotals {
+        GaugeTotals::from_providers(
+            weighted.iter().map(|totals| {
+                (
+                    &*totals.provider,
+                    totals.tokens,
+                    totals.cost_usd,
+                    totals.requests,
+                )
+            }),
+            selection,
+        )
+    }
+
+    pub(crate) fn ratios(&self) -> GaugeRatios {
+        let share = |part: f64, whole: f64| {
+            if whole <= f64::EPSILON {
+                0.0
+            } else {
+                (part / whole).clamp(0.0, 1.0)
+            }
+        };
+        GaugeRatios {
+            token: share(self.tokens, self.busiest_tokens),
+            spend: share(self.cost_usd, self.busiest_cost_usd),
+            activity: share(self.requests, self.all_requests),
+        }
+    }
+}
+
+/// Each provider's tokens, spend and requests with each entry weighted by
+/// `0.5^(age / half_life)`, so the dials follow recent behaviour, or
+/// `None` without a half-life. Entries whose timestamp cannot be parsed
+/// carry no weight. Every weight halves alike as time passes, so ratios
+/// taken from these hold until the entries change and they can be worked
+/// out once per refresh.
+pub(crate) fn decayed_totals(
+    data: &UsageData,
+    half_life_hours: Option<f64>,
+    now_epoch_secs: i64,
+) -> Option<Vec<WeightedTotals>> {
+    let half_life_secs = half_life_hours.filter(|hours| *hours > 0.0)? * 3600.0;
+    Some(weighted_totals(data, |entry| {
+        clock::parse_timestamp(&entry.timestamp).map_or(0.0, |at| {
+            let age_secs = (now_epoch_secs - at).max(0) as f64;
+            0.5_f64.powf(age_secs / half_life_secs)
+        })
+    }))
+}
+
+fn weighted_totals(data: &UsageData, weight: impl Fn(&UsageEntry) -> f64) -> Vec<WeightedTotals> {
+    let mut per_provider: BTreeMap<&Arc<str>, (f64, f64, f64)> = BTreeMap::new();
+    for entry in &data.entries {
+        let weight = weight(entry);
+        let provider = per_provider.entry(&entry.provider).or_default();
+        provider.0 += (entry.input_tokens + entry.output_tokens) as f64 * weight;
+        provider.1 += entry.cost_usd * weight;
+        provider.2 += weight;
+    }
+    per_provider
+        .into_iter()
+        .map(|(provider, (tokens, cost_usd, requests))| WeightedTotals {
+            provider: provider.clone(),
+            tokens,
+            cost_usd,
+            requests,
+        })
+        .collect()
+}
+
+/// Dollars per hour spent over the `window_secs` before `now_epoch_secs`.
+pub(crate) fn spend_rate_per_hour(data: &UsageData, now_epoch_secs: i64, window_secs: i64) -> f64 {
+    if window_secs <= 0 {
+        return 0.0;
+    }
+    let since = now_epoch_secs - window_secs;
+    let spent = data
+        .entries
+        .iter()
+        .filter(|entry| {
+            clock::parse_timestamp(&entry.timestamp)
+                .is_some_and(|at| (since..=now_epoch_secs).contains(&at))
+        })
+        .map(|entry| entry.cost_usd)
+        .sum::<f64>();
+    spent * 3600.0 / window_secs as f64
+}
+
+/// Cumulative spend curves for the forecast chart. X values are days since
+/// the start of the month (UTC), so both months share an axis.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct MonthSpend {
+    pub(crate) days_in_month: u32,
+    pub(crate) elapsed_days: f64,
+    pub(crate) current: Vec<(f64, f64)>,
+    pub(crate) previous: Vec<(f64, f64)>,
+    pub(crate) budget_usd: Option<f64>,
+}
+
+impl MonthSpend {
+    pub(crate) fn spent_usd(&self) -> f64 {
+        self.current.last().map_or(0.0, |(_, total)| *total)
+    }
+
+    /// Where a straight-line budget pace would be right now.
+    pub(crate) fn pace_usd(&self) -> Option<f64> {
+        self.budget_usd
+            .map(|budget| budget * self.elapsed_days / f64::from(self.days_in_month))
+    }
+}
+
+/// Month-to-date spend for the month containing `now_epoch_secs`, plus the
+/// previous month's full curve. Entries with unparseable timestamps are
+/// skipped.
+pub(crate) fn month_spend(data: &UsageData, now_epoch_secs: i64) -> MonthSpend {
+    month_spend_from(
+        data.entries.iter().filter_map(|entry| {
+            clock::parse_timestamp(&entry.timestamp).map(|at| (at, entry.cost_usd))
+        }),
+        data.budget_usd,
+        now_epoch_secs,
+    )
+}
+
+/// [`month_spend`] over `(time, cost)` pairs in any order.
+pub(crate) fn month_spend_from(
+    costs: impl IntoIterator<Item = (i64, f64)>,
+    budget_usd: Option<f64>,
+    now_epoch_secs: i64,
+) -> MonthSpend {
+    let (current_start, year, month) = clock::month_start(now_epoch_secs);
+    let (previous_year, previous_month) = clock::previous_month(year, month);
+    let previous_start =
+        clock::days_from_civil(previous_year, previous_month, 1) * clock::SECS_PER_DAY;
+    let previous_days = clock::days_in_month(previous_year, previous_month);
+    let elapsed_days = (now_epoch_secs - current_start) as f64 / clock::SECS_PER_DAY as f64;
+
+    let mut current = Vec::new();
+    let mut previous = Vec::new();
+    for (at, cost_usd) in costs {
+        if (current_start..=now_epoch_secs).contains(&at) {
+            current.push(((at - current_start) as f64, cost_usd));
+        } else if (previous_start..current_start).contains(&at) {
+            previous.push(((at - previous_start) as f64, cost_usd));
+        }
+    }
+
+    let mut current = cumulative_curve(current);
+    current.push((
+        elapsed_days,
+        current.last().map_or(0.0, |(_, total)| *total),
+    ));
+    let mut previous = cumulative_curve(previous);
+    if previous.len() > 1 {
+        previous.push((
+            f64::from(previous_days),
+            previous.last().map_or(0.0, |(_, total)| *total),
+        ));
+    }
+
+    MonthSpend {
+        days_in_month: clock::days_in_month(year, month),
+        elapsed_days,
+        current,
+        previous,
+        budget_usd,
+    }
+}
+
+fn cumulative_curve(mut points: Vec<(f64, f64)>) -> Vec<(f64, f64)> {
+    points.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+    let mut total = 0.0;
+    let mut curve = vec![(0.0, 0.0)];
+    for (offset_secs, cost_usd) in points {
+        total += cost_usd;
+        curve.push((offset_secs / clock::SECS_PER_DAY as f64, total));
+    }
+    curve
 }
 
 pub(crate) fn default_data_file() -> Result<PathBuf> {
@@ -257,6 +1313,26 @@ pub(crate) fn default_config_file() -> Result<PathBuf> {
     Ok(default_config_base_dir()?.join("config.json"))
 }
 
+pub(crate) fn default_store_file() -> Result<PathBuf> {
+    Ok(default_config_base_dir()?.join("store.json"))
+}
+
+/// Parsed Codex session state, kept next to the config file it was built
+/// with.
+pub(crate) fn import_cache_file(config_file: &Path) -> PathBuf {
+    config_file.with_file_name("codex-cache.json")
+}
+
+/// The alerts last handed to their channels, kept next to the config file
+/// so every process routing for it agrees on what was already sent.
+pub(crate) fn routed_alerts_file(config_file: &Path) -> PathBuf {
+    config_file.with_file_name("routed-alerts.json")
+}
+
+pub(crate) fn default_log_file() -> Result<PathBuf> {
+    Ok(default_config_base_dir()?.join("promptpetrol.log"))
+}
+
 fn default_config_base_dir() -> Result<PathBuf> {
     let base_dir = dirs::config_dir()
         .unwrap_or_else(|| PathBuf::from("."))
@@ -265,36 +1341,131 @@ fn default_config_base_dir() -> Result<PathBuf> {
     Ok(base_dir)
 }
 
+/// The config at `path`. Without a file, the defaults minus the placeholder
+/// API keys, kept in memory: only the setup wizard writes a first config.
 pub(crate) fn load_or_bootstrap_config(path: &Path) -> Result<AppConfig> {
     if path.exists() {
         let contents = fs::read_to_string(path)?;
-        let parsed = serde_json::from_str::<AppConfig>(&contents)?;
+        let mut parsed = serde_json::from_str::<AppConfig>(&contents)?;
+        apply_includes(&mut parsed, path)?;
+        validate_budget_schedule(&parsed)?;
+        validate_freeze(&parsed)?;
+        validate_provider_groups(&parsed)?;
+        LoadRange::from_config(&parsed).validate("load_")?;
+        if parsed.max_fps == 0 {
+            bail!("max_fps must be at least 1");
+        }
+        dashboard_layout(&parsed)?;
+        Keymap::new(&parsed)?;
         Ok(parsed)
     } else {
-        let seeded = AppConfig::default();
-        let payload = serde_json::to_string_pretty(&seeded)?;
-        fs::write(path, payload)?;
-        Ok(seeded)
+        let mut config = AppConfig::default();
+        config.api_keys.clear();
+        Ok(config)
     }
 }
 
+pub(crate) fn save_config(path: &Path, config: &AppConfig) -> Result<()> {
+    let payload = serde_json::to_string_pretty(config)?;
+    fs::write(path, payload)?;
+    Ok(())
+}
+
+/// Applies `edit` to the top-level object of the config file and writes it
+/// back. The file is edited as JSON rather than re-saved from the loaded
+/// config, so the rest of it stays as written and nothing merged in from
+/// `include` ends up in it.
+pub(crate) fn edit_config_json(
+    path: &Path,
+    edit: impl FnOnce(&mut Map<String, Value>) -> Result<()>,
+) -> Result<()> {
+    let contents = fs::read_to_string(path)?;
+    let mut config = serde_json::from_str::<Value>(&contents)
+        .map_err(|err| eyre!("{}: {err}", path.display()))?;
+    let Some(root) = config.as_object_mut() else {
+        bail!("{}: expected a JSON object", path.display());
+    };
+    edit(root)?;
+    write_atomically(path, serde_json::to_string_pretty(&config)?.as_bytes())
+}
+
+/// Writes through a sibling temp file and renames it into place, so a
+/// reader polling `path` never sees a half-written file.
+///
+/// The entries are serialized straight into the file, so a long history
+/// is never held in memory a second time as text.
+pub(crate) fn save_usage_data(path: &Path, data: &UsageData) -> Result<()> {
+    let _lock = lock::exclusive(path)?;
+    write_atomically_with(path, |out| Ok(serde_json::to_writer_pretty(out, data)?))
+}
+
+/// Writes through `<name>.tmp` and renames it into place, so a reader never
+/// sees a half-written file.
+pub(crate) fn write_atomically(path: &Path, contents: &[u8]) -> Result<()> {
+    write_atomically_with(path, |out| Ok(out.write_all(contents)?))
+}
+
+/// [`write_atomically`] for contents produced bit by bit by `write`. If
+/// `write` fails, the temp file is removed and `path` is left as it was.
+pub(crate) fn write_atomically_with(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<fs::File>) -> Result<()>,
+) -> Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+    let mut out = BufWriter::new(fs::File::create(&temp_path)?);
+    let written = write(&mut out).and_then(|()| Ok(out.flush()?));
+    drop(out);
+    if let Err(err) = written {
+        let _ = fs::remove_file(&temp_path);
+        return Err(err);
+    }
+    fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
 pub(crate) fn load_or_bootstrap_data(path: &Path, config: &AppConfig) -> Result<UsageData> {
     if path.exists() {
-        let contents = fs::read_to_string(path)?;
-        if let Ok(parsed) = serde_json::from_str::<UsageData>(&contents) {
-            return Ok(parsed);
-        }
-
-        let raw = serde_json::from_str::<RawUsageData>(&contents)?;
-        Ok(normalize_raw_usage(raw, config))
+        parse_usage_data(&read_locked(path)?, config)
     } else {
         let seeded = UsageData::default();
-        let payload = serde_json::to_string_pretty(&seeded)?;
-        fs::write(path, payload)?;
+        save_usage_data(path, &seeded)?;
         Ok(seeded)
     }
 }
 
+/// Records `path` as the data file each of `data`'s entries came from.
+pub(crate) fn mark_data_file(data: &mut UsageData, path: &Path) {
+    let path = path.display().to_string();
+    for entry in &mut data.entries {
+        entry.data_file = Some(path.clone());
+    }
+}
+
+/// Adds the entries of the data file at `path` to `data`. A budget in
+/// `data` wins over one in `more`.
+pub(crate) fn merge_data_file(data: &mut UsageData, path: &Path, mut more: UsageData) {
+    mark_data_file(&mut more, path);
+    data.budget_usd = data.budget_usd.or(more.budget_usd);
+    data.entries.append(&mut more.entries);
+}
+
+/// Reads a data file in either the stored or a raw provider format.
+pub(crate) fn parse_usage_data(contents: &str, config: &AppConfig) -> Result<UsageData> {
+    if let Ok(parsed) = serde_json::from_str::<UsageData>(contents) {
+        return Ok(parsed);
+    }
+    let raw = serde_json::from_str::<RawUsageData>(contents)?;
+    Ok(normalize_raw_usage(raw, config))
+}
+
+/// Reads `path` once no other process is writing it.
+pub(crate) fn read_locked(path: &Path) -> Result<String> {
+    let _lock = lock::shared(path)?;
+    Ok(fs::read_to_string(path)?)
+}
+
 fn normalize_raw_usage(raw: RawUsageData, config: &AppConfig) -> UsageData {
     let entries = raw
         .entries
@@ -319,24 +1490,76 @@ fn normalize_entry(raw: RawUsageEntry, config: &AppConfig) -> UsageEntry {
         _ => adapt_generic_tokens(&raw),
     };
 
-    let cost_usd = raw.cost_usd.unwrap_or_else(|| {
-        estimate_cost_usd(
-            &provider,
-            &raw.model,
-            input_tokens,
-            output_tokens,
-            &config.pricing,
-        )
-    });
+    let (foreign_cost, original_cost) = match (raw.cost, raw.currency.as_deref()) {
+        (Some(amount), Some(currency)) => convert_cost(amount, currency, config),
+        (Some(amount), None) => (Some(amount), None),
+        (None, _) => (None, None),
+    };
+    let (estimate, cost_source) = match (raw.billed_cost_usd, raw.cost_usd.or(foreign_cost)) {
+        (Some(cost_usd), _) => (
+            CostEstimate {
+                cost_usd,
+                pricing_match: None,
+            },
+            CostSource::Billing,
+        ),
+        (None, Some(cost_usd)) => (
+            CostEstimate {
+                cost_usd,
+                pricing_match: None,
+            },
+            CostSource::Payload,
+        ),
+        (None, None) => (
+            estimate_cost(
+                &provider,
+                &raw.model,
+                input_tokens,
+                output_tokens,
+                &config.pricing,
+            ),
+            CostSource::Estimated,
+        ),
+    };
 
     UsageEntry {
         timestamp: raw.timestamp,
-        provider,
-        model: raw.model,
+        provider: intern(&provider),
+        model: intern(&raw.model),
         input_tokens,
         output_tokens,
-        cost_usd,
+        cost_usd: estimate.cost_usd,
+        pricing_match: estimate.pricing_match,
+        cost_source: Some(cost_source),
+        original_cost,
+        api_key: raw.api_key.as_deref().map(mask_api_key),
+        project_id: raw.project_id,
+        tag: raw.tag,
+        data_file: None,
+    }
+}
+
+/// Converts `amount` billed in `currency` to the configured currency. The
+/// original is kept for exports; without a configured rate the entry falls
+/// back to an estimate from its tokens.
+fn convert_cost(
+    amount: f64,
+    currency: &str,
+    config: &AppConfig,
+) -> (Option<f64>, Option<OriginalCost>) {
+    let currency = currency.trim().to_uppercase();
+    if currency == config.currency.to_uppercase() {
+        return (Some(amount), None);
     }
+    let rate = config
+        .exchange_rates
+        .iter()
+        .find(|(code, _)| code.to_uppercase() == currency)
+        .map(|(_, rate)| *rate);
+    (
+        rate.map(|rate| amount * rate),
+        Some(OriginalCost { amount, currency }),
+    )
 }
 
 fn adapt_openai_tokens(raw: &RawUsageEntry) -> (u64, u64) {
@@ -438,38 +1661,88 @@ fn split_with_total(input: u64, output: u64, total: Option<u64>) -> (u64, u64) {
     (input, output)
 }
 
-pub(crate) fn estimate_cost_usd(
+pub(crate) fn estimate_cost(
     provider: &str,
     model: &str,
     input_tokens: u64,
     output_tokens: u64,
     pricing: &HashMap<String, ModelPricing>,
-) -> f64 {
-    if let Some(model_pricing) = lookup_pricing(pricing, provider, model) {
-        return (input_tokens as f64 / 1_000_000.0) * model_pricing.input_per_million_usd
-            + (output_tokens as f64 / 1_000_000.0) * model_pricing.output_per_million_usd;
+) -> CostEstimate {
+    if let Some((model_pricing, pricing_match)) = lookup_pricing(pricing, provider, model) {
+        return CostEstimate {
+            cost_usd: (input_tokens as f64 / 1_000_000.0) * model_pricing.input_per_million_usd
+                + (output_tokens as f64 / 1_000_000.0) * model_pricing.output_per_million_usd,
+            pricing_match: Some(pricing_match),
+        };
     }
 
-    0.0
+    CostEstimate {
+        cost_usd: 0.0,
+        pricing_match: Some(PricingMatch::Missing),
+    }
 }
 
 fn lookup_pricing<'a>(
     pricing: &'a HashMap<String, ModelPricing>,
     provider: &str,
     model: &str,
-) -> Option<&'a ModelPricing> {
+) -> Option<(&'a ModelPricing, PricingMatch)> {
+    matching_pricing(pricing, provider, model)
+        .map(|(_, model_pricing, pricing_match)| (model_pricing, pricing_match))
+}
+
+/// The pricing key `provider`/`model` is estimated with, its rates and how
+/// it matched.
+pub(crate) fn matching_pricing<'a>(
+    pricing: &'a HashMap<String, ModelPricing>,
+    provider: &str,
+    model: &str,
+) -> Option<(&'a str, &'a ModelPricing, PricingMatch)> {
     let exact = format!("{provider}/{model}");
-    if let Some(found) = pricing.get(&exact) {
-        return Some(found);
+    if let Some((key, found)) = pricing.get_key_value(&exact) {
+        return Some((key, found, PricingMatch::Exact));
     }
 
     let wildcard = format!("{provider}/*");
-    pricing.get(&wildcard)
+    if let Some((key, found)) = pricing.get_key_value(&wildcard) {
+        return Some((key, found, PricingMatch::Wildcard));
+    }
+
+    fuzzy_lookup_pricing(pricing, provider, model)
+        .map(|(key, found)| (key, found, PricingMatch::Fuzzy))
+}
+
+/// Picks the longest configured model for `provider` that `model` extends at
+/// a separator, so `claude-3.7-sonnet-latest` prices as `claude-3.7-sonnet`.
+fn fuzzy_lookup_pricing<'a>(
+    pricing: &'a HashMap<String, ModelPricing>,
+    provider: &str,
+    model: &str,
+) -> Option<(&'a str, &'a ModelPricing)> {
+    let provider_prefix = format!("{provider}/");
+    let model = model.to_lowercase();
+    pricing
+        .iter()
+        .filter_map(|(key, model_pricing)| {
+            let candidate = key.strip_prefix(&provider_prefix)?.to_lowercase();
+            if candidate.is_empty() || candidate == "*" {
+                return None;
+            }
+            let rest = model.strip_prefix(&candidate)?;
+            rest.starts_with(['-', '.', ':', '@', '_']).then_some((
+                candidate.len(),
+                key,
+                model_pricing,
+            ))
+        })
+        .max_by(|a, b| a.0.cmp(&b.0).then_with(|| b.1.cmp(a.1)))
+        .map(|(_, key, model_pricing)| (key.as_str(), model_pricing))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::make_temp_dir;
 
     #[test]
     fn normalizes_openai_entry() {
@@ -490,6 +1763,12 @@ mod tests {
                 total_tokens: None,
                 total_token_count: None,
                 cost_usd: None,
+                billed_cost_usd: None,
+                cost: None,
+                currency: None,
+                api_key: None,
+                project_id: None,
+                tag: None,
             }],
         };
 
@@ -499,6 +1778,437 @@ mod tests {
         assert!(normalized.entries[0].cost_usd > 0.0);
     }
 
+    #[test]
+    fn merged_data_files_keep_where_each_entry_came_from() {
+        let config = AppConfig::default();
+        let mut data = parse_usage_data(
+            r#"{"budget_usd": 50, "entries": [{"timestamp": "2026-02-10T03:15:00Z", "provider": "openai", "model": "gpt-4.1-mini", "input_tokens": 10, "output_tokens": 5, "cost_usd": 0.5}]}"#,
+            &config,
+        )
+        .expect("main file");
+        let tools = parse_usage_data(
+            r#"{"budget_usd": 80, "entries": [{"timestamp": "2026-02-09T00:00:00Z", "provider": "anthropic", "model": "claude-3.7-sonnet", "prompt_tokens": 100, "completion_tokens": 20}]}"#,
+            &config,
+        )
+        .expect("tool log");
+
+        mark_data_file(&mut data, Path::new("manual.json"));
+        merge_data_file(&mut data, Path::new("tools.json"), tools);
+        assert_eq!(data.budget_usd, Some(50.0));
+        assert_eq!(
+            data.entries
+                .iter()
+                .map(|entry| (&*entry.provider, entry.data_file.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("openai", Some("manual.json")),
+                ("anthropic", Some("tools.json")),
+            ]
+        );
+        let stored = serde_json::to_value(&data.entries[1]).expect("entry");
+        assert_eq!(stored["data_file"], "tools.json");
+    }
+
+    #[test]
+    fn saved_data_is_streamed_into_place_and_reads_back() {
+        let path = make_temp_dir("save").join("usage.json");
+        let config = AppConfig::default();
+        let data = parse_usage_data(
+            r#"{"budget_usd": 25, "entries": [
+                {"timestamp": "2026-03-01T10:00:00Z", "provider": "openai", "model": "gpt-4.1-mini",
+                 "input_tokens": 1200, "output_tokens": 300, "cost_usd": 0.75, "tag": "search-api"},
+                {"timestamp": "2026-03-01T11:00:00Z", "provider": "anthropic", "model": "claude-sonnet-4",
+                 "input_tokens": 800, "output_tokens": 90, "cost_usd": 0.25},
+                {"timestamp": "2026-03-02T09:30:00Z", "provider": "codex", "model": "gpt-5-codex",
+                 "input_tokens": 5000, "output_tokens": 700, "cost_usd": 1.5, "api_key": "team"}
+            ]}"#,
+            &config,
+        )
+        .expect("data");
+        save_usage_data(&path, &data).expect("save");
+
+        let read = parse_usage_data(&read_locked(&path).expect("read"), &config).expect("parse");
+        assert_eq!(read.entries.len(), 3);
+        assert_eq!(read.entries, data.entries);
+        assert_eq!(read.budget_usd, Some(25.0));
+        assert!(!path.with_extension("json.tmp").exists());
+
+        // A failed write leaves the file as it was and no temp file behind.
+        let err = write_atomically_with(&path, |out| {
+            out.write_all(b"{\"entries\": [")?;
+            bail!("serializer gave up")
+        })
+        .expect_err("failed write");
+        assert_eq!(err.to_string(), "serializer gave up");
+        assert!(!path.with_extension("json.tmp").exists());
+        let reread = parse_usage_data(&read_locked(&path).expect("read"), &config).expect("parse");
+        assert_eq!(reread.entries, data.entries);
+        let _ = fs::remove_dir_all(path.parent().expect("dir"));
+    }
+
+    #[test]
+    fn records_cost_source_and_authoritative_share() {
+        let raw_entry = |cost_usd: Option<f64>, billed_cost_usd: Option<f64>| RawUsageEntry {
+            timestamp: "2026-02-10T03:15:00Z".to_string(),
+            provider: "openai".to_string(),
+            model: "gpt-4.1-mini".to_string(),
+            input_tokens: Some(1_000_000),
+            output_tokens: Some(0),
+            prompt_tokens: None,
+            completion_tokens: None,
+            request_tokens: None,
+            response_tokens: None,
+            prompt_token_count: None,
+            candidates_token_count: None,
+            total_tokens: None,
+            total_token_count: None,
+            cost_usd,
+            billed_cost_usd,
+            cost: None,
+            currency: None,
+            api_key: None,
+            project_id: None,
+            tag: None,
+        };
+        let raw = RawUsageData {
+            budget_usd: None,
+            entries: vec![
+                raw_entry(Some(0.6), Some(0.5)),
+                raw_entry(Some(0.1), None),
+                raw_entry(None, None),
+            ],
+        };
+
+        let normalized = normalize_raw_usage(raw, &AppConfig::default());
+        let sources = normalized
+            .entries
+            .iter()
+            .map(UsageEntry::effective_cost_source)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sources,
+            vec![
+                CostSource::Billing,
+                CostSource::Payload,
+                CostSource::Estimated
+            ]
+        );
+        assert_eq!(normalized.entries[0].cost_usd, 0.5);
+
+        let stats = UsageSummary::from_data(&normalized)
+            .provider_stats("openai")
+            .expect("openai stats");
+        let percent = authoritative_percent(stats.authoritative_cost_usd, stats.total_cost_usd)
+            .expect("non-zero total");
+        assert!((percent - 60.0).abs() < 1e-9);
+        assert_eq!(authoritative_percent(0.0, 0.0), None);
+    }
+
+    #[test]
+    fn converts_foreign_currency_costs_and_keeps_the_original() {
+        let raw = serde_json::from_str::<RawUsageData>(
+            r#"{"budget_usd": null, "entries": [
+                {"timestamp": "2026-02-10T03:15:00Z", "provider": "mistral", "model": "large",
+                 "cost": 2.0, "currency": "eur"},
+                {"timestamp": "2026-02-10T03:15:00Z", "provider": "mistral", "model": "large",
+                 "cost": 1.0, "currency": "USD"},
+                {"timestamp": "2026-02-10T03:15:00Z", "provider": "mistral", "model": "large",
+                 "cost": 5.0, "currency": "GBP"}
+            ]}"#,
+        )
+        .expect("raw usage");
+        let mut config = AppConfig::default();
+        config.exchange_rates.insert("EUR".to_string(), 1.1);
+
+        let normalized = normalize_raw_usage(raw, &config);
+        let [eur, usd, gbp] = &normalized.entries[..] else {
+            panic!("expected three entries");
+        };
+        assert!((eur.cost_usd - 2.2).abs() < 1e-9);
+        assert_eq!(eur.effective_cost_source(), CostSource::Payload);
+        assert_eq!(
+            eur.original_cost,
+            Some(OriginalCost {
+                amount: 2.0,
+                currency: "EUR".to_string()
+            })
+        );
+        assert_eq!((usd.cost_usd, usd.original_cost.clone()), (1.0, None));
+        assert_eq!(gbp.effective_cost_source(), CostSource::Estimated);
+        assert_eq!(
+            gbp.original_cost.as_ref().map(|cost| cost.amount),
+            Some(5.0)
+        );
+
+        let summary = &provider_summaries(&normalized)[0];
+        assert_eq!(summary.original_costs.get("EUR"), Some(&2.0));
+        assert_eq!(summary.original_costs.get("GBP"), Some(&5.0));
+    }
+
+    #[test]
+    fn groups_spend_by_api_key_and_masks_secrets() {
+        let raw = serde_json::from_str::<RawUsageData>(
+            r#"{"budget_usd": null, "entries": [
+                {"timestamp": "2026-02-10T03:15:00Z", "provider": "openai", "model": "m",
+                 "cost_usd": 1.0, "api_key": "sk-proj-abcdefghijklmnop"},
+                {"timestamp": "2026-02-10T03:15:00Z", "provider": "anthropic", "model": "m",
+                 "cost_usd": 2.0, "key_alias": "billing-service"},
+                {"timestamp": "2026-02-10T03:15:00Z", "provider": "openai", "model": "m",
+                 "cost_usd": 0.5, "key_alias": "billing-service"},
+                {"timestamp": "2026-02-10T03:15:00Z", "provider": "openai", "model": "m",
+                 "cost_usd": 9.0}
+            ]}"#,
+        )
+        .expect("raw usage");
+        let normalized = normalize_raw_usage(raw, &AppConfig::default());
+        let keys = key_summaries(&normalized);
+
+        assert_eq!(keys.len(), 3);
+        assert_eq!(keys[0].name.as_deref(), Some("billing-service"));
+        assert_eq!(keys[0].providers, vec!["anthropic", "openai"]);
+        assert_eq!((keys[0].requests, keys[0].total_cost_usd), (2, 2.5));
+        assert_eq!(keys[1].name.as_deref(), Some("sk-…mnop"));
+        assert_eq!(
+            (keys[2].name.as_deref(), keys[2].total_cost_usd),
+            (None, 9.0)
+        );
+        assert_eq!(mask_api_key("sk-short"), "sk-short");
+    }
+
+    #[test]
+    fn leaderboard_ranks_models_by_achieved_output_cost() {
+        let entry = |timestamp: &str, model: &str, output_tokens: u64, cost_usd: f64| UsageEntry {
+            timestamp: timestamp.to_string(),
+            provider: "openai".into(),
+            model: model.into(),
+            input_tokens: 10_000,
+            output_tokens,
+            cost_usd,
+            pricing_match: None,
+            cost_source: Some(CostSource::Payload),
+            original_cost: None,
+            api_key: None,
+            project_id: None,
+            tag: None,
+            data_file: None,
+        };
+        let data = UsageData {
+            budget_usd: None,
+            entries: vec![
+                entry("2026-10-01T00:00:00Z", "gpt-4.1", 500_000, 4.0),
+                entry("2026-10-10T00:00:00Z", "gpt-4.1", 500_000, 4.0),
+                entry("2026-10-10T00:00:00Z", "gpt-4.1-mini", 250_000, 0.5),
+                entry("2026-10-10T00:00:00Z", "text-embedding-3", 0, 0.1),
+            ],
+        };
+        let ranked = model_leaderboard(&data, None);
+        assert_eq!(
+            ranked
+                .iter()
+                .map(|model| (model.model.as_str(), model.cost_per_million_output()))
+                .collect::<Vec<_>>(),
+            vec![("gpt-4.1-mini", 2.0), ("gpt-4.1", 8.0)]
+        );
+        assert_eq!(ranked[1].authoritative_cost_usd, 8.0);
+
+        let since = clock::parse_timestamp("2026-10-05T00:00:00Z").expect("timestamp");
+        assert_eq!(
+            model_leaderboard(&data, Some(since))[1].output_tokens,
+            500_000
+        );
+    }
+
+    #[test]
+    fn project_spend_uses_configured_names() {
+        let raw = serde_json::from_str::<RawUsageData>(
+            r#"{"budget_usd": null, "entries": [
+                {"timestamp": "2026-02-10T03:15:00Z", "provider": "openai", "model": "m",
+                 "cost_usd": 1.0, "project_id": "proj_search"},
+                {"timestamp": "2026-02-10T03:15:00Z", "provider": "openai", "model": "m",
+                 "cost_usd": 3.0, "project_id": "proj_unnamed"}
+            ]}"#,
+        )
+        .expect("raw usage");
+        let mut config = AppConfig::default();
+        config
+            .project_names
+            .insert("proj_search".to_string(), "Search".to_string());
+        let normalized = normalize_raw_usage(raw, &config);
+        assert_eq!(
+            normalized.entries[0].project_id.as_deref(),
+            Some("proj_search")
+        );
+
+        let names = project_summaries(&normalized, &config)
+            .into_iter()
+            .map(|group| group.name)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![Some("proj_unnamed".to_string()), Some("Search".to_string())]
+        );
+    }
+
+    #[test]
+    fn cost_multipliers_scale_providers_with_a_wildcard_fallback() {
+        let mut config = AppConfig::default();
+        let mut data = UsageData::default();
+        apply_cost_multipliers(&mut data, &config);
+        assert_eq!(data.entries[0].cost_usd, 0.084);
+
+        config.cost_multipliers.insert("openai".to_string(), 1.2);
+        config.cost_multipliers.insert("*".to_string(), 1.1);
+        apply_cost_multipliers(&mut data, &config);
+        assert!((data.entries[0].cost_usd - 0.1008).abs() < 1e-9);
+        assert!((data.entries[1].cost_usd - 0.3971).abs() < 1e-9);
+        assert_eq!(cost_multiplier(&AppConfig::default(), "openai"), 1.0);
+    }
+
+    #[test]
+    fn combined_stats_sum_the_listed_providers_only() {
+        let data = UsageData::default();
+        let summary = UsageSummary::from_data(&data);
+        let combined = summary
+            .combined_stats(["openai", "anthropic", "missing"])
+            .expect("stats");
+        assert_eq!(combined.provider, "openai + anthropic + missing");
+        assert_eq!(combined.requests, 2);
+        assert_eq!(combined.total_tokens, 7_600 + 2_400 + 10_400 + 5_800);
+        assert!((combined.total_cost_usd - 0.445).abs() < 1e-9);
+        assert!(summary.combined_stats(["missing"]).is_none());
+    }
+
+    #[test]
+    fn provider_items_fold_group_members_and_list_them_when_opened() {
+        let mut data = UsageData::default();
+        let mut codex = data.entries[0].clone();
+        codex.provider = "codex".into();
+        codex.cost_usd = 1.0;
+        data.entries.push(codex);
+        let mut groups = BTreeMap::new();
+        groups.insert(
+            "OpenAI".to_string(),
+            vec!["openai".to_string(), "codex".to_string()],
+        );
+        groups.insert("Empty".to_string(), vec!["nobody".to_string()]);
+
+        let top = provider_items(provider_summaries(&data), &groups, None);
+        let names = top.iter().map(ProviderItem::name).collect::<Vec<_>>();
+        assert_eq!(names, vec!["OpenAI", "anthropic", "gemini"]);
+        assert_eq!(top[0].members, vec!["codex", "openai"]);
+        assert!((top[0].summary.total_cost_usd - 1.084).abs() < 1e-9);
+        assert!(!top[1].is_group());
+
+        let opened = provider_items(provider_summaries(&data), &groups, Some("OpenAI"));
+        let names = opened.iter().map(ProviderItem::name).collect::<Vec<_>>();
+        assert_eq!(names, vec!["codex", "openai"]);
+
+        let mut config = AppConfig {
+            provider_groups: groups,
+            ..AppConfig::default()
+        };
+        validate_provider_groups(&config).expect("disjoint groups");
+        config
+            .provider_groups
+            .insert("Agents".to_string(), vec!["codex".to_string()]);
+        assert_eq!(
+            validate_provider_groups(&config)
+                .expect_err("codex twice")
+                .to_string(),
+            "provider_groups: codex is in both Agents and OpenAI"
+        );
+    }
+
+    #[test]
+    fn month_spend_splits_current_and_previous_month_curves() {
+        let entry = |timestamp: &str, cost_usd: f64| UsageEntry {
+            timestamp: timestamp.to_string(),
+            provider: "openai".into(),
+            model: "gpt-4.1-mini".into(),
+            input_tokens: 0,
+            output_tokens: 0,
+            cost_usd,
+            pricing_match: None,
+            cost_source: None,
+            original_cost: None,
+            api_key: None,
+            project_id: None,
+            tag: None,
+            data_file: None,
+        };
+        let data = UsageData {
+            budget_usd: Some(28.0),
+            entries: vec![
+                entry("2026-02-02T00:00:00Z", 2.0),
+                entry("2026-01-31T12:00:00Z", 1.5),
+                entry("2026-02-01T00:00:00Z", 1.0),
+                entry("2026-03-01T00:00:00Z", 9.0),
+                entry("not a time", 9.0),
+            ],
+        };
+        let now = clock::parse_timestamp("2026-02-08T00:00:00Z").expect("valid");
+
+        let spend = month_spend(&data, now);
+        assert_eq!(spend.days_in_month, 28);
+        assert_eq!(spend.elapsed_days, 7.0);
+        assert_eq!(
+            spend.current,
+            vec![(0.0, 0.0), (0.0, 1.0), (1.0, 3.0), (7.0, 3.0)]
+        );
+        assert_eq!(spend.previous, vec![(0.0, 0.0), (30.5, 1.5), (31.0, 1.5)]);
+        assert_eq!(spend.spent_usd(), 3.0);
+        assert_eq!(spend.pace_usd(), Some(7.0));
+    }
+
+    #[test]
+    fn decayed_totals_weight_recent_entries() {
+        let entry = |provider: &str, timestamp: &str| UsageEntry {
+            timestamp: timestamp.to_string(),
+            provider: provider.into(),
+            model: "m".into(),
+            input_tokens: 100,
+            output_tokens: 0,
+            cost_usd: 1.0,
+            pricing_match: None,
+            cost_source: None,
+            original_cost: None,
+            api_key: None,
+            project_id: None,
+            tag: None,
+            data_file: None,
+        };
+        let data = UsageData {
+            budget_usd: None,
+            entries: vec![
+                entry("old", "2026-02-01T00:00:00Z"),
+                entry("old", "2026-02-01T00:00:00Z"),
+                entry("old", "2026-02-01T00:00:00Z"),
+                entry("new", "2026-02-10T00:00:00Z"),
+            ],
+        };
+        let now = clock::parse_timestamp("2026-02-10T00:00:00Z").expect("valid");
+
+        let all_time =
+            GaugeTotals::from_weighted(&weighted_totals(&data, |_| 1.0), &["new"]).ratios();
+        assert!((all_time.token - 1.0 / 3.0).abs() < 1e-9);
+        assert!((all_time.activity - 0.25).abs() < 1e-9);
+
+        let decayed = decayed_totals(&data, Some(24.0), now).expect("weighted");
+        let recent = GaugeTotals::from_weighted(&decayed, &["new"]).ratios();
+        assert_eq!(recent.token, 1.0);
+        assert_eq!(recent.spend, 1.0);
+        assert!(recent.activity > 0.99);
+        // Worked out once per refresh, the ratios still hold a day later.
+        let later = decayed_totals(&data, Some(24.0), now + clock::SECS_PER_DAY).expect("weighted");
+        let later = GaugeTotals::from_weighted(&later, &["new"]).ratios();
+        assert!((later.activity - recent.activity).abs() < 1e-9);
+        assert_eq!(decayed_totals(&data, None, now), None);
+        assert_eq!(
+            GaugeTotals::from_weighted(&decayed, &[]).ratios(),
+            GaugeRatios::default()
+        );
+    }
+
     #[test]
     fn normalizes_gemini_total_only() {
         let raw = RawUsageData {
@@ -518,6 +2228,12 @@ mod tests {
                 total_tokens: None,
                 total_token_count: Some(1000),
                 cost_usd: None,
+                billed_cost_usd: None,
+                cost: None,
+                currency: None,
+                api_key: None,
+                project_id: None,
+                tag: None,
             }],
         };
 
@@ -525,4 +2241,76 @@ mod tests {
         assert_eq!(normalized.entries[0].input_tokens, 500);
         assert_eq!(normalized.entries[0].output_tokens, 500);
     }
+
+    #[test]
+    fn fuzzy_pricing_matches_longest_configured_prefix_at_separator() {
+        let mut pricing = AppConfig::default().pricing;
+        pricing.insert(
+            "anthropic/claude-3.7".to_string(),
+            ModelPricing {
+                input_per_million_usd: 1.0,
+                output_per_million_usd: 1.0,
+            },
+        );
+
+        let estimate = estimate_cost(
+            "anthropic",
+            "claude-3.7-sonnet-latest",
+            1_000_000,
+            0,
+            &pricing,
+        );
+        assert_eq!(estimate.pricing_match, Some(PricingMatch::Fuzzy));
+        assert_eq!(estimate.cost_usd, 3.0);
+
+        let exact = estimate_cost("anthropic", "claude-3.7-sonnet", 1_000_000, 0, &pricing);
+        assert_eq!(exact.pricing_match, Some(PricingMatch::Exact));
+
+        let no_boundary = estimate_cost("openai", "gpt-4.1-minimal", 1_000_000, 0, &pricing);
+        assert_eq!(no_boundary.pricing_match, Some(PricingMatch::Missing));
+        assert_eq!(no_boundary.cost_usd, 0.0);
+    }
+
+    #[test]
+    fn unpriced_models_groups_missing_pricing_by_provider_and_model() {
+        let raw = RawUsageData {
+            budget_usd: None,
+            entries: ["mystery-1", "mystery-1", "gpt-4.1-mini"]
+                .into_iter()
+                .map(|model| RawUsageEntry {
+                    timestamp: "2026-02-10T03:15:00Z".to_string(),
+                    provider: "openai".to_string(),
+                    model: model.to_string(),
+                    input_tokens: Some(100),
+                    output_tokens: Some(50),
+                    prompt_tokens: None,
+                    completion_tokens: None,
+                    request_tokens: None,
+                    response_tokens: None,
+                    prompt_token_count: None,
+                    candidates_token_count: None,
+                    total_tokens: None,
+                    total_token_count: None,
+                    cost_usd: None,
+                    billed_cost_usd: None,
+                    cost: None,
+                    currency: None,
+                    api_key: None,
+                    project_id: None,
+                    tag: None,
+                })
+                .collect(),
+        };
+
+        let normalized = normalize_raw_usage(raw, &AppConfig::default());
+        assert_eq!(
+            unpriced_models(&normalized),
+            vec![UnpricedModel {
+                provider: "openai".to_string(),
+                model: "mystery-1".to_string(),
+                entries: 2,
+                total_tokens: 300,
+            }]
+        );
+    }
 }
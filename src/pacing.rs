@@ -0,0 +1,119 @@
+use std::collections::BTreeMap;
+
+use crate::clock;
+use crate::codex_import::CodexRateLimit;
+use crate::models::UsageData;
+
+/// How much more Codex work fits in a limit window before it resets.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct WindowPacing {
+    /// Codex tokens (input and output) the window took per percent used.
+    pub(crate) tokens_per_percent: f64,
+    pub(crate) tokens_left: u64,
+    /// How many more sessions of each `codex_session_tokens` size fit, in
+    /// name order.
+    pub(crate) sessions_left: Vec<(String, u64)>,
+}
+
+/// Works out what is left of `limit` in tokens from the Codex tokens used
+/// since the window opened against the share it used. `None` before
+/// anything in the window says how tokens turn into percent, or once it
+/// has reset and the reading is out of date.
+pub(crate) fn window_pacing(
+    limit: &CodexRateLimit,
+    data: &UsageData,
+    session_tokens: &BTreeMap<String, u64>,
+    now_epoch_secs: i64,
+) -> Option<WindowPacing> {
+    let resets_at = limit.resets_at? as i64;
+    if now_epoch_secs >= resets_at || limit.used_percent <= 0.0 {
+        return None;
+    }
+    let opened_at = resets_at - limit.window_minutes as i64 * 60;
+    let used_tokens = data
+        .entries
+        .iter()
+        .filter(|entry| &*entry.provider == "codex")
+        .filter(|entry| {
+            clock::parse_timestamp(&entry.timestamp)
+                .is_some_and(|at| (opened_at..=now_epoch_secs).contains(&at))
+        })
+        .map(|entry| entry.input_tokens + entry.output_tokens)
+        .sum::<u64>();
+    if used_tokens == 0 {
+        return None;
+    }
+    let tokens_per_percent = used_tokens as f64 / limit.used_percent;
+    let tokens_left = ((100.0 - limit.used_percent).max(0.0) * tokens_per_percent) as u64;
+    let sessions_left = session_tokens
+        .iter()
+        .filter(|(_, tokens)| **tokens > 0)
+        .map(|(name, tokens)| (name.clone(), tokens_left / tokens))
+        .collect();
+    Some(WindowPacing {
+        tokens_per_percent,
+        tokens_left,
+        sessions_left,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::UsageEntry;
+
+    #[test]
+    fn pacing_scales_the_windows_tokens_to_what_is_left() {
+        let now = clock::parse_timestamp("2026-02-18T12:00:00Z").expect("timestamp");
+        let entry = |timestamp: &str, provider: &str, input_tokens: u64| UsageEntry {
+            timestamp: timestamp.to_string(),
+            provider: provider.into(),
+            model: "codex-cli".into(),
+            input_tokens,
+            output_tokens: 0,
+            cost_usd: 0.0,
+            pricing_match: None,
+            cost_source: None,
+            original_cost: None,
+            api_key: None,
+            project_id: None,
+            tag: None,
+            data_file: None,
+        };
+        let data = UsageData {
+            budget_usd: None,
+            entries: vec![
+                // Before the window opened.
+                entry("2026-02-18T08:00:00Z", "codex", 9_000_000),
+                entry("2026-02-18T10:00:00Z", "codex", 1_500_000),
+                entry("2026-02-18T11:00:00Z", "openai", 7_000_000),
+                entry("2026-02-18T11:30:00Z", "codex", 1_500_000),
+            ],
+        };
+        let limit = CodexRateLimit {
+            used_percent: 25.0,
+            window_minutes: 300,
+            resets_at: Some(now as u64 + 2 * 3600),
+        };
+        let sizes = BTreeMap::from([
+            ("heavy".to_string(), 2_000_000),
+            ("light".to_string(), 250_000),
+            ("none".to_string(), 0),
+        ]);
+
+        let pacing = window_pacing(&limit, &data, &sizes, now).expect("pacing");
+        assert_eq!(pacing.tokens_per_percent, 120_000.0);
+        assert_eq!(pacing.tokens_left, 9_000_000);
+        assert_eq!(
+            pacing.sessions_left,
+            vec![("heavy".to_string(), 4), ("light".to_string(), 36)]
+        );
+
+        assert!(window_pacing(&limit, &data, &sizes, now + 2 * 3600).is_none());
+        let idle = CodexRateLimit {
+            used_percent: 0.0,
+            ..limit
+        };
+        assert!(window_pacing(&idle, &data, &sizes, now).is_none());
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::BTreeMap;
+
+use crate::clock;
+use crate::models::{
+    AppConfig, CostSource, ModelPricing, PricingMatch, PricingOrigin, UsageData, UsageEntry,
+    cost_multiplier, matching_pricing,
+};
+
+/// Tokens and cost of a model's entries that got their cost one way.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct CostPart {
+    pub(crate) entries: usize,
+    pub(crate) input_tokens: u64,
+    pub(crate) output_tokens: u64,
+    /// As recorded, `cost_multipliers` included.
+    pub(crate) cost_usd: f64,
+}
+
+impl CostPart {
+    fn add(&mut self, entry: &UsageEntry) {
+        self.entries += 1;
+        self.input_tokens += entry.input_tokens;
+        self.output_tokens += entry.output_tokens;
+        self.cost_usd += entry.cost_usd;
+    }
+}
+
+/// How one model's cost on the leaderboard came about, for the `b` popover:
+/// what was estimated from which rates, what the source reported and what
+/// was applied on top.
+#[derive(Debug, Clone)]
+pub(crate) struct PricingBreakdown {
+    pub(crate) provider: String,
+    pub(crate) model: String,
+    /// The pricing key estimates use today, how it matched and its rates.
+    pub(crate) rates: Option<(String, PricingMatch, ModelPricing)>,
+    pub(crate) origin: Option<PricingOrigin>,
+    /// Costed locally from pricing.
+    pub(crate) estimated: CostPart,
+    /// Costed by the source, from its payload or a billing export.
+    pub(crate) reported: CostPart,
+    /// Reported costs billed in another currency, by currency, before
+    /// conversion.
+    pub(crate) converted: BTreeMap<String, f64>,
+    pub(crate) multiplier: f64,
+}
+
+impl PricingBreakdown {
+    pub(crate) fn total_usd(&self) -> f64 {
+        self.estimated.cost_usd + self.reported.cost_usd
+    }
+
+    /// `part`'s tokens at today's rates, before any multiplier.
+    pub(crate) fn list_cost(&self, part: &CostPart) -> Option<f64> {
+        let (_, _, rates) = self.rates.as_ref()?;
+        Some(
+            part.input_tokens as f64 / 1_000_000.0 * rates.input_per_million_usd
+                + part.output_tokens as f64 / 1_000_000.0 * rates.output_per_million_usd,
+        )
+    }
+
+    /// How far reported costs sit below list price, e.g. `0.25` when cache
+    /// reads and batch discounts took a quarter off; negative above it.
+    pub(crate) fn reported_discount(&self) -> Option<f64> {
+        let list = self.list_cost(&self.reported).filter(|list| *list > 0.0)?;
+        Some(1.0 - self.reported.cost_usd / self.multiplier / list)
+    }
+
+    /// Estimates were recorded with other rates than today's, e.g. before
+    /// a pricing change, so their cost differs from tokens times rates.
+    pub(crate) fn estimates_are_stale(&self) -> bool {
+        self.list_cost(&self.estimated)
+            .is_some_and(|list| (list * self.multiplier - self.estimated.cost_usd).abs() > 0.000_01)
+    }
+}
+
+/// The breakdown of `provider`/`model` over entries from
+/// `since_epoch_secs` on (all of them for `None`), the same window the
+/// leaderboard row covers.
+pub(crate) fn pricing_breakdown(
+    config: &AppConfig,
+    data: &UsageData,
+    provider: &str,
+    model: &str,
+    since_epoch_secs: Option<i64>,
+) -> PricingBreakdown {
+    let rates = matching_pricing(&config.pricing, provider, model)
+        .map(|(key, rates, pricing_match)| (key.to_string(), pricing_match, rates.clone()));
+    let origin = rates
+        .as_ref()
+        .and_then(|(key, _, _)| config.pricing_origins.get(key).cloned());
+    let mut breakdown = PricingBreakdown {
+        provider: provider.to_string(),
+        model: model.to_string(),
+        rates,
+        origin,
+        estimated: CostPart::default(),
+        reported: CostPart::default(),
+        converted: BTreeMap::new(),
+        multiplier: cost_multiplier(config, provider),
+    };
+    for entry in &data.entries {
+        if &*entry.provider != provider || &*entry.model != model {
+            continue;
+        }
+        if let Some(since) = since_epoch_secs
+            && clock::parse_timestamp(&entry.timestamp).is_none_or(|at| at < since)
+        {
+            continue;
+        }
+        match entry.effective_cost_source() {
+            CostSource::Estimated => breakdown.estimated.add(entry),
+            CostSource::Payload | CostSource::Billing => {
+                breakdown.reported.add(entry);
+                if let Some(original) = &entry.original_cost {
+                    *breakdown
+                        .converted
+                        .entry(original.currency.clone())
+                        .or_default() += original.amount;
+                }
+            }
+        }
+    }
+    breakdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::apply_cost_multipliers;
+
+    #[test]
+    fn splits_estimates_from_reported_costs_and_finds_the_discount() {
+        let mut config = AppConfig::default();
+        config.cost_multipliers.insert("openai".to_string(), 1.2);
+        config.pricing_origins.insert(
+            "openai/gpt-4.1-mini".to_string(),
+            PricingOrigin {
+                source: "team.json".to_string(),
+                updated_at: Some(1_772_000_000),
+            },
+        );
+        let mut data = serde_json::from_str::<UsageData>(
+            r#"{"budget_usd": 50, "entries": [
+                {"timestamp": "2026-03-01T10:00:00Z", "provider": "openai", "model": "gpt-4.1-mini",
+                 "input_tokens": 1000000, "output_tokens": 500000, "cost_usd": 1.0,
+                 "pricing_match": "exact", "cost_source": "estimated"},
+                {"timestamp": "2026-03-02T10:00:00Z", "provider": "openai", "model": "gpt-4.1-mini",
+                 "input_tokens": 2000000, "output_tokens": 0, "cost_usd": 0.6,
+                 "cost_source": "billing", "original_cost": {"amount": 0.55, "currency": "EUR"}},
+                {"timestamp": "2026-02-01T10:00:00Z", "provider": "openai", "model": "gpt-4.1-mini",
+                 "input_tokens": 9, "output_tokens": 9, "cost_usd": 9.0},
+                {"timestamp": "2026-03-02T10:00:00Z", "provider": "anthropic", "model": "gpt-4.1-mini",
+                 "input_tokens": 9, "output_tokens": 9, "cost_usd": 9.0}
+            ]}"#,
+        )
+        .expect("data");
+        apply_cost_multipliers(&mut data, &config);
+        let since = clock::parse_timestamp("2026-03-01T00:00:00Z");
+
+        let breakdown = pricing_breakdown(&config, &data, "openai", "gpt-4.1-mini", since);
+        let (key, pricing_match, _) = breakdown.rates.as_ref().expect("rates");
+        assert_eq!(
+            (key.as_str(), *pricing_match),
+            ("openai/gpt-4.1-mini", PricingMatch::Exact)
+        );
+        assert_eq!(
+            breakdown.origin.as_ref().expect("origin").source,
+            "team.json"
+        );
+        assert_eq!(breakdown.estimated.entries, 1);
+        assert_eq!(breakdown.reported.input_tokens, 2_000_000);
+        assert!((breakdown.total_usd() - 1.92).abs() < 1e-9);
+        // 1M in at $0.40 and 0.5M out at $1.60 is $1.20, not the $1.00
+        // recorded under older rates.
+        assert!(breakdown.estimates_are_stale());
+        // 2M in lists at $0.80; $0.60 was billed.
+        let discount = breakdown.reported_discount().expect("discount");
+        assert!((discount - 0.25).abs() < 1e-9);
+        assert_eq!(breakdown.converted["EUR"], 0.55);
+
+        let unpriced = pricing_breakdown(&config, &data, "anthropic", "gpt-4.1-mini", None);
+        assert!(unpriced.rates.is_none() && unpriced.origin.is_none());
+        assert_eq!(unpriced.reported.entries, 1);
+        assert_eq!(unpriced.reported_discount(), None);
+    }
+}
@@ -0,0 +1,326 @@
+use std::fs;
+use std::path::Path;
+
+use color_eyre::eyre::{Result, bail, eyre};
+use crossterm::event::{KeyCode, KeyEvent};
+use serde_json::{Map, Value};
+
+use crate::journal;
+use crate::lock;
+use crate::models::{
+    AppConfig, CostSource, ModelPricing, PricingMatch, UsageData, edit_config_json, estimate_cost,
+    matching_pricing, write_atomically,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PricingField {
+    Input,
+    Output,
+}
+
+pub(crate) enum PricingEditOutcome {
+    Continue,
+    Save(PricingChange),
+    Cancelled,
+}
+
+/// What `e` in the model view writes: `key`'s rates in the config, and
+/// the estimated entries of `provider`/`model` recosted with them.
+#[derive(Debug, Clone)]
+pub(crate) struct PricingChange {
+    pub(crate) key: String,
+    pub(crate) provider: String,
+    pub(crate) model: String,
+    pub(crate) pricing: ModelPricing,
+}
+
+/// The inline form `e` opens on a model row. Holds the rates as typed so
+/// a bad number can be fixed in place, and asks once more before saving.
+#[derive(Debug, Clone)]
+pub(crate) struct PricingEditor {
+    pub(crate) provider: String,
+    pub(crate) model: String,
+    /// The key saved: the model's own, even when a wildcard or fuzzy
+    /// match prices it today, so other models keep their rates.
+    pub(crate) key: String,
+    /// The key that prices the model now, when it is not `key`.
+    pub(crate) replaces: Option<String>,
+    pub(crate) input: String,
+    pub(crate) output: String,
+    pub(crate) field: PricingField,
+    /// Estimated entries of the model the new rates will recost.
+    pub(crate) affected: usize,
+    pub(crate) confirming: bool,
+    pub(crate) error: Option<String>,
+}
+
+impl PricingEditor {
+    /// Starts from the rates that price `provider`/`model` today, if any.
+    pub(crate) fn new(config: &AppConfig, data: &UsageData, provider: &str, model: &str) -> Self {
+        let key = format!("{provider}/{model}");
+        let current = matching_pricing(&config.pricing, provider, model);
+        let replaces = current
+            .filter(|(_, _, pricing_match)| *pricing_match != PricingMatch::Exact)
+            .map(|(matched, _, _)| matched.to_string());
+        let (input, output) = current.map_or_else(Default::default, |(_, rates, _)| {
+            (
+                rates.input_per_million_usd.to_string(),
+                rates.output_per_million_usd.to_string(),
+            )
+        });
+        let affected = data
+            .entries
+            .iter()
+            .filter(|entry| {
+                &*entry.provider == provider
+                    && &*entry.model == model
+                    && entry.effective_cost_source() == CostSource::Estimated
+            })
+            .count();
+        Self {
+            provider: provider.to_string(),
+            model: model.to_string(),
+            key,
+            replaces,
+            input,
+            output,
+            field: PricingField::Input,
+            affected,
+            confirming: false,
+            error: None,
+        }
+    }
+
+    pub(crate) fn handle_key(&mut self, key: KeyEvent) -> PricingEditOutcome {
+        if self.confirming {
+            return match key.code {
+                KeyCode::Enter | KeyCode::Char('y' | 'Y') => match self.change() {
+                    Ok(change) => PricingEditOutcome::Save(change),
+                    Err(err) => {
+                        self.confirming = false;
+                        self.error = Some(err);
+                        PricingEditOutcome::Continue
+                    }
+                },
+                KeyCode::Esc | KeyCode::Char('n' | 'N') => {
+                    self.confirming = false;
+                    PricingEditOutcome::Continue
+                }
+                _ => PricingEditOutcome::Continue,
+            };
+        }
+        match key.code {
+            KeyCode::Esc => return PricingEditOutcome::Cancelled,
+            KeyCode::Tab | KeyCode::BackTab | KeyCode::Up | KeyCode::Down => {
+                self.field = match self.field {
+                    PricingField::Input => PricingField::Output,
+                    PricingField::Output => PricingField::Input,
+                };
+            }
+            KeyCode::Enter => match self.change() {
+                Ok(_) => {
+                    self.error = None;
+                    self.confirming = true;
+                }
+                Err(err) => self.error = Some(err),
+            },
+            KeyCode::Backspace => {
+                self.text_field().pop();
+            }
+            KeyCode::Char(c) if c.is_ascii_digit() || c == '.' => self.text_field().push(c),
+            _ => {}
+        }
+        PricingEditOutcome::Continue
+    }
+
+    fn text_field(&mut self) -> &mut String {
+        match self.field {
+            PricingField::Input => &mut self.input,
+            PricingField::Output => &mut self.output,
+        }
+    }
+
+    fn change(&self) -> std::result::Result<PricingChange, String> {
+        Ok(PricingChange {
+            key: self.key.clone(),
+            provider: self.provider.clone(),
+            model: self.model.clone(),
+            pricing: ModelPricing {
+                input_per_million_usd: parse_rate("input", &self.input)?,
+                output_per_million_usd: parse_rate("output", &self.output)?,
+            },
+        })
+    }
+}
+
+fn parse_rate(name: &str, text: &str) -> std::result::Result<f64, String> {
+    match text.trim().parse::<f64>() {
+        Ok(rate) if rate.is_finite() && rate >= 0.0 => Ok(rate),
+        _ => Err(format!("The {name} rate must be a number, e.g. 0.40")),
+    }
+}
+
+/// Sets `key` under `pricing` in the config file, keeping the rest of the
+/// file as written.
+pub(crate) fn save_model_pricing(path: &Path, key: &str, pricing: &ModelPricing) -> Result<()> {
+    edit_config_json(path, |root| {
+        let Some(section) = root
+            .entry("pricing")
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+        else {
+            bail!("{}: pricing is not an object", path.display());
+        };
+        section.insert(key.to_string(), serde_json::to_value(pricing)?);
+        Ok(())
+    })
+}
+
+/// Recosts the estimated entries of `provider`/`model` in the data file
+/// with `config`'s pricing, journaled so `U` brings the old costs back.
+/// Entries without a stored cost are costed on every load and are left
+/// alone. Returns how many changed and the file as written, if it was.
+pub(crate) fn recost_data_file(
+    data_file: &Path,
+    config: &AppConfig,
+    provider: &str,
+    model: &str,
+) -> Result<(usize, Option<String>)> {
+    let _lock = lock::exclusive(data_file)?;
+    let previous = fs::read_to_string(data_file)?;
+    let mut data = serde_json::from_str::<Value>(&previous)?;
+    let entries = data
+        .get_mut("entries")
+        .and_then(Value::as_array_mut)
+        .ok_or_else(|| eyre!("{} has no entries list", data_file.display()))?;
+    let mut recosted = 0;
+    for entry in entries.iter_mut().filter_map(Value::as_object_mut) {
+        let text = |name: &str| entry.get(name).and_then(Value::as_str);
+        let estimated = match text("cost_source") {
+            Some(source) => source == "estimated",
+            None => entry.contains_key("pricing_match"),
+        };
+        if text("provider") != Some(provider)
+            || text("model") != Some(model)
+            || !estimated
+            || !entry.contains_key("cost_usd")
+        {
+            continue;
+        }
+        let tokens = |name: &str| entry.get(name).and_then(Value::as_u64).unwrap_or(0);
+        let estimate = estimate_cost(
+            provider,
+            model,
+            tokens("input_tokens"),
+            tokens("output_tokens"),
+            &config.pricing,
+        );
+        let pricing_match = serde_json::to_value(estimate.pricing_match)?;
+        if entry.get("cost_usd").and_then(Value::as_f64) == Some(estimate.cost_usd)
+            && entry.get("pricing_match") == Some(&pricing_match)
+        {
+            continue;
+        }
+        entry.insert("cost_usd".to_string(), estimate.cost_usd.into());
+        entry.insert("pricing_match".to_string(), pricing_match);
+        recosted += 1;
+    }
+    if recosted == 0 {
+        return Ok((0, None));
+    }
+    let contents = serde_json::to_string_pretty(&data)?;
+    journal::record(data_file, &previous, "recost")?;
+    write_atomically(data_file, contents.as_bytes())?;
+    Ok((recosted, Some(contents)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyModifiers;
+
+    use super::*;
+    use crate::models::{load_or_bootstrap_config, parse_usage_data};
+    use crate::test_support::make_temp_dir;
+
+    fn press(editor: &mut PricingEditor, code: KeyCode) -> PricingEditOutcome {
+        editor.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn edits_rates_then_saves_them_and_recosts_estimates() {
+        let dir = make_temp_dir("pricing-edit");
+        let config_file = dir.join("config.json");
+        let data_file = dir.join("usage.json");
+        fs::write(
+            &config_file,
+            r#"{"pricing": {"openai/*": {"input_per_million_usd": 1, "output_per_million_usd": 2}}, "currency": "USD"}"#,
+        )
+        .expect("config");
+        fs::write(
+            &data_file,
+            r#"{"budget_usd": 50, "entries": [
+                {"timestamp": "2026-03-01T10:00:00Z", "provider": "openai", "model": "o3",
+                 "input_tokens": 1000000, "output_tokens": 1000000, "cost_usd": 3.0,
+                 "pricing_match": "wildcard", "cost_source": "estimated"},
+                {"timestamp": "2026-03-01T11:00:00Z", "provider": "openai", "model": "o3",
+                 "input_tokens": 1000000, "output_tokens": 0, "cost_usd": 0.5},
+                {"timestamp": "2026-03-01T12:00:00Z", "provider": "openai", "model": "gpt-4o",
+                 "input_tokens": 1000000, "output_tokens": 0, "cost_usd": 1.0,
+                 "pricing_match": "wildcard", "cost_source": "estimated"}
+            ]}"#,
+        )
+        .expect("data");
+        let config = load_or_bootstrap_config(&config_file).expect("load config");
+        let data = parse_usage_data(&fs::read_to_string(&data_file).expect("read"), &config)
+            .expect("data");
+
+        let mut editor = PricingEditor::new(&config, &data, "openai", "o3");
+        assert_eq!(editor.key, "openai/o3");
+        assert_eq!(editor.replaces.as_deref(), Some("openai/*"));
+        assert_eq!((editor.input.as_str(), editor.output.as_str()), ("1", "2"));
+        assert_eq!(editor.affected, 1);
+
+        press(&mut editor, KeyCode::Backspace);
+        press(&mut editor, KeyCode::Enter);
+        assert!(editor.error.is_some() && !editor.confirming);
+        for c in "0.5".chars() {
+            press(&mut editor, KeyCode::Char(c));
+        }
+        press(&mut editor, KeyCode::Tab);
+        press(&mut editor, KeyCode::Char('x'));
+        press(&mut editor, KeyCode::Char('0'));
+        press(&mut editor, KeyCode::Enter);
+        assert!(editor.confirming);
+        press(&mut editor, KeyCode::Esc);
+        assert!(!editor.confirming);
+        press(&mut editor, KeyCode::Enter);
+        let PricingEditOutcome::Save(change) = press(&mut editor, KeyCode::Enter) else {
+            panic!("expected a save");
+        };
+        assert_eq!(change.pricing.input_per_million_usd, 0.5);
+        assert_eq!(change.pricing.output_per_million_usd, 20.0);
+
+        save_model_pricing(&config_file, &change.key, &change.pricing).expect("save");
+        let config = load_or_bootstrap_config(&config_file).expect("reload config");
+        assert_eq!(config.pricing.len(), 2);
+        assert_eq!(config.currency, "USD");
+        let saved = fs::read_to_string(&config_file).expect("read config");
+        assert!(saved.find("\"pricing\"") < saved.find("\"currency\""));
+        assert!(saved.find("\"openai/*\"") < saved.find("\"openai/o3\""));
+
+        let (recosted, contents) =
+            recost_data_file(&data_file, &config, "openai", "o3").expect("recost");
+        assert_eq!(recosted, 1);
+        let data = parse_usage_data(&contents.expect("written"), &config).expect("data");
+        assert_eq!(data.entries[0].cost_usd, 20.5);
+        assert_eq!(data.entries[0].pricing_match, Some(PricingMatch::Exact));
+        assert_eq!(data.entries[1].cost_usd, 0.5);
+        assert_eq!(data.entries[2].cost_usd, 1.0);
+        assert_eq!(journal::entries(&data_file).expect("journal").len(), 1);
+        assert_eq!(
+            recost_data_file(&data_file, &config, "openai", "o3").expect("again"),
+            (0, None)
+        );
+        fs::remove_dir_all(&dir).expect("cleanup");
+    }
+}
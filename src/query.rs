@@ -0,0 +1,370 @@
+use color_eyre::eyre::{Result, bail};
+
+use crate::clock;
+use crate::models::{UsageData, UsageEntry};
+
+/// A filter over usage entries such as
+/// `provider=codex and cost>0.5 and ts>=2026-02-01`: comparisons joined by
+/// `and`, with `or` between groups of them (`and` binds tighter). Text
+/// fields compare without regard to case, `~` tests for a substring, and
+/// a value with spaces goes in double quotes.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Query {
+    /// Alternatives, each a list of conditions that must all hold.
+    any_of: Vec<Vec<Condition>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Condition {
+    field: Field,
+    op: Op,
+    value: Value,
+}
+
+/// What a condition compares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Field {
+    Provider,
+    Model,
+    Tag,
+    Key,
+    Project,
+    Cost,
+    Tokens,
+    Input,
+    Output,
+    /// Unix seconds.
+    Time,
+}
+
+/// What a query can select from. Usage entries have every field; other
+/// things have the fields they can tell, and match no condition on the rest
+/// (except `!=`, as with an entry without a tag).
+pub(crate) trait Queryable {
+    /// The values of a text field; an entry has at most one, an alert
+    /// may be about several providers.
+    fn texts(&self, field: Field) -> impl Iterator<Item = &str>;
+    /// The value of a numeric field, [`Field::Time`] included.
+    fn number(&self, field: Field) -> Option<f64>;
+}
+
+impl Queryable for UsageEntry {
+    fn texts(&self, field: Field) -> impl Iterator<Item = &str> {
+        match field {
+            Field::Provider => Some(&*self.provider),
+            Field::Model => Some(&*self.model),
+            Field::Tag => self.tag.as_deref(),
+            Field::Key => self.api_key.as_deref(),
+            Field::Project => self.project_id.as_deref(),
+            _ => None,
+        }
+        .into_iter()
+    }
+
+    fn number(&self, field: Field) -> Option<f64> {
+        match field {
+            Field::Cost => Some(self.cost_usd),
+            Field::Tokens => Some((self.input_tokens + self.output_tokens) as f64),
+            Field::Input => Some(self.input_tokens as f64),
+            Field::Output => Some(self.output_tokens as f64),
+            Field::Time => clock::parse_timestamp(&self.timestamp).map(|at| at as f64),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Text(String),
+    Number(f64),
+    /// Unix seconds.
+    Time(i64),
+}
+
+/// Where two start at the same place the longer wins, so `>=` is not
+/// read as `>` followed by `=`.
+const OPERATORS: [(&str, Op); 7] = [
+    ("!=", Op::Ne),
+    ("<=", Op::Le),
+    (">=", Op::Ge),
+    ("=", Op::Eq),
+    ("<", Op::Lt),
+    (">", Op::Gt),
+    ("~", Op::Contains),
+];
+
+impl Field {
+    fn from_name(name: &str) -> Option<Field> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "provider" => Field::Provider,
+            "model" => Field::Model,
+            "tag" => Field::Tag,
+            "key" | "api_key" => Field::Key,
+            "project" | "project_id" => Field::Project,
+            "cost" | "cost_usd" => Field::Cost,
+            "tokens" => Field::Tokens,
+            "input" | "input_tokens" => Field::Input,
+            "output" | "output_tokens" => Field::Output,
+            "ts" | "time" | "timestamp" => Field::Time,
+            _ => return None,
+        })
+    }
+
+    fn is_text(self) -> bool {
+        matches!(
+            self,
+            Field::Provider | Field::Model | Field::Tag | Field::Key | Field::Project
+        )
+    }
+}
+
+impl Query {
+    pub(crate) fn parse(input: &str) -> Result<Query> {
+        let mut any_of = Vec::new();
+        let mut all_of = Vec::new();
+        let mut clause = String::new();
+        for word in words(input)? {
+            let joiner = word.to_ascii_lowercase();
+            if joiner == "and" || joiner == "or" {
+                if clause.is_empty() {
+                    bail!("query: `{word}` needs a condition before it");
+                }
+                all_of.push(Condition::parse(&std::mem::take(&mut clause))?);
+                if joiner == "or" {
+                    any_of.push(std::mem::take(&mut all_of));
+                }
+            } else {
+                clause.push_str(&word);
+            }
+        }
+        if clause.is_empty() {
+            bail!("query: expected a condition such as provider=openai or cost>0.5");
+        }
+        all_of.push(Condition::parse(&clause)?);
+        any_of.push(all_of);
+        Ok(Query { any_of })
+    }
+
+    pub(crate) fn matches(&self, target: &impl Queryable) -> bool {
+        self.any_of
+            .iter()
+            .any(|all_of| all_of.iter().all(|condition| condition.matches(target)))
+    }
+
+    /// Drops the entries of `data` the query does not match.
+    pub(crate) fn retain(&self, data: &mut UsageData) {
+        data.entries.retain(|entry| self.matches(entry));
+    }
+}
+
+impl Condition {
+    /// `clause` is one comparison with the spaces around its operator
+    /// already gone, e.g. `cost>0.5` or `model="gpt 5"`.
+    fn parse(clause: &str) -> Result<Condition> {
+        let Some((at, symbol, op)) = OPERATORS
+            .iter()
+            .filter_map(|(symbol, op)| clause.find(symbol).map(|at| (at, *symbol, *op)))
+            .min_by_key(|(at, symbol, _)| (*at, std::cmp::Reverse(symbol.len())))
+        else {
+            bail!("query: `{clause}` has no comparison (=, !=, <, <=, >, >= or ~)");
+        };
+        let (name, raw) = (&clause[..at], unquote(&clause[at + symbol.len()..]));
+        let Some(field) = Field::from_name(name) else {
+            bail!(
+                "query: unknown field `{name}` (expected provider, model, tag, key, project, cost, tokens, input, output or ts)"
+            );
+        };
+        if raw.is_empty() {
+            bail!("query: `{clause}` has no value");
+        }
+        let value = match field {
+            _ if field.is_text() => {
+                if !matches!(op, Op::Eq | Op::Ne | Op::Contains) {
+                    bail!("query: {name} can only be compared with =, != or ~");
+                }
+                Value::Text(raw.to_lowercase())
+            }
+            _ if op == Op::Contains => bail!("query: ~ only applies to text fields"),
+            Field::Time => match clock::parse_timestamp(raw) {
+                Some(at) => Value::Time(at),
+                None => bail!("query: {raw} is not a date (expected YYYY-MM-DD or a timestamp)"),
+            },
+            _ => match raw.parse::<f64>() {
+                Ok(number) if number.is_finite() => Value::Number(number),
+                _ => bail!("query: {raw} is not a number"),
+            },
+        };
+        Ok(Condition { field, op, value })
+    }
+
+    fn matches(&self, target: &impl Queryable) -> bool {
+        match &self.value {
+            Value::Text(expected) => {
+                let mut actual = target.texts(self.field).map(|actual| actual.to_lowercase());
+                match self.op {
+                    Op::Ne => actual.all(|actual| actual != *expected),
+                    Op::Contains => actual.any(|actual| actual.contains(expected)),
+                    _ => actual.any(|actual| actual == *expected),
+                }
+            }
+            Value::Number(expected) => target
+                .number(self.field)
+                .is_some_and(|actual| self.op.holds(actual.partial_cmp(expected))),
+            // Entries whose time cannot be read match no time condition.
+            Value::Time(expected) => target
+                .number(self.field)
+                .is_some_and(|actual| self.op.holds((actual as i64).partial_cmp(expected))),
+        }
+    }
+}
+
+impl Op {
+    fn holds(self, ordering: Option<std::cmp::Ordering>) -> bool {
+        use std::cmp::Ordering::{Equal, Greater, Less};
+        match (self, ordering) {
+            (_, None) => false,
+            (Op::Eq, Some(ordering)) => ordering == Equal,
+            (Op::Ne, Some(ordering)) => ordering != Equal,
+            (Op::Lt, Some(ordering)) => ordering == Less,
+            (Op::Le, Some(ordering)) => ordering != Greater,
+            (Op::Gt, Some(ordering)) => ordering == Greater,
+            (Op::Ge, Some(ordering)) => ordering != Less,
+            (Op::Contains, Some(_)) => false,
+        }
+    }
+}
+
+/// Splits `input` on whitespace outside double quotes, keeping the quotes.
+fn words(input: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+    for ch in input.chars() {
+        match ch {
+            '"' => {
+                quoted = !quoted;
+                word.push(ch);
+            }
+            _ if ch.is_whitespace() && !quoted => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            _ => word.push(ch),
+        }
+    }
+    if quoted {
+        bail!("query: unclosed quote");
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(timestamp: &str, provider: &str, model: &str, cost_usd: f64) -> UsageEntry {
+        UsageEntry {
+            timestamp: timestamp.to_string(),
+            provider: provider.into(),
+            model: model.into(),
+            input_tokens: 1_000,
+            output_tokens: 200,
+            cost_usd,
+            pricing_match: None,
+            cost_source: None,
+            original_cost: None,
+            api_key: None,
+            project_id: None,
+            tag: Some("search-api".into()),
+            data_file: None,
+        }
+    }
+
+    fn matching(query: &str, entries: &[UsageEntry]) -> Vec<usize> {
+        let query = Query::parse(query).expect("query");
+        (0..entries.len())
+            .filter(|&index| query.matches(&entries[index]))
+            .collect()
+    }
+
+    #[test]
+    fn filters_entries_by_field_comparisons() {
+        let entries = [
+            entry("2026-01-31T23:59:00Z", "codex", "gpt-5-codex", 0.9),
+            entry("2026-02-01T00:00:00Z", "codex", "gpt-5-codex", 0.4),
+            entry("2026-02-03T10:00:00Z", "Codex", "gpt 5 mini", 0.6),
+            entry("2026-02-04T10:00:00Z", "anthropic", "claude-sonnet-4", 1.2),
+            entry("not a time", "codex", "gpt-5-codex", 2.0),
+        ];
+
+        assert_eq!(
+            matching("provider=codex and cost>0.5 and ts>=2026-02-01", &entries),
+            [2]
+        );
+        assert_eq!(
+            matching("PROVIDER = codex AND cost >= 0.9", &entries),
+            [0, 4]
+        );
+        assert_eq!(
+            matching("provider=anthropic or cost<0.5 and ts<2026-02-02", &entries),
+            [1, 3]
+        );
+        assert_eq!(
+            matching("model~mini or model=\"gpt 5 mini\"", &entries),
+            [2]
+        );
+        assert_eq!(matching("provider!=codex", &entries), [3]);
+        assert_eq!(
+            matching("tokens=1200 and project!=x and tag=search-api", &entries).len(),
+            5
+        );
+        assert_eq!(matching("key=team", &entries), Vec::<usize>::new());
+
+        let mut data = UsageData {
+            budget_usd: None,
+            entries: entries.to_vec(),
+        };
+        Query::parse("ts>=2026-02-04T00:00:00Z")
+            .expect("query")
+            .retain(&mut data);
+        assert_eq!(data.entries.len(), 1);
+
+        for (query, error) in [
+            ("", "expected a condition"),
+            ("cost>0.5 and", "expected a condition"),
+            ("or cost>1", "needs a condition"),
+            ("price>1", "unknown field `price`"),
+            ("provider>codex", "=, != or ~"),
+            ("cost~1", "only applies to text"),
+            ("cost>cheap", "not a number"),
+            ("ts>=yesterday", "not a date"),
+            ("model=\"gpt", "unclosed quote"),
+            ("provider", "no comparison"),
+        ] {
+            let message = Query::parse(query).expect_err(query).to_string();
+            assert!(message.contains(error), "{query}: {message}");
+        }
+    }
+}
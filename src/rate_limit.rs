@@ -0,0 +1,149 @@
+use std::cell::Cell;
+use std::fmt;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::models::AppConfig;
+
+/// Requests per minute a poller gets when `request_limits` does not say.
+const DEFAULT_REQUESTS_PER_MINUTE: u32 = 30;
+/// Longest a request waits for its budget; beyond that the pass stops and
+/// picks up again on the next refresh.
+const MAX_WAIT: Duration = Duration::from_secs(10);
+
+/// A token bucket holding up to a minute's worth of requests for one
+/// poller, refilled continuously.
+#[derive(Debug)]
+pub(crate) struct RequestBudget {
+    name: &'static str,
+    per_minute: Cell<u32>,
+    tokens: Cell<f64>,
+    updated: Cell<Instant>,
+}
+
+/// The budget ran dry for longer than a request is willing to wait.
+#[derive(Debug)]
+pub(crate) struct BudgetExhausted {
+    name: &'static str,
+    per_minute: u32,
+}
+
+impl fmt::Display for BudgetExhausted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} request budget of {}/min used up, continuing next refresh",
+            self.name, self.per_minute
+        )
+    }
+}
+
+impl std::error::Error for BudgetExhausted {}
+
+impl RequestBudget {
+    /// A full budget for the poller `name`, sized from `request_limits`.
+    pub(crate) fn new(name: &'static str, config: &AppConfig) -> Self {
+        let per_minute = requests_per_minute(config, name);
+        Self {
+            name,
+            per_minute: Cell::new(per_minute),
+            tokens: Cell::new(f64::from(per_minute)),
+            updated: Cell::new(Instant::now()),
+        }
+    }
+
+    /// Picks up a changed limit from a reloaded config, keeping what has
+    /// been spent.
+    pub(crate) fn configure(&self, config: &AppConfig) {
+        self.per_minute.set(requests_per_minute(config, self.name));
+    }
+
+    /// Takes one request from the budget, sleeping for the next one when it
+    /// is due soon enough.
+    pub(crate) fn acquire(&self) -> Result<(), BudgetExhausted> {
+        match self.try_take(Instant::now()) {
+            Ok(()) => Ok(()),
+            Err(wait) if wait <= MAX_WAIT => {
+                thread::sleep(wait);
+                if self.try_take(Instant::now()).is_err() {
+                    // Rounding can leave the bucket a hair short after the
+                    // computed wait; the request is due all the same.
+                    self.tokens.set(self.tokens.get() - 1.0);
+                }
+                Ok(())
+            }
+            Err(_) => Err(BudgetExhausted {
+                name: self.name,
+                per_minute: self.per_minute.get(),
+            }),
+        }
+    }
+
+    /// Takes a request if one is available, else says how long until one
+    /// is.
+    fn try_take(&self, now: Instant) -> Result<(), Duration> {
+        let per_minute = f64::from(self.per_minute.get().max(1));
+        let per_sec = per_minute / 60.0;
+        let elapsed = now.saturating_duration_since(self.updated.get());
+        let tokens = (self.tokens.get() + elapsed.as_secs_f64() * per_sec).min(per_minute);
+        self.updated.set(now);
+        if tokens >= 1.0 {
+            self.tokens.set(tokens - 1.0);
+            Ok(())
+        } else {
+            self.tokens.set(tokens);
+            Err(Duration::from_secs_f64((1.0 - tokens) / per_sec))
+        }
+    }
+}
+
+fn requests_per_minute(config: &AppConfig, name: &str) -> u32 {
+    config
+        .request_limits
+        .get(name)
+        .copied()
+        .unwrap_or(DEFAULT_REQUESTS_PER_MINUTE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_allows_a_minute_of_requests_then_refills() {
+        let mut config = AppConfig::default();
+        config.request_limits.insert("langfuse".to_string(), 6);
+        let budget = RequestBudget::new("langfuse", &config);
+        let start = budget.updated.get();
+
+        for _ in 0..6 {
+            budget.try_take(start).expect("within budget");
+        }
+        let wait = budget.try_take(start).expect_err("over budget");
+        assert_eq!(wait, Duration::from_secs(10));
+        budget
+            .try_take(start + Duration::from_secs(10))
+            .expect("refilled");
+
+        config.request_limits.insert("langfuse".to_string(), 1);
+        budget.configure(&config);
+        let wait = budget
+            .try_take(start + Duration::from_secs(10))
+            .expect_err("slower now");
+        assert_eq!(wait, Duration::from_secs(60));
+        assert_eq!(
+            BudgetExhausted {
+                name: "langfuse",
+                per_minute: 1
+            }
+            .to_string(),
+            "langfuse request budget of 1/min used up, continuing next refresh"
+        );
+        assert_eq!(
+            RequestBudget::new("team_budget", &AppConfig::default())
+                .per_minute
+                .get(),
+            DEFAULT_REQUESTS_PER_MINUTE
+        );
+    }
+}
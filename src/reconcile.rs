@@ -0,0 +1,267 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+
+use crate::clock;
+use crate::models::{AppConfig, PricingMatch, UsageData, cost_multiplier, estimate_cost};
+
+/// Estimated entries left out because billed usage already covers them,
+/// and what that changed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(crate) struct Reconciliation {
+    pub(crate) matched: usize,
+    /// What the dropped estimates came to.
+    pub(crate) estimated_usd: f64,
+    /// What the billed entries that cover them came to.
+    pub(crate) billed_usd: f64,
+}
+
+impl Reconciliation {
+    /// Billed minus estimated: how far the estimates were off.
+    pub(crate) fn delta_usd(&self) -> f64 {
+        self.billed_usd - self.estimated_usd
+    }
+}
+
+/// A billed entry an estimate may turn out to duplicate.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Billed {
+    at: i64,
+    index: usize,
+    used: bool,
+}
+
+/// The vendor `provider` bills under: its `provider_groups` group, so
+/// `codex` sessions meet `openai` billing rows, or else itself.
+fn vendor<'a>(config: &'a AppConfig, provider: &'a str) -> &'a str {
+    config
+        .provider_groups
+        .iter()
+        .find(|(_, members)| members.iter().any(|member| member == provider))
+        .map_or(provider, |(group, _)| group.as_str())
+}
+
+/// Drops each estimated entry (an importer's session, a log line) that
+/// an authoritative one (a billing poller, a payload cost) reports too:
+/// same vendor and model, timestamps within `window_minutes` and token
+/// counts within `tolerance_percent` of each other. Each billed entry
+/// covers at most one estimate, so two sessions are never folded into one
+/// bill; an estimate takes the nearest billed entry still free.
+pub(crate) fn reconcile(data: &mut UsageData, config: &AppConfig) -> Reconciliation {
+    let mut reconciliation = Reconciliation::default();
+    if !config.reconcile.enabled {
+        return reconciliation;
+    }
+    let window = i64::try_from(config.reconcile.window_minutes)
+        .unwrap_or(i64::MAX / 60)
+        .saturating_mul(60);
+    let tolerance = config.reconcile.tolerance_percent / 100.0;
+
+    // Billed entries by vendor and model, in time order.
+    let mut billed: HashMap<(&str, &str), Vec<Billed>> = HashMap::new();
+    for (index, entry) in data.entries.iter().enumerate() {
+        if entry.effective_cost_source().is_authoritative()
+            && let Some(at) = clock::parse_timestamp(&entry.timestamp)
+        {
+            billed
+                .entry((vendor(config, &entry.provider), &entry.model))
+                .or_default()
+                .push(Billed {
+                    at,
+                    index,
+                    used: false,
+                });
+        }
+    }
+    if billed.is_empty() {
+        return reconciliation;
+    }
+    for candidates in billed.values_mut() {
+        candidates.sort_unstable();
+    }
+
+    let mut dropped = vec![false; data.entries.len()];
+    for (index, entry) in data.entries.iter().enumerate() {
+        if entry.effective_cost_source().is_authoritative() {
+            continue;
+        }
+        let tokens = entry.input_tokens + entry.output_tokens;
+        let (Some(at), Some(candidates)) = (
+            clock::parse_timestamp(&entry.timestamp),
+            billed.get_mut(&(vendor(config, &entry.provider), &*entry.model)),
+        ) else {
+            continue;
+        };
+        if tokens == 0 {
+            continue;
+        }
+        let start = candidates.partition_point(|billed| billed.at < at - window);
+        let nearest = candidates[start..]
+            .iter_mut()
+            .take_while(|billed| billed.at <= at + window)
+            .filter(|billed| {
+                let entry = &data.entries[billed.index];
+                let billed_tokens = entry.input_tokens + entry.output_tokens;
+                !billed.used
+                    && tokens.abs_diff(billed_tokens) as f64
+                        <= tolerance * tokens.max(billed_tokens) as f64
+            })
+            .min_by_key(|billed| billed.at.abs_diff(at));
+        if let Some(billed) = nearest {
+            billed.used = true;
+            dropped[index] = true;
+            reconciliation.matched += 1;
+            reconciliation.estimated_usd += entry.cost_usd;
+            reconciliation.billed_usd += data.entries[billed.index].cost_usd;
+        }
+    }
+    if reconciliation.matched > 0 {
+        let mut dropped = dropped.into_iter();
+        data.entries.retain(|_| !dropped.next().unwrap_or_default());
+    }
+    reconciliation
+}
+
+/// One vendor's billed usage in one UTC month, priced the way estimates
+/// are, to see how far the fuel gauge can be trusted.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct MonthlyAccuracy {
+    pub(crate) vendor: String,
+    pub(crate) year: i32,
+    pub(crate) month: u32,
+    /// Billed entries with pricing to estimate them by.
+    pub(crate) entries: usize,
+    pub(crate) billed_usd: f64,
+    /// The same entries' tokens at today's rates, `cost_multipliers`
+    /// included.
+    pub(crate) estimated_usd: f64,
+    /// Billed entries left out for lack of pricing.
+    pub(crate) unpriced: usize,
+}
+
+impl MonthlyAccuracy {
+    /// How far estimates land from the bill, in percent of it: positive
+    /// when they overshoot.
+    pub(crate) fn error_percent(&self) -> Option<f64> {
+        (self.billed_usd > 0.0)
+            .then(|| (self.estimated_usd - self.billed_usd) / self.billed_usd * 100.0)
+    }
+}
+
+/// Prices every authoritative entry as if an importer had estimated it
+/// and adds both up per vendor and month, oldest month first. Reported
+/// costs take cache reads and batch discounts into account; estimates
+/// cannot, so the error says how much to allow for.
+pub(crate) fn estimate_accuracy(data: &UsageData, config: &AppConfig) -> Vec<MonthlyAccuracy> {
+    let mut months: BTreeMap<(&str, i32, u32), MonthlyAccuracy> = BTreeMap::new();
+    for entry in &data.entries {
+        if !entry.effective_cost_source().is_authoritative() {
+            continue;
+        }
+        let Some(at) = clock::parse_timestamp(&entry.timestamp) else {
+            continue;
+        };
+        let (_, year, month) = clock::month_start(at);
+        let vendor = vendor(config, &entry.provider);
+        let row = months
+            .entry((vendor, year, month))
+            .or_insert_with(|| MonthlyAccuracy {
+                vendor: vendor.to_string(),
+                year,
+                month,
+                ..MonthlyAccuracy::default()
+            });
+        let estimate = estimate_cost(
+            &entry.provider,
+            &entry.model,
+            entry.input_tokens,
+            entry.output_tokens,
+            &config.pricing,
+        );
+        if estimate.pricing_match == Some(PricingMatch::Missing) {
+            row.unpriced += 1;
+            continue;
+        }
+        row.entries += 1;
+        row.billed_usd += entry.cost_usd;
+        row.estimated_usd += estimate.cost_usd * cost_multiplier(config, &entry.provider);
+    }
+    months.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimates_covered_by_billing_are_dropped_once() {
+        let mut config = AppConfig::default();
+        config.provider_groups.insert(
+            "OpenAI".to_string(),
+            vec!["openai".to_string(), "codex".to_string()],
+        );
+        let mut data = serde_json::from_str::<UsageData>(
+            r#"{"budget_usd": 50, "entries": [
+                {"timestamp": "2026-03-01T10:00:00Z", "provider": "codex", "model": "gpt-5",
+                 "input_tokens": 90000, "output_tokens": 10000, "cost_usd": 0.50, "cost_source": "estimated"},
+                {"timestamp": "2026-03-01T10:04:00Z", "provider": "codex", "model": "gpt-5",
+                 "input_tokens": 95000, "output_tokens": 10000, "cost_usd": 0.52, "cost_source": "estimated"},
+                {"timestamp": "2026-03-01T10:03:00Z", "provider": "openai", "model": "gpt-5",
+                 "input_tokens": 100000, "output_tokens": 4000, "cost_usd": 0.40, "cost_source": "billing"},
+                {"timestamp": "2026-03-01T12:00:00Z", "provider": "codex", "model": "gpt-5",
+                 "input_tokens": 100000, "output_tokens": 0, "cost_usd": 0.45, "cost_source": "estimated"},
+                {"timestamp": "2026-03-01T12:01:00Z", "provider": "openai", "model": "gpt-5",
+                 "input_tokens": 500000, "output_tokens": 0, "cost_usd": 2.00, "cost_source": "billing"},
+                {"timestamp": "2026-03-01T12:01:00Z", "provider": "anthropic", "model": "gpt-5",
+                 "input_tokens": 100000, "output_tokens": 0, "cost_usd": 0.30, "cost_source": "payload"}
+            ]}"#,
+        )
+        .expect("data");
+
+        let reconciliation = reconcile(&mut data, &config);
+        // The 10:03 bill covers one of the two sessions beside it, not
+        // both; the 12:00 one is five times smaller than the bill there,
+        // and the anthropic row is another vendor.
+        assert_eq!(reconciliation.matched, 1);
+        assert_eq!(data.entries.len(), 5);
+        assert!(data.entries.iter().all(|entry| entry.cost_usd != 0.50));
+        assert!((reconciliation.delta_usd() + 0.10).abs() < 1e-9);
+
+        config.reconcile.enabled = false;
+        let mut untouched = data.clone();
+        assert_eq!(
+            reconcile(&mut untouched, &config),
+            Reconciliation::default()
+        );
+        assert_eq!(untouched.entries.len(), 5);
+    }
+
+    #[test]
+    fn billed_months_are_priced_like_estimates() {
+        let mut config = AppConfig::default();
+        config.cost_multipliers.insert("openai".to_string(), 1.25);
+        let data = serde_json::from_str::<UsageData>(
+            r#"{"budget_usd": 50, "entries": [
+                {"timestamp": "2026-03-05T10:00:00Z", "provider": "openai", "model": "gpt-4.1-mini",
+                 "input_tokens": 1000000, "output_tokens": 0, "cost_usd": 0.40, "cost_source": "billing"},
+                {"timestamp": "2026-02-10T10:00:00Z", "provider": "openai", "model": "gpt-4.1-mini",
+                 "input_tokens": 1000000, "output_tokens": 0, "cost_usd": 0.50, "cost_source": "billing"},
+                {"timestamp": "2026-03-06T10:00:00Z", "provider": "openai", "model": "gpt-4.1-mini",
+                 "input_tokens": 1000000, "output_tokens": 0, "cost_usd": 9.0, "cost_source": "estimated"},
+                {"timestamp": "2026-03-07T10:00:00Z", "provider": "openai", "model": "no-such-model",
+                 "input_tokens": 1000000, "output_tokens": 0, "cost_usd": 3.0, "cost_source": "billing"}
+            ]}"#,
+        )
+        .expect("data");
+
+        let months = estimate_accuracy(&data, &config);
+        assert_eq!(months.len(), 2);
+        // 1M in lists at $0.40, $0.50 after the multiplier: spot on in
+        // February, 25% over what March billed.
+        assert_eq!((months[0].year, months[0].month), (2026, 2));
+        assert!(months[0].error_percent().expect("error").abs() < 1e-9);
+        assert_eq!((months[1].vendor.as_str(), months[1].month), ("openai", 3));
+        assert_eq!((months[1].entries, months[1].unpriced), (1, 1));
+        assert!((months[1].error_percent().expect("error") - 25.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,260 @@
+use std::time::{Duration, Instant};
+
+use crate::models::AppConfig;
+
+/// Unchanged refresh cycles tolerated before intervals start stretching.
+const IDLE_CYCLES_BEFORE_BACKOFF: u32 = 3;
+/// Upper bound on how far idle backoff multiplies configured intervals.
+const MAX_IDLE_BACKOFF_FACTOR: u32 = 6;
+/// Remote APIs are polled no more often than this unless configured.
+const REMOTE_MIN_DEFAULT_INTERVAL: Duration = Duration::from_secs(300);
+/// Editor logs feed day-level totals, so they need not be read as often as
+/// Codex sessions.
+const EDITOR_MIN_DEFAULT_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RefreshTask {
+    Config,
+    Usage,
+    CodexImport,
+    LangfuseImport,
+    EditorImport,
+}
+
+impl RefreshTask {
+    pub(crate) const ALL: [RefreshTask; 5] = [
+        RefreshTask::Config,
+        RefreshTask::Usage,
+        RefreshTask::CodexImport,
+        RefreshTask::LangfuseImport,
+        RefreshTask::EditorImport,
+    ];
+
+    /// Key used under `refresh_intervals` in `config.json`.
+    pub(crate) fn config_key(self) -> &'static str {
+        match self {
+            RefreshTask::Config => "config",
+            RefreshTask::Usage => "usage",
+            RefreshTask::CodexImport => "codex",
+            RefreshTask::LangfuseImport => "langfuse",
+            RefreshTask::EditorImport => "editors",
+        }
+    }
+
+    /// Interval used when `refresh_intervals` has no entry for this task.
+    fn default_interval(self, default: Duration) -> Duration {
+        match self {
+            RefreshTask::LangfuseImport => default.max(REMOTE_MIN_DEFAULT_INTERVAL),
+            RefreshTask::EditorImport => default.max(EDITOR_MIN_DEFAULT_INTERVAL),
+            _ => default,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct RefreshSchedule {
+    task: RefreshTask,
+    interval: Duration,
+    last_run: Option<Instant>,
+}
+
+/// Tracks when each refresh source is next due so the event loop can sleep
+/// until the earliest deadline instead of reloading everything on one timer.
+///
+/// When usage stops changing, every interval is stretched by an idle factor
+/// that grows one step per few quiet cycles and snaps back to 1 as soon as
+/// new usage shows up.
+#[derive(Debug, Clone)]
+pub(crate) struct RefreshPlanner {
+    default_interval: Duration,
+    schedules: Vec<RefreshSchedule>,
+    adaptive: bool,
+    idle_cycles: u32,
+    idle_factor: u32,
+}
+
+impl RefreshPlanner {
+    pub(crate) fn new(config: &AppConfig, default_interval: Duration) -> Self {
+        let schedules = RefreshTask::ALL
+            .iter()
+            .map(|task| RefreshSchedule {
+                task: *task,
+                interval: configured_interval(config, *task, default_interval),
+                last_run: None,
+            })
+            .collect();
+        Self {
+            default_interval,
+            schedules,
+            adaptive: config.adaptive_refresh,
+            idle_cycles: 0,
+            idle_factor: 1,
+        }
+    }
+
+    /// Re-reads intervals after a config reload while keeping run history.
+    pub(crate) fn reconfigure(&mut self, config: &AppConfig) {
+        for schedule in &mut self.schedules {
+            schedule.interval = configured_interval(config, schedule.task, self.default_interval);
+        }
+        self.adaptive = config.adaptive_refresh;
+        if !self.adaptive {
+            self.reset_idle();
+        }
+    }
+
+    /// Feeds back whether the last refresh cycle found new usage.
+    pub(crate) fn record_activity(&mut self, changed: bool) {
+        if changed || !self.adaptive {
+            self.reset_idle();
+            return;
+        }
+        self.idle_cycles += 1;
+        if self.idle_cycles >= IDLE_CYCLES_BEFORE_BACKOFF {
+            self.idle_cycles = 0;
+            self.idle_factor = (self.idle_factor + 1).min(MAX_IDLE_BACKOFF_FACTOR);
+        }
+    }
+
+    pub(crate) fn reset_idle(&mut self) {
+        self.idle_cycles = 0;
+        self.idle_factor = 1;
+    }
+
+    pub(crate) fn idle_factor(&self) -> u32 {
+        self.idle_factor
+    }
+
+    /// Effective interval for `task`, including any idle backoff.
+    pub(crate) fn interval(&self, task: RefreshTask) -> Duration {
+        self.schedules
+            .iter()
+            .find(|schedule| schedule.task == task)
+            .map(|schedule| schedule.interval)
+            .unwrap_or(self.default_interval)
+            * self.idle_factor
+    }
+
+    pub(crate) fn due_tasks(&self, now: Instant) -> Vec<RefreshTask> {
+        self.schedules
+            .iter()
+            .filter(|schedule| self.time_until_due(schedule, now).is_zero())
+            .map(|schedule| schedule.task)
+            .collect()
+    }
+
+    pub(crate) fn mark_ran(&mut self, task: RefreshTask, now: Instant) {
+        if let Some(schedule) = self.schedules.iter_mut().find(|s| s.task == task) {
+            schedule.last_run = Some(now);
+        }
+    }
+
+    pub(crate) fn time_until_next(&self, now: Instant) -> Duration {
+        self.schedules
+            .iter()
+            .map(|schedule| self.time_until_due(schedule, now))
+            .min()
+            .unwrap_or(self.default_interval)
+    }
+
+    fn time_until_due(&self, schedule: &RefreshSchedule, now: Instant) -> Duration {
+        match schedule.last_run {
+            Some(last_run) => (schedule.interval * self.idle_factor)
+                .saturating_sub(now.saturating_duration_since(last_run)),
+            None => Duration::ZERO,
+        }
+    }
+}
+
+fn configured_interval(config: &AppConfig, task: RefreshTask, default: Duration) -> Duration {
+    config
+        .refresh_intervals
+        .get(task.config_key())
+        .copied()
+        .filter(|seconds| *seconds > 0)
+        .map(Duration::from_secs)
+        .unwrap_or_else(|| task.default_interval(default))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn planner_uses_configured_intervals_with_default_fallback() {
+        let mut config = AppConfig::default();
+        config
+            .refresh_intervals
+            .insert("config".to_string(), 86_400);
+        config.refresh_intervals.insert("codex".to_string(), 0);
+        let planner = RefreshPlanner::new(&config, Duration::from_secs(10));
+
+        assert_eq!(
+            planner.interval(RefreshTask::Config),
+            Duration::from_secs(86_400)
+        );
+        assert_eq!(
+            planner.interval(RefreshTask::Usage),
+            Duration::from_secs(10)
+        );
+        assert_eq!(
+            planner.interval(RefreshTask::CodexImport),
+            Duration::from_secs(10)
+        );
+    }
+
+    #[test]
+    fn planner_reports_only_tasks_past_their_interval() {
+        let mut config = AppConfig::default();
+        config.refresh_intervals.insert("config".to_string(), 3600);
+        config.refresh_intervals.insert("codex".to_string(), 5);
+        let mut planner = RefreshPlanner::new(&config, Duration::from_secs(10));
+        let start = Instant::now();
+
+        assert_eq!(planner.due_tasks(start), RefreshTask::ALL.to_vec());
+        for task in RefreshTask::ALL {
+            planner.mark_ran(task, start);
+        }
+        assert!(planner.due_tasks(start).is_empty());
+        assert_eq!(planner.time_until_next(start), Duration::from_secs(5));
+
+        let later = start + Duration::from_secs(11);
+        assert_eq!(
+            planner.due_tasks(later),
+            vec![RefreshTask::Usage, RefreshTask::CodexImport]
+        );
+    }
+
+    #[test]
+    fn idle_cycles_stretch_intervals_until_activity_resumes() {
+        let mut config = AppConfig::default();
+        let mut planner = RefreshPlanner::new(&config, Duration::from_secs(10));
+
+        for _ in 0..IDLE_CYCLES_BEFORE_BACKOFF * 2 {
+            planner.record_activity(false);
+        }
+        assert_eq!(planner.idle_factor(), 3);
+        assert_eq!(
+            planner.interval(RefreshTask::Usage),
+            Duration::from_secs(30)
+        );
+
+        for _ in 0..IDLE_CYCLES_BEFORE_BACKOFF * 20 {
+            planner.record_activity(false);
+        }
+        assert_eq!(planner.idle_factor(), MAX_IDLE_BACKOFF_FACTOR);
+
+        planner.record_activity(true);
+        assert_eq!(
+            planner.interval(RefreshTask::Usage),
+            Duration::from_secs(10)
+        );
+
+        config.adaptive_refresh = false;
+        planner.reconfigure(&config);
+        for _ in 0..IDLE_CYCLES_BEFORE_BACKOFF * 2 {
+            planner.record_activity(false);
+        }
+        assert_eq!(planner.idle_factor(), 1);
+    }
+}
@@ -0,0 +1,282 @@
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use color_eyre::eyre::{Result, bail};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::Serialize;
+
+use crate::rate_limit::RequestBudget;
+
+/// How often a single request is tried before its error is returned.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RetryPolicy {
+    pub(crate) attempts: u32,
+    pub(crate) base_delay: Duration,
+    pub(crate) max_delay: Duration,
+}
+
+/// Kept short: pollers run on the refresh worker, and the circuit breaker
+/// takes over once a provider stays down.
+pub(crate) const POLLER_RETRY: RetryPolicy = RetryPolicy {
+    attempts: 3,
+    base_delay: Duration::from_millis(500),
+    max_delay: Duration::from_secs(4),
+};
+
+/// Consecutive failed passes after which a poller is paused.
+const BREAKER_THRESHOLD: u32 = 3;
+const BREAKER_COOLDOWN: Duration = Duration::from_secs(60);
+const BREAKER_MAX_COOLDOWN: Duration = Duration::from_secs(15 * 60);
+
+/// Errors worth another try: rate limiting, server errors and the
+/// network itself. Anything else (a 401, a bad URL) fails straight away.
+pub(crate) fn is_transient(err: &ureq::Error) -> bool {
+    matches!(
+        err,
+        ureq::Error::StatusCode(429 | 500..=599)
+            | ureq::Error::Io(_)
+            | ureq::Error::Timeout(_)
+            | ureq::Error::HostNotFound
+            | ureq::Error::ConnectionFailed
+            | ureq::Error::BodyStalled
+    )
+}
+
+/// Runs `call` until it succeeds, fails with a non-transient error or
+/// runs out of attempts, sleeping with exponential backoff and jitter in
+/// between. Every attempt is taken from `budget`. A failed request's
+/// `ureq::Error` can be recovered with `downcast_ref`.
+pub(crate) fn retry<T>(
+    policy: &RetryPolicy,
+    budget: &RequestBudget,
+    mut call: impl FnMut() -> Result<T, ureq::Error>,
+) -> Result<T> {
+    let mut attempt = 1;
+    loop {
+        budget.acquire()?;
+        match call() {
+            Err(err) if attempt < policy.attempts && is_transient(&err) => {
+                thread::sleep(backoff(policy, attempt));
+                attempt += 1;
+            }
+            result => return Ok(result?),
+        }
+    }
+}
+
+/// Half of the exponential delay, plus up to the other half at random, so
+/// clients that failed together do not retry together.
+fn backoff(policy: &RetryPolicy, attempt: u32) -> Duration {
+    let delay = policy
+        .base_delay
+        .saturating_mul(1 << (attempt - 1).min(16))
+        .min(policy.max_delay);
+    let mut byte = [0u8];
+    let jitter = SystemRandom::new()
+        .fill(&mut byte)
+        .map_or(0.5, |()| f64::from(byte[0]) / 255.0);
+    delay / 2 + delay.mul_f64(jitter / 2.0)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum BreakerState {
+    Closed,
+    /// Paused; calls fail fast until the cooldown ends.
+    Open,
+    /// The cooldown ended; the next call decides.
+    HalfOpen,
+}
+
+/// How one poller has been doing, for the diagnostics overlay and
+/// `/status`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct PollerHealth {
+    pub(crate) name: &'static str,
+    pub(crate) state: BreakerState,
+    pub(crate) consecutive_failures: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) retry_in_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) last_error: Option<String>,
+    #[serde(skip)]
+    pub(crate) last_success: Option<SystemTime>,
+}
+
+/// Stops calling a poller that keeps failing, so one flapping API does not
+/// add its timeouts to every refresh. Each time it trips again the pause
+/// doubles, up to 15 minutes.
+#[derive(Debug, Clone)]
+pub(crate) struct CircuitBreaker {
+    name: &'static str,
+    consecutive_failures: u32,
+    trips: u32,
+    open_until: Option<Instant>,
+    last_error: Option<String>,
+    last_success: Option<SystemTime>,
+}
+
+impl CircuitBreaker {
+    pub(crate) fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            consecutive_failures: 0,
+            trips: 0,
+            open_until: None,
+            last_error: None,
+            last_success: None,
+        }
+    }
+
+    /// Runs `poll` unless the breaker is open, and records how it went.
+    pub(crate) fn call<T>(&mut self, now: Instant, poll: impl FnOnce() -> Result<T>) -> Result<T> {
+        if let Some(until) = self.open_until.filter(|until| now < *until) {
+            bail!(
+                "{} paused after {} failures, retrying in {}s",
+                self.name,
+                self.consecutive_failures,
+                until.saturating_duration_since(now).as_secs().max(1)
+            );
+        }
+        let result = poll();
+        match &result {
+            Ok(_) => {
+                self.consecutive_failures = 0;
+                self.trips = 0;
+                self.open_until = None;
+                self.last_error = None;
+                self.last_success = Some(SystemTime::now());
+            }
+            Err(err) => {
+                self.consecutive_failures += 1;
+                self.last_error = Some(err.to_string());
+                if self.consecutive_failures >= BREAKER_THRESHOLD {
+                    let cooldown = BREAKER_COOLDOWN
+                        .saturating_mul(1 << self.trips.min(8))
+                        .min(BREAKER_MAX_COOLDOWN);
+                    self.trips += 1;
+                    self.open_until = Some(now + cooldown);
+                }
+            }
+        }
+        result
+    }
+
+    /// Lets the next call through now instead of waiting out the pause,
+    /// which the monotonic clock stretches by however long the machine
+    /// slept. A failure still trips it again, for longer.
+    pub(crate) fn retry_now(&mut self, now: Instant) {
+        if let Some(until) = self.open_until.as_mut() {
+            *until = (*until).min(now);
+        }
+    }
+
+    pub(crate) fn health(&self, now: Instant) -> PollerHealth {
+        let state = match self.open_until {
+            Some(until) if now < until => BreakerState::Open,
+            Some(_) => BreakerState::HalfOpen,
+            None => BreakerState::Closed,
+        };
+        PollerHealth {
+            name: self.name,
+            state,
+            consecutive_failures: self.consecutive_failures,
+            retry_in_secs: self
+                .open_until
+                .filter(|until| now < *until)
+                .map(|until| until.saturating_duration_since(now).as_secs()),
+            last_error: self.last_error.clone(),
+            last_success: self.last_success,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use color_eyre::eyre::eyre;
+
+    use super::*;
+    use crate::models::AppConfig;
+
+    #[test]
+    fn retries_transient_errors_only() {
+        let policy = RetryPolicy {
+            attempts: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(2),
+        };
+        let budget = RequestBudget::new("test", &AppConfig::default());
+        let calls = Cell::new(0);
+        let result = retry(&policy, &budget, || {
+            calls.set(calls.get() + 1);
+            if calls.get() < 3 {
+                Err(ureq::Error::StatusCode(503))
+            } else {
+                Ok(calls.get())
+            }
+        });
+        assert_eq!(result.expect("third try"), 3);
+
+        calls.set(0);
+        let result = retry(&policy, &budget, || {
+            calls.set(calls.get() + 1);
+            Err::<(), _>(ureq::Error::StatusCode(401))
+        });
+        assert!(matches!(
+            result.expect_err("401").downcast_ref::<ureq::Error>(),
+            Some(ureq::Error::StatusCode(401))
+        ));
+        assert_eq!(calls.get(), 1);
+
+        let delay = backoff(&POLLER_RETRY, 2);
+        assert!(delay >= Duration::from_millis(500) && delay <= Duration::from_secs(1));
+        assert!(backoff(&POLLER_RETRY, 10) <= POLLER_RETRY.max_delay);
+    }
+
+    #[test]
+    fn breaker_opens_after_repeated_failures_and_backs_off() {
+        let mut breaker = CircuitBreaker::new("langfuse");
+        let start = Instant::now();
+        for _ in 0..BREAKER_THRESHOLD {
+            assert!(breaker.call(start, || Err::<(), _>(eyre!("503"))).is_err());
+        }
+        let health = breaker.health(start);
+        assert_eq!(health.state, BreakerState::Open);
+        assert_eq!(health.retry_in_secs, Some(60));
+
+        let ran = Cell::new(false);
+        let err = breaker
+            .call(start + Duration::from_secs(30), || {
+                ran.set(true);
+                Ok(())
+            })
+            .expect_err("open");
+        assert!(!ran.get());
+        assert_eq!(
+            err.to_string(),
+            "langfuse paused after 3 failures, retrying in 30s"
+        );
+
+        let later = start + Duration::from_secs(61);
+        assert_eq!(breaker.health(later).state, BreakerState::HalfOpen);
+        assert!(breaker.call(later, || Err::<(), _>(eyre!("503"))).is_err());
+        assert_eq!(breaker.health(later).retry_in_secs, Some(120));
+
+        // Woken from a suspend: the pause is over at once, and one more
+        // failure trips it for longer.
+        let mut woken = breaker.clone();
+        woken.retry_now(later);
+        assert_eq!(woken.health(later).state, BreakerState::HalfOpen);
+        assert!(woken.call(later, || Err::<(), _>(eyre!("503"))).is_err());
+        assert_eq!(woken.health(later).retry_in_secs, Some(240));
+
+        let recovered = later + Duration::from_secs(121);
+        breaker.call(recovered, || Ok(())).expect("closed again");
+        let health = breaker.health(recovered);
+        assert_eq!(health.state, BreakerState::Closed);
+        assert_eq!(health.consecutive_failures, 0);
+        assert!(health.last_success.is_some());
+    }
+}
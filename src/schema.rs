@@ -0,0 +1,113 @@
+use color_eyre::eyre::Result;
+use schemars::{Schema, schema_for};
+
+use crate::models::{AppConfig, UsageData};
+
+/// A file `promptpetrol schema` describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SchemaKind {
+    /// `config.json`.
+    Config,
+    /// A data file in the stored format, the one PromptPetrol writes.
+    Usage,
+}
+
+impl SchemaKind {
+    pub(crate) const ALL: [SchemaKind; 2] = [SchemaKind::Config, SchemaKind::Usage];
+
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.name().eq_ignore_ascii_case(name))
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            SchemaKind::Config => "config",
+            SchemaKind::Usage => "usage",
+        }
+    }
+}
+
+/// The JSON Schema of `kind`, derived from the serde models the file is
+/// read with, so defaults, renames and skipped fields match what loading
+/// accepts. Doc comments become descriptions.
+pub(crate) fn json_schema(kind: SchemaKind) -> Schema {
+    match kind {
+        SchemaKind::Config => schema_for!(AppConfig),
+        SchemaKind::Usage => schema_for!(UsageData),
+    }
+}
+
+pub(crate) fn format_json_schema(kind: SchemaKind) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&json_schema(kind))? + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+
+    /// Every key `value` has is a property of `schema`, resolving `$ref`s
+    /// into `$defs`, so a field the schema left out fails here.
+    fn assert_described(schema: &Value, defs: &Value, value: &Value, path: &str) {
+        let schema = match schema["$ref"].as_str() {
+            Some(reference) => &defs[reference.trim_start_matches("#/$defs/")],
+            None => schema,
+        };
+        match value {
+            Value::Object(fields) if schema["properties"].is_object() => {
+                for (key, field) in fields {
+                    let property = &schema["properties"][key];
+                    assert!(!property.is_null(), "{path}.{key} is not in the schema");
+                    assert_described(property, defs, field, &format!("{path}.{key}"));
+                }
+            }
+            Value::Array(items) if schema["items"].is_object() => {
+                for item in items {
+                    assert_described(&schema["items"], defs, item, &format!("{path}[]"));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn schemas_describe_every_serialized_field() {
+        let config = serde_json::to_value(json_schema(SchemaKind::Config)).expect("schema");
+        assert_eq!(config["type"], "object");
+        // Every setting has a default, so `{}` is a valid config.
+        assert!(config["required"].is_null());
+        let defaults = serde_json::to_value(AppConfig {
+            spending_frozen_since: Some("2026-10-14T08:00:00Z".to_string()),
+            throttle_file: Some("~/throttle.json".to_string()),
+            ..AppConfig::default()
+        })
+        .expect("config");
+        assert_described(&config, &config["$defs"], &defaults, "config");
+
+        let usage = serde_json::to_value(json_schema(SchemaKind::Usage)).expect("schema");
+        let data = serde_json::from_str::<Value>(
+            r#"{"budget_usd": 50.0, "entries": [
+                {"timestamp": "2026-03-01T10:00:00Z", "provider": "openai", "model": "gpt-5",
+                 "input_tokens": 10, "output_tokens": 5, "cost_usd": 0.5,
+                 "pricing_match": "exact", "cost_source": "estimated", "tag": "chat",
+                 "original_cost": {"amount": 0.45, "currency": "EUR"},
+                 "api_key": "team", "project_id": "proj_1", "data_file": "extra.json"}
+            ]}"#,
+        )
+        .expect("data");
+        let stored = serde_json::to_value(
+            serde_json::from_value::<UsageData>(data.clone()).expect("usage data"),
+        )
+        .expect("usage");
+        assert_eq!(stored, data);
+        assert_described(&usage, &usage["$defs"], &stored, "usage");
+        // No budget is a valid file; no entries list is not.
+        assert_eq!(usage["required"], serde_json::json!(["entries"]));
+
+        assert_eq!(SchemaKind::from_name("Usage"), Some(SchemaKind::Usage));
+        assert_eq!(SchemaKind::from_name("data"), None);
+    }
+}
@@ -0,0 +1,480 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+use crate::clock;
+use crate::models::{
+    GaugeTotals, ModelStats, MonthSpend, PricingMatch, ProviderStats, ProviderSummary,
+    UnpricedModel, UsageData, UsageEntry, month_spend_from,
+};
+
+/// Running totals of one provider's entries on one UTC day.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct DayTotals {
+    pub(crate) tokens: u64,
+    pub(crate) cost_usd: f64,
+    pub(crate) authoritative_cost_usd: f64,
+    pub(crate) requests: usize,
+    pub(crate) fuzzy_priced_requests: usize,
+    /// Requests no pricing matched, and their tokens.
+    pub(crate) unpriced_requests: usize,
+    pub(crate) unpriced_tokens: u64,
+    /// Converted entries in the currency they were billed in.
+    pub(crate) original_costs: BTreeMap<String, f64>,
+}
+
+impl DayTotals {
+    fn add(&mut self, entry: &UsageEntry) {
+        self.tokens += entry.input_tokens + entry.output_tokens;
+        self.cost_usd += entry.cost_usd;
+        if entry.effective_cost_source().is_authoritative() {
+            self.authoritative_cost_usd += entry.cost_usd;
+        }
+        self.requests += 1;
+        match entry.pricing_match {
+            Some(PricingMatch::Fuzzy) => self.fuzzy_priced_requests += 1,
+            Some(PricingMatch::Missing) => {
+                self.unpriced_requests += 1;
+                self.unpriced_tokens += entry.input_tokens + entry.output_tokens;
+            }
+            _ => {}
+        }
+        if let Some(original) = &entry.original_cost {
+            *self
+                .original_costs
+                .entry(original.currency.clone())
+                .or_default() += original.amount;
+        }
+    }
+
+    fn merge(&mut self, other: &DayTotals) {
+        self.tokens += other.tokens;
+        self.cost_usd += other.cost_usd;
+        self.authoritative_cost_usd += other.authoritative_cost_usd;
+        self.requests += other.requests;
+        self.fuzzy_priced_requests += other.fuzzy_priced_requests;
+        self.unpriced_requests += other.unpriced_requests;
+        self.unpriced_tokens += other.unpriced_tokens;
+        for (currency, amount) in &other.original_costs {
+            *self.original_costs.entry(currency.clone()).or_default() += amount;
+        }
+    }
+}
+
+/// Usage added up per provider and day as entries arrive, so the provider
+/// list, the Info line, the all-time dials, the forecast and the alerts
+/// read a handful of totals instead of every entry on every frame.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct UsageSummary {
+    /// Provider, then day number (days since 1970-01-01); `None` holds
+    /// entries whose time cannot be read.
+    days: BTreeMap<Arc<str>, BTreeMap<Option<i64>, DayTotals>>,
+    /// Each provider's days added up, kept alongside so reads don't have
+    /// to.
+    totals: BTreeMap<Arc<str>, DayTotals>,
+    /// Totals per provider and model, for drilling into a provider.
+    models: BTreeMap<(Arc<str>, Arc<str>), DayTotals>,
+    /// Spend per second of the clock across providers, for windows shorter
+    /// than a day.
+    costs_at: BTreeMap<i64, f64>,
+}
+
+impl UsageSummary {
+    pub(crate) fn from_data(data: &UsageData) -> UsageSummary {
+        let mut summary = UsageSummary::default();
+        for entry in &data.entries {
+            summary.add(entry);
+        }
+        summary
+    }
+
+    /// Brings a summary of `old` up to date with `new`. A refresh mostly
+    /// adds entries, either at the end or in front of a later importer's,
+    /// so only what is new gets added; any other change starts over.
+    pub(crate) fn update(&mut self, old: &[UsageEntry], new: &[UsageEntry]) {
+        let prefix = old
+            .iter()
+            .zip(new)
+            .take_while(|(old, new)| old == new)
+            .count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(old, new)| old == new)
+            .count();
+        if prefix + suffix < old.len() {
+            *self = UsageSummary::default();
+            for entry in new {
+                self.add(entry);
+            }
+            return;
+        }
+        for entry in &new[prefix..new.len() - suffix] {
+            self.add(entry);
+        }
+    }
+
+    pub(crate) fn add(&mut self, entry: &UsageEntry) {
+        let at = clock::parse_timestamp(&entry.timestamp);
+        let day = at.map(|at| at.div_euclid(clock::SECS_PER_DAY));
+        if let Some(at) = at {
+            *self.costs_at.entry(at).or_default() += entry.cost_usd;
+        }
+        self.days
+            .entry(entry.provider.clone())
+            .or_default()
+            .entry(day)
+            .or_default()
+            .add(entry);
+        self.totals
+            .entry(entry.provider.clone())
+            .or_default()
+            .add(entry);
+        self.models
+            .entry((entry.provider.clone(), entry.model.clone()))
+            .or_default()
+            .add(entry);
+    }
+
+    /// Same as [`crate::models::provider_summaries`] over the entries
+    /// added so far.
+    pub(crate) fn provider_summaries(&self) -> Vec<ProviderSummary> {
+        let mut summaries = self
+            .totals
+            .iter()
+            .map(|(provider, totals)| ProviderSummary {
+                provider: provider.to_string(),
+                total_tokens: totals.tokens,
+                total_cost_usd: totals.cost_usd,
+                authoritative_cost_usd: totals.authoritative_cost_usd,
+                fuzzy_priced_entries: totals.fuzzy_priced_requests,
+                original_costs: totals.original_costs.clone(),
+            })
+            .collect::<Vec<_>>();
+        summaries.sort_by(|a, b| {
+            b.total_cost_usd
+                .partial_cmp(&a.total_cost_usd)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| b.total_tokens.cmp(&a.total_tokens))
+                .then_with(|| a.provider.cmp(&b.provider))
+        });
+        summaries
+    }
+
+    pub(crate) fn provider_stats(&self, provider: &str) -> Option<ProviderStats> {
+        if provider.is_empty() {
+            return None;
+        }
+        self.stats(provider.to_string(), [provider])
+    }
+
+    /// Combined stats across several providers, labelled `a + b + ...` in
+    /// the order given. `None` when none of them has any entries.
+    pub(crate) fn combined_stats<'a>(
+        &self,
+        providers: impl IntoIterator<Item = &'a str>,
+    ) -> Option<ProviderStats> {
+        let providers = providers.into_iter().collect::<Vec<_>>();
+        if providers.is_empty() {
+            return None;
+        }
+        self.stats(providers.join(" + "), providers)
+    }
+
+    fn stats<'a>(
+        &self,
+        label: String,
+        providers: impl IntoIterator<Item = &'a str>,
+    ) -> Option<ProviderStats> {
+        let mut combined = DayTotals::default();
+        for provider in providers.into_iter().collect::<BTreeSet<_>>() {
+            if let Some(totals) = self.totals.get(provider) {
+                combined.merge(totals);
+            }
+        }
+        (combined.requests > 0).then_some(ProviderStats {
+            provider: label,
+            total_tokens: combined.tokens,
+            total_cost_usd: combined.cost_usd,
+            authoritative_cost_usd: combined.authoritative_cost_usd,
+            requests: combined.requests,
+            fuzzy_priced_requests: combined.fuzzy_priced_requests,
+        })
+    }
+
+    /// The stats of each model the given providers used, most expensive
+    /// first.
+    pub(crate) fn model_stats<'a>(
+        &self,
+        providers: impl IntoIterator<Item = &'a str>,
+    ) -> Vec<ModelStats> {
+        let providers = providers.into_iter().collect::<BTreeSet<_>>();
+        let mut models = self
+            .models
+            .iter()
+            .filter(|((provider, _), _)| providers.contains(&**provider))
+            .map(|((provider, model), totals)| ModelStats {
+                provider: provider.to_string(),
+                model: model.to_string(),
+                total_tokens: totals.tokens,
+                total_cost_usd: totals.cost_usd,
+                authoritative_cost_usd: totals.authoritative_cost_usd,
+                requests: totals.requests,
+                fuzzy_priced_requests: totals.fuzzy_priced_requests,
+            })
+            .collect::<Vec<_>>();
+        models.sort_by(|a, b| {
+            b.total_cost_usd
+                .partial_cmp(&a.total_cost_usd)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| b.total_tokens.cmp(&a.total_tokens))
+                .then_with(|| (&a.provider, &a.model).cmp(&(&b.provider, &b.model)))
+        });
+        models
+    }
+
+    /// [`crate::models::month_spend`] by day: each day's spend counts from
+    /// its midnight.
+    pub(crate) fn month_spend(&self, budget_usd: Option<f64>, now_epoch_secs: i64) -> MonthSpend {
+        let mut days = BTreeMap::<i64, f64>::new();
+        for (day, totals) in self.days.values().flatten() {
+            if let Some(day) = day {
+                *days.entry(*day).or_default() += totals.cost_usd;
+            }
+        }
+        month_spend_from(
+            days.into_iter()
+                .map(|(day, cost_usd)| (day * clock::SECS_PER_DAY, cost_usd)),
+            budget_usd,
+            now_epoch_secs,
+        )
+    }
+
+    /// What `providers` spent on each of the `days` UTC days up to day
+    /// number `today`, oldest first.
+    pub(crate) fn daily_costs(&self, providers: &[&str], today: i64, days: usize) -> Vec<f64> {
+        let first_day = today + 1 - days as i64;
+        let mut costs = vec![0.0; days];
+        for provider in providers.iter().collect::<BTreeSet<_>>() {
+            let Some(by_day) = self.days.get(*provider) else {
+                continue;
+            };
+            for (day, totals) in by_day.range(Some(first_day)..=Some(today)) {
+                if let Some(cost) = day
+                    .and_then(|day| usize::try_from(day - first_day).ok())
+                    .and_then(|day| costs.get_mut(day))
+                {
+                    *cost += totals.cost_usd;
+                }
+            }
+        }
+        costs
+    }
+
+    /// [`crate::models::spend_rate_per_hour`] over the entries added.
+    pub(crate) fn spend_rate_per_hour(&self, now_epoch_secs: i64, window_secs: i64) -> f64 {
+        if window_secs <= 0 {
+            return 0.0;
+        }
+        let spent = self
+            .costs_at
+            .range(now_epoch_secs - window_secs..=now_epoch_secs)
+            .map(|(_, cost_usd)| cost_usd)
+            .sum::<f64>();
+        spent * 3600.0 / window_secs as f64
+    }
+
+    /// [`crate::models::unpriced_models`] over the entries added.
+    pub(crate) fn unpriced_models(&self) -> Vec<UnpricedModel> {
+        let mut unpriced = self
+            .models
+            .iter()
+            .filter(|(_, totals)| totals.unpriced_requests > 0)
+            .map(|((provider, model), totals)| UnpricedModel {
+                provider: provider.to_string(),
+                model: model.to_string(),
+                entries: totals.unpriced_requests,
+                total_tokens: totals.unpriced_tokens,
+            })
+            .collect::<Vec<_>>();
+        unpriced.sort_by(|a, b| {
+            b.total_tokens
+                .cmp(&a.total_tokens)
+                .then_with(|| a.provider.cmp(&b.provider))
+                .then_with(|| a.model.cmp(&b.model))
+        });
+        unpriced
+    }
+
+    /// The dial totals without a half-life, where every entry counts the
+    /// same.
+    pub(crate) fn gauge_totals(&self, selection: &[&str]) -> GaugeTotals {
+        GaugeTotals::from_providers(
+            self.totals.iter().map(|(provider, totals)| {
+                (
+                    &**provider,
+                    totals.tokens as f64,
+                    totals.cost_usd,
+                    totals.requests as f64,
+                )
+            }),
+            selection,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{month_spend, provider_summaries, spend_rate_per_hour, unpriced_models};
+
+    #[test]
+    fn summaries_match_a_full_scan_and_keep_each_day_apart() {
+        let mut data = UsageData::default();
+        let mut late = data.entries[0].clone();
+        late.timestamp = "2026-02-11T09:00:00Z".to_string();
+        late.cost_usd = 0.5;
+        data.entries.push(late);
+        let mut undated = data.entries[0].clone();
+        undated.timestamp = "whenever".to_string();
+        data.entries.push(undated);
+
+        let summary = UsageSummary::from_data(&data);
+        assert_eq!(summary.provider_summaries(), provider_summaries(&data));
+        let days = summary.days["openai"]
+            .iter()
+            .map(|(day, totals)| (*day, totals.requests))
+            .collect::<Vec<_>>();
+        let day = |date: &str| clock::parse_timestamp(date).map(|at| at / clock::SECS_PER_DAY);
+        assert_eq!(
+            days,
+            vec![(None, 1), (day("2026-02-09"), 1), (day("2026-02-11"), 1)]
+        );
+
+        let stats = summary.provider_stats("openai").expect("openai");
+        assert_eq!(stats.requests, 3);
+        assert!((stats.total_cost_usd - (0.084 * 2.0 + 0.5)).abs() < 1e-9);
+        assert_eq!(
+            summary
+                .combined_stats(["openai", "openai"])
+                .map(|stats| stats.requests),
+            Some(3)
+        );
+        let ratios = summary.gauge_totals(&["anthropic"]).ratios();
+        assert!((ratios.token - 16_200.0 / 30_000.0).abs() < 1e-9);
+        assert!((ratios.spend - 0.361 / (0.084 * 2.0 + 0.5)).abs() < 1e-9);
+        assert!((ratios.activity - 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn daily_costs_cover_the_selections_last_days() {
+        let entry = |provider: &str, timestamp: &str, cost_usd: f64| UsageEntry {
+            timestamp: timestamp.to_string(),
+            provider: provider.into(),
+            model: "m".into(),
+            input_tokens: 0,
+            output_tokens: 0,
+            cost_usd,
+            pricing_match: None,
+            cost_source: None,
+            original_cost: None,
+            api_key: None,
+            project_id: None,
+            tag: None,
+            data_file: None,
+        };
+        let summary = UsageSummary::from_data(&UsageData {
+            budget_usd: None,
+            entries: vec![
+                entry("openai", "2026-02-02T00:00:00Z", 2.0),
+                entry("openai", "2026-01-31T12:00:00Z", 1.5),
+                entry("openai", "2026-01-30T23:59:59Z", 4.0),
+                entry("gemini", "2026-02-01T00:00:00Z", 1.0),
+                entry("openai", "2026-03-01T00:00:00Z", 9.0),
+                entry("openai", "not a time", 9.0),
+            ],
+        });
+        let today =
+            clock::parse_timestamp("2026-02-08T00:00:00Z").expect("valid") / clock::SECS_PER_DAY;
+
+        // Jan 31 is the first of the last nine days; March is yet to come.
+        assert_eq!(
+            summary.daily_costs(&["openai", "gemini", "openai"], today, 9),
+            vec![1.5, 1.0, 2.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]
+        );
+        assert_eq!(summary.daily_costs(&["anthropic"], today, 3), vec![0.0; 3]);
+    }
+
+    #[test]
+    fn updates_add_only_new_entries_and_serve_the_per_frame_figures() {
+        let entry = |provider: &str, timestamp: &str, pricing_match| UsageEntry {
+            timestamp: timestamp.to_string(),
+            provider: provider.into(),
+            model: "m".into(),
+            input_tokens: 100,
+            output_tokens: 20,
+            cost_usd: 0.5,
+            pricing_match,
+            cost_source: None,
+            original_cost: None,
+            api_key: None,
+            project_id: None,
+            tag: None,
+            data_file: None,
+        };
+        let codex = |timestamp| entry("codex", timestamp, None);
+        let langfuse = entry(
+            "langfuse",
+            "2026-02-05T09:00:00Z",
+            Some(PricingMatch::Missing),
+        );
+        let old = vec![codex("2026-01-31T12:00:00Z"), langfuse.clone()];
+        let mut summary = UsageSummary::from_data(&UsageData {
+            budget_usd: None,
+            entries: old.clone(),
+        });
+
+        // New Codex entries land in front of the Langfuse ones.
+        let data = UsageData {
+            budget_usd: Some(28.0),
+            entries: vec![
+                codex("2026-01-31T12:00:00Z"),
+                codex("2026-02-07T23:30:00Z"),
+                codex("2026-02-07T23:50:00Z"),
+                langfuse.clone(),
+            ],
+        };
+        summary.update(&old, &data.entries);
+        assert_eq!(summary, UsageSummary::from_data(&data));
+
+        // A dropped entry starts over.
+        let mut fewer = data.entries.clone();
+        fewer.remove(1);
+        let mut rebuilt = summary.clone();
+        rebuilt.update(&data.entries, &fewer);
+        assert_eq!(
+            rebuilt,
+            UsageSummary::from_data(&UsageData {
+                budget_usd: None,
+                entries: fewer,
+            })
+        );
+
+        let now = clock::parse_timestamp("2026-02-08T00:00:00Z").expect("valid");
+        let spend = summary.month_spend(data.budget_usd, now);
+        assert_eq!(spend.spent_usd(), month_spend(&data, now).spent_usd());
+        assert_eq!(
+            spend.current,
+            vec![(0.0, 0.0), (4.0, 0.5), (6.0, 1.5), (7.0, 1.5)]
+        );
+        assert_eq!(spend.previous, vec![(0.0, 0.0), (30.0, 0.5), (31.0, 0.5)]);
+        for window_secs in [600, 3600, 0] {
+            assert_eq!(
+                summary.spend_rate_per_hour(now, window_secs),
+                spend_rate_per_hour(&data, now, window_secs)
+            );
+        }
+        assert_eq!(summary.unpriced_models(), unpriced_models(&data));
+        assert_eq!(summary.unpriced_models()[0].total_tokens, 120);
+    }
+}
@@ -0,0 +1,199 @@
+use std::fmt::Write as _;
+
+use crate::budget_period::{budget_spend, period_start};
+use crate::codex_import::CodexRateLimit;
+use crate::models::provider_summaries;
+use crate::query::Query;
+use crate::ui::{format_money, format_reset_from, format_token_count};
+use crate::worker::Snapshot;
+
+/// What `promptpetrol summary` prints: spend per provider, then the
+/// budget, the team budget and the Codex rate limits where there are any.
+/// `filter` narrows the provider table only; the budget lines always count
+/// every entry, as the budget does.
+pub(crate) fn format_summary_report(
+    snapshot: &Snapshot,
+    filter: Option<&Query>,
+    now_epoch_secs: i64,
+) -> String {
+    let currency = &snapshot.config.currency;
+    let providers = match filter {
+        Some(filter) => {
+            let mut data = snapshot.data.clone();
+            filter.retain(&mut data);
+            provider_summaries(&data)
+        }
+        None => provider_summaries(&snapshot.data),
+    };
+    let total_cost = providers.iter().map(|p| p.total_cost_usd).sum::<f64>();
+    let total_tokens = providers.iter().map(|p| p.total_tokens).sum::<u64>();
+    let share = |cost: f64| {
+        if total_cost > 0.0 {
+            format!("{:.0}%", cost / total_cost * 100.0)
+        } else {
+            "-".to_string()
+        }
+    };
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{:<24} {:>10} {:>12} {:>6}",
+        "provider", "tokens", "cost", "share"
+    );
+    for provider in &providers {
+        let _ = writeln!(
+            out,
+            "{:<24} {:>10} {:>12} {:>6}",
+            provider.provider,
+            format_token_count(provider.total_tokens),
+            format_money(provider.total_cost_usd, currency),
+            share(provider.total_cost_usd)
+        );
+    }
+    let _ = writeln!(
+        out,
+        "{:<24} {:>10} {:>12} {:>6}",
+        "total",
+        format_token_count(total_tokens),
+        format_money(total_cost, currency),
+        share(total_cost)
+    );
+    let _ = writeln!(out);
+
+    match snapshot.data.budget_usd {
+        Some(budget) => {
+            let spent = budget_spend(&snapshot.data, &snapshot.config, now_epoch_secs);
+            let period = match period_start(&snapshot.config, now_epoch_secs) {
+                Some(_) => " this month",
+                None => "",
+            };
+            let left = if spent > budget {
+                format!("over by {}", format_money(spent - budget, currency))
+            } else {
+                format!("{} left", format_money(budget - spent, currency))
+            };
+            let _ = write!(
+                out,
+                "Budget: {}, spent {}{period}, {left}",
+                format_money(budget, currency),
+                format_money(spent, currency)
+            );
+            if snapshot.carried_over_usd > 0.0 {
+                let _ = write!(
+                    out,
+                    " ({} carried over)",
+                    format_money(snapshot.carried_over_usd, currency)
+                );
+            }
+            let _ = writeln!(out);
+        }
+        None => {
+            let _ = writeln!(out, "Budget: none set");
+        }
+    }
+    if let Some(since) = snapshot.config.spending_frozen_since.as_deref() {
+        let _ = writeln!(out, "Spending frozen since {since}");
+    }
+    if let Some(team) = snapshot.team_budget.as_ref() {
+        let spent = team.spent_usd();
+        let _ = write!(
+            out,
+            "Team: spent {} across {} members",
+            format_money(spent, currency),
+            team.members.len()
+        );
+        if let Some(budget) = team.budget_or(snapshot.data.budget_usd) {
+            let _ = write!(out, " of {}", format_money(budget, currency));
+        }
+        let _ = writeln!(out);
+    }
+
+    if let Some(limits) = snapshot.codex_limits.as_ref() {
+        let limits = limits.as_of(now_epoch_secs);
+        let now = u64::try_from(now_epoch_secs).unwrap_or(0);
+        let limit_line = |label: &str, limit: Option<&CodexRateLimit>| match limit {
+            Some(limit) => format!(
+                "{label}: {:.0}% used, resets {}",
+                limit.used_percent,
+                format_reset_from(limit.resets_at, now)
+            ),
+            None => format!("{label}: no reading"),
+        };
+        let _ = writeln!(
+            out,
+            "{}",
+            limit_line("Codex 5h limit", limits.primary.as_ref())
+        );
+        let _ = writeln!(
+            out,
+            "{}",
+            limit_line("Codex weekly limit", limits.secondary.as_ref())
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::UsageData;
+
+    #[test]
+    fn summary_lists_providers_budget_and_codex_limits() {
+        let now = 1_780_000_000;
+        let data = UsageData::default();
+        let total = data.entries.iter().map(|entry| entry.cost_usd).sum::<f64>();
+        let mut snapshot = Snapshot::for_test(data);
+        snapshot.codex_limits = Some(
+            serde_json::from_value(serde_json::json!({
+                "timestamp": "2026-05-28T20:00:00Z",
+                "primary": {"used_percent": 42.0, "window_minutes": 300,
+                            "resets_at": now as u64 + 3_720},
+                "secondary": null,
+            }))
+            .expect("limits"),
+        );
+
+        let report = format_summary_report(&snapshot, None, now);
+        let lines = report.lines().collect::<Vec<_>>();
+        assert!(lines[0].starts_with("provider"));
+        assert!(lines[1].starts_with("anthropic"));
+        assert!(lines[1].ends_with('%'));
+        let total_line = lines.iter().find(|line| line.starts_with("total"));
+        assert!(
+            total_line
+                .expect("total")
+                .contains(&format_money(total, "USD"))
+        );
+        assert!(report.contains(&format!(
+            "Budget: $50.000, spent {}, {} left",
+            format_money(total, "USD"),
+            format_money(50.0 - total, "USD")
+        )));
+        assert!(report.contains("Codex 5h limit: 42% used, resets in 1h 2m"));
+        assert!(report.contains("Codex weekly limit: no reading"));
+        assert!(!report.contains("frozen"));
+
+        let openai = snapshot
+            .data
+            .entries
+            .iter()
+            .filter(|entry| &*entry.provider == "openai")
+            .map(|entry| entry.cost_usd)
+            .sum::<f64>();
+        let filter = Query::parse("provider=openai").expect("query");
+        let report = format_summary_report(&snapshot, Some(&filter), now);
+        let lines = report.lines().collect::<Vec<_>>();
+        assert!(lines[1].starts_with("openai"));
+        assert!(lines[2].starts_with("total"));
+        assert!(lines[2].contains(&format_money(openai, "USD")));
+        assert!(report.contains(&format!("spent {},", format_money(total, "USD"))));
+
+        snapshot.data.budget_usd = None;
+        snapshot.codex_limits = None;
+        let report = format_summary_report(&snapshot, None, now);
+        assert!(report.contains("Budget: none set"));
+        assert!(!report.contains("Codex"));
+    }
+}
@@ -0,0 +1,250 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{Result, eyre};
+use serde_json::Value;
+
+use crate::journal;
+use crate::lock;
+use crate::models::{AppConfig, UsageEntry, parse_usage_data, write_atomically};
+
+/// Conflict copies are renamed with this suffix once merged, so they are
+/// not merged again but stay around for inspection.
+const MERGED_SUFFIX: &str = ".merged";
+
+/// What merging conflict copies into the data file did.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct MergeReport {
+    pub(crate) files: usize,
+    pub(crate) recovered: usize,
+    /// The data file as written after the merge.
+    pub(crate) contents: String,
+}
+
+impl MergeReport {
+    pub(crate) fn describe(&self) -> String {
+        format!(
+            "Merged {} sync conflict file{}: recovered {} entr{}",
+            self.files,
+            if self.files == 1 { "" } else { "s" },
+            self.recovered,
+            if self.recovered == 1 { "y" } else { "ies" }
+        )
+    }
+}
+
+/// Conflict copies of `data_file` left by sync clients: Syncthing's
+/// `usage.sync-conflict-<date>-<device>.json` and Dropbox's
+/// `usage (conflicted copy <date>).json`.
+pub(crate) fn conflict_files(data_file: &Path) -> Result<Vec<PathBuf>> {
+    let (Some(stem), Some(dir)) = (
+        data_file.file_stem().and_then(|stem| stem.to_str()),
+        data_file.parent(),
+    ) else {
+        return Ok(Vec::new());
+    };
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let extension = data_file
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| format!(".{ext}"))
+        .unwrap_or_default();
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let Some(middle) = name
+            .strip_prefix(stem)
+            .and_then(|rest| rest.strip_suffix(extension.as_str()))
+        else {
+            continue;
+        };
+        if middle.starts_with(".sync-conflict-")
+            || (middle.starts_with(" (") && middle.contains("conflicted copy"))
+        {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Folds entries that only exist in conflict copies back into `data_file`.
+/// Entries match by their `id` when they have one, else by timestamp,
+/// provider, model, tokens, cost and tag. The previous contents are
+/// journaled as a `merge` so it can be undone, and each merged copy is
+/// renamed to `<name>.merged`. `None` when there are no conflict copies.
+pub(crate) fn merge_sync_conflicts(
+    data_file: &Path,
+    config: &AppConfig,
+) -> Result<Option<MergeReport>> {
+    let files = conflict_files(data_file)?;
+    if files.is_empty() || !data_file.exists() {
+        return Ok(None);
+    }
+
+    let _lock = lock::exclusive(data_file)?;
+    let previous = fs::read_to_string(data_file)?;
+    let mut merged = serde_json::from_str::<Value>(&previous)?;
+    let (_, main_keys) = keyed_entries(&previous, config)?;
+    let mut seen = main_keys.into_iter().collect::<HashSet<_>>();
+    let mut recovered = Vec::new();
+    for file in &files {
+        let contents = fs::read_to_string(file)?;
+        let (raw_entries, keys) =
+            keyed_entries(&contents, config).map_err(|err| eyre!("{}: {err}", file.display()))?;
+        for (raw, key) in raw_entries.into_iter().zip(keys) {
+            if seen.insert(key) {
+                recovered.push(raw);
+            }
+        }
+    }
+
+    let contents = if recovered.is_empty() {
+        previous
+    } else {
+        let entries = merged
+            .get_mut("entries")
+            .and_then(Value::as_array_mut)
+            .ok_or_else(|| eyre!("{} has no entries list", data_file.display()))?;
+        entries.extend(recovered.iter().cloned());
+        let contents = serde_json::to_string_pretty(&merged)?;
+        journal::record(data_file, &previous, "merge")?;
+        write_atomically(data_file, contents.as_bytes())?;
+        contents
+    };
+    for file in &files {
+        let mut name = file.file_name().unwrap_or_default().to_os_string();
+        name.push(MERGED_SUFFIX);
+        fs::rename(file, file.with_file_name(name))?;
+    }
+    Ok(Some(MergeReport {
+        files: files.len(),
+        recovered: recovered.len(),
+        contents,
+    }))
+}
+
+/// The file's raw entries alongside the key each one merges by.
+fn keyed_entries(contents: &str, config: &AppConfig) -> Result<(Vec<Value>, Vec<String>)> {
+    let raw = serde_json::from_str::<Value>(contents)?
+        .get("entries")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    let data = parse_usage_data(contents, config)?;
+    let keys = raw
+        .iter()
+        .zip(&data.entries)
+        .map(|(raw, entry)| match raw.get("id") {
+            Some(Value::String(id)) => format!("id:{id}"),
+            Some(id @ Value::Number(_)) => format!("id:{id}"),
+            _ => entry_fingerprint(entry),
+        })
+        .collect();
+    Ok((raw, keys))
+}
+
+fn entry_fingerprint(entry: &UsageEntry) -> String {
+    format!(
+        "{}|{}|{}|{}|{}|{:.9}|{}",
+        entry.timestamp,
+        entry.provider,
+        entry.model,
+        entry.input_tokens,
+        entry.output_tokens,
+        entry.cost_usd,
+        entry.tag.as_deref().unwrap_or_default()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::make_temp_dir;
+
+    #[test]
+    fn recovers_entries_only_found_in_conflict_copies() {
+        let dir = make_temp_dir("sync");
+        let data_file = dir.join("usage.json");
+        let entry = |id: &str, timestamp: &str| {
+            format!(
+                r#"{{"id": "{id}", "timestamp": "{timestamp}", "provider": "openai",
+                    "model": "gpt-4.1-mini", "prompt_tokens": 10, "completion_tokens": 5}}"#
+            )
+        };
+        let unkeyed = r#"{"timestamp": "2026-02-10T05:00:00Z", "provider": "anthropic",
+            "model": "claude-3.7-sonnet", "input_tokens": 1, "output_tokens": 1, "cost_usd": 0.5}"#;
+        fs::write(
+            &data_file,
+            format!(
+                r#"{{"budget_usd": 10, "entries": [{}, {unkeyed}]}}"#,
+                entry("a", "2026-02-10T01:00:00Z")
+            ),
+        )
+        .expect("write");
+        let syncthing = dir.join("usage.sync-conflict-20260210-031500-ABCDEFG.json");
+        fs::write(
+            &syncthing,
+            format!(
+                r#"{{"budget_usd": 10, "entries": [{}, {}, {unkeyed}]}}"#,
+                entry("a", "2026-02-10T01:00:00Z"),
+                entry("b", "2026-02-10T02:00:00Z")
+            ),
+        )
+        .expect("write");
+        let dropbox = dir.join("usage (conflicted copy 2026-02-10).json");
+        fs::write(
+            &dropbox,
+            format!(
+                r#"{{"budget_usd": 10, "entries": [{}, {}]}}"#,
+                entry("b", "2026-02-10T02:00:00Z"),
+                entry("c", "2026-02-10T03:00:00Z")
+            ),
+        )
+        .expect("write");
+        fs::write(dir.join("usage.json.lock"), "").expect("write");
+
+        assert_eq!(
+            conflict_files(&data_file).expect("conflicts"),
+            vec![dropbox.clone(), syncthing.clone()]
+        );
+        let report = merge_sync_conflicts(&data_file, &AppConfig::default())
+            .expect("merge")
+            .expect("report");
+        assert_eq!((report.files, report.recovered), (2, 2));
+        assert_eq!(
+            report.describe(),
+            "Merged 2 sync conflict files: recovered 2 entries"
+        );
+
+        let merged = parse_usage_data(
+            &fs::read_to_string(&data_file).expect("read"),
+            &AppConfig::default(),
+        )
+        .expect("parse");
+        assert_eq!(merged.entries.len(), 4);
+        assert_eq!(merged.entries[1].cost_usd, 0.5);
+        assert!(!syncthing.exists());
+        assert!(
+            dir.join(format!(
+                "{}.merged",
+                syncthing.file_name().unwrap().to_string_lossy()
+            ))
+            .exists()
+        );
+        assert_eq!(journal::entries(&data_file).expect("journal").len(), 1);
+        assert_eq!(
+            merge_sync_conflicts(&data_file, &AppConfig::default()).expect("merge"),
+            None
+        );
+        let _ = fs::remove_dir_all(dir);
+    }
+}
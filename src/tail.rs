@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use color_eyre::Result;
+use tokio::sync::mpsc;
+
+use crate::daemon::shutdown_signal;
+use crate::models::UsageData;
+use crate::worker::{RefreshWorker, run_refresh_worker};
+
+/// Entries seen so far, keyed by their serialized form. Entries carry no
+/// id, so identical ones are counted: a second identical request is new
+/// even though its line matches the first.
+#[derive(Debug, Default)]
+pub(crate) struct EntryTracker {
+    seen: HashMap<String, usize>,
+}
+
+impl EntryTracker {
+    /// One JSON line per entry of `data` not in the previous call's data,
+    /// in store order. Entries that went away are forgotten, so they are
+    /// new again if they come back.
+    pub(crate) fn new_entries(&mut self, data: &UsageData) -> Result<Vec<String>> {
+        let mut seen = HashMap::with_capacity(self.seen.len());
+        let mut lines = Vec::new();
+        for entry in &data.entries {
+            let line = serde_json::to_string(entry)?;
+            let count = seen.entry(line.clone()).or_insert(0);
+            *count += 1;
+            if *count > self.seen.get(&line).copied().unwrap_or(0) {
+                lines.push(line);
+            }
+        }
+        self.seen = seen;
+        Ok(lines)
+    }
+}
+
+/// `promptpetrol tail`: follows the store `worker` reads and prints each
+/// entry that shows up in it as one line of JSON on stdout, flushed as it
+/// goes. Entries already there at the start are skipped. Runs until SIGINT,
+/// SIGTERM or the reader closing the pipe.
+pub(crate) async fn run_tail(worker: RefreshWorker) -> Result<()> {
+    let mut tracker = EntryTracker::default();
+    tracker.new_entries(&worker.snapshot().data)?;
+
+    let (command_tx, command_rx) = mpsc::unbounded_channel();
+    let (snapshot_tx, mut snapshot_rx) = mpsc::unbounded_channel();
+    let worker_task = tokio::spawn(run_refresh_worker(worker, command_rx, snapshot_tx));
+
+    loop {
+        tokio::select! {
+            snapshot = snapshot_rx.recv() => {
+                let Some(snapshot) = snapshot else {
+                    break;
+                };
+                let mut out = io::stdout().lock();
+                let written = tracker
+                    .new_entries(&snapshot.data)?
+                    .iter()
+                    .try_for_each(|line| writeln!(out, "{line}"))
+                    .and_then(|()| out.flush());
+                match written {
+                    Ok(()) => {}
+                    Err(err) if err.kind() == io::ErrorKind::BrokenPipe => break,
+                    Err(err) => return Err(err.into()),
+                }
+            }
+            _ = shutdown_signal() => break,
+        }
+    }
+
+    drop(command_tx);
+    drop(snapshot_rx);
+    worker_task.await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_entries_added_since_the_last_look_are_new() {
+        let mut data = UsageData::default();
+        let mut tracker = EntryTracker::default();
+        assert_eq!(
+            tracker.new_entries(&data).expect("lines").len(),
+            data.entries.len()
+        );
+        assert!(tracker.new_entries(&data).expect("lines").is_empty());
+
+        // A repeat of an existing request is new usage too.
+        let repeat = data.entries[0].clone();
+        data.entries.push(repeat.clone());
+        let lines = tracker.new_entries(&data).expect("lines");
+        assert_eq!(lines, [serde_json::to_string(&repeat).expect("json")]);
+        let line = serde_json::from_str::<serde_json::Value>(&lines[0]).expect("parse");
+        assert_eq!(line["provider"], &*repeat.provider);
+
+        data.entries.pop();
+        assert!(tracker.new_entries(&data).expect("lines").is_empty());
+        data.entries.push(repeat);
+        assert_eq!(tracker.new_entries(&data).expect("lines").len(), 1);
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use color_eyre::eyre::{Result, bail, eyre};
+use serde::{Deserialize, Serialize};
+
+use crate::clock;
+use crate::http;
+use crate::models::{NetworkConfig, TeamBudgetConfig};
+use crate::rate_limit::RequestBudget;
+use crate::retry::{POLLER_RETRY, retry};
+
+const SYNC_TIMEOUT: Duration = Duration::from_secs(10);
+/// Attempts at a conditional PUT before giving up on a busy shared blob.
+const MAX_PUT_ATTEMPTS: usize = 3;
+
+/// The shared JSON blob behind a team budget. Every member writes only its
+/// own entry, so concurrent writers never undo each other's spend.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct TeamBudgetState {
+    /// Team-wide budget; when absent each member's local budget is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) budget_usd: Option<f64>,
+    #[serde(default)]
+    pub(crate) members: BTreeMap<String, MemberSpend>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct MemberSpend {
+    pub(crate) spent_usd: f64,
+    pub(crate) updated_at: String,
+}
+
+impl TeamBudgetState {
+    pub(crate) fn spent_usd(&self) -> f64 {
+        self.members.values().map(|member| member.spent_usd).sum()
+    }
+
+    /// The shared budget, falling back to this machine's own.
+    pub(crate) fn budget_or(&self, local_budget: Option<f64>) -> Option<f64> {
+        self.budget_usd.or(local_budget)
+    }
+
+    fn record(&mut self, member: &str, spent_usd: f64, now_epoch_secs: i64) {
+        self.members.insert(
+            member.to_string(),
+            MemberSpend {
+                spent_usd,
+                updated_at: clock::format_timestamp(now_epoch_secs),
+            },
+        );
+    }
+}
+
+/// Publishes `spent_usd` as this machine's share of the team budget and
+/// returns the merged state. The blob is read, updated and written back
+/// with `If-Match` on its ETag, so a concurrent write from another member
+/// makes the PUT fail and the cycle start again from fresh state. Servers
+/// without ETags (plain presigned S3 URLs, for one) get an unconditional
+/// PUT.
+pub(crate) fn sync_team_budget(
+    config: &TeamBudgetConfig,
+    network: &NetworkConfig,
+    budget: &RequestBudget,
+    spent_usd: f64,
+) -> Result<TeamBudgetState> {
+    let agent = http::agent(network, SYNC_TIMEOUT)?;
+    let token = config
+        .token_env
+        .as_deref()
+        .and_then(|name| std::env::var(name).ok());
+    let member = config.member_name();
+    let read_url = config.read_url.as_deref().unwrap_or(&config.url);
+
+    for _ in 0..MAX_PUT_ATTEMPTS {
+        let response = retry(&POLLER_RETRY, budget, || {
+            let mut request = agent.get(read_url);
+            if let Some(token) = token.as_deref() {
+                request = request.header("Authorization", format!("Bearer {token}"));
+            }
+            request.call()
+        });
+        let (mut state, etag) = match response {
+            Ok(mut response) => {
+                let etag = response
+                    .headers()
+                    .get("etag")
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string);
+                let body = response.body_mut().read_to_string()?;
+                let state = serde_json::from_str::<TeamBudgetState>(&body)
+                    .map_err(|err| eyre!("team budget state: {err}"))?;
+                (state, etag)
+            }
+            Err(err) if status_code(&err) == Some(404) => (TeamBudgetState::default(), None),
+            Err(err) => bail!("team budget GET: {err}"),
+        };
+        state.record(&member, spent_usd, clock::now_epoch_secs());
+
+        let body = serde_json::to_string_pretty(&state)?;
+        let response = retry(&POLLER_RETRY, budget, || {
+            let mut request = agent.put(&config.url).content_type("application/json");
+            if let Some(token) = token.as_deref() {
+                request = request.header("Authorization", format!("Bearer {token}"));
+            }
+            if let Some(etag) = etag.as_deref() {
+                request = request.header("If-Match", etag);
+            }
+            request.send(&body)
+        });
+        match response {
+            Ok(_) => return Ok(state),
+            Err(err) if status_code(&err) == Some(412) => continue,
+            Err(err) => bail!("team budget PUT: {err}"),
+        }
+    }
+    bail!("team budget PUT: state kept changing underneath, giving up for this cycle")
+}
+
+fn status_code(err: &color_eyre::Report) -> Option<u16> {
+    match err.downcast_ref::<ureq::Error>() {
+        Some(ureq::Error::StatusCode(status)) => Some(*status),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recording_replaces_only_this_members_share() {
+        let mut state = serde_json::from_str::<TeamBudgetState>(
+            r#"{
+                "budget_usd": 500,
+                "members": {
+                    "ana": {"spent_usd": 120.5, "updated_at": "2026-03-01T09:00:00Z"},
+                    "bo": {"spent_usd": 30, "updated_at": "2026-03-01T09:00:00Z"}
+                }
+            }"#,
+        )
+        .expect("state");
+
+        state.record("bo", 45.0, 0);
+        state.record("cy", 4.5, 0);
+
+        assert_eq!(state.budget_usd, Some(500.0));
+        assert_eq!(state.members["ana"].spent_usd, 120.5);
+        assert_eq!(state.members["bo"].spent_usd, 45.0);
+        assert_eq!(state.members["bo"].updated_at, "1970-01-01T00:00:00Z");
+        assert_eq!(state.spent_usd(), 170.0);
+        assert_eq!(
+            serde_json::from_str::<TeamBudgetState>("{}").expect("empty"),
+            TeamBudgetState::default()
+        );
+    }
+}
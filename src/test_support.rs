@@ -0,0 +1,15 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A fresh directory under the system temp dir, named
+/// `promptpetrol-<prefix>-<nanos>` so concurrent tests don't collide.
+pub(crate) fn make_temp_dir(prefix: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("clock")
+        .as_nanos();
+    let path = std::env::temp_dir().join(format!("promptpetrol-{prefix}-{nanos}"));
+    fs::create_dir_all(&path).expect("create temp dir");
+    path
+}
@@ -0,0 +1,195 @@
+use ratatui::buffer::Buffer;
+use ratatui::style::{Color, Modifier};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Colour preset for severities and gauges, set with `theme` in the config.
+/// The colour-blind presets keep to hues that stay apart under that kind
+/// of colour blindness (after Okabe and Ito).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Theme {
+    #[default]
+    Default,
+    /// Red-green, weak green.
+    Deuteranopia,
+    /// Red-green, weak red: alerts use orange, which reads brighter than red.
+    Protanopia,
+    /// Blue-yellow.
+    Tritanopia,
+}
+
+impl Theme {
+    pub(crate) fn palette(self) -> Palette {
+        match self {
+            Theme::Default => Palette {
+                ok: Color::Green,
+                warn: Color::Yellow,
+                alert: Color::Red,
+                info: Color::Cyan,
+            },
+            Theme::Deuteranopia => Palette {
+                ok: Color::Rgb(0, 114, 178),
+                warn: Color::Rgb(240, 228, 66),
+                alert: Color::Rgb(213, 94, 0),
+                info: Color::Rgb(86, 180, 233),
+            },
+            Theme::Protanopia => Palette {
+                ok: Color::Rgb(0, 114, 178),
+                warn: Color::Rgb(240, 228, 66),
+                alert: Color::Rgb(230, 159, 0),
+                info: Color::Rgb(86, 180, 233),
+            },
+            Theme::Tritanopia => Palette {
+                ok: Color::Rgb(0, 158, 115),
+                warn: Color::Rgb(204, 121, 167),
+                alert: Color::Rgb(213, 94, 0),
+                info: Color::Rgb(0, 158, 115),
+            },
+        }
+    }
+}
+
+/// How bad a reading is. Shown with a symbol as well as a colour so it
+/// still reads when the colours don't. Ordered from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Severity {
+    Ok,
+    Warn,
+    Alert,
+}
+
+impl Severity {
+    pub(crate) fn symbol(self) -> &'static str {
+        match self {
+            Severity::Ok => "✓",
+            Severity::Warn => "!",
+            Severity::Alert => "‼",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Palette {
+    pub(crate) ok: Color,
+    pub(crate) warn: Color,
+    pub(crate) alert: Color,
+    /// Readings and neutral highlights.
+    pub(crate) info: Color,
+}
+
+impl Palette {
+    pub(crate) fn severity(&self, severity: Severity) -> Color {
+        match severity {
+            Severity::Ok => self.ok,
+            Severity::Warn => self.warn,
+            Severity::Alert => self.alert,
+        }
+    }
+}
+
+/// Whether to draw without colour: `NO_COLOR` is set, or the terminal
+/// does not claim 256 colours, where the palettes' colours come out wrong
+/// or not at all. `var` looks up an environment variable.
+pub(crate) fn detect_monochrome(var: impl Fn(&str) -> Option<String>) -> bool {
+    let set = |name: &str| var(name).filter(|value| !value.is_empty());
+    if set("NO_COLOR").is_some() {
+        return true;
+    }
+    if set("COLORTERM").is_some_and(|value| matches!(value.as_str(), "truecolor" | "24bit")) {
+        return false;
+    }
+    match set("TERM") {
+        Some(term) => term == "dumb" || !(term.contains("256color") || term.contains("direct")),
+        // The Windows console has colour but no TERM.
+        None => !cfg!(windows),
+    }
+}
+
+/// Redraws a finished frame without colour. Anything on a coloured
+/// background is shown in reverse video and coloured text in bold, so
+/// badges, alerts and the selection still stand out.
+pub(crate) fn to_monochrome(buffer: &mut Buffer) {
+    for cell in &mut buffer.content {
+        let mut modifier = cell.modifier;
+        if !matches!(cell.bg, Color::Reset | Color::Black) {
+            modifier |= Modifier::REVERSED;
+        }
+        match cell.fg {
+            Color::Reset | Color::Black | Color::White | Color::Gray => {}
+            Color::DarkGray => modifier |= Modifier::DIM,
+            _ => modifier |= Modifier::BOLD,
+        }
+        cell.fg = Color::Reset;
+        cell.bg = Color::Reset;
+        cell.modifier = modifier;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::layout::Rect;
+    use ratatui::style::Style;
+
+    use super::*;
+
+    #[test]
+    fn every_preset_keeps_its_severities_apart() {
+        for theme in [
+            Theme::Default,
+            Theme::Deuteranopia,
+            Theme::Protanopia,
+            Theme::Tritanopia,
+        ] {
+            let palette = theme.palette();
+            let colors = [Severity::Ok, Severity::Warn, Severity::Alert]
+                .map(|severity| palette.severity(severity));
+            assert!(
+                colors[0] != colors[1] && colors[1] != colors[2] && colors[0] != colors[2],
+                "{theme:?}"
+            );
+        }
+        let theme: Theme = serde_json::from_str("\"deuteranopia\"").expect("preset");
+        assert_eq!(theme, Theme::Deuteranopia);
+    }
+
+    #[test]
+    fn basic_terminals_fall_back_to_monochrome() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        assert!(!detect_monochrome(env(&[("TERM", "xterm-256color")])));
+        assert!(!detect_monochrome(env(&[
+            ("TERM", "xterm"),
+            ("COLORTERM", "truecolor")
+        ])));
+        assert!(detect_monochrome(env(&[("TERM", "xterm")])));
+        assert!(detect_monochrome(env(&[("TERM", "dumb")])));
+        assert!(detect_monochrome(env(&[
+            ("TERM", "xterm-256color"),
+            ("NO_COLOR", "1")
+        ])));
+
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 3, 1));
+        buffer[(0, 0)].set_style(Style::default().fg(Color::Black).bg(Color::Red));
+        buffer[(1, 0)].set_style(Style::default().fg(Color::Rgb(0, 114, 178)));
+        to_monochrome(&mut buffer);
+        assert_eq!(
+            (
+                buffer[(0, 0)].fg,
+                buffer[(0, 0)].bg,
+                buffer[(0, 0)].modifier
+            ),
+            (Color::Reset, Color::Reset, Modifier::REVERSED)
+        );
+        assert_eq!(
+            (buffer[(1, 0)].fg, buffer[(1, 0)].modifier),
+            (Color::Reset, Modifier::BOLD)
+        );
+        assert_eq!(buffer[(2, 0)].modifier, Modifier::empty());
+    }
+}
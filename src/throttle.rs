@@ -0,0 +1,143 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use color_eyre::eyre::Result;
+use serde::Serialize;
+
+use crate::alerts::{Alert, AlertLevel};
+use crate::codex_import::{expand_home, home_dir};
+use crate::models::{AppConfig, write_atomically};
+
+/// How hard an agent wrapper polling the throttle file should back off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ThrottleSeverity {
+    SlowDown,
+    Stop,
+}
+
+/// Contents of the throttle file while something is breached.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct Throttle {
+    pub(crate) severity: ThrottleSeverity,
+    /// The message of the most severe breach.
+    pub(crate) reason: String,
+    pub(crate) alerts: Vec<Alert>,
+}
+
+/// Budget, spend-rate, limit and runaway alerts at `Alert` level call for
+/// throttling; an exhausted budget or a spending freeze calls for stopping.
+/// `None` when nothing does.
+pub(crate) fn throttle_for(alerts: &[Alert]) -> Option<Throttle> {
+    let breaches = alerts
+        .iter()
+        .filter(|alert| alert.level == AlertLevel::Alert)
+        .map(|alert| (severity(alert), alert))
+        .collect::<Vec<_>>();
+    let (severity, worst) = breaches
+        .iter()
+        .copied()
+        .max_by_key(|(severity, _)| *severity)?;
+    Some(Throttle {
+        severity,
+        reason: worst.message.clone(),
+        alerts: breaches
+            .into_iter()
+            .map(|(_, alert)| alert.clone())
+            .collect(),
+    })
+}
+
+fn severity(alert: &Alert) -> ThrottleSeverity {
+    if alert.key == "budget.exhausted" || alert.key.starts_with("spend.frozen") {
+        ThrottleSeverity::Stop
+    } else {
+        ThrottleSeverity::SlowDown
+    }
+}
+
+/// Brings the configured throttle file in line with `alerts`: written
+/// while a breach holds, removed once none does. Left alone when its
+/// contents would not change, so pollers can watch its mtime.
+pub(crate) fn update_throttle_file(config: &AppConfig, alerts: &[Alert]) -> Result<()> {
+    let Some(path) = config.throttle_file.as_deref() else {
+        return Ok(());
+    };
+    write_throttle(
+        &expand_home(path, &home_dir()),
+        throttle_for(alerts).as_ref(),
+    )
+}
+
+fn write_throttle(path: &Path, throttle: Option<&Throttle>) -> Result<()> {
+    let Some(throttle) = throttle else {
+        return match fs::remove_file(path) {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        };
+    };
+    let contents = serde_json::to_string_pretty(throttle)?;
+    if fs::read_to_string(path).is_ok_and(|current| current == contents) {
+        return Ok(());
+    }
+    write_atomically(path, contents.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::make_temp_dir;
+
+    fn alert(key: &str, level: AlertLevel, message: &str) -> Alert {
+        Alert {
+            key: key.to_string(),
+            level,
+            provider: None,
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn throttle_file_follows_the_worst_breach() {
+        let dir = make_temp_dir("throttle");
+        let path = dir.join("throttle.json");
+        let config = AppConfig {
+            throttle_file: Some(path.to_string_lossy().into_owned()),
+            ..AppConfig::default()
+        };
+
+        let watch = alert("budget.watch", AlertLevel::Watch, "80%");
+        update_throttle_file(&config, std::slice::from_ref(&watch)).expect("update");
+        assert!(!path.exists());
+
+        let codex = alert(
+            "codex.5h",
+            AlertLevel::Alert,
+            "codex 5h limit at 95.0% used",
+        );
+        update_throttle_file(&config, std::slice::from_ref(&codex)).expect("update");
+        let written = serde_json::from_str::<serde_json::Value>(
+            &fs::read_to_string(&path).expect("throttle file"),
+        )
+        .expect("json");
+        assert_eq!(written["severity"], "slow_down");
+        assert_eq!(written["reason"], "codex 5h limit at 95.0% used");
+
+        let exhausted = alert("budget.exhausted", AlertLevel::Alert, "spent $12.00");
+        let throttle = throttle_for(&[codex, exhausted, watch]).expect("throttle");
+        assert_eq!(throttle.severity, ThrottleSeverity::Stop);
+        assert_eq!(throttle.reason, "spent $12.00");
+        assert_eq!(throttle.alerts.len(), 2);
+        let frozen = alert("spend.frozen", AlertLevel::Alert, "SPENDING FROZEN");
+        assert_eq!(
+            throttle_for(&[frozen]).expect("throttle").severity,
+            ThrottleSeverity::Stop
+        );
+
+        update_throttle_file(&config, &[]).expect("update");
+        assert!(!path.exists());
+        update_throttle_file(&config, &[]).expect("already clear");
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -1,97 +1,112 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashSet};
+use std::str::FromStr;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use ratatui::Frame;
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
 use ratatui::style::{Color, Modifier, Style};
-use ratatui::symbols::border;
+use ratatui::symbols::{Marker, border};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::canvas::{Canvas, Circle, Line as CanvasLine};
-use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+use ratatui::widgets::{
+    Axis, Block, Borders, Chart, Clear, Dataset, Gauge, GraphType, Paragraph, Wrap,
+};
 
-use crate::app::App;
-use crate::codex_import::{
-    CodexRateLimit, CodexRateLimits, codex_import_diagnostics, latest_codex_limits,
+use crate::app::{App, DashboardView, TourStep};
+use crate::budget_period::{BudgetRollover, PeriodPreview, period_preview};
+use crate::clock;
+use crate::codex_import::{CodexRateLimit, CodexRateLimits, ImportProgress, SessionCost};
+use crate::context_growth::{ContextGrowth, HeavyLoad};
+use crate::editor_import::PlanQuota;
+use crate::freeze::{frozen_since, spend_while_frozen};
+use crate::importers::ImporterStatus;
+use crate::keymap::{Action, KeyContext, Keymap};
+use crate::layout::dashboard_layout;
+use crate::models::{
+    AppConfig, PricingMatch, ProviderItem, UnpricedModel, authoritative_percent, cost_multiplier,
+};
+use crate::pricing_breakdown::{PricingBreakdown, pricing_breakdown};
+use crate::pricing_editor::{PricingEditor, PricingField};
+use crate::query::{Field, Query, Queryable};
+use crate::reconcile::{MonthlyAccuracy, estimate_accuracy};
+use crate::retry::{BreakerState, PollerHealth};
+use crate::theme::{Palette, Severity};
+use crate::widgets::{
+    AppState, Dial, draw_custom_layout, draw_forecast_chart, draw_gauges, draw_key_table,
+    draw_model_leaderboard, draw_project_table, draw_provider_models, draw_spend_figures,
+    draw_spend_history, draw_zoomed_dial,
 };
-use crate::models::{provider_stats, provider_summaries};
+use crate::wizard::{SetupWizard, WIZARD_STEPS, WizardStep};
 
 const APP_NAME: &str = "PromptPetrol";
+const SIDEBAR_WIDTH: u16 = 30;
+/// Days the Daily Cost panel covers, today included.
+const SPEND_HISTORY_DAYS: usize = 30;
+const SPEND_HISTORY_HEIGHT: u16 = 5;
 
 pub(crate) fn draw(frame: &mut Frame<'_>, app: &App) {
-    let providers = provider_summaries(&app.data);
-    let area = frame.area();
+    if let Some(dial) = app.zoomed_dial {
+        let state = AppState::new(app, clock::now_epoch_secs());
+        draw_zoomed_dial(frame, frame.area(), &state, dial, &app.dial_trend(dial));
+        if let Some(dial) = app.inspected_dial {
+            draw_inspect_overlay(frame, &state, dial);
+        }
+        if app.show_help {
+            draw_help_overlay(frame, &Keymap::new(&app.config).unwrap_or_default());
+        }
+        return;
+    }
+    let items = app.provider_items();
+    let selected_provider = app.selected_provider.as_deref().unwrap_or("");
+    let kiosk = app.kiosk.is_some();
+    let screen = match frozen_since(&app.config) {
+        Some(since) => {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(1), Constraint::Min(0)])
+                .split(frame.area());
+            draw_freeze_banner(frame, rows[0], app, since);
+            rows[1]
+        }
+        None => frame.area(),
+    };
+    let (sidebar, area) = if app.show_sidebar && !kiosk {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(SIDEBAR_WIDTH), Constraint::Min(40)])
+            .split(screen);
+        draw_provider_sidebar(frame, columns[0], app, &items, selected_provider);
+        (Some(columns[0]), columns[1])
+    } else {
+        (None, screen)
+    };
 
+    // The spend history only goes in when the view keeps enough room.
+    let history = area.height >= 8 + 12 + SPEND_HISTORY_HEIGHT;
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(8), Constraint::Min(8)])
+        .constraints([
+            Constraint::Length(8),
+            Constraint::Min(8),
+            Constraint::Length(if history { SPEND_HISTORY_HEIGHT } else { 0 }),
+        ])
         .split(area);
     let top_panels = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(44), Constraint::Percentage(56)])
+        .constraints(if kiosk {
+            // Room for both figures in block letters.
+            [Constraint::Percentage(64), Constraint::Percentage(36)]
+        } else {
+            [Constraint::Percentage(44), Constraint::Percentage(56)]
+        })
         .split(chunks[0]);
 
-    let selected_provider = app.selected_provider.as_deref().unwrap_or("");
-    let selected_stats = provider_stats(&app.data, selected_provider);
-    let max_cost = providers
-        .iter()
-        .map(|p| p.total_cost_usd)
-        .fold(0.0_f64, f64::max);
-    let max_tokens = providers
-        .iter()
-        .map(|p| p.total_tokens)
-        .fold(0_u64, u64::max);
-
-    let budget_ratio = match (selected_stats.as_ref(), app.data.budget_usd) {
-        (Some(provider), Some(budget)) if budget > 0.0 => {
-            (provider.total_cost_usd / budget).clamp(0.0, 1.0)
-        }
-        _ => 0.0,
-    };
-    let token_ratio = selected_stats
-        .as_ref()
-        .map(|provider| {
-            if max_tokens == 0 {
-                0.0
-            } else {
-                (provider.total_tokens as f64 / max_tokens as f64).clamp(0.0, 1.0)
-            }
-        })
-        .unwrap_or(0.0);
-    let spend_ratio = selected_stats
-        .as_ref()
-        .map(|provider| {
-            if max_cost <= f64::EPSILON {
-                0.0
-            } else {
-                (provider.total_cost_usd / max_cost).clamp(0.0, 1.0)
-            }
-        })
-        .unwrap_or(0.0);
-    let activity_ratio = selected_stats
-        .as_ref()
-        .map(|provider| {
-            let total_requests = app.data.entries.len();
-            if total_requests == 0 {
-                0.0
-            } else {
-                (provider.requests as f64 / total_requests as f64).clamp(0.0, 1.0)
-            }
-        })
-        .unwrap_or(0.0);
-    let fuel_ratio = (1.0 - budget_ratio).clamp(0.0, 1.0);
-    let is_codex = selected_provider == "codex";
-    let codex_limits = if is_codex {
-        latest_codex_limits(&app.codex_cache)
-    } else {
-        None
-    };
-    let codex_import_age_secs = if is_codex {
-        codex_import_diagnostics(&app.codex_cache)
-            .last_import_at
-            .and_then(|timestamp| SystemTime::now().duration_since(timestamp).ok())
-            .map(|duration| duration.as_secs())
-    } else {
-        None
-    };
+    let state = AppState::new(app, clock::now_epoch_secs());
+    let aggregated = !app.marked_providers.is_empty();
+    let selected_stats = app.selected_stats();
+    let is_codex = state.dials.is_codex;
+    let plan_quota = state.plan_quota;
+    let palette = &state.palette;
 
     let basic_line = if let Some(provider) = selected_stats.as_ref() {
         if is_codex {
@@ -100,10 +115,29 @@ pub(crate) fn draw(frame: &mut Frame<'_>, app: &App) {
                 app.config.codex_import.model, provider.total_tokens, provider.requests
             )
         } else {
+            let fuzzy_marker = if provider.fuzzy_priced_requests > 0 {
+                " ~estimated (fuzzy match)"
+            } else {
+                ""
+            };
+            let authority =
+                authoritative_percent(provider.authoritative_cost_usd, provider.total_cost_usd)
+                    .map(|percent| format!(" ({percent:.0}% authoritative)"))
+                    .unwrap_or_default();
+            let label = if aggregated {
+                format!("Σ {}", provider.provider)
+            } else {
+                provider.provider.clone()
+            };
+            let multiplier = cost_multiplier(&app.config, &provider.provider);
+            let markup = if aggregated || multiplier == 1.0 {
+                String::new()
+            } else {
+                format!(" incl. {:+.0}%", (multiplier - 1.0) * 100.0)
+            };
             format!(
-                "{APP_NAME} | {} | ${:.3} | {} tok | {} req",
-                provider.provider,
-                provider.total_cost_usd,
+                "{APP_NAME} | {label} | {}{markup}{authority}{fuzzy_marker} | {} tok | {} req",
+                format_money(provider.total_cost_usd, &app.config.currency),
                 provider.total_tokens,
                 provider.requests
             )
@@ -111,330 +145,895 @@ pub(crate) fn draw(frame: &mut Frame<'_>, app: &App) {
     } else {
         format!("{APP_NAME} | No provider data")
     };
-    let info_line = if app.status.is_empty() {
+    let basic_line = match plan_quota {
+        Some(quota) => format!(
+            "{basic_line} | plan {:.0} of {:.0} {}{}",
+            quota.used,
+            quota.limit,
+            quota.unit,
+            quota
+                .resets_at
+                .as_deref()
+                .and_then(|resets_at| resets_at.get(..10))
+                .map(|date| format!(", resets {date}"))
+                .unwrap_or_default()
+        ),
+        None => basic_line,
+    };
+    let basic_line = match app.team_budget.as_ref() {
+        Some(team) => format!(
+            "{basic_line} | team {}{} ({} members)",
+            format_money(team.spent_usd(), &app.config.currency),
+            team.budget_or(app.data.budget_usd)
+                .map(|budget| format!(" of {}", format_money(budget, &app.config.currency)))
+                .unwrap_or_default(),
+            team.members.len()
+        ),
+        None => basic_line,
+    };
+    let info_line = if app.status.is_empty() || kiosk {
         basic_line
     } else {
         format!("{basic_line} | {}", app.status)
     };
-    let alert_lines = if is_codex {
-        build_codex_alert_lines(codex_limits.as_ref(), codex_import_age_secs)
+    let alerts = alert_lines(app, &state);
+    let (selected_icon, accent) = provider_badge(&app.config, selected_provider);
+    let mut info_lines = Vec::with_capacity(2);
+    if !app.show_sidebar && !kiosk {
+        info_lines.push(provider_strip(
+            &app.config,
+            &items,
+            selected_provider,
+            &app.marked_providers,
+        ));
+    }
+    info_lines.push(Line::from(vec![
+        Span::styled(format!("{selected_icon} "), Style::default().fg(accent)),
+        Span::raw(info_line),
+    ]));
+    if kiosk {
+        draw_spend_figures(frame, top_panels[0], &state);
     } else {
-        build_alert_lines(fuel_ratio, token_ratio, spend_ratio, activity_ratio)
+        frame.render_widget(
+            Paragraph::new(info_lines)
+                .block(rounded_block("Info").border_style(Style::default().fg(accent))),
+            top_panels[0],
+        );
+    }
+    draw_alerts_panel(frame, top_panels[1], app, palette, alerts);
+
+    match app.view {
+        _ if state.provider_models.is_some() => draw_provider_models(frame, chunks[1], &state),
+        DashboardView::Forecast => draw_forecast_chart(frame, chunks[1], &state),
+        DashboardView::Keys => draw_key_table(frame, chunks[1], &state),
+        DashboardView::Projects => draw_project_table(frame, chunks[1], &state),
+        DashboardView::Models => draw_model_leaderboard(frame, chunks[1], &state),
+        // The config was checked when it was loaded.
+        DashboardView::Dials => match dashboard_layout(&app.config).unwrap_or_default() {
+            layout if layout.is_empty() => draw_gauges(frame, chunks[1], &state),
+            layout => draw_custom_layout(frame, chunks[1], &state, &layout),
+        },
+    }
+    if history {
+        draw_spend_history(
+            frame,
+            chunks[2],
+            &state,
+            selected_stats
+                .as_ref()
+                .map_or("nothing selected", |stats| stats.provider.as_str()),
+            &app.summary.daily_costs(
+                &app.selected_members(),
+                state.now_epoch_secs.div_euclid(clock::SECS_PER_DAY),
+                SPEND_HISTORY_DAYS,
+            ),
+        );
+    }
+
+    if let Some(progress) = app.import_progress {
+        draw_import_progress(frame, progress);
+    }
+    if app.show_unpriced {
+        draw_unpriced_overlay(frame, &app.summary.unpriced_models());
+    }
+    if app.show_diagnostics {
+        draw_diagnostics_overlay(frame, app);
+    }
+    if app.show_context_growth {
+        draw_context_growth_overlay(frame, app);
+    }
+    if app.show_period_preview {
+        let preview = period_preview(
+            &app.config,
+            &app.data,
+            app.carried_over_usd,
+            clock::now_epoch_secs(),
+        );
+        draw_period_preview_overlay(frame, &preview, &app.config.currency);
+    }
+    if app.show_estimate_accuracy {
+        let months = estimate_accuracy(&app.data, &app.config);
+        draw_estimate_accuracy_overlay(frame, &months, &app.config.currency);
+    }
+    if app.show_status_history {
+        draw_status_history_overlay(frame, app);
+    }
+    if app.show_pricing_breakdown
+        && let Some((provider, model)) = app.model_under_cursor()
+    {
+        let since = state.leaderboard_window.since(state.now_epoch_secs);
+        let breakdown = pricing_breakdown(&app.config, state.data, &provider, &model, since);
+        draw_pricing_breakdown_overlay(
+            frame,
+            &breakdown,
+            &app.config,
+            state.leaderboard_window.label(),
+        );
+    }
+    if let Some(editor) = &app.pricing_editor {
+        draw_pricing_editor_overlay(frame, editor, &app.config.currency);
+    }
+    if let Some(dial) = app.inspected_dial {
+        draw_inspect_overlay(frame, &state, dial);
+    }
+    if let Some(index) = app.limit_replay {
+        draw_limit_replay_overlay(frame, app, palette, index);
+    }
+    if let Some(step) = app.tour {
+        let target = match step {
+            // Without the sidebar, providers are on a strip in Info.
+            TourStep::Providers => Some(sidebar.unwrap_or(top_panels[0])),
+            TourStep::Info => Some(top_panels[0]),
+            TourStep::Alerts => Some(top_panels[1]),
+            TourStep::Dials => Some(chunks[1]),
+            TourStep::Keys => None,
+        };
+        draw_tour_overlay(frame, palette, step, target);
+    }
+    if app.show_help {
+        draw_help_overlay(frame, &Keymap::new(&app.config).unwrap_or_default());
+    }
+}
+
+/// Built-in icon and accent for the providers PromptPetrol knows about;
+/// anything else gets a neutral dot.
+fn default_provider_badge(provider: &str) -> (&'static str, Color) {
+    match provider {
+        "openai" => ("◎", Color::Green),
+        "anthropic" => ("✶", Color::Rgb(217, 119, 87)),
+        "gemini" => ("✦", Color::Blue),
+        "codex" => ("▣", Color::Cyan),
+        "opus" => ("♪", Color::Magenta),
+        _ => ("●", Color::Gray),
+    }
+}
+
+fn provider_badge(config: &AppConfig, provider: &str) -> (String, Color) {
+    let (icon, color) = default_provider_badge(provider);
+    let Some(custom) = config.provider_badges.get(provider) else {
+        return (icon.to_string(), color);
     };
-    frame.render_widget(
-        Paragraph::new(info_line).block(rounded_block("Info")),
-        top_panels[0],
-    );
-    frame.render_widget(
-        Paragraph::new(alert_lines).block(rounded_block("Alerts")),
-        top_panels[1],
-    );
+    let icon = custom
+        .icon
+        .as_deref()
+        .filter(|icon| !icon.trim().is_empty())
+        .unwrap_or(icon)
+        .to_string();
+    let color = custom
+        .color
+        .as_deref()
+        .and_then(|color| Color::from_str(color).ok())
+        .unwrap_or(color);
+    (icon, color)
+}
 
-    let gauge_block_title = if is_codex {
-        "Codex Limit Dials"
-    } else {
-        "Usage Dials"
+fn provider_strip(
+    config: &AppConfig,
+    items: &[ProviderItem],
+    selected: &str,
+    marked: &BTreeSet<String>,
+) -> Line<'static> {
+    let mut spans = Vec::with_capacity(items.len());
+    for item in items {
+        let summary = &item.summary;
+        let group_suffix = if item.is_group() { "▸" } else { "" };
+        let mark = if marked.contains(&summary.provider) {
+            "*"
+        } else {
+            ""
+        };
+        let (icon, color) = provider_badge(config, &summary.provider);
+        let style = if summary.provider == selected {
+            Style::default()
+                .fg(Color::Black)
+                .bg(color)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(color)
+        };
+        spans.push(Span::styled(
+            format!(" {icon} {}{group_suffix}{mark} ", summary.provider),
+            style,
+        ));
+    }
+    Line::from(spans)
+}
+
+/// Left-hand provider list in summary order: name on the first row, token
+/// and cost preview underneath, selected provider highlighted. Groups show
+/// their member count and open with Enter.
+fn draw_provider_sidebar(
+    frame: &mut Frame<'_>,
+    area: Rect,
+    app: &App,
+    items: &[ProviderItem],
+    selected: &str,
+) {
+    let mut lines = Vec::with_capacity(items.len() * 2);
+    for (index, item) in items.iter().enumerate() {
+        let summary = &item.summary;
+        let (icon, color) = provider_badge(&app.config, &summary.provider);
+        let is_selected = summary.provider == selected;
+        let name_style = if is_selected {
+            Style::default()
+                .fg(Color::Black)
+                .bg(color)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(color)
+        };
+        let marker = if is_selected { "▶" } else { " " };
+        let mark = if app.marked_providers.contains(&summary.provider) {
+            "*"
+        } else {
+            " "
+        };
+        let hotkey = if index < 9 {
+            format!("{}", index + 1)
+        } else {
+            " ".to_string()
+        };
+        lines.push(Line::from(vec![
+            Span::raw(marker),
+            Span::styled(hotkey, Style::default().fg(Color::DarkGray)),
+            Span::styled(mark, Style::default().fg(Color::Yellow)),
+            Span::styled(format!(" {icon} {} ", summary.provider), name_style),
+            Span::styled(
+                if item.is_group() {
+                    format!(" ▸{}", item.members.len())
+                } else {
+                    String::new()
+                },
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]));
+        lines.push(Line::from(Span::styled(
+            format!(
+                "    {} tok  {}",
+                format_token_count(summary.total_tokens),
+                format_money(summary.total_cost_usd, &app.config.currency)
+            ),
+            Style::default().fg(Color::Gray),
+        )));
+    }
+    if lines.is_empty() {
+        lines.push(Line::from("No providers yet"));
+    }
+
+    let title = match app.open_group.as_deref() {
+        Some(group) => format!("Providers › {group}"),
+        None => "Providers".to_string(),
     };
-    let gauge_block = rounded_block(gauge_block_title);
-    let gauge_inner = gauge_block.inner(chunks[1]);
-    frame.render_widget(gauge_block, chunks[1]);
+    frame.render_widget(Paragraph::new(lines).block(rounded_block(&title)), area);
+}
 
-    if is_codex {
-        let codex_gauges = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-            .split(gauge_inner);
-        let five_hour_ratio = codex_limits
-            .as_ref()
-            .and_then(|limits| limits.primary.as_ref())
-            .map(|limit| (limit.used_percent / 100.0).clamp(0.0, 1.0))
-            .unwrap_or(0.0);
-        let weekly_ratio = codex_limits
-            .as_ref()
-            .and_then(|limits| limits.secondary.as_ref())
-            .map(|limit| (limit.used_percent / 100.0).clamp(0.0, 1.0))
-            .unwrap_or(0.0);
-        render_analog_gauge(frame, codex_gauges[0], "5h Limit", five_hour_ratio, "used");
-        render_analog_gauge(frame, codex_gauges[1], "Weekly Limit", weekly_ratio, "used");
-    } else {
-        let gauge_rows = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-            .split(gauge_inner);
-        let top_gauges = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-            .split(gauge_rows[0]);
-        let bottom_gauges = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-            .split(gauge_rows[1]);
+pub(crate) fn format_token_count(tokens: u64) -> String {
+    match tokens {
+        0..1_000 => tokens.to_string(),
+        1_000..1_000_000 => format!("{:.1}k", tokens as f64 / 1_000.0),
+        _ => format!("{:.1}M", tokens as f64 / 1_000_000.0),
+    }
+}
+
+/// One line of the Alerts panel. `key` names the condition apart from
+/// the figures in the line, like `Alert::key`, so a condition reported
+/// twice is shown once and keeps its age while its figures change.
+pub(crate) struct AlertLine {
+    pub(crate) key: String,
+    pub(crate) severity: Severity,
+    pub(crate) line: Line<'static>,
+    /// What the line is about, for a query typed into the search.
+    providers: Vec<String>,
+    models: Vec<String>,
+}
 
-        render_analog_gauge(frame, top_gauges[0], "Fuel Tank", fuel_ratio, "left");
-        render_analog_gauge(frame, top_gauges[1], "RPM", token_ratio, "load");
-        render_analog_gauge(frame, bottom_gauges[0], "Throttle", spend_ratio, "burn");
-        render_analog_gauge(frame, bottom_gauges[1], "Traffic", activity_ratio, "flow");
+impl AlertLine {
+    fn new(key: impl Into<String>, severity: Severity, spans: Vec<Span<'static>>) -> Self {
+        AlertLine {
+            key: key.into(),
+            severity,
+            line: Line::from(spans),
+            providers: Vec::new(),
+            models: Vec::new(),
+        }
     }
 
-    if app.show_help {
-        draw_help_overlay(frame);
+    fn about<'a>(
+        mut self,
+        providers: impl IntoIterator<Item = &'a str>,
+        models: impl IntoIterator<Item = &'a str>,
+    ) -> Self {
+        self.providers = providers.into_iter().map(str::to_string).collect();
+        self.models = models.into_iter().map(str::to_string).collect();
+        self
+    }
+
+    pub(crate) fn text(&self) -> String {
+        self.line.to_string().trim().to_string()
     }
 }
 
-fn render_analog_gauge(frame: &mut Frame<'_>, area: Rect, title: &str, ratio: f64, unit: &str) {
-    let ratio = ratio.clamp(0.0, 1.0);
-    let gauge_color = if ratio >= 0.9 {
-        Color::Red
-    } else if ratio >= 0.7 {
-        Color::Yellow
-    } else {
-        Color::Cyan
-    };
-    let dial_block = rounded_block(title);
+impl Queryable for AlertLine {
+    fn texts(&self, field: Field) -> impl Iterator<Item = &str> {
+        match field {
+            Field::Provider => &self.providers[..],
+            Field::Model => &self.models[..],
+            _ => &[],
+        }
+        .iter()
+        .map(String::as_str)
+    }
 
-    frame.render_widget(
-        Canvas::default()
-            .block(dial_block)
-            .x_bounds([-1.2, 1.2])
-            .y_bounds([-1.2, 1.2])
-            .paint(|ctx| {
-                ctx.draw(&Circle {
-                    x: 0.0,
-                    y: 0.0,
-                    radius: 1.0,
-                    color: Color::DarkGray,
-                });
-
-                for step in 0..=10 {
-                    let tick_ratio = step as f64 / 10.0;
-                    let tick_angle = 225.0 - (270.0 * tick_ratio);
-                    let tick_rad = tick_angle.to_radians();
-                    let (outer_x, outer_y) = (tick_rad.cos() * 0.96, tick_rad.sin() * 0.96);
-                    let (inner_x, inner_y) = (tick_rad.cos() * 0.82, tick_rad.sin() * 0.82);
-                    ctx.draw(&CanvasLine {
-                        x1: inner_x,
-                        y1: inner_y,
-                        x2: outer_x,
-                        y2: outer_y,
-                        color: Color::Gray,
-                    });
-                }
+    fn number(&self, _field: Field) -> Option<f64> {
+        None
+    }
+}
 
-                let angle_deg = 225.0 - (270.0 * ratio);
-                let angle = angle_deg.to_radians();
-                let (needle_x, needle_y) = (angle.cos() * 0.76, angle.sin() * 0.76);
-                ctx.draw(&CanvasLine {
-                    x1: 0.0,
-                    y1: 0.0,
-                    x2: needle_x,
-                    y2: needle_y,
-                    color: gauge_color,
-                });
-                ctx.draw(&Circle {
-                    x: 0.0,
-                    y: 0.0,
-                    radius: 0.05,
-                    color: Color::White,
-                });
-            }),
-        area,
+/// The Alerts panel's lines: the most severe first and, among equals,
+/// the most recently raised, one per key, narrowed to the severity chosen
+/// with `A` and the text searched for with `/`.
+pub(crate) fn alert_lines(app: &App, state: &AppState<'_>) -> Vec<AlertLine> {
+    let mut alerts = collect_alerts(app, state);
+    // Lines not seen yet count as just raised. The sort is stable, so
+    // lines raised together keep the order `collect_alerts` gives them.
+    let now = SystemTime::now();
+    alerts.sort_by_key(|alert| {
+        let raised = app.alert_raised.get(&alert.key).map_or(now, |(_, at)| *at);
+        (Reverse(alert.severity), Reverse(raised))
+    });
+    let mut seen = HashSet::new();
+    alerts.retain(|alert| seen.insert(alert.key.clone()));
+    // A search that reads as a query (`provider=codex`) keeps the lines
+    // about matching providers and models; anything else is plain text.
+    let query = Query::parse(&app.alert_search).ok();
+    let search = app.alert_search.to_lowercase();
+    alerts.retain(|alert| {
+        alert.severity >= app.alert_filter
+            && match &query {
+                Some(query) => query.matches(alert),
+                None => search.is_empty() || alert.text().to_lowercase().contains(&search),
+            }
+    });
+    alerts
+}
+
+/// Every alert line that applies, unsorted and possibly repeating a key.
+pub(crate) fn collect_alerts(app: &App, state: &AppState<'_>) -> Vec<AlertLine> {
+    let palette = &state.palette;
+    let dials = &state.dials;
+    let currency = &app.config.currency;
+    let now_epoch_secs = clock::now_epoch_secs();
+    // The spend-rate alarm, runaway sessions, importer errors and plan
+    // quotas are listed whichever provider is selected.
+    let mut alerts = Vec::new();
+    if let Some(rate) = app.config.spend_rate_alert.as_ref() {
+        let per_hour = app
+            .summary
+            .spend_rate_per_hour(now_epoch_secs, rate.window_minutes as i64 * 60);
+        if per_hour > rate.usd_per_hour {
+            alerts.push(burn_rate_line(palette, per_hour, currency));
+        }
+    }
+    let preview = period_preview(&app.config, &app.data, app.carried_over_usd, now_epoch_secs);
+    if preview.is_due() {
+        alerts.push(next_period_line(palette, &preview, currency));
+    }
+    alerts.extend(
+        app.runaway_sessions
+            .iter()
+            .map(|session| runaway_session_line(palette, session, currency).about(["codex"], [])),
+    );
+    alerts.extend(
+        app.heavy_load
+            .as_ref()
+            .map(|load| heavy_load_line(palette, load).about(["codex"], [])),
     );
+    alerts.extend(
+        app.importers
+            .iter()
+            .filter(|status| status.enabled)
+            .filter_map(|status| importer_error_line(palette, status)),
+    );
+    alerts.extend(app.plan_quotas.iter().filter_map(|quota| {
+        plan_quota_line(palette, quota).map(|line| line.about([quota.provider.as_str()], []))
+    }));
+    if dials.is_codex {
+        let import_age_secs = app
+            .codex_diagnostics
+            .last_import_at
+            .map(|stamp| stamp.age().as_secs());
+        alerts.extend(
+            build_codex_alert_lines(palette, dials.codex_limits.as_ref(), import_age_secs)
+                .into_iter()
+                .map(|line| line.about(["codex"], [])),
+        );
+    } else {
+        let selection = app.selected_members();
+        alerts.extend(
+            build_alert_lines(
+                palette,
+                dials.fuel_ratio,
+                dials.token_ratio,
+                dials.spend_ratio,
+                dials.activity_ratio,
+            )
+            .into_iter()
+            .map(|line| line.about(selection.iter().copied(), [])),
+        );
+    }
+    let unpriced = app.summary.unpriced_models();
+    if !unpriced.is_empty() {
+        alerts.push(unpriced_alert_line(palette, &unpriced).about(
+            unpriced.iter().map(|model| model.provider.as_str()),
+            unpriced.iter().map(|model| model.model.as_str()),
+        ));
+    }
+    alerts
+}
 
-    let value_text = format!("{:>5.1}% {unit}", ratio * 100.0);
-    let value_area = Rect {
-        x: area.x.saturating_add(1),
-        y: area.y.saturating_add(area.height.saturating_sub(2)),
-        width: area.width.saturating_sub(2),
-        height: 1,
+/// The Alerts panel: from the top, with a count of what doesn't fit, or
+/// scrolled to its cursor while `a` has it.
+fn draw_alerts_panel(
+    frame: &mut Frame<'_>,
+    area: Rect,
+    app: &App,
+    palette: &Palette,
+    alerts: Vec<AlertLine>,
+) {
+    let visible = usize::from(area.height.saturating_sub(2)).max(1);
+    let total = alerts.len();
+    let cursor = app.alert_row.min(total.saturating_sub(1));
+    let scroll = if app.alerts_focused {
+        (cursor + 1).saturating_sub(visible)
+    } else {
+        0
     };
-    frame.render_widget(
-        Paragraph::new(value_text).style(
-            Style::default()
-                .fg(gauge_color)
-                .bg(Color::Black)
-                .add_modifier(Modifier::BOLD),
-        ),
-        value_area,
-    );
+    let mut title = "Alerts".to_string();
+    match app.alert_filter {
+        Severity::Ok => {}
+        Severity::Warn => title.push_str(" ! and up"),
+        Severity::Alert => title.push_str(" ‼ only"),
+    }
+    if app.alert_search_open || !app.alert_search.is_empty() {
+        title.push_str(&format!(" /{}", app.alert_search));
+        if app.alert_search_open {
+            title.push('_');
+        }
+    }
+    if total > visible {
+        title.push_str(&format!(
+            " {}-{} of {total}",
+            scroll + 1,
+            (scroll + visible).min(total)
+        ));
+    }
+    if app.alerts_focused {
+        title.push_str(" (j/k, A filter, / search, Esc)");
+    } else if total > visible {
+        title.push_str(" (a to scroll)");
+    }
+
+    let mut lines = alerts
+        .into_iter()
+        .enumerate()
+        .skip(scroll)
+        .map(|(row, alert)| {
+            if app.alerts_focused && row == cursor {
+                alert
+                    .line
+                    .patch_style(Style::default().add_modifier(Modifier::REVERSED))
+            } else {
+                alert.line
+            }
+        })
+        .collect::<Vec<_>>();
+    if lines.is_empty() {
+        lines.push(Line::from(Span::styled(
+            " No alerts match",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    let mut block = rounded_block(&title);
+    if app.alerts_focused {
+        block = block.border_style(Style::default().fg(palette.info));
+    }
+    frame.render_widget(Paragraph::new(lines).block(block), area);
 }
 
 fn build_alert_lines(
+    palette: &Palette,
     fuel_ratio: f64,
     token_ratio: f64,
     spend_ratio: f64,
     activity_ratio: f64,
-) -> Vec<Line<'static>> {
+) -> Vec<AlertLine> {
     vec![
-        alert_line("LOW FUEL", fuel_ratio <= 0.20, fuel_ratio, true),
-        alert_line("HIGH RPM", token_ratio >= 0.85, token_ratio, false),
-        alert_line("OVERBURN", spend_ratio >= 0.85, spend_ratio, false),
-        alert_line("TRAFFIC JAM", activity_ratio >= 0.90, activity_ratio, false),
+        alert_line(palette, "LOW FUEL", fuel_ratio <= 0.20, fuel_ratio, true),
+        alert_line(palette, "HIGH RPM", token_ratio >= 0.85, token_ratio, false),
+        alert_line(palette, "OVERBURN", spend_ratio >= 0.85, spend_ratio, false),
+        alert_line(
+            palette,
+            "TRAFFIC JAM",
+            activity_ratio >= 0.90,
+            activity_ratio,
+            false,
+        ),
     ]
 }
 
-fn alert_line(label: &str, alert: bool, ratio: f64, low_is_bad: bool) -> Line<'static> {
+/// A severity's symbol and state word on its colour, so the state reads
+/// without telling the colours apart.
+fn severity_badge(palette: &Palette, severity: Severity, state: &str) -> Span<'static> {
+    Span::styled(
+        format!(" {} {state:<7} ", severity.symbol()),
+        Style::default()
+            .fg(Color::Black)
+            .bg(palette.severity(severity))
+            .add_modifier(Modifier::BOLD),
+    )
+}
+
+/// Label for an alert that is firing, on the alert colour.
+fn alert_label(palette: &Palette, label: &str) -> Span<'static> {
+    Span::styled(
+        format!(" {label:<11} "),
+        Style::default()
+            .fg(Color::Black)
+            .bg(palette.alert)
+            .add_modifier(Modifier::BOLD),
+    )
+}
+
+fn alert_line(
+    palette: &Palette,
+    label: &str,
+    alert: bool,
+    ratio: f64,
+    low_is_bad: bool,
+) -> AlertLine {
     let ratio_pct = ratio * 100.0;
+    let key = format!("gauge.{}", label.to_lowercase().replace(' ', "_"));
     if alert {
-        return Line::from(vec![
+        return AlertLine::new(
+            key,
+            Severity::Alert,
+            vec![
+                alert_label(palette, label),
+                severity_badge(palette, Severity::Alert, "ALERT"),
+                Span::styled(
+                    format!(" {:>5.1}%", ratio_pct),
+                    Style::default().fg(palette.alert),
+                ),
+            ],
+        );
+    }
+
+    let healthy = if low_is_bad {
+        ratio >= 0.35
+    } else {
+        ratio <= 0.70
+    };
+    let (severity, state) = if healthy {
+        (Severity::Ok, "NOMINAL")
+    } else {
+        (Severity::Warn, "WATCH")
+    };
+
+    AlertLine::new(
+        key,
+        severity,
+        vec![
+            Span::styled(format!(" {label:<11} "), Style::default().fg(Color::Gray)),
+            severity_badge(palette, severity, state),
+            Span::styled(
+                format!(" {:>5.1}%", ratio_pct),
+                Style::default().fg(palette.info),
+            ),
+        ],
+    )
+}
+
+fn unpriced_alert_line(palette: &Palette, unpriced: &[UnpricedModel]) -> AlertLine {
+    let entries = unpriced.iter().map(|model| model.entries).sum::<usize>();
+    AlertLine::new(
+        "unpriced",
+        Severity::Warn,
+        vec![
+            Span::styled(" UNPRICED    ", Style::default().fg(Color::Gray)),
+            severity_badge(palette, Severity::Warn, "WARN"),
+            Span::styled(
+                format!(
+                    " {entries} entries unpriced ({} models, u to list)",
+                    unpriced.len()
+                ),
+                Style::default().fg(palette.warn),
+            ),
+        ],
+    )
+}
+
+fn importer_error_line(palette: &Palette, status: &ImporterStatus) -> Option<AlertLine> {
+    let error = status.error.as_ref()?;
+    Some(AlertLine::new(
+        format!("importer.{}", status.importer.name()),
+        Severity::Warn,
+        vec![
+            Span::styled(" IMPORTER    ", Style::default().fg(Color::Gray)),
+            severity_badge(palette, Severity::Warn, "WARN"),
+            Span::styled(
+                format!(" {}: {error} (d for details)", status.importer.name()),
+                Style::default().fg(palette.warn),
+            ),
+        ],
+    ))
+}
+
+/// Plan-billed editors near their allowance, at the Codex limits'
+/// thresholds.
+fn plan_quota_line(palette: &Palette, quota: &PlanQuota) -> Option<AlertLine> {
+    let ratio = quota.used_ratio();
+    let (severity, label, state) = if ratio >= 0.9 {
+        (Severity::Alert, alert_label(palette, "PLAN QUOTA"), "ALERT")
+    } else if ratio >= 0.75 {
+        (
+            Severity::Warn,
+            Span::styled(" PLAN QUOTA  ", Style::default().fg(Color::Gray)),
+            "WATCH",
+        )
+    } else {
+        return None;
+    };
+    Some(AlertLine::new(
+        format!("plan_quota.{}", quota.provider),
+        severity,
+        vec![
+            label,
+            severity_badge(palette, severity, state),
+            Span::styled(
+                format!(
+                    " {} {:.0} of {:.0} {}",
+                    quota.provider, quota.used, quota.limit, quota.unit
+                ),
+                Style::default().fg(palette.severity(severity)),
+            ),
+        ],
+    ))
+}
+
+fn burn_rate_line(palette: &Palette, per_hour: f64, currency: &str) -> AlertLine {
+    AlertLine::new(
+        "spend.rate",
+        Severity::Alert,
+        vec![
+            alert_label(palette, "BURN RATE"),
+            severity_badge(palette, Severity::Alert, "ALERT"),
+            Span::styled(
+                format!(" {}/h", format_money(per_hour, currency)),
+                Style::default().fg(palette.alert),
+            ),
+        ],
+    )
+}
+
+fn runaway_session_line(palette: &Palette, session: &SessionCost, currency: &str) -> AlertLine {
+    let name = session.path.file_name().map_or_else(
+        || session.path.display().to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    AlertLine::new(
+        format!("session.runaway.{}", session.path.display()),
+        Severity::Alert,
+        vec![
+            alert_label(palette, "RUNAWAY"),
+            severity_badge(palette, Severity::Alert, "ALERT"),
             Span::styled(
-                format!(" {label:<11} "),
+                format!(" {} {name}", format_money(session.cost_usd, currency)),
+                Style::default().fg(palette.alert),
+            ),
+        ],
+    )
+}
+
+fn heavy_load_line(palette: &Palette, load: &HeavyLoad) -> AlertLine {
+    let name = load.path.file_name().map_or_else(
+        || load.path.display().to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    AlertLine::new(
+        format!("session.heavy_load.{}", load.path.display()),
+        Severity::Warn,
+        vec![
+            Span::styled(
+                format!(" {} HEAVY LOAD ", Severity::Warn.symbol()),
                 Style::default()
                     .fg(Color::Black)
-                    .bg(Color::Red)
+                    .bg(palette.warn)
                     .add_modifier(Modifier::BOLD),
             ),
             Span::styled(
-                "  ALERT  ",
+                format!(
+                    " {} input last turn, compact {name}",
+                    format_token_count(load.turn_input_tokens)
+                ),
+                Style::default().fg(palette.warn),
+            ),
+        ],
+    )
+}
+
+fn format_budget(budget: Option<f64>, currency: &str) -> String {
+    budget.map_or_else(
+        || "none".to_string(),
+        |budget| format_money(budget, currency),
+    )
+}
+
+/// A notice rather than a warning, so it ranks with the nominal lines.
+fn next_period_line(palette: &Palette, preview: &PeriodPreview, currency: &str) -> AlertLine {
+    let (year, month) = preview.next;
+    AlertLine::new(
+        "budget.next_period",
+        Severity::Ok,
+        vec![
+            Span::styled(
+                " NEXT PERIOD ",
                 Style::default()
                     .fg(Color::Black)
-                    .bg(Color::Yellow)
+                    .bg(palette.info)
                     .add_modifier(Modifier::BOLD),
             ),
             Span::styled(
-                format!(" {:>5.1}%", ratio_pct),
-                Style::default().fg(Color::Red),
+                format!(
+                    " budget {} → {} on {year:04}-{month:02}-01, n to review",
+                    format_budget(preview.current_budget_usd, currency),
+                    format_budget(preview.next_budget_usd, currency)
+                ),
+                Style::default().fg(palette.info),
             ),
-        ]);
-    }
-
-    let healthy = if low_is_bad {
-        ratio >= 0.35
-    } else {
-        ratio <= 0.70
-    };
-    let state = if healthy { "NOMINAL" } else { "WATCH  " };
-    let state_bg = if healthy { Color::Green } else { Color::Yellow };
-
-    Line::from(vec![
-        Span::styled(format!(" {label:<11} "), Style::default().fg(Color::Gray)),
-        Span::styled(
-            format!(" {state} "),
-            Style::default()
-                .fg(Color::Black)
-                .bg(state_bg)
-                .add_modifier(Modifier::BOLD),
-        ),
-        Span::styled(
-            format!(" {:>5.1}%", ratio_pct),
-            Style::default().fg(Color::Cyan),
-        ),
-    ])
+        ],
+    )
 }
 
 fn build_codex_alert_lines(
+    palette: &Palette,
     limits: Option<&CodexRateLimits>,
     import_age_secs: Option<u64>,
-) -> Vec<Line<'static>> {
+) -> Vec<AlertLine> {
     let Some(limits) = limits else {
         return vec![
-            Line::from(Span::styled(
-                " Codex rate limits unavailable ",
-                Style::default().fg(Color::Yellow),
-            )),
-            codex_freshness_line(import_age_secs),
+            AlertLine::new(
+                "codex.limits",
+                Severity::Warn,
+                vec![Span::styled(
+                    format!(
+                        " {} Codex rate limits unavailable ",
+                        Severity::Warn.symbol()
+                    ),
+                    Style::default().fg(palette.warn),
+                )],
+            ),
+            codex_freshness_line(palette, import_age_secs),
         ];
     };
 
     vec![
-        codex_alert_line("5H LIMIT", limits.primary.as_ref()),
-        codex_alert_line("WEEKLY", limits.secondary.as_ref()),
-        codex_freshness_line(import_age_secs),
+        codex_alert_line(palette, "5H LIMIT", limits.primary.as_ref()),
+        codex_alert_line(palette, "WEEKLY", limits.secondary.as_ref()),
+        codex_freshness_line(palette, import_age_secs),
     ]
 }
 
-fn codex_freshness_line(import_age_secs: Option<u64>) -> Line<'static> {
+fn codex_freshness_line(palette: &Palette, import_age_secs: Option<u64>) -> AlertLine {
     let Some(age_secs) = import_age_secs else {
-        return Line::from(vec![
-            Span::styled(" FRESHNESS ", Style::default().fg(Color::Gray)),
-            Span::styled(
-                " UNKNOWN ",
-                Style::default()
-                    .fg(Color::Black)
-                    .bg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
-            ),
-        ]);
+        return AlertLine::new(
+            "codex.freshness",
+            Severity::Warn,
+            vec![
+                Span::styled(" FRESHNESS ", Style::default().fg(Color::Gray)),
+                severity_badge(palette, Severity::Warn, "UNKNOWN"),
+            ],
+        );
     };
 
-    let (state, color) = if age_secs <= 30 {
-        ("LIVE", Color::Green)
+    let (state, severity) = if age_secs <= 30 {
+        ("LIVE", Severity::Ok)
     } else if age_secs <= 120 {
-        ("STALE", Color::Yellow)
+        ("STALE", Severity::Warn)
     } else {
-        ("OLD", Color::Red)
+        ("OLD", Severity::Alert)
     };
 
-    Line::from(vec![
-        Span::styled(" FRESHNESS ", Style::default().fg(Color::Gray)),
-        Span::styled(
-            format!(" {:<7} ", state),
-            Style::default()
-                .fg(Color::Black)
-                .bg(color)
-                .add_modifier(Modifier::BOLD),
-        ),
-        Span::styled(
-            format!(" updated {age_secs}s ago"),
-            Style::default().fg(Color::Cyan),
-        ),
-    ])
+    AlertLine::new(
+        "codex.freshness",
+        severity,
+        vec![
+            Span::styled(" FRESHNESS ", Style::default().fg(Color::Gray)),
+            severity_badge(palette, severity, state),
+            Span::styled(
+                format!(" updated {age_secs}s ago"),
+                Style::default().fg(palette.info),
+            ),
+        ],
+    )
 }
 
-fn codex_alert_line(label: &str, limit: Option<&CodexRateLimit>) -> Line<'static> {
+fn codex_alert_line(palette: &Palette, label: &str, limit: Option<&CodexRateLimit>) -> AlertLine {
+    let key = format!("codex.{}", label.to_lowercase().replace(' ', "_"));
     let Some(limit) = limit else {
-        return Line::from(vec![
-            Span::styled(format!(" {label:<8} "), Style::default().fg(Color::Gray)),
-            Span::styled(
-                " UNAVAILABLE ",
-                Style::default()
-                    .fg(Color::Black)
-                    .bg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
-            ),
-        ]);
+        return AlertLine::new(
+            key,
+            Severity::Warn,
+            vec![
+                Span::styled(format!(" {label:<8} "), Style::default().fg(Color::Gray)),
+                severity_badge(palette, Severity::Warn, "UNAVAILABLE"),
+            ],
+        );
     };
 
     let ratio = (limit.used_percent / 100.0).clamp(0.0, 1.0);
-    let state = if ratio >= 0.9 {
-        ("ALERT", Color::Red)
+    let (state, severity) = if ratio >= 0.9 {
+        ("ALERT", Severity::Alert)
     } else if ratio >= 0.75 {
-        ("WATCH", Color::Yellow)
+        ("WATCH", Severity::Warn)
     } else {
-        ("NOMINAL", Color::Green)
+        ("NOMINAL", Severity::Ok)
     };
 
-    Line::from(vec![
-        Span::styled(format!(" {label:<8} "), Style::default().fg(Color::Gray)),
-        Span::styled(
-            format!(" {:<7} ", state.0),
-            Style::default()
-                .fg(Color::Black)
-                .bg(state.1)
-                .add_modifier(Modifier::BOLD),
-        ),
-        Span::styled(
-            format!(" {:>5.1}% ", limit.used_percent),
-            Style::default().fg(Color::Cyan),
-        ),
-        Span::styled(
-            format!(
-                "{}m reset {}",
-                limit.window_minutes,
-                format_reset_timing(limit.resets_at)
+    AlertLine::new(
+        key,
+        severity,
+        vec![
+            Span::styled(format!(" {label:<8} "), Style::default().fg(Color::Gray)),
+            severity_badge(palette, severity, state),
+            Span::styled(
+                format!(" {:>5.1}% ", limit.used_percent),
+                Style::default().fg(palette.info),
             ),
-            Style::default().fg(Color::Yellow),
-        ),
-    ])
+            Span::styled(
+                format!(
+                    "{}m reset {}",
+                    limit.window_minutes,
+                    format_reset_timing(limit.resets_at)
+                ),
+                Style::default().fg(Color::Yellow),
+            ),
+        ],
+    )
 }
 
 fn format_reset_timing(resets_at: Option<u64>) -> String {
-    let Some(target_epoch) = resets_at else {
-        return "unknown".to_string();
-    };
     let now_epoch = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .map(|d| d.as_secs())
         .unwrap_or(0);
+    format_reset_from(resets_at, now_epoch)
+}
+
+/// How long after `now_epoch` the window resets.
+pub(crate) fn format_reset_from(resets_at: Option<u64>, now_epoch: u64) -> String {
+    let Some(target_epoch) = resets_at else {
+        return "unknown".to_string();
+    };
 
     if target_epoch <= now_epoch {
         return "now".to_string();
@@ -446,25 +1045,1227 @@ fn format_reset_timing(resets_at: Option<u64>) -> String {
     format!("in {hours}h {minutes}m")
 }
 
-fn draw_help_overlay(frame: &mut Frame<'_>) {
-    let area = centered_rect(60, 40, frame.area());
-    let help_lines = vec![
-        Line::from("Controls"),
-        Line::from("q : quit"),
-        Line::from("r : reload usage/config"),
-        Line::from("Left/h/k : previous provider"),
-        Line::from("Right/l/j : next provider"),
-        Line::from("? : toggle help"),
+/// A day of Codex rate-limit readings on a time axis, with the one being
+/// replayed marked on it and spelled out above.
+fn draw_limit_replay_overlay(frame: &mut Frame<'_>, app: &App, palette: &Palette, index: usize) {
+    let history = &app.codex_limit_history;
+    let (Some((at, limits)), Some((start, _)), Some((end, _))) =
+        (history.get(index), history.first(), history.last())
+    else {
+        return;
+    };
+    let area = centered_rect(80, 70, frame.area());
+    frame.render_widget(Clear, area);
+    let title = format!(
+        "Codex Limit Replay {}/{} (←/→ step, PgUp/PgDn hour, Home/End, Esc close)",
+        index + 1,
+        history.len()
+    );
+    let block = rounded_block(&title);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(4), Constraint::Min(0)])
+        .split(inner);
+
+    let clock_time = |epoch: i64| clock::format_timestamp(epoch)[11..16].to_string();
+    let reading = |label: &str, limit: Option<&CodexRateLimit>, color: Color| {
+        let Some(limit) = limit else {
+            return Line::from(Span::styled(
+                format!("{label:<8} unavailable"),
+                Style::default().fg(Color::DarkGray),
+            ));
+        };
+        let reset = limit.resets_at.map_or_else(
+            || "reset unknown".to_string(),
+            |resets_at| {
+                let resets = clock::format_timestamp(resets_at as i64);
+                let day = if resets[..10] == clock::format_timestamp(*at)[..10] {
+                    String::new()
+                } else {
+                    format!("{} ", &resets[5..10])
+                };
+                format!(
+                    "resets {day}{} UTC, {} from then",
+                    clock_time(resets_at as i64),
+                    format_reset_from(Some(resets_at), (*at).max(0) as u64)
+                )
+            },
+        );
+        Line::from(vec![
+            Span::styled(format!("{label:<8}"), Style::default().fg(color)),
+            Span::raw(format!("{:>5.1}%  ", limit.used_percent)),
+            Span::styled(reset, Style::default().fg(Color::Gray)),
+        ])
+    };
+    let taken = clock::format_timestamp(*at);
+    let readout = vec![
+        Line::from(format!("{} {} UTC", &taken[..10], &taken[11..19])),
+        Line::from(""),
+        reading("5h", limits.primary.as_ref(), palette.info),
+        reading("weekly", limits.secondary.as_ref(), palette.warn),
+    ];
+    frame.render_widget(Paragraph::new(readout), rows[0]);
+    if app.no_canvas {
+        return;
+    }
+
+    let hours = |epoch: i64| (epoch - start) as f64 / 3600.0;
+    // Steps rather than slopes: a reading holds until the next one.
+    let series = |pick: fn(&CodexRateLimits) -> Option<&CodexRateLimit>| {
+        let mut points: Vec<(f64, f64)> = Vec::new();
+        for (at, limits) in history {
+            let Some(limit) = pick(limits) else {
+                continue;
+            };
+            if let Some(&(_, previous)) = points.last() {
+                points.push((hours(*at), previous));
+            }
+            points.push((hours(*at), limit.used_percent));
+        }
+        points
+    };
+    let primary = series(|limits| limits.primary.as_ref());
+    let secondary = series(|limits| limits.secondary.as_ref());
+    let cursor = [(hours(*at), 0.0), (hours(*at), 100.0)];
+    let dataset = |name: &'static str, color: Color, data| {
+        Dataset::default()
+            .name(name)
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(color))
+            .data(data)
+    };
+    let chart = Chart::new(vec![
+        dataset("5h", palette.info, &primary),
+        dataset("weekly", palette.warn, &secondary),
+        dataset("replay", Color::White, &cursor),
+    ])
+    .x_axis(
+        Axis::default()
+            .style(Style::default().fg(Color::Gray))
+            .bounds([0.0, hours(*end).max(1.0 / 60.0)])
+            .labels([clock_time(*start), clock_time(*end)]),
+    )
+    .y_axis(
+        Axis::default()
+            .style(Style::default().fg(Color::Gray))
+            .bounds([0.0, 100.0])
+            .labels(["0%", "50%", "100%"]),
+    );
+    frame.render_widget(chart, rows[1]);
+}
+
+/// What goes into a dial's reading, so "Traffic 34% flow" means
+/// something.
+fn draw_inspect_overlay(frame: &mut Frame<'_>, state: &AppState<'_>, dial: Dial) {
+    let area = centered_rect(70, 40, frame.area());
+    let (title, ratio, unit) = dial.reading(state);
+    let label = |text: &str| Span::styled(format!("{text:<9}"), Style::default().fg(Color::Gray));
+    let mut lines = vec![
+        Line::from(Span::styled(
+            format!("{title}: {:.1}% {unit}", ratio * 100.0),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+    ];
+    lines.extend(
+        dial.explanation(state)
+            .into_iter()
+            .map(|(name, text)| Line::from(vec![label(name), Span::raw(text)])),
+    );
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(rounded_block(&format!(
+                "Inspect {title} (i next dial, Esc close)"
+            ))),
+        area,
+    );
+}
+
+fn tour_card(step: TourStep) -> (&'static str, &'static str) {
+    match step {
+        TourStep::Providers => (
+            "Providers",
+            "Every provider in your usage data, with its tokens and cost. \
+             ←/→ (or h/l) change the selection and 1-9 jump to one; space marks \
+             several to add them up and Enter opens a group. s hides the list.",
+        ),
+        TourStep::Info => (
+            "Info",
+            "The selection's spend, and how much of it the provider reported \
+             itself rather than PromptPetrol estimating it from pricing. Status \
+             messages end the line; m lists earlier ones.",
+        ),
+        TourStep::Alerts => (
+            "Alerts",
+            "Warnings at a glance: ✓ nominal, ! watch, ‼ alert. Low fuel, heavy \
+             load, runaway sessions and the burn rate show here, as do unpriced \
+             models (u lists them) and budget changes (n previews them).",
+        ),
+        TourStep::Dials => (
+            "Dials",
+            "Fuel Tank is the budget left. RPM, Throttle and Traffic compare the \
+             selection's tokens, spend and requests with the other providers'. \
+             i explains a dial, z zooms one, and Tab moves on to the forecast \
+             and the key, project and model tables.",
+        ),
+        TourStep::Keys => (
+            "That's it",
+            "? lists every key, o takes this tour again and q quits.",
+        ),
+    }
+}
+
+/// The tour's current stop: the panel it is about outlined, and a card
+/// explaining it placed clear of the panel.
+fn draw_tour_overlay(
+    frame: &mut Frame<'_>,
+    palette: &Palette,
+    step: TourStep,
+    target: Option<Rect>,
+) {
+    let screen = frame.area();
+    if let Some(target) = target {
+        frame.render_widget(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_set(border::THICK)
+                .border_style(
+                    Style::default()
+                        .fg(palette.warn)
+                        .add_modifier(Modifier::BOLD),
+                ),
+            target,
+        );
+    }
+    let width = screen.width.min(66);
+    let height = screen.height.min(9);
+    let y = match target {
+        Some(target) if target.y + target.height / 2 < screen.height / 2 => {
+            screen.bottom() - height
+        }
+        Some(_) => screen.y,
+        None => screen.y + (screen.height - height) / 2,
+    };
+    let card = Rect {
+        x: screen.x + (screen.width - width) / 2,
+        y,
+        width,
+        height,
+    };
+    let (title, text) = tour_card(step);
+    let lines = vec![
+        Line::from(text),
+        Line::from(""),
+        Line::from(Span::styled(
+            "→/Enter next   ← back   Esc ends the tour",
+            Style::default().fg(Color::DarkGray),
+        )),
     ];
 
+    frame.render_widget(Clear, card);
+    frame.render_widget(
+        Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            rounded_block(&format!(
+                "Tour {}/{}: {title}",
+                step.number(),
+                TourStep::ALL.len()
+            ))
+            .border_style(Style::default().fg(palette.warn)),
+        ),
+        card,
+    );
+}
+
+/// Lists the active keymap, remaps included; the diagnostics keys apply
+/// while that overlay is open.
+fn draw_help_overlay(frame: &mut Frame<'_>, keymap: &Keymap) {
+    let area = centered_rect(90, 94, frame.area());
+    let entry = |(keys, description): (String, &str)| Line::from(format!("{keys} : {description}"));
+    let mut help_lines = vec![Line::from("Controls")];
+    for (keys, description) in keymap.help_entries(KeyContext::Dashboard) {
+        let after_next_provider = description == Action::NextProvider.description();
+        help_lines.push(entry((keys, description)));
+        if after_next_provider {
+            help_lines.push(Line::from("1-9 : jump to provider by position"));
+        }
+    }
+    help_lines.push(Line::from(""));
+    help_lines.push(Line::from("Import diagnostics"));
+    help_lines.extend(
+        keymap
+            .help_entries(KeyContext::Diagnostics)
+            .into_iter()
+            .map(entry),
+    );
+    help_lines.push(Line::from(""));
+    help_lines.push(Line::from("Status history, alerts and table views"));
+    help_lines.extend(keymap.help_entries(KeyContext::Rows).into_iter().map(entry));
+    help_lines.push(Line::from("digits : count for the next row key"));
+
+    frame.render_widget(Clear, area);
+    let block = rounded_block("Keyboard Help");
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    // Two columns when one would run off the bottom, split where half the
+    // wrapped rows are on the left.
+    if help_lines.len() > inner.height as usize {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .spacing(2)
+            .split(inner);
+        let width = usize::from(columns[0].width.max(1));
+        let rows = |line: &Line<'_>| line.width().div_ceil(width).max(1);
+        let half = help_lines.iter().map(rows).sum::<usize>().div_ceil(2);
+        let mut left_rows = 0;
+        let split = help_lines
+            .iter()
+            .position(|line| {
+                left_rows += rows(line);
+                left_rows > half
+            })
+            .unwrap_or(help_lines.len());
+        let right = help_lines.split_off(split);
+        frame.render_widget(
+            Paragraph::new(help_lines).wrap(Wrap { trim: false }),
+            columns[0],
+        );
+        frame.render_widget(Paragraph::new(right).wrap(Wrap { trim: false }), columns[1]);
+    } else {
+        frame.render_widget(Paragraph::new(help_lines), inner);
+    }
+}
+
+fn draw_status_history_overlay(frame: &mut Frame<'_>, app: &App) {
+    let area = centered_rect(80, 60, frame.area());
+    let now = SystemTime::now();
+    let history_lines = if app.status_history.is_empty() {
+        vec![Line::from("No status messages yet")]
+    } else {
+        app.status_history
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(row, message)| {
+                let age_secs = now
+                    .duration_since(message.at)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0);
+                let line = Line::from(vec![
+                    Span::styled(
+                        format!("{:>6} ", format_age(age_secs)),
+                        Style::default().fg(Color::Gray),
+                    ),
+                    Span::raw(message.text.clone()),
+                ]);
+                if row == app.history_row {
+                    line.patch_style(Style::default().add_modifier(Modifier::REVERSED))
+                } else {
+                    line
+                }
+            })
+            .collect()
+    };
+    // Scrolled just far enough to keep the cursor row in view.
+    let scroll = (app.history_row + 1).saturating_sub(usize::from(area.height.saturating_sub(2)));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(history_lines)
+            .block(rounded_block("Status History (newest first, y to yank)"))
+            .scroll((u16::try_from(scroll).unwrap_or(u16::MAX), 0)),
+        area,
+    );
+}
+
+/// Across the top of the screen for as long as spending is frozen, with
+/// whatever was spent regardless.
+fn draw_freeze_banner(frame: &mut Frame<'_>, area: Rect, app: &App, since: i64) {
+    let spent = spend_while_frozen(&app.data, &app.config);
+    let since = clock::format_timestamp(since);
+    let mut text = format!(
+        " ❄ SPENDING FROZEN since {} {} UTC · governor {}/day",
+        &since[..10],
+        &since[11..16],
+        format_money(0.0, &app.config.currency)
+    );
+    if !spent.is_empty() {
+        text.push_str(&format!(
+            " · {} {} since ({})",
+            spent.len(),
+            entries_label(spent.len()),
+            format_money(
+                spent.iter().map(|entry| entry.cost_usd).sum(),
+                &app.config.currency
+            )
+        ));
+    }
+    text.push_str(" · F lifts it ");
+    let palette = app.config.theme.palette();
+    frame.render_widget(
+        Paragraph::new(text).style(
+            Style::default()
+                .fg(Color::White)
+                .bg(if spent.is_empty() {
+                    palette.info
+                } else {
+                    palette.alert
+                })
+                .add_modifier(Modifier::BOLD),
+        ),
+        area,
+    );
+}
+
+fn draw_unpriced_overlay(frame: &mut Frame<'_>, unpriced: &[UnpricedModel]) {
+    let area = centered_rect(70, 50, frame.area());
+    let mut lines = vec![Line::from(
+        "Entries below were costed at 0 because no pricing key matched.",
+    )];
+    if unpriced.is_empty() {
+        lines.push(Line::from("All entries are priced."));
+    } else {
+        lines.push(Line::from(Span::styled(
+            format!("{:<40} {:>8} {:>12}", "provider/model", "entries", "tokens"),
+            Style::default().fg(Color::Gray),
+        )));
+        lines.extend(unpriced.iter().map(|model| {
+            Line::from(format!(
+                "{:<40} {:>8} {:>12}",
+                format!("{}/{}", model.provider, model.model),
+                model.entries,
+                model.total_tokens
+            ))
+        }));
+    }
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines).block(rounded_block("Unpriced Models")),
+        area,
+    );
+}
+
+fn draw_estimate_accuracy_overlay(
+    frame: &mut Frame<'_>,
+    months: &[MonthlyAccuracy],
+    currency: &str,
+) {
+    let area = centered_rect(80, 60, frame.area());
+    let mut lines = vec![Line::from(
+        "Billed usage priced at today's rates, as if it had been estimated.",
+    )];
+    if months.is_empty() {
+        lines.push(Line::from(
+            "No billed usage yet: add a billing poller or entries with a reported cost.",
+        ));
+    } else {
+        lines.push(Line::from(Span::styled(
+            format!(
+                "{:<16} {:<7} {:>7} {:>11} {:>11} {:>8}  trend",
+                "vendor", "month", "entries", "billed", "estimated", "error"
+            ),
+            Style::default().fg(Color::Gray),
+        )));
+    }
+    let mut previous: Option<&MonthlyAccuracy> = None;
+    for month in months {
+        let error = month.error_percent();
+        let same_vendor = previous.filter(|previous| previous.vendor == month.vendor);
+        let vendor = if same_vendor.is_some() {
+            ""
+        } else {
+            month.vendor.as_str()
+        };
+        let mut spans = vec![Span::raw(format!(
+            "{vendor:<16} {:04}-{:02} {:>7} {:>11} {:>11} ",
+            month.year,
+            month.month,
+            month.entries,
+            format_money(month.billed_usd, currency),
+            format_money(month.estimated_usd, currency),
+        ))];
+        spans.push(match error {
+            Some(error) => Span::styled(
+                format!("{error:>+7.1}%"),
+                Style::default().fg(match error.abs() {
+                    error if error <= 5.0 => Color::Green,
+                    error if error <= 15.0 => Color::Yellow,
+                    _ => Color::Red,
+                }),
+            ),
+            None => Span::raw(format!("{:>8}", "-")),
+        });
+        // Whether estimates got closer to the bill than the month before.
+        let before = same_vendor.and_then(MonthlyAccuracy::error_percent);
+        if let (Some(error), Some(before)) = (error, before) {
+            let change = error.abs() - before.abs();
+            spans.push(if change.abs() < 0.05 {
+                Span::raw("  steady")
+            } else if change < 0.0 {
+                Span::styled(
+                    format!("  ▼ {:.1} pts closer", -change),
+                    Style::default().fg(Color::Green),
+                )
+            } else {
+                Span::styled(
+                    format!("  ▲ {change:.1} pts further"),
+                    Style::default().fg(Color::Red),
+                )
+            });
+        }
+        if month.unpriced > 0 {
+            spans.push(Span::styled(
+                format!("  ({} unpriced)", month.unpriced),
+                Style::default().fg(Color::Gray),
+            ));
+        }
+        lines.push(Line::from(spans));
+        previous = Some(month);
+    }
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines).block(rounded_block("Estimates vs Billing")),
+        area,
+    );
+}
+
+fn draw_pricing_breakdown_overlay(
+    frame: &mut Frame<'_>,
+    breakdown: &PricingBreakdown,
+    config: &AppConfig,
+    window: &str,
+) {
+    let area = centered_rect(75, 60, frame.area());
+    let currency = config.currency.as_str();
+    let money = |amount: f64| format_money(amount, currency);
+    let label = |text: &str| Span::styled(format!("{text:<12}"), Style::default().fg(Color::Gray));
+    let row = |name: &str, text: String| Line::from(vec![label(name), Span::raw(text)]);
+    let mut lines = Vec::new();
+    match &breakdown.rates {
+        Some((key, pricing_match, rates)) => {
+            let how = match pricing_match {
+                PricingMatch::Exact => "exact key",
+                PricingMatch::Wildcard => "provider wildcard",
+                PricingMatch::Fuzzy => "fuzzy match (longest prefix)",
+                PricingMatch::Missing => "no match",
+            };
+            lines.push(row("Pricing", format!("{key} ({how})")));
+            lines.push(row(
+                "Rates",
+                format!(
+                    "{} in, {} out per 1M tokens",
+                    money(rates.input_per_million_usd),
+                    money(rates.output_per_million_usd)
+                ),
+            ));
+            lines.push(row(
+                "Source",
+                match &breakdown.origin {
+                    Some(origin) => format!(
+                        "{}, as of {}",
+                        origin.source,
+                        origin
+                            .updated_at
+                            .map_or_else(|| "unknown".to_string(), clock::format_timestamp)
+                    ),
+                    None => "built-in defaults".to_string(),
+                },
+            ));
+        }
+        None => lines.push(row(
+            "Pricing",
+            "no pricing key matches: estimates cost 0 (see u)".to_string(),
+        )),
+    }
+    lines.push(row("Window", window.to_string()));
+    lines.push(Line::from(""));
+
+    let estimated = &breakdown.estimated;
+    if estimated.entries > 0 {
+        lines.push(row(
+            "Estimated",
+            format!(
+                "{} {}, costed from the rates",
+                estimated.entries,
+                entries_label(estimated.entries)
+            ),
+        ));
+        if let Some((_, _, rates)) = &breakdown.rates {
+            let input = estimated.input_tokens as f64 / 1_000_000.0 * rates.input_per_million_usd;
+            let output =
+                estimated.output_tokens as f64 / 1_000_000.0 * rates.output_per_million_usd;
+            lines.push(row(
+                "",
+                format!(
+                    "{} in × {} = {}",
+                    format_token_count(estimated.input_tokens),
+                    money(rates.input_per_million_usd),
+                    money(input)
+                ),
+            ));
+            lines.push(row(
+                "",
+                format!(
+                    "{} out × {} = {}",
+                    format_token_count(estimated.output_tokens),
+                    money(rates.output_per_million_usd),
+                    money(output)
+                ),
+            ));
+        }
+        let mut recorded = format!("recorded {}", money(estimated.cost_usd));
+        if breakdown.estimates_are_stale() {
+            recorded.push_str(", priced with other rates when imported");
+        }
+        lines.push(row("", recorded));
+    }
+    let reported = &breakdown.reported;
+    if reported.entries > 0 {
+        lines.push(row(
+            "Reported",
+            format!(
+                "{} {}, {} for {} in and {} out, costed by the source",
+                reported.entries,
+                entries_label(reported.entries),
+                money(reported.cost_usd),
+                format_token_count(reported.input_tokens),
+                format_token_count(reported.output_tokens)
+            ),
+        ));
+        if let (Some(list), Some(discount)) =
+            (breakdown.list_cost(reported), breakdown.reported_discount())
+        {
+            let versus = if discount >= 0.0 {
+                format!(
+                    "{:.0}% below list: cache reads, batch or plan discounts",
+                    discount * 100.0
+                )
+            } else {
+                format!("{:.0}% above list", -discount * 100.0)
+            };
+            lines.push(row("", format!("list price {}, {versus}", money(list))));
+        }
+        for (original, amount) in &breakdown.converted {
+            let rate = config
+                .exchange_rates
+                .get(original)
+                .map_or_else(|| "no rate".to_string(), |rate| format!("{rate}"));
+            lines.push(row(
+                "",
+                format!("billed as {amount:.3} {original}, converted at {rate}"),
+            ));
+        }
+    }
+    if breakdown.multiplier != 1.0 {
+        lines.push(row(
+            "Multiplier",
+            format!(
+                "×{:.2} from cost_multipliers, included above",
+                breakdown.multiplier
+            ),
+        ));
+    }
+    lines.push(Line::from(vec![
+        label("Total"),
+        Span::styled(
+            money(breakdown.total_usd()),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+    ]));
+
     frame.render_widget(Clear, area);
     frame.render_widget(
-        Paragraph::new(help_lines).block(rounded_block("Keyboard Help")),
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(rounded_block(&format!(
+                "Pricing: {}/{} (b or Esc to close)",
+                breakdown.provider, breakdown.model
+            ))),
         area,
     );
 }
 
-fn rounded_block<'a>(title: &'a str) -> Block<'a> {
+fn draw_pricing_editor_overlay(frame: &mut Frame<'_>, editor: &PricingEditor, currency: &str) {
+    let area = centered_rect(70, 40, frame.area());
+    let label = |text: &str| Span::styled(format!("{text:<12}"), Style::default().fg(Color::Gray));
+    let field = |which: PricingField, value: &str| {
+        let focused = editor.field == which && !editor.confirming;
+        Span::styled(
+            format!("{value}{}", if focused { "▏" } else { "" }),
+            if focused {
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            },
+        )
+    };
+    let mut lines = vec![
+        Line::from(vec![label("Key"), Span::raw(editor.key.clone())]),
+        Line::from(vec![
+            label("Input"),
+            field(PricingField::Input, &editor.input),
+            Span::raw(format!(" {currency} per 1M tokens")),
+        ]),
+        Line::from(vec![
+            label("Output"),
+            field(PricingField::Output, &editor.output),
+            Span::raw(format!(" {currency} per 1M tokens")),
+        ]),
+    ];
+    if let Some(replaces) = &editor.replaces {
+        lines.push(Line::from(vec![
+            label(""),
+            Span::styled(
+                format!("adds its own key; {replaces} keeps pricing the other models"),
+                Style::default().fg(Color::Gray),
+            ),
+        ]));
+    }
+    lines.push(Line::from(""));
+    if let Some(error) = &editor.error {
+        lines.push(Line::from(Span::styled(
+            error.clone(),
+            Style::default().fg(Color::Red),
+        )));
+    }
+    lines.push(if editor.confirming {
+        Line::from(Span::styled(
+            format!(
+                "Save {} to the config and recost {} estimated {}? Enter or y saves, Esc goes back",
+                editor.key,
+                editor.affected,
+                entries_label(editor.affected)
+            ),
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ))
+    } else {
+        Line::from(Span::styled(
+            "Tab switches rate, Enter to review, Esc cancels",
+            Style::default().fg(Color::Gray),
+        ))
+    });
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(rounded_block(&format!(
+                "Edit pricing: {}/{}",
+                editor.provider, editor.model
+            ))),
+        area,
+    );
+}
+
+fn entries_label(count: usize) -> &'static str {
+    if count == 1 { "entry" } else { "entries" }
+}
+
+fn draw_import_progress(frame: &mut Frame<'_>, progress: ImportProgress) {
+    let popup = centered_rect(50, 20, frame.area());
+    let area = Rect {
+        y: popup.y + popup.height.saturating_sub(3) / 2,
+        height: popup.height.min(3),
+        ..popup
+    };
+    let label = if progress.total == 0 {
+        "discovering session files...".to_string()
+    } else {
+        format!(
+            "{}/{} files checked, {} parsed",
+            progress.checked, progress.total, progress.parsed
+        )
+    };
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Gauge::default()
+            .block(rounded_block("Importing Codex sessions"))
+            .gauge_style(Style::default().fg(Color::Cyan))
+            .ratio(progress.ratio().clamp(0.0, 1.0))
+            .label(label),
+        area,
+    );
+}
+
+/// A network poller's retry state: healthy, failing, or paused by its
+/// circuit breaker.
+fn poller_line(palette: &Palette, poller: &PollerHealth) -> Line<'static> {
+    let (state, severity) = match (poller.state, poller.consecutive_failures) {
+        (BreakerState::Open, _) => (
+            format!(
+                "paused, retrying in {}s",
+                poller.retry_in_secs.unwrap_or_default()
+            ),
+            Severity::Alert,
+        ),
+        (BreakerState::HalfOpen, _) => ("retrying".to_string(), Severity::Warn),
+        (BreakerState::Closed, 0) => ("healthy".to_string(), Severity::Ok),
+        (BreakerState::Closed, failures) => (format!("{failures} failures"), Severity::Warn),
+    };
+    let since = poller
+        .last_success
+        .and_then(|at| SystemTime::now().duration_since(at).ok())
+        .map(|age| format!(" last ok {} ago", format_age(age.as_secs())))
+        .unwrap_or_default();
+    Line::from(vec![
+        Span::raw(format!("  poller {:<12}", poller.name)),
+        Span::styled(
+            format!("{} {state}", severity.symbol()),
+            Style::default().fg(palette.severity(severity)),
+        ),
+        Span::raw(since),
+    ])
+}
+
+fn draw_diagnostics_overlay(frame: &mut Frame<'_>, app: &App) {
+    let area = centered_rect(80, 60, frame.area());
+    let diagnostics = &app.codex_diagnostics;
+    let palette = app.config.theme.palette();
+    let mut lines = vec![Line::from(Span::styled(
+        "Importers (↑/↓ select, space toggles):",
+        Style::default().fg(Color::Gray),
+    ))];
+    for (index, status) in app.importers.iter().enumerate() {
+        let marker = if index == app.selected_importer {
+            "▶"
+        } else {
+            " "
+        };
+        let (state, symbol, color) = match (status.enabled, status.error.is_some()) {
+            (false, _) => ("off", " ", Color::DarkGray),
+            (true, true) => ("error", Severity::Alert.symbol(), palette.alert),
+            (true, false) => ("on", Severity::Ok.symbol(), palette.ok),
+        };
+        let updated = status
+            .last_run
+            .filter(|_| status.enabled)
+            .map(|at| at.age())
+            .map(|age| format!(" updated {} ago", format_age(age.as_secs())))
+            .unwrap_or_default();
+        lines.push(Line::from(vec![
+            Span::raw(format!("{marker} {:<10}", status.importer.name())),
+            Span::styled(format!("{symbol} {state:<6}"), Style::default().fg(color)),
+            Span::raw(format!("entries:{}{updated}", status.entries)),
+        ]));
+        if let Some(error) = status.error.as_ref() {
+            lines.push(Line::from(Span::styled(
+                format!("    {error}"),
+                Style::default().fg(palette.alert),
+            )));
+        }
+    }
+    for poller in &app.pollers {
+        lines.push(poller_line(&palette, poller));
+    }
+    let reconciliation = &app.reconciliation;
+    if reconciliation.matched > 0 {
+        let currency = &app.config.currency;
+        let delta = reconciliation.delta_usd();
+        lines.push(Line::from(format!(
+            "  reconciled {} {} already billed: estimated {}, billed {} ({}{})",
+            reconciliation.matched,
+            if reconciliation.matched == 1 {
+                "estimate"
+            } else {
+                "estimates"
+            },
+            format_money(reconciliation.estimated_usd, currency),
+            format_money(reconciliation.billed_usd, currency),
+            if delta < 0.0 { "-" } else { "+" },
+            format_money(delta.abs(), currency)
+        )));
+    }
+    lines.push(Line::from(""));
+    lines.extend([
+        Line::from(format!(
+            "files:{} refreshed:{} parse errors:{} no usage:{} unreadable:{} oversized:{}",
+            diagnostics.active_files,
+            diagnostics.refreshed_files,
+            diagnostics.parse_error_files,
+            diagnostics.no_usage_or_limits_files,
+            diagnostics.unreadable_files,
+            diagnostics.oversized_files
+        )),
+        Line::from(""),
+    ]);
+    for (source, status) in &app.editor_imports {
+        let updated = status
+            .last_run
+            .map(|at| at.age())
+            .map(|age| format!(" updated {} ago", format_age(age.as_secs())))
+            .unwrap_or_default();
+        lines.push(Line::from(format!(
+            "{}: files:{} entries:{} skipped lines:{} unreadable:{}{updated}",
+            source.provider(),
+            status.files,
+            status.records,
+            status.skipped_lines,
+            status.unreadable_files
+        )));
+        if let Some(error) = status.error.as_ref() {
+            lines.push(Line::from(Span::styled(
+                format!("  {error}"),
+                Style::default().fg(palette.alert),
+            )));
+        }
+    }
+    if !app.editor_imports.is_empty() {
+        lines.push(Line::from(""));
+    }
+    if diagnostics.quarantined.is_empty() {
+        lines.push(Line::from("No quarantined session files."));
+    } else {
+        lines.push(Line::from(Span::styled(
+            "Quarantined (skipped until the file changes; t to retry):",
+            Style::default().fg(palette.warn),
+        )));
+        for session in &diagnostics.quarantined {
+            lines.push(Line::from(session.path.display().to_string()));
+            lines.push(Line::from(Span::styled(
+                format!(
+                    "  line {} (byte {}), {} failures: {}",
+                    session.line, session.offset, session.failures, session.error
+                ),
+                Style::default().fg(Color::Gray),
+            )));
+        }
+    }
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(rounded_block("Import Diagnostics")),
+        area,
+    );
+}
+
+/// Input tokens per turn scaled to the session's largest turn, keeping the
+/// latest `width` turns.
+fn turn_sparkline(turns: &[u64], width: usize) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let turns = &turns[turns.len().saturating_sub(width)..];
+    let max = turns.iter().copied().max().unwrap_or(0).max(1);
+    turns
+        .iter()
+        .map(|tokens| BARS[(*tokens * (BARS.len() as u64 - 1) / max) as usize])
+        .collect()
+}
+
+fn context_growth_line(
+    palette: &Palette,
+    session: &ContextGrowth,
+    currency: &str,
+) -> Line<'static> {
+    let name = session.path.file_name().map_or_else(
+        || session.path.display().to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    let last = session.turn_input_tokens.last().copied().unwrap_or(0);
+    let color = if session.is_bloated() {
+        palette.warn
+    } else {
+        palette.ok
+    };
+    Line::from(vec![
+        Span::raw(format!(
+            "{:>5} {:>8} ",
+            session.turn_input_tokens.len(),
+            format_token_count(last)
+        )),
+        Span::styled(
+            format!(
+                "{:>7.0}% {:>10} ",
+                session.carried_share() * 100.0,
+                format_money(session.carried_cost_usd, currency)
+            ),
+            Style::default().fg(color),
+        ),
+        Span::styled(
+            format!("{:<24} ", turn_sparkline(&session.turn_input_tokens, 24)),
+            Style::default().fg(Color::Cyan),
+        ),
+        Span::raw(name),
+        Span::styled(
+            if session.is_bloated() {
+                "  ! compact"
+            } else {
+                ""
+            },
+            Style::default()
+                .fg(palette.warn)
+                .add_modifier(Modifier::BOLD),
+        ),
+    ])
+}
+
+fn draw_context_growth_overlay(frame: &mut Frame<'_>, app: &App) {
+    let area = centered_rect(90, 70, frame.area());
+    let palette = app.config.theme.palette();
+    let mut lines = vec![
+        Line::from(
+            "Each turn re-sends the conversation so far; carried is input beyond the first turn's.",
+        ),
+        Line::from(Span::styled(
+            format!(
+                "{:>5} {:>8} {:>8} {:>10} {:<24} session",
+                "turns", "last", "carried", "cost", "input per turn"
+            ),
+            Style::default().fg(Color::Gray),
+        )),
+    ];
+    if app.context_growth.is_empty() {
+        lines.push(Line::from("No Codex sessions with enough turns yet."));
+    }
+    lines.extend(
+        app.context_growth
+            .iter()
+            .map(|session| context_growth_line(&palette, session, &app.config.currency)),
+    );
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines).block(rounded_block("Context Growth")),
+        area,
+    );
+}
+
+fn draw_period_preview_overlay(frame: &mut Frame<'_>, preview: &PeriodPreview, currency: &str) {
+    let area = centered_rect(70, 50, frame.area());
+    let label = |text: &str| Span::styled(format!("{text:<12}"), Style::default().fg(Color::Gray));
+    let (year, month) = preview.current;
+    let (next_year, next_month) = preview.next;
+    let left_hours = preview.secs_left / 3600;
+    let current_source = match preview.current_source.as_deref() {
+        Some(key) => format!("budget_schedule {key}"),
+        None => "data file".to_string(),
+    };
+    let mut lines = vec![
+        Line::from(vec![
+            label("Period"),
+            Span::raw(format!(
+                "{year:04}-{month:02} → {next_year:04}-{next_month:02}, rolls over in {}d {}h (UTC)",
+                left_hours / 24,
+                left_hours % 24
+            )),
+        ]),
+        Line::from(vec![
+            label("Spent"),
+            Span::raw(format!(
+                "{} this month",
+                format_money(preview.spent_usd, currency)
+            )),
+        ]),
+        Line::from(vec![
+            label("Budget now"),
+            Span::raw(format!(
+                "{} ({current_source})",
+                format_budget(preview.current_budget_usd, currency)
+            )),
+        ]),
+        Line::from(vec![
+            label("Carry-over"),
+            Span::raw(match preview.rollover {
+                None => "none: unspent budget does not roll over".to_string(),
+                Some(BudgetRollover::Reset) => {
+                    "reset: every month starts from its base budget".to_string()
+                }
+                Some(rollover) => format!(
+                    "{} brought in, {} carries into {next_year:04}-{next_month:02} if nothing more is spent{}",
+                    format_money(preview.carried_in_usd, currency),
+                    format_money(preview.carry_out_usd, currency),
+                    match rollover {
+                        BudgetRollover::Cap(percent) => {
+                            format!(" (capped at {percent}% of the base)")
+                        }
+                        _ => String::new(),
+                    }
+                ),
+            }),
+        ]),
+    ];
+    if preview.staged.is_empty() {
+        lines.push(Line::from(vec![
+            label("Staged"),
+            Span::raw("nothing in budget_schedule after this month"),
+        ]));
+    }
+    for (index, (key, budget)) in preview.staged.iter().enumerate() {
+        lines.push(Line::from(vec![
+            label(if index == 0 { "Staged" } else { "" }),
+            Span::raw(format!("{key}  {}", format_money(*budget, currency))),
+        ]));
+    }
+    let changed = preview.next_budget_usd != preview.current_budget_usd;
+    lines.push(Line::from(vec![
+        label("Next period"),
+        Span::styled(
+            format!(
+                "{}{}",
+                format_budget(preview.next_budget_usd, currency),
+                if changed { "" } else { " (unchanged)" }
+            ),
+            Style::default()
+                .fg(if changed { Color::Cyan } else { Color::Green })
+                .add_modifier(Modifier::BOLD),
+        ),
+    ]));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines).block(rounded_block("Next Period Preview")),
+        area,
+    );
+}
+
+fn format_age(age_secs: u64) -> String {
+    if age_secs < 60 {
+        format!("{age_secs}s")
+    } else if age_secs < 3600 {
+        format!("{}m", age_secs / 60)
+    } else {
+        format!("{}h", age_secs / 3600)
+    }
+}
+
+pub(crate) fn draw_setup_wizard(frame: &mut Frame<'_>, wizard: &SetupWizard) {
+    let area = centered_rect(70, 60, frame.area());
+    let title = format!("{APP_NAME} Setup ({}/{WIZARD_STEPS})", wizard.step.number());
+    let field = |label: &str, value: &str, placeholder: &str| {
+        let shown = if value.is_empty() {
+            Span::styled(
+                placeholder.to_string(),
+                Style::default().fg(Color::DarkGray),
+            )
+        } else {
+            Span::raw(value.to_string())
+        };
+        Line::from(vec![
+            Span::styled(format!("{label}: "), Style::default().fg(Color::Gray)),
+            shown,
+            Span::styled("_", Style::default().add_modifier(Modifier::SLOW_BLINK)),
+        ])
+    };
+
+    let mut lines = match wizard.step {
+        WizardStep::Budget => vec![
+            Line::from("Welcome! No config file was found, so let's create one."),
+            Line::from(""),
+            Line::from("What is your spending budget for the fuel gauge?"),
+            field("Budget", &wizard.budget, "50"),
+        ],
+        WizardStep::Currency => vec![
+            Line::from("Which currency are your costs and pricing in?"),
+            field("Currency", &wizard.currency, "USD"),
+        ],
+        WizardStep::CodexImport => vec![
+            Line::from("Import usage from Codex CLI session logs?"),
+            Line::from(vec![
+                Span::styled("Codex import: ", Style::default().fg(Color::Gray)),
+                Span::styled(
+                    if wizard.codex_enabled {
+                        " YES "
+                    } else {
+                        " NO "
+                    },
+                    Style::default()
+                        .fg(Color::Black)
+                        .bg(if wizard.codex_enabled {
+                            Color::Green
+                        } else {
+                            Color::Yellow
+                        })
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled("  (y / n / space)", Style::default().fg(Color::DarkGray)),
+            ]),
+        ],
+        WizardStep::SessionsDir => vec![
+            Line::from("Where are your Codex sessions stored?"),
+            Line::from("Leave blank to use ~/.codex/sessions."),
+            field("Sessions dir", &wizard.sessions_dir, "~/.codex/sessions"),
+        ],
+        WizardStep::Review => {
+            let choices = wizard.choices();
+            let mut review = vec![
+                Line::from("Review your setup:"),
+                Line::from(format!(
+                    "  Budget:       {}",
+                    format_money(choices.budget_usd, &choices.currency)
+                )),
+                Line::from(format!("  Currency:     {}", choices.currency)),
+                Line::from(format!(
+                    "  Codex import: {}",
+                    if choices.codex_enabled { "on" } else { "off" }
+                )),
+            ];
+            if choices.codex_enabled {
+                review.push(Line::from(format!(
+                    "  Sessions dir: {}",
+                    choices
+                        .codex_sessions_dir
+                        .as_deref()
+                        .unwrap_or("~/.codex/sessions (default)")
+                )));
+            }
+            if wizard.keeps_existing_data {
+                review.push(Line::from(Span::styled(
+                    "  An existing usage file was found; its budget is kept.",
+                    Style::default().fg(Color::Yellow),
+                )));
+            }
+            review
+        }
+    };
+
+    if let Some(error) = wizard.error.as_ref() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            error.clone(),
+            Style::default().fg(Color::Red),
+        )));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        if wizard.step == WizardStep::Review {
+            "Enter: save and start | Up/Shift+Tab: back | Esc: quit without saving"
+        } else {
+            "Enter: next | Up/Shift+Tab: back | Esc: quit without saving"
+        },
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(Paragraph::new(lines).block(rounded_block(&title)), area);
+}
+
+pub(crate) fn format_money(amount: f64, currency: &str) -> String {
+    let symbol = match currency {
+        "USD" => Some("$"),
+        "EUR" => Some("€"),
+        "GBP" => Some("£"),
+        "JPY" => Some("¥"),
+        _ => None,
+    };
+    match symbol {
+        Some(symbol) => format!("{symbol}{amount:.3}"),
+        None => format!("{amount:.3} {currency}"),
+    }
+}
+
+pub(crate) fn rounded_block<'a>(title: &'a str) -> Block<'a> {
     Block::default()
         .borders(Borders::ALL)
         .border_set(border::ROUNDED)
@@ -489,3 +2290,64 @@ fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
         ])
         .split(vertical[1])[1]
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::models::ProviderBadge;
+    use crate::theme::Theme;
+
+    use super::*;
+
+    #[test]
+    fn provider_badge_applies_partial_overrides_and_ignores_bad_colors() {
+        let mut config = AppConfig::default();
+        config.provider_badges.insert(
+            "openai".to_string(),
+            ProviderBadge {
+                icon: Some("O".to_string()),
+                color: Some("not-a-color".to_string()),
+            },
+        );
+        config.provider_badges.insert(
+            "local".to_string(),
+            ProviderBadge {
+                icon: None,
+                color: Some("#112233".to_string()),
+            },
+        );
+
+        assert_eq!(
+            provider_badge(&config, "openai"),
+            ("O".to_string(), Color::Green)
+        );
+        assert_eq!(
+            provider_badge(&config, "local"),
+            ("●".to_string(), Color::Rgb(0x11, 0x22, 0x33))
+        );
+        assert_eq!(
+            provider_badge(&config, "gemini"),
+            ("✦".to_string(), Color::Blue)
+        );
+    }
+
+    #[test]
+    fn alert_severities_carry_a_symbol_and_the_theme_colour() {
+        let palette = Theme::Protanopia.palette();
+        let text = |line: &Line<'_>| {
+            line.spans
+                .iter()
+                .map(|span| span.content.as_ref())
+                .collect::<String>()
+        };
+        let lines = build_alert_lines(&palette, 0.1, 0.5, 0.75, 0.0)
+            .into_iter()
+            .map(|alert| alert.line)
+            .collect::<Vec<_>>();
+        assert!(text(&lines[0]).contains("‼ ALERT"));
+        assert!(text(&lines[1]).contains("✓ NOMINAL"));
+        assert!(text(&lines[2]).contains("! WATCH"));
+        assert_eq!(lines[0].spans[1].style.bg, Some(palette.alert));
+        assert_eq!(lines[2].spans[1].style.bg, Some(palette.warn));
+        assert_ne!(palette.alert, Color::Red);
+    }
+}
@@ -0,0 +1,1650 @@
+use ratatui::Frame;
+use ratatui::layout::Alignment;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::symbols::Marker;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::canvas::{Canvas, Circle, Line as CanvasLine};
+use ratatui::widgets::{Axis, Chart, Clear, Dataset, GraphType, LineGauge, Paragraph, Sparkline};
+
+use crate::app::{App, DashboardView, LeaderboardWindow};
+use crate::big_text::{BIG_TEXT_HEIGHT, big_text, big_text_width};
+use crate::budget_period::{period_spend, period_start};
+use crate::codex_import::{CodexRateLimit, CodexRateLimits};
+use crate::editor_import::PlanQuota;
+use crate::freeze::daily_allowance;
+use crate::models::{
+    AppConfig, GaugeTotals, ModelEfficiency, ModelStats, SpendGroup, UsageData,
+    authoritative_percent, key_summaries, model_leaderboard, project_summaries,
+};
+use crate::pacing::window_pacing;
+use crate::summary::UsageSummary;
+use crate::theme::Palette;
+use crate::ui::{format_money, format_reset_from, format_token_count, rounded_block};
+
+/// What the dashboard widgets read, worked out from the app once per
+/// frame.
+pub(crate) struct AppState<'a> {
+    pub(crate) config: &'a AppConfig,
+    pub(crate) data: &'a UsageData,
+    /// `data` added up, for what would otherwise scan it on every frame.
+    pub(crate) summary: &'a UsageSummary,
+    /// The Codex limits whatever provider is selected, with windows that
+    /// have reset since the reading rolled forward to `now_epoch_secs`.
+    pub(crate) codex_limits: Option<CodexRateLimits>,
+    /// The selected provider's plan allowance, for plan-billed editors.
+    pub(crate) plan_quota: Option<&'a PlanQuota>,
+    /// Spend counted against `budget_usd`: the team's when a team budget
+    /// is shared, else the selection's.
+    pub(crate) spent_usd: f64,
+    pub(crate) budget_usd: Option<f64>,
+    /// What `spent_usd` covers: "team" or the selected provider.
+    pub(crate) spent_by: String,
+    pub(crate) dials: DialReadings,
+    pub(crate) leaderboard_window: LeaderboardWindow,
+    pub(crate) now_epoch_secs: i64,
+    /// `--no-canvas`: line gauges and plain text instead of drawings.
+    pub(crate) no_canvas: bool,
+    pub(crate) palette: Palette,
+    /// The row cursor, while a table view is shown.
+    pub(crate) table_row: Option<usize>,
+    /// Set by Enter on a provider: what the selection is called and its
+    /// models, listed in place of the view.
+    pub(crate) provider_models: Option<(String, Vec<ModelStats>)>,
+}
+
+/// What the dials read for the current selection.
+pub(crate) struct DialReadings {
+    pub(crate) is_codex: bool,
+    /// Only set while Codex itself is selected.
+    pub(crate) codex_limits: Option<CodexRateLimits>,
+    pub(crate) fuel_title: &'static str,
+    pub(crate) fuel_ratio: f64,
+    pub(crate) token_ratio: f64,
+    pub(crate) spend_ratio: f64,
+    pub(crate) activity_ratio: f64,
+    /// What the RPM, Throttle and Traffic ratios are worked out from.
+    pub(crate) totals: GaugeTotals,
+}
+
+impl<'a> AppState<'a> {
+    pub(crate) fn new(app: &'a App, now_epoch_secs: i64) -> Self {
+        let selected_provider = app.selected_provider.as_deref().unwrap_or("");
+        let single = app.marked_providers.is_empty()
+            && !app.config.provider_groups.contains_key(selected_provider);
+        // A shared team budget drains with everyone's spend, whatever is
+        // selected.
+        let (spent_usd, budget_usd, spent_by) =
+            match (app.team_budget.as_ref(), app.selected_stats()) {
+                (Some(team), _) => (
+                    team.spent_usd(),
+                    team.budget_or(app.data.budget_usd),
+                    "team".to_string(),
+                ),
+                // A monthly budget (`budget_rollover`) drains with this
+                // month's spend only.
+                (None, Some(provider)) => (
+                    match period_start(&app.config, now_epoch_secs) {
+                        Some(since) => period_spend(&app.data, &app.selected_members(), since),
+                        None => provider.total_cost_usd,
+                    },
+                    app.data.budget_usd,
+                    provider.provider,
+                ),
+                (None, None) => (0.0, app.data.budget_usd, "nothing".to_string()),
+            };
+        let budget_ratio = match budget_usd {
+            Some(budget) if budget > 0.0 => (spent_usd / budget).clamp(0.0, 1.0),
+            _ => 0.0,
+        };
+        let selection = app.selected_members();
+        let totals = match app.decayed_totals.as_ref() {
+            Some(decayed) => GaugeTotals::from_weighted(decayed, &selection),
+            None => app.summary.gauge_totals(&selection),
+        };
+        let ratios = totals.ratios();
+        let is_codex = single && selected_provider == "codex";
+        // Plan-billed editors have no dollar budget to burn; their tank is
+        // the plan allowance instead.
+        let plan_quota = app
+            .plan_quotas
+            .iter()
+            .find(|quota| single && quota.provider == selected_provider);
+        let codex_limits = app
+            .codex_limits
+            .as_ref()
+            .map(|limits| limits.as_of(now_epoch_secs));
+        Self {
+            config: &app.config,
+            data: &app.data,
+            summary: &app.summary,
+            codex_limits: codex_limits.clone(),
+            plan_quota,
+            spent_usd,
+            budget_usd,
+            spent_by,
+            dials: DialReadings {
+                is_codex,
+                codex_limits: codex_limits.filter(|_| is_codex),
+                fuel_title: if plan_quota.is_some() {
+                    "Plan Quota"
+                } else {
+                    "Fuel Tank"
+                },
+                fuel_ratio: match plan_quota {
+                    Some(quota) => 1.0 - quota.used_ratio(),
+                    None => (1.0 - budget_ratio).clamp(0.0, 1.0),
+                },
+                token_ratio: ratios.token,
+                spend_ratio: ratios.spend,
+                activity_ratio: ratios.activity,
+                totals,
+            },
+            leaderboard_window: app.leaderboard_window,
+            now_epoch_secs,
+            no_canvas: app.no_canvas,
+            palette: app.config.theme.palette(),
+            table_row: (app.view.is_table() || app.show_provider_models).then_some(app.table_row),
+            provider_models: app
+                .show_provider_models
+                .then(|| {
+                    app.selected_stats().map(|stats| {
+                        (
+                            stats.provider,
+                            app.summary.model_stats(app.selected_members()),
+                        )
+                    })
+                })
+                .flatten(),
+        }
+    }
+}
+
+/// One dial, for zooming it to the whole screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Dial {
+    Fuel,
+    Rpm,
+    Throttle,
+    Traffic,
+    CodexFiveHour,
+    CodexWeekly,
+}
+
+impl Dial {
+    pub(crate) const ALL: [Dial; 6] = [
+        Dial::Fuel,
+        Dial::Rpm,
+        Dial::Throttle,
+        Dial::Traffic,
+        Dial::CodexFiveHour,
+        Dial::CodexWeekly,
+    ];
+
+    /// The dial after this one, `None` after the last.
+    pub(crate) fn next(self) -> Option<Dial> {
+        Dial::ALL.get(self as usize + 1).copied()
+    }
+
+    /// Title, needle position and the word after the percentage.
+    pub(crate) fn reading(self, state: &AppState<'_>) -> (&'static str, f64, &'static str) {
+        let dials = &state.dials;
+        match self {
+            Dial::Fuel => (dials.fuel_title, dials.fuel_ratio, "left"),
+            Dial::Rpm => ("RPM", dials.token_ratio, "load"),
+            Dial::Throttle => ("Throttle", dials.spend_ratio, "burn"),
+            Dial::Traffic => ("Traffic", dials.activity_ratio, "flow"),
+            Dial::CodexFiveHour => (
+                "Codex 5h Limit",
+                limit_ratio(
+                    state
+                        .codex_limits
+                        .as_ref()
+                        .and_then(|limits| limits.primary.as_ref()),
+                ),
+                "used",
+            ),
+            Dial::CodexWeekly => (
+                "Codex Weekly Limit",
+                limit_ratio(
+                    state
+                        .codex_limits
+                        .as_ref()
+                        .and_then(|limits| limits.secondary.as_ref()),
+                ),
+                "used",
+            ),
+        }
+    }
+
+    /// How the reading is worked out, for the inspect overlay: what the
+    /// dial shows, the formula, its inputs and the data it covers.
+    pub(crate) fn explanation(self, state: &AppState<'_>) -> Vec<(&'static str, String)> {
+        let currency = &state.config.currency;
+        let totals = &state.dials.totals;
+        let window = match state
+            .config
+            .gauge_half_life_hours
+            .filter(|hours| *hours > 0.0)
+        {
+            Some(hours) => {
+                format!("every entry, weighted by 0.5^(age / {hours}h) (gauge_half_life_hours)")
+            }
+            None => {
+                "every entry on record (set gauge_half_life_hours to favour recent use)".to_string()
+            }
+        };
+        let codex_limit = |limit: Option<&CodexRateLimit>| match limit {
+            Some(limit) => vec![
+                (
+                    "Shows",
+                    "how much of the Codex rate limit is used".to_string(),
+                ),
+                (
+                    "Formula",
+                    "used percent from the latest Codex session event".to_string(),
+                ),
+                ("Inputs", format!("{:.1}% used", limit.used_percent)),
+                (
+                    "Window",
+                    format!("Codex's rolling {}m window", limit.window_minutes),
+                ),
+            ],
+            None => vec![
+                (
+                    "Shows",
+                    "how much of the Codex rate limit is used".to_string(),
+                ),
+                (
+                    "Inputs",
+                    "none yet: no Codex session has reported its limits".to_string(),
+                ),
+            ],
+        };
+        match self {
+            Dial::Fuel => match (state.plan_quota, state.budget_usd) {
+                (Some(quota), _) => vec![
+                    ("Shows", "how much of the plan allowance is left".to_string()),
+                    ("Formula", "1 - used / allowance".to_string()),
+                    (
+                        "Inputs",
+                        format!("{} of {} {} used", quota.used, quota.limit, quota.unit),
+                    ),
+                    (
+                        "Window",
+                        match quota.resets_at.as_deref() {
+                            Some(at) => format!("the plan period, resetting {at}"),
+                            None => "the plan period".to_string(),
+                        },
+                    ),
+                ],
+                (None, Some(budget)) if budget > 0.0 => vec![
+                    ("Shows", "how much of the budget is left".to_string()),
+                    ("Formula", "1 - spent / budget".to_string()),
+                    (
+                        "Inputs",
+                        format!(
+                            "{} spent by {} of {}",
+                            format_money(state.spent_usd, currency),
+                            state.spent_by,
+                            format_money(budget, currency)
+                        ),
+                    ),
+                    (
+                        "Window",
+                        if state.spent_by == "team" {
+                            "the team's spend as last synced".to_string()
+                        } else if state.config.budget_rollover.is_some() {
+                            "this month's spend (UTC), against a budget that rolls over".to_string()
+                        } else {
+                            "all recorded spend".to_string()
+                        },
+                    ),
+                ],
+                _ => vec![
+                    ("Shows", "how much of the budget is left".to_string()),
+                    (
+                        "Inputs",
+                        "no budget is set, so the tank reads full; set budget_usd or budget_schedule"
+                            .to_string(),
+                    ),
+                ],
+            },
+            Dial::Rpm => vec![
+                (
+                    "Shows",
+                    "the selection's tokens against the busiest single provider's".to_string(),
+                ),
+                (
+                    "Formula",
+                    "(input + output tokens) / busiest provider's tokens".to_string(),
+                ),
+                (
+                    "Inputs",
+                    format!(
+                        "{} / {} tokens",
+                        format_token_count(totals.tokens.round() as u64),
+                        format_token_count(totals.busiest_tokens.round() as u64)
+                    ),
+                ),
+                ("Window", window),
+            ],
+            Dial::Throttle => vec![
+                (
+                    "Shows",
+                    "the selection's spend against the busiest single provider's".to_string(),
+                ),
+                ("Formula", "cost / busiest provider's cost".to_string()),
+                (
+                    "Inputs",
+                    format!(
+                        "{} / {}",
+                        format_money(totals.cost_usd, currency),
+                        format_money(totals.busiest_cost_usd, currency)
+                    ),
+                ),
+                ("Window", window),
+            ],
+            Dial::Traffic => vec![
+                ("Shows", "the selection's share of all requests".to_string()),
+                (
+                    "Formula",
+                    "requests / requests from every provider".to_string(),
+                ),
+                (
+                    "Inputs",
+                    format!(
+                        "{:.0} / {:.0} requests",
+                        totals.requests, totals.all_requests
+                    ),
+                ),
+                ("Window", window),
+            ],
+            Dial::CodexFiveHour => {
+                codex_limit(state.codex_limits.as_ref().and_then(|limits| limits.primary.as_ref()))
+            }
+            Dial::CodexWeekly => {
+                codex_limit(state.codex_limits.as_ref().and_then(|limits| limits.secondary.as_ref()))
+            }
+        }
+    }
+}
+
+fn limit_ratio(limit: Option<&CodexRateLimit>) -> f64 {
+    limit
+        .map(|limit| (limit.used_percent / 100.0).clamp(0.0, 1.0))
+        .unwrap_or(0.0)
+}
+
+pub(crate) type DrawWidget = fn(&mut Frame<'_>, Rect, &AppState<'_>);
+
+/// A widget `dashboard_layout` can place, under the name it goes by there.
+#[derive(Debug)]
+pub(crate) struct WidgetSpec {
+    pub(crate) name: &'static str,
+    pub(crate) draw: DrawWidget,
+}
+
+/// Every widget a layout can use.
+pub(crate) const WIDGETS: [WidgetSpec; 10] = [
+    WidgetSpec {
+        name: "gauge:fuel",
+        draw: draw_fuel_gauge,
+    },
+    WidgetSpec {
+        name: "gauge:rpm",
+        draw: |frame, area, state| {
+            render_dial(frame, area, state, "RPM", state.dials.token_ratio, "load")
+        },
+    },
+    WidgetSpec {
+        name: "gauge:throttle",
+        draw: |frame, area, state| {
+            render_dial(
+                frame,
+                area,
+                state,
+                "Throttle",
+                state.dials.spend_ratio,
+                "burn",
+            )
+        },
+    },
+    WidgetSpec {
+        name: "gauge:traffic",
+        draw: |frame, area, state| {
+            render_dial(
+                frame,
+                area,
+                state,
+                "Traffic",
+                state.dials.activity_ratio,
+                "flow",
+            )
+        },
+    },
+    // Placed explicitly, the Codex dials show whatever is selected.
+    WidgetSpec {
+        name: "codex:limits",
+        draw: |frame, area, state| {
+            draw_codex_limit_gauges(frame, area, state, state.codex_limits.as_ref())
+        },
+    },
+    WidgetSpec {
+        name: "figures:spend",
+        draw: draw_spend_figures,
+    },
+    WidgetSpec {
+        name: "chart:daily",
+        draw: draw_forecast_chart,
+    },
+    WidgetSpec {
+        name: "table:models",
+        draw: draw_model_leaderboard,
+    },
+    WidgetSpec {
+        name: "table:keys",
+        draw: draw_key_table,
+    },
+    WidgetSpec {
+        name: "table:projects",
+        draw: draw_project_table,
+    },
+];
+
+pub(crate) fn find_widget(name: &str) -> Option<&'static WidgetSpec> {
+    WIDGETS.iter().find(|widget| widget.name == name)
+}
+
+/// The dials view as `dashboard_layout` composes it: rows of equal height,
+/// each split evenly between its widgets.
+pub(crate) fn draw_custom_layout(
+    frame: &mut Frame<'_>,
+    area: Rect,
+    state: &AppState<'_>,
+    layout: &[Vec<&WidgetSpec>],
+) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![
+            Constraint::Ratio(1, layout.len() as u32);
+            layout.len()
+        ])
+        .split(area);
+    for (row, row_area) in layout.iter().zip(rows.iter()) {
+        let cells = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Ratio(1, row.len() as u32); row.len()])
+            .split(*row_area);
+        for (widget, cell) in row.iter().zip(cells.iter()) {
+            (widget.draw)(frame, *cell, state);
+        }
+    }
+}
+
+/// The fixed dials grid: four dials, or the two Codex limits while Codex
+/// is selected.
+pub(crate) fn draw_gauges(frame: &mut Frame<'_>, area: Rect, state: &AppState<'_>) {
+    let readings = &state.dials;
+    let gauge_block_title = if readings.is_codex {
+        "Codex Limit Dials"
+    } else {
+        "Usage Dials"
+    };
+    let gauge_block = rounded_block(gauge_block_title);
+    let gauge_inner = gauge_block.inner(area);
+    frame.render_widget(gauge_block, area);
+
+    if readings.is_codex {
+        draw_codex_limit_gauges(frame, gauge_inner, state, readings.codex_limits.as_ref());
+    } else {
+        let gauge_rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(gauge_inner);
+        let top_gauges = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(gauge_rows[0]);
+        let bottom_gauges = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(gauge_rows[1]);
+
+        draw_fuel_gauge(frame, top_gauges[0], state);
+        render_dial(
+            frame,
+            top_gauges[1],
+            state,
+            "RPM",
+            readings.token_ratio,
+            "load",
+        );
+        render_dial(
+            frame,
+            bottom_gauges[0],
+            state,
+            "Throttle",
+            readings.spend_ratio,
+            "burn",
+        );
+        render_dial(
+            frame,
+            bottom_gauges[1],
+            state,
+            "Traffic",
+            readings.activity_ratio,
+            "flow",
+        );
+    }
+}
+
+fn draw_fuel_gauge(frame: &mut Frame<'_>, area: Rect, state: &AppState<'_>) {
+    render_dial(
+        frame,
+        area,
+        state,
+        state.dials.fuel_title,
+        state.dials.fuel_ratio,
+        "left",
+    );
+}
+
+fn draw_codex_limit_gauges(
+    frame: &mut Frame<'_>,
+    area: Rect,
+    state: &AppState<'_>,
+    limits: Option<&CodexRateLimits>,
+) {
+    // The timelines only go in when the dials keep enough room to read.
+    let timelines_height = 4 + pacing_sizes(state.config).count() as u16;
+    let (area, timelines) = if area.height >= 12 + timelines_height {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(timelines_height)])
+            .split(area);
+        (rows[0], Some(rows[1]))
+    } else {
+        (area, None)
+    };
+    if let Some(timelines) = timelines {
+        draw_limit_timelines(frame, timelines, state, limits);
+    }
+    let codex_gauges = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+    let five_hour_ratio = limit_ratio(limits.and_then(|limits| limits.primary.as_ref()));
+    let weekly_ratio = limit_ratio(limits.and_then(|limits| limits.secondary.as_ref()));
+    render_dial(
+        frame,
+        codex_gauges[0],
+        state,
+        "5h Limit",
+        five_hour_ratio,
+        "used",
+    );
+    render_dial(
+        frame,
+        codex_gauges[1],
+        state,
+        "Weekly Limit",
+        weekly_ratio,
+        "used",
+    );
+}
+
+/// The 5h and weekly windows laid out in time, from their start to their
+/// reset: the bar is filled as far as the limit is used and `│` marks
+/// now, so use running ahead of the clock shows as fill past the marker.
+/// Under them, how many more sessions of each `codex_session_tokens` size
+/// each window has room for.
+fn draw_limit_timelines(
+    frame: &mut Frame<'_>,
+    area: Rect,
+    state: &AppState<'_>,
+    limits: Option<&CodexRateLimits>,
+) {
+    let block = rounded_block("Windows (█ used, │ now, ┃ reset)");
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let windows = [
+        ("5h", limits.and_then(|limits| limits.primary.as_ref())),
+        (
+            "weekly",
+            limits.and_then(|limits| limits.secondary.as_ref()),
+        ),
+    ];
+    // Both bars get the same width so their resets line up.
+    let figures_width = windows
+        .iter()
+        .filter_map(|(_, limit)| *limit)
+        .map(|limit| {
+            timeline_figures(limit, state.now_epoch_secs)
+                .chars()
+                .count()
+        })
+        .max()
+        .unwrap_or(0);
+    let bar_width = (inner.width as usize).saturating_sub(TIMELINE_LABEL_WIDTH + figures_width + 1);
+    let lines = windows
+        .iter()
+        .map(|(label, limit)| {
+            limit_timeline(
+                label,
+                *limit,
+                state.now_epoch_secs,
+                bar_width,
+                &state.palette,
+            )
+        })
+        .collect::<Vec<_>>();
+    let pacing = windows.map(|(label, limit)| {
+        let pacing = limit.and_then(|limit| {
+            window_pacing(
+                limit,
+                state.data,
+                &state.config.codex_session_tokens,
+                state.now_epoch_secs,
+            )
+        });
+        (label, pacing)
+    });
+    let pacing_lines = pacing_sizes(state.config).map(|name| {
+        let room = pacing
+            .iter()
+            .map(|(label, pacing)| {
+                let sessions = pacing.as_ref().and_then(|pacing| {
+                    pacing
+                        .sessions_left
+                        .iter()
+                        .find(|(size, _)| size == name)
+                        .map(|(_, sessions)| format!("~{sessions}"))
+                });
+                format!(
+                    "{} more before the {label} reset",
+                    sessions.as_deref().unwrap_or("?")
+                )
+            })
+            .collect::<Vec<_>>();
+        Line::from(vec![
+            Span::raw(format!("{name:<TIMELINE_LABEL_WIDTH$}")),
+            Span::styled(room.join(", "), Style::default().fg(Color::Gray)),
+        ])
+    });
+    frame.render_widget(
+        Paragraph::new(lines.into_iter().chain(pacing_lines).collect::<Vec<_>>()),
+        inner,
+    );
+}
+
+/// The session sizes pacing is shown for.
+fn pacing_sizes(config: &AppConfig) -> impl Iterator<Item = &String> {
+    config
+        .codex_session_tokens
+        .iter()
+        .filter(|(_, tokens)| **tokens > 0)
+        .map(|(name, _)| name)
+}
+
+const TIMELINE_LABEL_WIDTH: usize = 7;
+
+/// How far through its window a limit is, 0 to 1, when its reset is known.
+fn window_elapsed(limit: &CodexRateLimit, now_epoch_secs: i64) -> Option<f64> {
+    let window_secs = (limit.window_minutes * 60).max(1) as f64;
+    limit.resets_at.map(|resets_at| {
+        let start = resets_at as f64 - window_secs;
+        ((now_epoch_secs as f64 - start) / window_secs).clamp(0.0, 1.0)
+    })
+}
+
+fn timeline_figures(limit: &CodexRateLimit, now_epoch_secs: i64) -> String {
+    let used = limit.used_percent.clamp(0.0, 100.0);
+    match window_elapsed(limit, now_epoch_secs) {
+        Some(elapsed) => format!(
+            " {used:.0}% used, {:.0}% of the window gone, resets {}",
+            elapsed * 100.0,
+            format_reset_from(limit.resets_at, now_epoch_secs.max(0) as u64)
+        ),
+        None => format!(" {used:.0}% used, reset unknown"),
+    }
+}
+
+/// One window's timeline with a `bar_width` bar, left out below ten
+/// columns.
+fn limit_timeline(
+    label: &str,
+    limit: Option<&CodexRateLimit>,
+    now_epoch_secs: i64,
+    bar_width: usize,
+    palette: &Palette,
+) -> Line<'static> {
+    let label = Span::raw(format!("{label:<TIMELINE_LABEL_WIDTH$}"));
+    let Some(limit) = limit else {
+        return Line::from(vec![
+            label,
+            Span::styled("no reading yet", Style::default().fg(Color::DarkGray)),
+        ]);
+    };
+    let figures = timeline_figures(limit, now_epoch_secs);
+    if bar_width < 10 {
+        return Line::from(vec![label, Span::raw(figures.trim_start().to_string())]);
+    }
+
+    let used = (limit.used_percent / 100.0).clamp(0.0, 1.0);
+    let filled = (used * bar_width as f64).round() as usize;
+    let now = window_elapsed(limit, now_epoch_secs)
+        .map(|elapsed| ((elapsed * bar_width as f64) as usize).min(bar_width - 1));
+    let fill = Style::default().fg(gauge_color(palette, used));
+    let empty = Style::default().fg(Color::DarkGray);
+    let mut spans = vec![label];
+    for column in 0..bar_width {
+        if Some(column) == now {
+            spans.push(Span::styled(
+                "│",
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        } else if column < filled {
+            spans.push(Span::styled("█", fill));
+        } else {
+            spans.push(Span::styled("░", empty));
+        }
+    }
+    spans.push(Span::styled("┃", Style::default().fg(palette.info)));
+    spans.push(Span::raw(figures));
+    Line::from(spans)
+}
+
+/// One dial over the whole of `area`, for a wall monitor: the dial, its
+/// value in large type and how it moved over recent refreshes. `trend`
+/// holds readings in thousandths, oldest first.
+pub(crate) fn draw_zoomed_dial(
+    frame: &mut Frame<'_>,
+    area: Rect,
+    state: &AppState<'_>,
+    dial: Dial,
+    trend: &[u64],
+) {
+    let (title, ratio, unit) = dial.reading(state);
+    let ratio = ratio.clamp(0.0, 1.0);
+    frame.render_widget(Clear, area);
+    let block = rounded_block("Zoom (z next dial, Esc close)");
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(8),
+            Constraint::Length(BIG_TEXT_HEIGHT as u16 + 2),
+            Constraint::Length(6),
+        ])
+        .split(inner);
+    render_dial(frame, rows[0], state, title, ratio, unit);
+    draw_big_figure(
+        frame,
+        rows[1],
+        state,
+        &format!("{title}, % {unit}"),
+        &format!("{:.1}%", ratio * 100.0),
+        gauge_color(&state.palette, ratio),
+    );
+    let trend_title = match trend.len() {
+        0 | 1 => "Trend (builds up with each refresh)".to_string(),
+        samples => format!("Trend (last {samples} refreshes)"),
+    };
+    frame.render_widget(
+        Sparkline::default()
+            .block(rounded_block(&trend_title))
+            .max(1000)
+            .style(Style::default().fg(gauge_color(&state.palette, ratio)))
+            .data(trend.iter().copied()),
+        rows[2],
+    );
+}
+
+/// Daily cost of `label` over the last `costs.len()` days, oldest on the
+/// left, each day as wide as the panel allows, to spot the days that drove
+/// the total.
+pub(crate) fn draw_spend_history(
+    frame: &mut Frame<'_>,
+    area: Rect,
+    state: &AppState<'_>,
+    label: &str,
+    costs: &[f64],
+) {
+    let currency = &state.config.currency;
+    let peak = costs.iter().copied().fold(0.0, f64::max);
+    let title = format!(
+        "Daily Cost: {label}, last {} days ({} total, {} peak)",
+        costs.len(),
+        format_money(costs.iter().sum(), currency),
+        format_money(peak, currency)
+    );
+    if peak <= 0.0 {
+        frame.render_widget(
+            Paragraph::new(format!("No spend in the last {} days.", costs.len()))
+                .block(rounded_block(&title)),
+            area,
+        );
+        return;
+    }
+    let day_width = (usize::from(area.width.saturating_sub(2)) / costs.len()).max(1);
+    // Bar heights are whole numbers; thousandths of the peak are enough.
+    let bars = costs
+        .iter()
+        .flat_map(|cost| std::iter::repeat_n((cost / peak * 1000.0).round() as u64, day_width));
+    frame.render_widget(
+        Sparkline::default()
+            .block(rounded_block(&title))
+            .max(1000)
+            .style(Style::default().fg(state.palette.info))
+            .data(bars),
+        area,
+    );
+}
+
+/// `figure` in block letters when the panel has room for them and
+/// `--no-canvas` is off, in plain bold type otherwise.
+fn draw_big_figure(
+    frame: &mut Frame<'_>,
+    area: Rect,
+    state: &AppState<'_>,
+    title: &str,
+    figure: &str,
+    color: Color,
+) {
+    let block = rounded_block(title).border_style(Style::default().fg(color));
+    let inner = block.inner(area);
+    let style = Style::default().fg(color).add_modifier(Modifier::BOLD);
+    let lines = match big_text(figure) {
+        Some(rows)
+            if !state.no_canvas
+                && inner.height as usize >= BIG_TEXT_HEIGHT
+                && big_text_width(figure).is_some_and(|width| width <= inner.width as usize) =>
+        {
+            rows.into_iter()
+                .map(|row| Line::from(Span::styled(row, style)))
+                .collect()
+        }
+        _ => vec![Line::from(Span::styled(figure.to_string(), style))],
+    };
+    frame.render_widget(
+        Paragraph::new(lines)
+            .alignment(Alignment::Center)
+            .block(block),
+        area,
+    );
+}
+
+/// Spend and what is left of the budget in block letters, readable from
+/// across the room.
+pub(crate) fn draw_spend_figures(frame: &mut Frame<'_>, area: Rect, state: &AppState<'_>) {
+    let currency = &state.config.currency;
+    let halves = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+    draw_big_figure(
+        frame,
+        halves[0],
+        state,
+        &format!("Spent by {}", state.spent_by),
+        &format_money(state.spent_usd, currency),
+        state.palette.info,
+    );
+    match state.budget_usd {
+        Some(budget) => {
+            let left = budget - state.spent_usd;
+            draw_big_figure(
+                frame,
+                halves[1],
+                state,
+                &format!("Left of {}", format_money(budget, currency)),
+                &format_money(left, currency),
+                if left <= 0.0 {
+                    state.palette.alert
+                } else {
+                    gauge_color(&state.palette, state.spent_usd / budget)
+                },
+            );
+        }
+        None => draw_big_figure(frame, halves[1], state, "Left", "-", Color::Gray),
+    }
+}
+
+fn gauge_color(palette: &Palette, ratio: f64) -> Color {
+    if ratio >= 0.9 {
+        palette.alert
+    } else if ratio >= 0.7 {
+        palette.warn
+    } else {
+        palette.info
+    }
+}
+
+/// An analog dial, or with `--no-canvas` a labeled line gauge that screen
+/// readers and braille displays can read.
+fn render_dial(
+    frame: &mut Frame<'_>,
+    area: Rect,
+    state: &AppState<'_>,
+    title: &str,
+    ratio: f64,
+    unit: &str,
+) {
+    if !state.no_canvas {
+        render_analog_gauge(frame, area, &state.palette, title, ratio, unit);
+        return;
+    }
+    let ratio = ratio.clamp(0.0, 1.0);
+    let block = rounded_block(title);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    frame.render_widget(
+        LineGauge::default()
+            .ratio(ratio)
+            .label(format!("{title}: {:.1}% {unit}", ratio * 100.0))
+            .filled_style(Style::default().fg(gauge_color(&state.palette, ratio))),
+        Rect {
+            height: inner.height.min(1),
+            ..inner
+        },
+    );
+}
+
+fn render_analog_gauge(
+    frame: &mut Frame<'_>,
+    area: Rect,
+    palette: &Palette,
+    title: &str,
+    ratio: f64,
+    unit: &str,
+) {
+    let ratio = ratio.clamp(0.0, 1.0);
+    let gauge_color = gauge_color(palette, ratio);
+    let dial_block = rounded_block(title);
+
+    frame.render_widget(
+        Canvas::default()
+            .block(dial_block)
+            .x_bounds([-1.2, 1.2])
+            .y_bounds([-1.2, 1.2])
+            .paint(|ctx| {
+                ctx.draw(&Circle {
+                    x: 0.0,
+                    y: 0.0,
+                    radius: 1.0,
+                    color: Color::DarkGray,
+                });
+
+                for step in 0..=10 {
+                    let tick_ratio = step as f64 / 10.0;
+                    let tick_angle = 225.0 - (270.0 * tick_ratio);
+                    let tick_rad = tick_angle.to_radians();
+                    let (outer_x, outer_y) = (tick_rad.cos() * 0.96, tick_rad.sin() * 0.96);
+                    let (inner_x, inner_y) = (tick_rad.cos() * 0.82, tick_rad.sin() * 0.82);
+                    ctx.draw(&CanvasLine {
+                        x1: inner_x,
+                        y1: inner_y,
+                        x2: outer_x,
+                        y2: outer_y,
+                        color: Color::Gray,
+                    });
+                }
+
+                let angle_deg = 225.0 - (270.0 * ratio);
+                let angle = angle_deg.to_radians();
+                let (needle_x, needle_y) = (angle.cos() * 0.76, angle.sin() * 0.76);
+                ctx.draw(&CanvasLine {
+                    x1: 0.0,
+                    y1: 0.0,
+                    x2: needle_x,
+                    y2: needle_y,
+                    color: gauge_color,
+                });
+                ctx.draw(&Circle {
+                    x: 0.0,
+                    y: 0.0,
+                    radius: 0.05,
+                    color: Color::White,
+                });
+            }),
+        area,
+    );
+
+    let value_text = format!("{:>5.1}% {unit}", ratio * 100.0);
+    let value_area = Rect {
+        x: area.x.saturating_add(1),
+        y: area.y.saturating_add(area.height.saturating_sub(2)),
+        width: area.width.saturating_sub(2),
+        height: 1,
+    };
+    frame.render_widget(
+        Paragraph::new(value_text).style(
+            Style::default()
+                .fg(gauge_color)
+                .bg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+        ),
+        value_area,
+    );
+}
+
+/// The rows a table view lists below its header, as drawn, for the row
+/// cursor and yanking. Other views have none.
+pub(crate) fn table_rows(state: &AppState<'_>, view: DashboardView) -> Vec<Line<'static>> {
+    if let Some((_, models)) = &state.provider_models {
+        return provider_model_rows(state, models);
+    }
+    match view {
+        DashboardView::Keys => spend_group_rows(state, "key", &key_summaries(state.data)),
+        DashboardView::Projects => spend_group_rows(
+            state,
+            "project",
+            &project_summaries(state.data, state.config),
+        ),
+        DashboardView::Models => leaderboard_rows(
+            state,
+            &model_leaderboard(
+                state.data,
+                state.leaderboard_window.since(state.now_epoch_secs),
+            ),
+        ),
+        DashboardView::Dials | DashboardView::Forecast => Vec::new(),
+    }
+}
+
+/// Appends `rows` to `lines`, highlighting the row cursor and scrolling
+/// far enough that it stays inside `area`.
+fn push_table_rows(
+    lines: &mut Vec<Line<'static>>,
+    mut rows: Vec<Line<'static>>,
+    cursor: Option<usize>,
+    area: Rect,
+) {
+    let Some(cursor) = cursor.filter(|_| !rows.is_empty()) else {
+        lines.extend(rows);
+        return;
+    };
+    let cursor = cursor.min(rows.len() - 1);
+    rows[cursor] = std::mem::take(&mut rows[cursor])
+        .patch_style(Style::default().add_modifier(Modifier::REVERSED));
+    let visible = usize::from(area.height.saturating_sub(2))
+        .saturating_sub(lines.len())
+        .max(1);
+    lines.extend(rows.into_iter().skip((cursor + 1).saturating_sub(visible)));
+}
+
+pub(crate) fn draw_key_table(frame: &mut Frame<'_>, area: Rect, state: &AppState<'_>) {
+    draw_spend_groups(
+        frame,
+        area,
+        state,
+        "Spend by API Key",
+        "key",
+        &key_summaries(state.data),
+    );
+}
+
+pub(crate) fn draw_project_table(frame: &mut Frame<'_>, area: Rect, state: &AppState<'_>) {
+    draw_spend_groups(
+        frame,
+        area,
+        state,
+        "Spend by Project",
+        "project",
+        &project_summaries(state.data, state.config),
+    );
+}
+
+/// Spend per API key or project across all providers, to find which key
+/// or service is responsible.
+fn draw_spend_groups(
+    frame: &mut Frame<'_>,
+    area: Rect,
+    state: &AppState<'_>,
+    title: &str,
+    column: &str,
+    groups: &[SpendGroup],
+) {
+    let mut lines = Vec::with_capacity(groups.len() + 1);
+    if groups.iter().all(|group| group.name.is_none()) {
+        lines.push(Line::from(format!(
+            "No entries carry a {column} yet; importers that know it fill it in."
+        )));
+    }
+    lines.push(Line::from(Span::styled(
+        format!(
+            "{:<28} {:<24} {:>6} {:>10} {:>12} {:>6}",
+            column, "providers", "req", "tokens", "cost", "share"
+        ),
+        Style::default().fg(Color::Gray),
+    )));
+    push_table_rows(
+        &mut lines,
+        spend_group_rows(state, column, groups),
+        state.table_row,
+        area,
+    );
+    frame.render_widget(Paragraph::new(lines).block(rounded_block(title)), area);
+}
+
+fn spend_group_rows(
+    state: &AppState<'_>,
+    column: &str,
+    groups: &[SpendGroup],
+) -> Vec<Line<'static>> {
+    let total_cost = groups.iter().map(|group| group.total_cost_usd).sum::<f64>();
+    groups
+        .iter()
+        .map(|group| {
+            let share = if total_cost > 0.0 {
+                format!("{:.0}%", group.total_cost_usd / total_cost * 100.0)
+            } else {
+                "-".to_string()
+            };
+            let line = format!(
+                "{:<28} {:<24} {:>6} {:>10} {:>12} {:>6}",
+                group
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| format!("(no {column})")),
+                group.providers.join(","),
+                group.requests,
+                format_token_count(group.total_tokens),
+                format_money(group.total_cost_usd, &state.config.currency),
+                share
+            );
+            if group.name.is_some() {
+                Line::from(line)
+            } else {
+                Line::from(Span::styled(line, Style::default().fg(Color::DarkGray)))
+            }
+        })
+        .collect()
+}
+
+/// The selected provider's models, the second level below the provider
+/// list.
+pub(crate) fn draw_provider_models(frame: &mut Frame<'_>, area: Rect, state: &AppState<'_>) {
+    let Some((label, models)) = &state.provider_models else {
+        return;
+    };
+    let mut lines = vec![Line::from(Span::styled(
+        format!(
+            "{:<28} {:>6} {:>10} {:>12} {:>6} {:>8}",
+            "model", "req", "tokens", "cost", "share", "reported"
+        ),
+        Style::default().fg(Color::Gray),
+    ))];
+    push_table_rows(
+        &mut lines,
+        provider_model_rows(state, models),
+        state.table_row,
+        area,
+    );
+    let title = format!("Models of {label} (Esc back)");
+    frame.render_widget(Paragraph::new(lines).block(rounded_block(&title)), area);
+}
+
+fn provider_model_rows(state: &AppState<'_>, models: &[ModelStats]) -> Vec<Line<'static>> {
+    let total_cost = models.iter().map(|model| model.total_cost_usd).sum::<f64>();
+    // Several providers are selected when a group or marked set is.
+    let one_provider = models
+        .windows(2)
+        .all(|pair| pair[0].provider == pair[1].provider);
+    models
+        .iter()
+        .map(|model| {
+            let share = if total_cost > 0.0 {
+                format!("{:.0}%", model.total_cost_usd / total_cost * 100.0)
+            } else {
+                "-".to_string()
+            };
+            let reported =
+                authoritative_percent(model.authoritative_cost_usd, model.total_cost_usd)
+                    .map_or_else(|| "-".to_string(), |percent| format!("{percent:.0}%"));
+            Line::from(format!(
+                "{:<28} {:>6} {:>10} {:>12} {:>6} {:>8}",
+                if one_provider {
+                    model.model.clone()
+                } else {
+                    format!("{}/{}", model.provider, model.model)
+                },
+                model.requests,
+                format_token_count(model.total_tokens),
+                format_money(model.total_cost_usd, &state.config.currency),
+                share,
+                reported
+            ))
+        })
+        .collect()
+}
+
+/// Models by what their output actually cost, with what each would have
+/// saved at the cheapest model's rate.
+pub(crate) fn draw_model_leaderboard(frame: &mut Frame<'_>, area: Rect, state: &AppState<'_>) {
+    let window = state.leaderboard_window;
+    let ranked = model_leaderboard(state.data, window.since(state.now_epoch_secs));
+    let mut lines = vec![Line::from(Span::styled(
+        format!(
+            "{:>3} {:<36} {:>10} {:>12} {:>12} {:>12} {:>8}",
+            "#", "provider/model", "output", "cost", "per 1M out", "vs #1", "reported"
+        ),
+        Style::default().fg(Color::Gray),
+    ))];
+    if ranked.is_empty() {
+        lines.push(Line::from(format!(
+            "No output tokens recorded in the {}.",
+            window.label()
+        )));
+    }
+    push_table_rows(
+        &mut lines,
+        leaderboard_rows(state, &ranked),
+        state.table_row,
+        area,
+    );
+    let title = format!(
+        "Cost per 1M Output Tokens ({}, w to change)",
+        window.label()
+    );
+    frame.render_widget(Paragraph::new(lines).block(rounded_block(&title)), area);
+}
+
+fn leaderboard_rows(state: &AppState<'_>, ranked: &[ModelEfficiency]) -> Vec<Line<'static>> {
+    let currency = &state.config.currency;
+    let best = ranked
+        .first()
+        .map_or(0.0, |model| model.cost_per_million_output());
+    ranked
+        .iter()
+        .enumerate()
+        .map(|(index, model)| {
+            let extra = model.total_cost_usd - best * model.output_tokens as f64 / 1_000_000.0;
+            let reported =
+                authoritative_percent(model.authoritative_cost_usd, model.total_cost_usd)
+                    .map_or_else(|| "-".to_string(), |percent| format!("{percent:.0}%"));
+            Line::from(vec![
+                Span::raw(format!(
+                    "{:>3} {:<36} {:>10} {:>12} ",
+                    index + 1,
+                    format!("{}/{}", model.provider, model.model),
+                    format_token_count(model.output_tokens),
+                    format_money(model.total_cost_usd, currency),
+                )),
+                Span::styled(
+                    format!(
+                        "{:>12} ",
+                        format_money(model.cost_per_million_output(), currency)
+                    ),
+                    Style::default().fg(if index == 0 {
+                        Color::Green
+                    } else {
+                        Color::White
+                    }),
+                ),
+                Span::styled(
+                    format!(
+                        "{:>12} ",
+                        if index == 0 {
+                            "best".to_string()
+                        } else {
+                            format!("+{}", format_money(extra, currency))
+                        }
+                    ),
+                    Style::default().fg(if index == 0 {
+                        Color::Green
+                    } else {
+                        Color::Yellow
+                    }),
+                ),
+                Span::raw(format!("{reported:>8}")),
+            ])
+        })
+        .collect()
+}
+
+/// Month-to-date cumulative spend against a straight-line budget pace and
+/// last month's curve, all on a shared day-of-month axis.
+pub(crate) fn draw_forecast_chart(frame: &mut Frame<'_>, area: Rect, state: &AppState<'_>) {
+    let spend = state
+        .summary
+        .month_spend(state.data.budget_usd, state.now_epoch_secs);
+    let currency = &state.config.currency;
+    let allowance = daily_allowance(state.config, state.data, state.now_epoch_secs);
+    if state.no_canvas {
+        let money = |amount: Option<f64>| {
+            amount.map_or_else(
+                || "none".to_string(),
+                |amount| format_money(amount, currency),
+            )
+        };
+        let lines = vec![
+            Line::from(format!(
+                "Spent this month: {}",
+                format_money(spend.spent_usd(), currency)
+            )),
+            Line::from(format!("Budget: {}", money(spend.budget_usd))),
+            Line::from(format!("Budget pace today: {}", money(spend.pace_usd()))),
+            Line::from(format!("Governor: {} a day", money(allowance))),
+            Line::from(format!(
+                "Last month in total: {}",
+                money(spend.previous.last().map(|(_, total)| *total))
+            )),
+        ];
+        frame.render_widget(Paragraph::new(lines).block(rounded_block("Forecast")), area);
+        return;
+    }
+    let days = f64::from(spend.days_in_month);
+    let pace_line = spend
+        .budget_usd
+        .map(|budget| vec![(0.0, 0.0), (days, budget)]);
+
+    let mut datasets = Vec::with_capacity(3);
+    if let Some(pace_line) = pace_line.as_ref() {
+        datasets.push(
+            Dataset::default()
+                .name("budget pace")
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::DarkGray))
+                .data(pace_line),
+        );
+    }
+    if spend.previous.len() > 1 {
+        datasets.push(
+            Dataset::default()
+                .name("last month")
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::Blue))
+                .data(&spend.previous),
+        );
+    }
+    datasets.push(
+        Dataset::default()
+            .name("this month")
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Yellow))
+            .data(&spend.current),
+    );
+
+    let y_max = spend
+        .current
+        .iter()
+        .chain(&spend.previous)
+        .map(|(_, total)| *total)
+        .chain(spend.budget_usd)
+        .fold(0.0_f64, f64::max)
+        .max(0.01)
+        * 1.1;
+    let x_max = days.max(spend.previous.last().map_or(0.0, |(day, _)| *day));
+
+    let title = match spend.pace_usd() {
+        Some(pace) if spend.spent_usd() > pace => format!(
+            "Forecast — {} ahead of budget pace",
+            format_money(spend.spent_usd() - pace, currency)
+        ),
+        Some(pace) => format!(
+            "Forecast — {} under budget pace",
+            format_money(pace - spend.spent_usd(), currency)
+        ),
+        None => "Forecast — no budget set".to_string(),
+    };
+    let title = match allowance {
+        Some(_) if state.config.spending_frozen_since.is_some() => {
+            format!(
+                "{title} · FROZEN, governor {}/day",
+                format_money(0.0, currency)
+            )
+        }
+        Some(allowance) => format!(
+            "{title} · governor {}/day",
+            format_money(allowance, currency)
+        ),
+        None => title,
+    };
+
+    let chart = Chart::new(datasets)
+        .block(rounded_block(&title))
+        .x_axis(
+            Axis::default()
+                .title("day")
+                .style(Style::default().fg(Color::Gray))
+                .bounds([0.0, x_max])
+                .labels([
+                    "1".to_string(),
+                    format!("{}", spend.days_in_month / 2 + 1),
+                    format!("{}", spend.days_in_month),
+                ]),
+        )
+        .y_axis(
+            Axis::default()
+                .style(Style::default().fg(Color::Gray))
+                .bounds([0.0, y_max])
+                .labels([
+                    format_money(0.0, currency),
+                    format_money(y_max / 2.0, currency),
+                    format_money(y_max, currency),
+                ]),
+        );
+    frame.render_widget(chart, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+
+    use super::*;
+    use crate::clock;
+    use crate::models::{CostSource, UsageEntry};
+
+    fn state<'a>(
+        config: &'a AppConfig,
+        data: &'a UsageData,
+        summary: &'a UsageSummary,
+    ) -> AppState<'a> {
+        AppState {
+            config,
+            data,
+            summary,
+            codex_limits: None,
+            plan_quota: None,
+            spent_usd: 7.5,
+            budget_usd: Some(10.0),
+            spent_by: "openai".to_string(),
+            dials: DialReadings {
+                is_codex: false,
+                codex_limits: None,
+                fuel_title: "Fuel Tank",
+                fuel_ratio: 0.25,
+                token_ratio: 0.0,
+                spend_ratio: 0.0,
+                activity_ratio: 0.0,
+                totals: GaugeTotals::default(),
+            },
+            leaderboard_window: LeaderboardWindow::All,
+            no_canvas: false,
+            now_epoch_secs: clock::parse_timestamp("2026-10-16T00:00:00Z").expect("timestamp"),
+            palette: config.theme.palette(),
+            table_row: None,
+            provider_models: None,
+        }
+    }
+
+    fn render(width: u16, height: u16, draw: DrawWidget, state: &AppState<'_>) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+        terminal
+            .draw(|frame| draw(frame, frame.area(), state))
+            .expect("draw");
+        let buffer = terminal.backend().buffer();
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn widgets_render_from_the_view_model_alone() {
+        let config = AppConfig::default();
+        let entry = |model: &str, output_tokens: u64, cost_usd: f64| UsageEntry {
+            timestamp: "2026-10-10T00:00:00Z".to_string(),
+            provider: "openai".into(),
+            model: model.into(),
+            input_tokens: 0,
+            output_tokens,
+            cost_usd,
+            pricing_match: None,
+            cost_source: Some(CostSource::Payload),
+            original_cost: None,
+            api_key: None,
+            project_id: None,
+            tag: None,
+            data_file: None,
+        };
+        let data = UsageData {
+            budget_usd: None,
+            entries: vec![
+                entry("gpt-4.1", 1_000_000, 8.0),
+                entry("gpt-4.1-mini", 1_000_000, 2.0),
+            ],
+        };
+        let summary = UsageSummary::from_data(&data);
+        let state = state(&config, &data, &summary);
+
+        let table = render(
+            100,
+            5,
+            find_widget("table:models").expect("models").draw,
+            &state,
+        );
+        assert!(table[0].starts_with("╭Cost per 1M Output Tokens (all time, w to change)"));
+        assert!(table[2].contains("1 openai/gpt-4.1-mini"));
+        assert!(table[3].contains("2 openai/gpt-4.1 "));
+        assert!(table[3].contains("+$6.000"));
+
+        let gauge = render(
+            30,
+            12,
+            find_widget("gauge:fuel").expect("fuel").draw,
+            &state,
+        );
+        assert!(gauge[0].starts_with("╭Fuel Tank"));
+        assert!(gauge[10].contains("25.0% left"));
+        assert!(find_widget("gauge:oil").is_none());
+
+        let figures = render(60, 7, draw_spend_figures, &state);
+        assert!(figures[0].starts_with("╭Spent by openai"));
+        assert!(figures[0].contains("╭Left of $10.000"));
+        let left = big_text("$2.500").expect("glyphs");
+        assert!(figures[1].contains(&left[0]) && figures[5].contains(&left[4]));
+    }
+
+    #[test]
+    fn no_canvas_mode_draws_text_a_screen_reader_can_follow() {
+        let config = AppConfig::default();
+        let data = UsageData {
+            budget_usd: Some(10.0),
+            entries: Vec::new(),
+        };
+        let summary = UsageSummary::from_data(&data);
+        let state = AppState {
+            no_canvas: true,
+            ..state(&config, &data, &summary)
+        };
+
+        let gauge = render(40, 4, find_widget("gauge:fuel").expect("fuel").draw, &state);
+        assert!(gauge[1].starts_with("│Fuel Tank: 25.0% left"));
+        let figures = render(60, 7, draw_spend_figures, &state);
+        assert!(figures[1].contains("$7.500"));
+        let forecast = render(40, 6, draw_forecast_chart, &state);
+        assert_eq!(forecast[2], "│Budget: $10.000                       │");
+    }
+
+    #[test]
+    fn limit_timelines_mark_now_against_the_used_share() {
+        let palette = AppConfig::default().theme.palette();
+        let now = clock::parse_timestamp("2026-10-16T00:00:00Z").expect("timestamp");
+        let five_hour = CodexRateLimit {
+            used_percent: 50.0,
+            window_minutes: 300,
+            resets_at: Some(now as u64 + 3600),
+        };
+        let text = |line: Line<'_>| line.to_string();
+        assert_eq!(
+            text(limit_timeline("5h", Some(&five_hour), now, 10, &palette)),
+            "5h     █████░░░│░┃ 50% used, 80% of the window gone, resets in 1h 0m"
+        );
+        assert_eq!(
+            text(limit_timeline("5h", Some(&five_hour), now, 9, &palette)),
+            "5h     50% used, 80% of the window gone, resets in 1h 0m"
+        );
+        let unknown = CodexRateLimit {
+            resets_at: None,
+            ..five_hour
+        };
+        assert_eq!(
+            text(limit_timeline("5h", Some(&unknown), now, 10, &palette)),
+            "5h     █████░░░░░┃ 50% used, reset unknown"
+        );
+        assert_eq!(
+            text(limit_timeline("weekly", None, now, 10, &palette)),
+            "weekly no reading yet"
+        );
+    }
+
+    #[test]
+    fn inspect_explains_a_dial_from_its_inputs() {
+        let config = AppConfig::default();
+        let data = UsageData {
+            budget_usd: Some(10.0),
+            entries: Vec::new(),
+        };
+        let summary = UsageSummary::from_data(&data);
+        let mut state = state(&config, &data, &summary);
+        state.dials.totals = GaugeTotals {
+            requests: 12.0,
+            all_requests: 35.0,
+            ..GaugeTotals::default()
+        };
+
+        let fuel = Dial::Fuel.explanation(&state);
+        assert_eq!(fuel[1], ("Formula", "1 - spent / budget".to_string()));
+        assert_eq!(
+            fuel[2],
+            ("Inputs", "$7.500 spent by openai of $10.000".to_string())
+        );
+        let traffic = Dial::Traffic.explanation(&state);
+        assert_eq!(traffic[2], ("Inputs", "12 / 35 requests".to_string()));
+        assert!(traffic[3].1.starts_with("every entry on record"));
+
+        let weighted = AppConfig {
+            gauge_half_life_hours: Some(24.0),
+            ..AppConfig::default()
+        };
+        let state = AppState {
+            config: &weighted,
+            ..state
+        };
+        assert!(
+            Dial::Traffic.explanation(&state)[3]
+                .1
+                .contains("0.5^(age / 24h)")
+        );
+        assert_eq!(
+            Dial::CodexWeekly.explanation(&state)[1].0,
+            "Inputs",
+            "no limits reported yet"
+        );
+    }
+}
@@ -0,0 +1,330 @@
+use std::fs;
+use std::path::Path;
+
+use color_eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+
+use crate::models::{
+    AppConfig, UsageData, default_currency, is_placeholder_key, save_config, save_usage_data,
+};
+
+const DEFAULT_BUDGET_USD: f64 = 50.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum WizardStep {
+    Budget,
+    Currency,
+    CodexImport,
+    SessionsDir,
+    Review,
+}
+
+impl WizardStep {
+    pub(crate) fn number(self) -> usize {
+        match self {
+            WizardStep::Budget => 1,
+            WizardStep::Currency => 2,
+            WizardStep::CodexImport => 3,
+            WizardStep::SessionsDir => 4,
+            WizardStep::Review => 5,
+        }
+    }
+}
+
+pub(crate) const WIZARD_STEPS: usize = 5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SetupChoices {
+    pub(crate) budget_usd: f64,
+    pub(crate) currency: String,
+    pub(crate) codex_enabled: bool,
+    pub(crate) codex_sessions_dir: Option<String>,
+}
+
+pub(crate) enum WizardOutcome {
+    Continue,
+    Finished(SetupChoices),
+    Cancelled,
+}
+
+/// First-run setup flow shown when no config file exists yet. Holds raw
+/// text for each field so validation errors can be shown in place.
+#[derive(Debug, Clone)]
+pub(crate) struct SetupWizard {
+    pub(crate) step: WizardStep,
+    pub(crate) budget: String,
+    pub(crate) currency: String,
+    pub(crate) codex_enabled: bool,
+    pub(crate) sessions_dir: String,
+    pub(crate) error: Option<String>,
+    pub(crate) keeps_existing_data: bool,
+}
+
+impl SetupWizard {
+    pub(crate) fn new(keeps_existing_data: bool) -> Self {
+        Self {
+            step: WizardStep::Budget,
+            budget: String::new(),
+            currency: String::new(),
+            codex_enabled: true,
+            sessions_dir: String::new(),
+            error: None,
+            keeps_existing_data,
+        }
+    }
+
+    pub(crate) fn handle_key(&mut self, key: KeyEvent) -> WizardOutcome {
+        match key.code {
+            KeyCode::Esc => return WizardOutcome::Cancelled,
+            KeyCode::Enter => return self.advance(),
+            KeyCode::BackTab | KeyCode::Up => self.back(),
+            KeyCode::Backspace => {
+                if let Some(field) = self.text_field() {
+                    field.pop();
+                }
+            }
+            KeyCode::Char(c) if self.step == WizardStep::CodexImport => match c {
+                'y' | 'Y' => self.codex_enabled = true,
+                'n' | 'N' => self.codex_enabled = false,
+                ' ' => self.codex_enabled = !self.codex_enabled,
+                _ => {}
+            },
+            KeyCode::Char(c) => {
+                if let Some(field) = self.text_field() {
+                    field.push(c);
+                }
+            }
+            _ => {}
+        }
+        WizardOutcome::Continue
+    }
+
+    fn text_field(&mut self) -> Option<&mut String> {
+        match self.step {
+            WizardStep::Budget => Some(&mut self.budget),
+            WizardStep::Currency => Some(&mut self.currency),
+            WizardStep::SessionsDir => Some(&mut self.sessions_dir),
+            WizardStep::CodexImport | WizardStep::Review => None,
+        }
+    }
+
+    fn advance(&mut self) -> WizardOutcome {
+        self.error = None;
+        self.step = match self.step {
+            WizardStep::Budget => {
+                if let Err(err) = parse_budget(&self.budget) {
+                    self.error = Some(err);
+                    return WizardOutcome::Continue;
+                }
+                WizardStep::Currency
+            }
+            WizardStep::Currency => {
+                if let Err(err) = parse_currency(&self.currency) {
+                    self.error = Some(err);
+                    return WizardOutcome::Continue;
+                }
+                WizardStep::CodexImport
+            }
+            WizardStep::CodexImport if self.codex_enabled => WizardStep::SessionsDir,
+            WizardStep::CodexImport | WizardStep::SessionsDir => WizardStep::Review,
+            WizardStep::Review => return WizardOutcome::Finished(self.choices()),
+        };
+        WizardOutcome::Continue
+    }
+
+    fn back(&mut self) {
+        self.error = None;
+        self.step = match self.step {
+            WizardStep::Budget | WizardStep::Currency => WizardStep::Budget,
+            WizardStep::CodexImport => WizardStep::Currency,
+            WizardStep::SessionsDir => WizardStep::CodexImport,
+            WizardStep::Review if self.codex_enabled => WizardStep::SessionsDir,
+            WizardStep::Review => WizardStep::CodexImport,
+        };
+    }
+
+    /// Values as they will be written; only valid once the earlier steps
+    /// have passed validation.
+    pub(crate) fn choices(&self) -> SetupChoices {
+        let sessions_dir = self.sessions_dir.trim();
+        SetupChoices {
+            budget_usd: parse_budget(&self.budget).unwrap_or(DEFAULT_BUDGET_USD),
+            currency: parse_currency(&self.currency).unwrap_or_else(|_| default_currency()),
+            codex_enabled: self.codex_enabled,
+            codex_sessions_dir: (self.codex_enabled && !sessions_dir.is_empty())
+                .then(|| sessions_dir.to_string()),
+        }
+    }
+}
+
+fn parse_budget(input: &str) -> std::result::Result<f64, String> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Ok(DEFAULT_BUDGET_USD);
+    }
+    match trimmed.parse::<f64>() {
+        Ok(value) if value > 0.0 && value.is_finite() => Ok(value),
+        _ => Err(format!("budget must be a positive number, got {trimmed:?}")),
+    }
+}
+
+fn parse_currency(input: &str) -> std::result::Result<String, String> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Ok(default_currency());
+    }
+    if trimmed.len() == 3 && trimmed.chars().all(|c| c.is_ascii_alphabetic()) {
+        Ok(trimmed.to_ascii_uppercase())
+    } else {
+        Err(format!(
+            "currency must be a 3-letter code like USD, got {trimmed:?}"
+        ))
+    }
+}
+
+/// Whether the dashboard should open the wizard: there is no config yet,
+/// or only one seeded by an older version, whose API keys are all still
+/// `<set-…-key>` placeholders. A config the wizard wrote has no keys at
+/// all, so it is not asked again.
+pub(crate) fn needs_setup(config_file: &Path) -> bool {
+    let Ok(contents) = fs::read_to_string(config_file) else {
+        return !config_file.exists();
+    };
+    serde_json::from_str::<AppConfig>(&contents).is_ok_and(|config| {
+        !config.api_keys.is_empty() && config.api_keys.values().all(|key| is_placeholder_key(key))
+    })
+}
+
+/// Writes the chosen config (without placeholder API keys) and, when no
+/// usage file exists yet, an empty one carrying the chosen budget. A
+/// seeded config already there keeps its other settings.
+pub(crate) fn apply_setup_choices(
+    choices: &SetupChoices,
+    config_file: &Path,
+    data_file: &Path,
+) -> Result<()> {
+    let mut config = fs::read_to_string(config_file)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<AppConfig>(&contents).ok())
+        .unwrap_or_default();
+    config.api_keys.retain(|_, key| !is_placeholder_key(key));
+    config.currency = choices.currency.clone();
+    config.codex_import.enabled = choices.codex_enabled;
+    config.codex_import.sessions_dir = choices.codex_sessions_dir.clone();
+    save_config(config_file, &config)?;
+
+    if !data_file.exists() {
+        let data = UsageData {
+            budget_usd: Some(choices.budget_usd),
+            entries: Vec::new(),
+        };
+        save_usage_data(data_file, &data)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyModifiers;
+
+    use super::*;
+    use crate::models::load_or_bootstrap_config;
+    use crate::test_support::make_temp_dir;
+
+    fn press(wizard: &mut SetupWizard, code: KeyCode) -> WizardOutcome {
+        wizard.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn type_text(wizard: &mut SetupWizard, text: &str) {
+        for c in text.chars() {
+            press(wizard, KeyCode::Char(c));
+        }
+    }
+
+    #[test]
+    fn wizard_rejects_invalid_budget_and_collects_choices() {
+        let mut wizard = SetupWizard::new(false);
+        type_text(&mut wizard, "abc");
+        press(&mut wizard, KeyCode::Enter);
+        assert_eq!(wizard.step, WizardStep::Budget);
+        assert!(wizard.error.is_some());
+
+        for _ in 0..3 {
+            press(&mut wizard, KeyCode::Backspace);
+        }
+        type_text(&mut wizard, "120");
+        press(&mut wizard, KeyCode::Enter);
+        type_text(&mut wizard, "eur");
+        press(&mut wizard, KeyCode::Enter);
+        assert_eq!(wizard.step, WizardStep::CodexImport);
+        press(&mut wizard, KeyCode::Enter);
+        type_text(&mut wizard, "/data/codex");
+        press(&mut wizard, KeyCode::Enter);
+        assert_eq!(wizard.step, WizardStep::Review);
+
+        let WizardOutcome::Finished(choices) = press(&mut wizard, KeyCode::Enter) else {
+            panic!("expected wizard to finish");
+        };
+        assert_eq!(
+            choices,
+            SetupChoices {
+                budget_usd: 120.0,
+                currency: "EUR".to_string(),
+                codex_enabled: true,
+                codex_sessions_dir: Some("/data/codex".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn wizard_skips_sessions_dir_when_codex_disabled() {
+        let mut wizard = SetupWizard::new(false);
+        press(&mut wizard, KeyCode::Enter);
+        press(&mut wizard, KeyCode::Enter);
+        press(&mut wizard, KeyCode::Char('n'));
+        press(&mut wizard, KeyCode::Enter);
+        assert_eq!(wizard.step, WizardStep::Review);
+        press(&mut wizard, KeyCode::Up);
+        assert_eq!(wizard.step, WizardStep::CodexImport);
+
+        let choices = wizard.choices();
+        assert_eq!(choices.budget_usd, DEFAULT_BUDGET_USD);
+        assert_eq!(choices.currency, "USD");
+        assert!(!choices.codex_enabled);
+        assert!(choices.codex_sessions_dir.is_none());
+    }
+
+    #[test]
+    fn headless_runs_leave_setup_to_the_wizard() {
+        let dir = make_temp_dir("wizard");
+        let config_file = dir.join("config.json");
+        let data_file = dir.join("usage.json");
+        assert!(needs_setup(&config_file));
+        let config = load_or_bootstrap_config(&config_file).expect("config");
+        assert!(config.api_keys.is_empty());
+        assert!(!config_file.exists());
+
+        // A config seeded by an older version: placeholders only.
+        let seeded = AppConfig {
+            max_fps: 30,
+            ..AppConfig::default()
+        };
+        save_config(&config_file, &seeded).expect("seed");
+        assert!(needs_setup(&config_file));
+
+        let choices = SetupWizard::new(false).choices();
+        apply_setup_choices(&choices, &config_file, &data_file).expect("apply");
+        let config = load_or_bootstrap_config(&config_file).expect("config");
+        assert!(config.api_keys.is_empty());
+        assert_eq!(config.max_fps, 30);
+        assert!(!needs_setup(&config_file));
+
+        let mut keyed = config;
+        keyed
+            .api_keys
+            .insert("openai".to_string(), "sk-live-1234567890abcdef".to_string());
+        save_config(&config_file, &keyed).expect("save");
+        assert!(!needs_setup(&config_file));
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,1005 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use color_eyre::eyre::{Result, bail, eyre};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+
+use crate::alert_routing;
+use crate::alerts::evaluate_alerts;
+use crate::budget_period::{apply_budget_schedule, budget_spend};
+use crate::clock;
+use crate::codex_import::{
+    CodexImportCache, CodexImportDiagnostics, CodexRateLimits, ImportProgress, QuarantinedSession,
+    SessionCost, append_codex_entries, codex_import_diagnostics, codex_limit_history, expand_home,
+    failing_sessions, home_dir, imported_entry_count, imported_token_total, latest_codex_limits,
+    load_import_cache, rediscover_sessions, refresh_codex_sessions,
+    refresh_codex_sessions_with_progress, retry_quarantined, runaway_sessions, save_import_cache,
+};
+use crate::context_growth::{ContextGrowth, HeavyLoad, context_growth, heavy_load};
+use crate::editor_import::{
+    EditorImportCache, EditorSource, EditorSourceStatus, PlanQuota, append_editor_entries,
+    imported_editor_tokens, plan_quotas, refresh_editor_imports,
+};
+use crate::freeze::set_spending_frozen;
+use crate::importers::{Importer, ImporterStatus, set_importer_enabled};
+use crate::journal;
+use crate::langfuse_import::{
+    LangfuseCache, append_langfuse_entries, imported_generation_tokens, refresh_langfuse,
+};
+use crate::load_range::{LoadRange, retain_in_range};
+use crate::models::{
+    AppConfig, UsageData, apply_cost_multipliers, import_cache_file, load_or_bootstrap_config,
+    load_or_bootstrap_data, mark_data_file, merge_data_file, parse_usage_data, read_locked,
+    routed_alerts_file,
+};
+use crate::pricing_editor::{PricingChange, recost_data_file, save_model_pricing};
+use crate::rate_limit::RequestBudget;
+use crate::reconcile::{Reconciliation, reconcile};
+use crate::redact::Redactor;
+use crate::refresh::{RefreshPlanner, RefreshTask};
+use crate::retry::{CircuitBreaker, PollerHealth};
+use crate::sync_conflicts::merge_sync_conflicts;
+use crate::team_budget::{TeamBudgetState, sync_team_budget};
+use crate::throttle::update_throttle_file;
+
+/// Immutable view of everything the UI renders, published after each refresh.
+#[derive(Debug, Clone)]
+pub(crate) struct Snapshot {
+    pub(crate) config: AppConfig,
+    pub(crate) data: UsageData,
+    pub(crate) codex_limits: Option<CodexRateLimits>,
+    /// Codex rate-limit readings of the last day, oldest first, for the
+    /// replay.
+    pub(crate) codex_limit_history: Vec<(i64, CodexRateLimits)>,
+    pub(crate) codex_diagnostics: CodexImportDiagnostics,
+    pub(crate) status: String,
+    /// Last state fetched from the shared team budget, if one is configured.
+    pub(crate) team_budget: Option<TeamBudgetState>,
+    pub(crate) editor_imports: Vec<(EditorSource, EditorSourceStatus)>,
+    /// Plan allowances metered in prompts or credits rather than dollars.
+    pub(crate) plan_quotas: Vec<PlanQuota>,
+    pub(crate) importers: Vec<ImporterStatus>,
+    /// Sessions over `session_cost_ceiling_usd`, most expensive first.
+    pub(crate) runaway_sessions: Vec<SessionCost>,
+    /// Per-turn input growth of recent Codex sessions.
+    pub(crate) context_growth: Vec<ContextGrowth>,
+    /// Set while the running Codex session is over `heavy_load_turn_tokens`.
+    pub(crate) heavy_load: Option<HeavyLoad>,
+    /// Network pollers in use, with their retry state.
+    pub(crate) pollers: Vec<PollerHealth>,
+    /// Estimates dropped as duplicates of billed usage.
+    pub(crate) reconciliation: Reconciliation,
+    /// Unspent budget `budget_rollover` brought into this month, part of
+    /// `data.budget_usd`.
+    pub(crate) carried_over_usd: f64,
+}
+
+impl Snapshot {
+    /// `data` under the default config, with nothing imported or polled.
+    #[cfg(test)]
+    pub(crate) fn for_test(data: UsageData) -> Snapshot {
+        Snapshot {
+            config: AppConfig::default(),
+            data,
+            codex_limits: None,
+            codex_limit_history: Vec::new(),
+            codex_diagnostics: codex_import_diagnostics(&CodexImportCache::default()),
+            status: "Ready".to_string(),
+            team_budget: None,
+            editor_imports: Vec::new(),
+            plan_quotas: Vec::new(),
+            importers: Vec::new(),
+            runaway_sessions: Vec::new(),
+            context_growth: Vec::new(),
+            heavy_load: None,
+            pollers: Vec::new(),
+            reconciliation: Reconciliation::default(),
+            carried_over_usd: 0.0,
+        }
+    }
+}
+
+/// How a refresh task went the last time it ran.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TaskRun {
+    pub(crate) task: RefreshTask,
+    pub(crate) took: Duration,
+    pub(crate) error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum WorkerCommand {
+    Reload,
+    /// Parse quarantined session files again.
+    RetryQuarantined,
+    /// Switch an importer on or off in the config file.
+    SetImporter {
+        importer: Importer,
+        enabled: bool,
+    },
+    /// Restore the data file to before its last journaled change.
+    Undo,
+    /// Save a model's rates to the config file and recost its estimates.
+    SetPricing(PricingChange),
+    /// Freeze spending from now on, or lift the freeze.
+    SetFrozen(bool),
+}
+
+/// Owns the on-disk sources and importer caches. All filesystem work happens
+/// here so the render loop never blocks on a scan.
+pub(crate) struct RefreshWorker {
+    data_file: PathBuf,
+    /// Further `--data-file`s, read but never written; the config's
+    /// `data_files` add to them.
+    extra_data_files: Vec<PathBuf>,
+    config_file: PathBuf,
+    config: AppConfig,
+    base_data: UsageData,
+    codex_cache: CodexImportCache,
+    langfuse_cache: LangfuseCache,
+    editor_cache: EditorImportCache,
+    planner: RefreshPlanner,
+    last_error: Option<String>,
+    /// Each task's last run, in the order they first ran, for `diagnose`.
+    task_runs: Vec<TaskRun>,
+    /// False for a viewer reading a daemon's store: the store already holds
+    /// the imported entries, so importing again would count them twice.
+    collect_imports: bool,
+    /// Where the Codex import cache is persisted between runs.
+    import_cache_file: Option<PathBuf>,
+    /// True until the first Codex import pass has run. That pass happens in
+    /// the background so the dashboard can show progress instead of
+    /// blocking startup.
+    initial_import_pending: bool,
+    import_progress: Option<UnboundedSender<ImportProgress>>,
+    team_budget: Option<TeamBudgetState>,
+    last_team_sync: Option<Instant>,
+    /// The data file as last read, journaled when it changes so the change
+    /// can be undone. Not kept for a viewer's store, which the daemon
+    /// rewrites on every refresh.
+    data_contents: Option<String>,
+    /// Modification time and length of each data file when they were last
+    /// read, to notice edits by other processes between timed refreshes.
+    data_stamps: Vec<Option<(SystemTime, u64)>>,
+    /// `--since`/`--until`; the config's `load_since`/`load_until` fill in
+    /// whichever is unset.
+    load_range: LoadRange,
+    /// One-off news from the last refresh, shown instead of the usual
+    /// status line until the next one.
+    notice: Option<String>,
+    langfuse_breaker: CircuitBreaker,
+    team_budget_breaker: CircuitBreaker,
+    langfuse_requests: RequestBudget,
+    team_budget_requests: RequestBudget,
+}
+
+impl RefreshWorker {
+    pub(crate) fn new(
+        data_file: PathBuf,
+        extra_data_files: Vec<PathBuf>,
+        config_file: PathBuf,
+        refresh_interval: Duration,
+    ) -> Result<Self> {
+        Self::build(
+            data_file,
+            extra_data_files,
+            config_file,
+            refresh_interval,
+            true,
+        )
+    }
+
+    /// A worker that only follows `store_file` as written by
+    /// `promptpetrol daemon`, leaving session imports to the daemon.
+    pub(crate) fn viewer(
+        store_file: PathBuf,
+        config_file: PathBuf,
+        refresh_interval: Duration,
+    ) -> Result<Self> {
+        if !store_file.exists() {
+            bail!(
+                "no daemon store at {}; start `promptpetrol daemon` first",
+                store_file.display()
+            );
+        }
+        Self::build(store_file, Vec::new(), config_file, refresh_interval, false)
+    }
+
+    fn build(
+        data_file: PathBuf,
+        extra_data_files: Vec<PathBuf>,
+        config_file: PathBuf,
+        refresh_interval: Duration,
+        collect_imports: bool,
+    ) -> Result<Self> {
+        let config = load_config(&config_file, collect_imports)?;
+        let notice = if collect_imports {
+            merge_sync_conflicts(&data_file, &config)?.map(|report| report.describe())
+        } else {
+            None
+        };
+        let import_cache_file = collect_imports.then(|| import_cache_file(&config_file));
+        let codex_cache = match import_cache_file.as_deref() {
+            Some(path) if config.codex_import.enabled => load_import_cache(path),
+            _ => CodexImportCache::default(),
+        };
+        let mut planner = RefreshPlanner::new(&config, refresh_interval);
+        let now = Instant::now();
+        planner.mark_ran(RefreshTask::Config, now);
+        planner.mark_ran(RefreshTask::Usage, now);
+        let initial_import_pending = config.codex_import.enabled;
+        let langfuse_requests = RequestBudget::new("langfuse", &config);
+        let team_budget_requests = RequestBudget::new("team_budget", &config);
+        let data_contents = if collect_imports {
+            read_locked(&data_file).ok()
+        } else {
+            None
+        };
+        let mut worker = Self {
+            data_file,
+            extra_data_files,
+            config_file,
+            config,
+            base_data: UsageData::default(),
+            codex_cache,
+            langfuse_cache: LangfuseCache::default(),
+            editor_cache: EditorImportCache::default(),
+            planner,
+            last_error: None,
+            task_runs: Vec::new(),
+            collect_imports,
+            import_cache_file,
+            initial_import_pending,
+            import_progress: None,
+            team_budget: None,
+            last_team_sync: None,
+            data_contents,
+            data_stamps: Vec::new(),
+            load_range: LoadRange::default(),
+            notice,
+            langfuse_breaker: CircuitBreaker::new("langfuse"),
+            team_budget_breaker: CircuitBreaker::new("team budget"),
+            langfuse_requests,
+            team_budget_requests,
+        };
+        worker.data_stamps = worker.data_stamps();
+        worker.base_data = worker.load_data()?;
+        Ok(worker)
+    }
+
+    /// Sends progress of the first import pass to `progress`.
+    pub(crate) fn with_import_progress(
+        mut self,
+        progress: UnboundedSender<ImportProgress>,
+    ) -> Self {
+        self.import_progress = Some(progress);
+        self
+    }
+
+    /// Keeps only the entries within `range`, from every source.
+    pub(crate) fn with_load_range(mut self, range: LoadRange) -> Self {
+        self.load_range = range;
+        let bounds = self.load_bounds();
+        retain_in_range(&mut self.base_data, bounds);
+        self
+    }
+
+    /// The loaded range as Unix seconds, with relative days counted from now.
+    fn load_bounds(&self) -> (Option<i64>, Option<i64>) {
+        self.load_range
+            .or(LoadRange::from_config(&self.config))
+            .bounds(clock::now_epoch_secs())
+    }
+
+    pub(crate) fn initial_import_pending(&self) -> bool {
+        self.initial_import_pending
+    }
+
+    /// Runs the pending import passes on the current thread, for callers
+    /// that need complete data before they go on (exports, the daemon).
+    pub(crate) fn import_now(&mut self) {
+        self.run_tasks(
+            &[
+                RefreshTask::CodexImport,
+                RefreshTask::LangfuseImport,
+                RefreshTask::EditorImport,
+            ],
+            Instant::now(),
+        );
+    }
+
+    pub(crate) fn task_runs(&self) -> &[TaskRun] {
+        &self.task_runs
+    }
+
+    /// Codex session files that failed to parse, with where and why.
+    pub(crate) fn failing_sessions(&self) -> Vec<QuarantinedSession> {
+        failing_sessions(&self.codex_cache)
+    }
+
+    /// Persists the import cache so the next start skips unchanged files.
+    pub(crate) fn save_import_cache(&self) -> Result<()> {
+        match self.import_cache_file.as_deref() {
+            Some(path) if self.config.codex_import.enabled => {
+                save_import_cache(&self.codex_cache, path)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The data file with every importer's entries, less the estimates
+    /// that billed entries report too, and the budget carried over into
+    /// this month.
+    fn merged_data(&self) -> (UsageData, Reconciliation, f64) {
+        let mut data = self.base_data.clone();
+        append_codex_entries(&mut data, &self.config, &self.codex_cache);
+        append_langfuse_entries(&mut data, &self.config, &self.langfuse_cache);
+        append_editor_entries(&mut data, &self.config, &self.editor_cache);
+        retain_in_range(&mut data, self.load_bounds());
+        apply_cost_multipliers(&mut data, &self.config);
+        let reconciliation = reconcile(&mut data, &self.config);
+        let carried = apply_budget_schedule(&mut data, &self.config, clock::now_epoch_secs());
+        (data, reconciliation, carried)
+    }
+
+    /// Errors quote URLs, headers and config values, and the snapshot is
+    /// served over HTTP and delivered to alert channels, so every error in
+    /// it goes through the config's [`Redactor`] first.
+    pub(crate) fn snapshot(&self) -> Snapshot {
+        let (data, reconciliation, carried_over_usd) = self.merged_data();
+        let redactor = Redactor::new(&self.config);
+        let status = match (self.last_error.as_ref(), self.notice.as_ref()) {
+            (Some(err), _) => format!("Reload failed: {err}"),
+            (None, Some(notice)) => notice.clone(),
+            (None, None) => build_status_line(&self.config, &self.codex_cache, &self.planner),
+        };
+        let status = redactor.text(&status);
+        let mut importers = self.importer_statuses();
+        for importer in &mut importers {
+            importer.error = importer.error.as_deref().map(|error| redactor.text(error));
+        }
+        let mut pollers = self.poller_health();
+        for poller in &mut pollers {
+            poller.last_error = poller
+                .last_error
+                .as_deref()
+                .map(|error| redactor.text(error));
+        }
+        Snapshot {
+            config: self.config.clone(),
+            data,
+            codex_limits: latest_codex_limits(&self.codex_cache),
+            codex_limit_history: codex_limit_history(
+                &self.codex_cache,
+                clock::now_epoch_secs() - clock::SECS_PER_DAY,
+            ),
+            codex_diagnostics: codex_import_diagnostics(&self.codex_cache),
+            status,
+            team_budget: self.team_budget.clone(),
+            editor_imports: self.editor_cache.statuses(),
+            plan_quotas: plan_quotas(&self.config, &self.editor_cache, clock::now_epoch_secs()),
+            importers,
+            runaway_sessions: self
+                .config
+                .session_cost_ceiling_usd
+                .map(|ceiling| runaway_sessions(&self.config, &self.codex_cache, ceiling))
+                .unwrap_or_default(),
+            context_growth: context_growth(&self.config, &self.codex_cache),
+            heavy_load: heavy_load(&self.config, &self.codex_cache, SystemTime::now()),
+            pollers,
+            reconciliation,
+            carried_over_usd,
+        }
+    }
+
+    fn poller_health(&self) -> Vec<PollerHealth> {
+        let now = Instant::now();
+        let langfuse = self
+            .config
+            .langfuse
+            .as_ref()
+            .is_some_and(|langfuse| langfuse.enabled);
+        [
+            (langfuse, &self.langfuse_breaker),
+            (self.config.team_budget.is_some(), &self.team_budget_breaker),
+        ]
+        .into_iter()
+        .filter(|(active, _)| *active)
+        .map(|(_, breaker)| breaker.health(now))
+        .collect()
+    }
+
+    fn importer_statuses(&self) -> Vec<ImporterStatus> {
+        Importer::ALL
+            .into_iter()
+            .map(|importer| {
+                let enabled = importer.is_enabled(&self.config);
+                let (last_run, entries, error) = match importer {
+                    Importer::Codex => {
+                        let diagnostics = codex_import_diagnostics(&self.codex_cache);
+                        let error =
+                            match (diagnostics.quarantined.len(), diagnostics.parse_error_files) {
+                                (0, 0) => None,
+                                (0, failed) => {
+                                    Some(format!("{failed} session files failed to parse"))
+                                }
+                                (quarantined, _) => {
+                                    Some(format!("{quarantined} session files quarantined"))
+                                }
+                            };
+                        (
+                            diagnostics.last_import_at,
+                            imported_entry_count(&self.codex_cache),
+                            error,
+                        )
+                    }
+                    Importer::Langfuse => (
+                        self.langfuse_cache.last_run,
+                        self.langfuse_cache.generation_count(),
+                        self.langfuse_cache.last_error.clone(),
+                    ),
+                    Importer::Continue | Importer::Zed | Importer::JetBrains => importer
+                        .editor_source()
+                        .and_then(|source| self.editor_cache.status(source))
+                        .map_or((None, 0, None), |status| {
+                            (status.last_run, status.records, status.error.clone())
+                        }),
+                };
+                ImporterStatus {
+                    importer,
+                    enabled,
+                    last_run,
+                    entries: if enabled { entries } else { 0 },
+                    error: error.filter(|_| enabled),
+                }
+            })
+            .collect()
+    }
+
+    /// Writes an importer toggle to the config file; the config reload that
+    /// follows applies it.
+    fn set_importer(&self, importer: Importer, enabled: bool) -> Result<()> {
+        if !self.collect_imports {
+            bail!("importers run in the daemon; toggle them in its config");
+        }
+        set_importer_enabled(&self.config_file, importer, enabled)
+    }
+
+    /// Cheap summary of the usage the dashboard would show; any change
+    /// between refresh cycles counts as activity.
+    fn usage_fingerprint(&self) -> (usize, u64, u64, u64, u64) {
+        let base_tokens = self
+            .base_data
+            .entries
+            .iter()
+            .map(|entry| entry.input_tokens + entry.output_tokens)
+            .sum();
+        (
+            self.base_data.entries.len(),
+            base_tokens,
+            imported_token_total(&self.codex_cache),
+            imported_generation_tokens(&self.langfuse_cache),
+            imported_editor_tokens(&self.editor_cache),
+        )
+    }
+
+    /// After a suspend, nothing waited on so far is worth waiting out: the
+    /// idle backoff, the Codex discovery backoff, paused pollers and the
+    /// team sync interval all start over.
+    fn wake_up(&mut self, now: Instant) {
+        self.planner.reset_idle();
+        rediscover_sessions(&mut self.codex_cache);
+        self.langfuse_breaker.retry_now(now);
+        self.team_budget_breaker.retry_now(now);
+        self.last_team_sync = None;
+    }
+
+    fn run_tasks(&mut self, tasks: &[RefreshTask], now: Instant) {
+        self.last_error = None;
+        self.notice = None;
+        let before = self.usage_fingerprint();
+        for task in tasks {
+            let started = Instant::now();
+            let result = match task {
+                RefreshTask::Config => self.refresh_config(),
+                RefreshTask::Usage => self.refresh_usage(),
+                RefreshTask::CodexImport => {
+                    match self.import_progress.as_ref() {
+                        Some(progress) if self.initial_import_pending => {
+                            refresh_codex_sessions_with_progress(
+                                &self.config,
+                                &mut self.codex_cache,
+                                |update| {
+                                    let _ = progress.send(update);
+                                },
+                            );
+                        }
+                        _ => refresh_codex_sessions(&self.config, &mut self.codex_cache),
+                    }
+                    self.initial_import_pending = false;
+                    Ok(())
+                }
+                RefreshTask::LangfuseImport => self.langfuse_breaker.call(now, || {
+                    refresh_langfuse(
+                        &self.config,
+                        &mut self.langfuse_cache,
+                        &self.langfuse_requests,
+                    )
+                }),
+                RefreshTask::EditorImport => {
+                    refresh_editor_imports(&self.config, &mut self.editor_cache);
+                    Ok(())
+                }
+            };
+            self.planner.mark_ran(*task, now);
+            let run = TaskRun {
+                task: *task,
+                took: started.elapsed(),
+                error: result.err().map(|err| err.to_string()),
+            };
+            if let Some(error) = run.error.as_ref() {
+                self.last_error.get_or_insert(error.clone());
+            }
+            match self.task_runs.iter_mut().find(|last| last.task == *task) {
+                Some(last) => *last = run,
+                None => self.task_runs.push(run),
+            }
+        }
+        if tasks.iter().any(|task| {
+            matches!(
+                task,
+                RefreshTask::Usage
+                    | RefreshTask::CodexImport
+                    | RefreshTask::LangfuseImport
+                    | RefreshTask::EditorImport
+            )
+        }) {
+            let changed = self.usage_fingerprint() != before;
+            self.planner.record_activity(changed);
+        }
+        if let Err(err) = self.sync_team_budget(now) {
+            self.last_error.get_or_insert(err.to_string());
+        }
+    }
+
+    /// Publishes local spend to the team budget at most once per
+    /// `sync_interval_secs`: what counts against the budget, so only this
+    /// month's under `budget_rollover`. A failed sync keeps the last known
+    /// team state.
+    fn sync_team_budget(&mut self, now: Instant) -> Result<()> {
+        let Some(team) = self.config.team_budget.as_ref() else {
+            self.team_budget = None;
+            return Ok(());
+        };
+        let interval = Duration::from_secs(team.sync_interval_secs);
+        if self
+            .last_team_sync
+            .is_some_and(|last| now.saturating_duration_since(last) < interval)
+        {
+            return Ok(());
+        }
+        self.last_team_sync = Some(now);
+        let spent = budget_spend(&self.merged_data().0, &self.config, clock::now_epoch_secs());
+        self.team_budget = Some(self.team_budget_breaker.call(now, || {
+            sync_team_budget(
+                team,
+                &self.config.network,
+                &self.team_budget_requests,
+                spent,
+            )
+        })?);
+        Ok(())
+    }
+
+    fn refresh_config(&mut self) -> Result<()> {
+        self.config = load_config(&self.config_file, self.collect_imports)?;
+        self.planner.reconfigure(&self.config);
+        self.langfuse_requests.configure(&self.config);
+        self.team_budget_requests.configure(&self.config);
+        Ok(())
+    }
+
+    fn refresh_usage(&mut self) -> Result<()> {
+        if self.collect_imports {
+            self.journal_data_file_change()?;
+            if let Some(report) = merge_sync_conflicts(&self.data_file, &self.config)? {
+                self.notice = Some(report.describe());
+                self.data_contents = Some(report.contents);
+            }
+        }
+        self.data_stamps = self.data_stamps();
+        self.base_data = self.load_data()?;
+        Ok(())
+    }
+
+    /// The data file with the extra ones merged in. Entries only say which
+    /// file they came from when there is more than one.
+    fn load_data(&self) -> Result<UsageData> {
+        let mut data = load_or_bootstrap_data(&self.data_file, &self.config)?;
+        let extra_data_files = self.all_extra_data_files();
+        if extra_data_files.is_empty() {
+            retain_in_range(&mut data, self.load_bounds());
+            return Ok(data);
+        }
+        mark_data_file(&mut data, &self.data_file);
+        for path in &extra_data_files {
+            let more = read_locked(path)
+                .and_then(|contents| parse_usage_data(&contents, &self.config))
+                .map_err(|err| eyre!("data file {}: {err}", path.display()))?;
+            merge_data_file(&mut data, path, more);
+            retain_in_range(&mut data, self.load_bounds());
+        }
+        data.entries.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+        Ok(data)
+    }
+
+    /// The extra data files from the command line and the config, without
+    /// repeats. A viewer has none: the daemon's store already holds them.
+    fn all_extra_data_files(&self) -> Vec<PathBuf> {
+        if !self.collect_imports {
+            return Vec::new();
+        }
+        let home = home_dir();
+        let mut files: Vec<PathBuf> = Vec::new();
+        for path in self.extra_data_files.iter().cloned().chain(
+            self.config
+                .data_files
+                .iter()
+                .map(|path| expand_home(path, &home)),
+        ) {
+            if path != self.data_file && !files.contains(&path) {
+                files.push(path);
+            }
+        }
+        files
+    }
+
+    fn data_stamps(&self) -> Vec<Option<(SystemTime, u64)>> {
+        std::iter::once(self.data_file.clone())
+            .chain(self.all_extra_data_files())
+            .map(|path| file_stamp(&path))
+            .collect()
+    }
+
+    /// True when another process (an editor, a sync client, or the daemon
+    /// for a viewer) has written a data file since it was last read.
+    fn data_file_changed(&self) -> bool {
+        self.data_stamps() != self.data_stamps
+    }
+
+    /// Keeps the previous version of the data file when it was edited since
+    /// the last read.
+    fn journal_data_file_change(&mut self) -> Result<()> {
+        let current = read_locked(&self.data_file).ok();
+        if let (Some(previous), Some(current)) = (self.data_contents.as_ref(), current.as_ref())
+            && previous != current
+            && !journal::is_undo_result(&self.data_file, current)
+        {
+            journal::record(&self.data_file, previous, "edit")?;
+        }
+        self.data_contents = current;
+        Ok(())
+    }
+
+    /// Writes `change` to the config file, reloads it and recosts the
+    /// estimates the data file stored under the old rates; imported entries
+    /// are costed afresh on every merge. Returns a status line.
+    fn set_pricing(&mut self, change: &PricingChange) -> Result<String> {
+        if !self.collect_imports {
+            bail!("the viewer shows the daemon's store; edit pricing in the daemon's config");
+        }
+        save_model_pricing(&self.config_file, &change.key, &change.pricing)?;
+        self.refresh_config()?;
+        let (recosted, contents) = recost_data_file(
+            &self.data_file,
+            &self.config,
+            &change.provider,
+            &change.model,
+        )?;
+        if let Some(contents) = contents {
+            self.data_contents = Some(contents);
+        }
+        self.refresh_usage()?;
+        Ok(format!(
+            "Priced {} at {} in, {} out per 1M; recosted {recosted} stored {}",
+            change.key,
+            change.pricing.input_per_million_usd,
+            change.pricing.output_per_million_usd,
+            if recosted == 1 { "entry" } else { "entries" }
+        ))
+    }
+
+    /// Writes the freeze to the config file and reloads it. Returns a
+    /// status line.
+    fn set_frozen(&mut self, frozen: bool) -> Result<String> {
+        if !self.collect_imports {
+            bail!("the viewer shows the daemon's store; freeze spending in the daemon's config");
+        }
+        let since = frozen.then(|| clock::format_timestamp(clock::now_epoch_secs()));
+        set_spending_frozen(&self.config_file, since.as_deref())?;
+        self.refresh_config()?;
+        Ok(match since {
+            Some(since) => format!("Spending frozen since {since}; F lifts the freeze"),
+            None => "Spending freeze lifted".to_string(),
+        })
+    }
+
+    /// Restores the data file to before its last journaled change and
+    /// reloads it. Returns a status line describing what happened.
+    fn undo_last_change(&mut self) -> Result<String> {
+        if !self.collect_imports {
+            bail!("the viewer shows the daemon's store; run undo where the data file lives");
+        }
+        let Some((entry, contents)) = journal::undo(&self.data_file)? else {
+            return Ok("Nothing to undo".to_string());
+        };
+        self.data_contents = Some(contents);
+        self.refresh_usage()?;
+        Ok(format!(
+            "Undid {} ({})",
+            entry.operation,
+            clock::format_timestamp(entry.recorded_at)
+        ))
+    }
+}
+
+fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+fn load_config(path: &Path, collect_imports: bool) -> Result<AppConfig> {
+    let mut config = load_or_bootstrap_config(path)?;
+    if !collect_imports {
+        config.codex_import.enabled = false;
+        if let Some(langfuse) = config.langfuse.as_mut() {
+            langfuse.enabled = false;
+        }
+        if let Some(continue_dev) = config.continue_dev.as_mut() {
+            continue_dev.enabled = false;
+        }
+        if let Some(zed) = config.zed.as_mut() {
+            zed.enabled = false;
+        }
+        if let Some(jetbrains) = config.jetbrains.as_mut() {
+            jetbrains.enabled = false;
+        }
+        // The daemon's store already carries the multiplied costs.
+        config.cost_multipliers.clear();
+    }
+    Ok(config)
+}
+
+/// How often the data file is checked for outside edits between timed
+/// refreshes; a stat per second is cheap next to re-reading it.
+const DATA_FILE_POLL: Duration = Duration::from_secs(1);
+
+/// How far the wall clock may run ahead of the monotonic one between two
+/// polls before the gap is taken for a suspend. The monotonic clock stops
+/// while the machine sleeps, so on waking every interval still has the
+/// time it had left.
+const SUSPEND_GAP: Duration = Duration::from_secs(30);
+
+/// Importer task: sleeps until the planner's next deadline (or a forced
+/// reload, or an outside edit to the data file), runs the due refreshes on
+/// the blocking pool, and publishes a fresh snapshot. Exits when either
+/// channel is closed.
+pub(crate) async fn run_refresh_worker(
+    mut worker: RefreshWorker,
+    mut commands: UnboundedReceiver<WorkerCommand>,
+    snapshots: UnboundedSender<Snapshot>,
+) -> Result<()> {
+    let (delivery_tx, mut delivery_failures) = unbounded_channel::<Vec<String>>();
+    let routed_file = routed_alerts_file(&worker.config_file);
+    let mut last_poll = clock::Stamp::now();
+    loop {
+        let wait = worker.planner.time_until_next(Instant::now());
+        let mut slept = None;
+        let tasks = tokio::select! {
+            command = commands.recv() => match command {
+                Some(WorkerCommand::Reload) => {
+                    worker.planner.reset_idle();
+                    RefreshTask::ALL.to_vec()
+                }
+                Some(WorkerCommand::SetImporter { importer, enabled }) => {
+                    let set;
+                    (worker, set) = run_blocking(worker, move |worker| {
+                        worker.set_importer(importer, enabled)
+                    })
+                    .await?;
+                    if let Err(err) = set {
+                        worker.last_error = Some(err.to_string());
+                        if snapshots.send(worker.snapshot()).is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                    RefreshTask::ALL.to_vec()
+                }
+                Some(WorkerCommand::SetPricing(change)) => {
+                    let status;
+                    (worker, status) = run_blocking(worker, move |worker| {
+                        worker
+                            .set_pricing(&change)
+                            .unwrap_or_else(|err| format!("Saving pricing failed: {err}"))
+                    })
+                    .await?;
+                    let mut snapshot = worker.snapshot();
+                    snapshot.status = status;
+                    if snapshots.send(snapshot).is_err() {
+                        break;
+                    }
+                    continue;
+                }
+                Some(WorkerCommand::SetFrozen(frozen)) => {
+                    let status;
+                    (worker, status) = run_blocking(worker, move |worker| {
+                        worker
+                            .set_frozen(frozen)
+                            .unwrap_or_else(|err| format!("Freezing spending failed: {err}"))
+                    })
+                    .await?;
+                    let mut snapshot = worker.snapshot();
+                    snapshot.status = status;
+                    // The throttle file and the channels hear of a freeze
+                    // (or its end) now, not on the next refresh.
+                    if worker.collect_imports {
+                        route_new_alerts(&snapshot, &routed_file, &delivery_tx);
+                    }
+                    if snapshots.send(snapshot).is_err() {
+                        break;
+                    }
+                    continue;
+                }
+                Some(WorkerCommand::Undo) => {
+                    let status;
+                    (worker, status) = run_blocking(worker, |worker| {
+                        worker
+                            .undo_last_change()
+                            .unwrap_or_else(|err| format!("Undo failed: {err}"))
+                    })
+                    .await?;
+                    let mut snapshot = worker.snapshot();
+                    snapshot.status = status;
+                    if snapshots.send(snapshot).is_err() {
+                        break;
+                    }
+                    continue;
+                }
+                Some(WorkerCommand::RetryQuarantined) => {
+                    retry_quarantined(&mut worker.codex_cache);
+                    vec![RefreshTask::CodexImport]
+                }
+                None => break,
+            },
+            Some(failures) = delivery_failures.recv() => {
+                let mut snapshot = worker.snapshot();
+                snapshot.status = Redactor::new(&snapshot.config)
+                    .text(&format!("Alert delivery failed: {}", failures.join("; ")));
+                if snapshots.send(snapshot).is_err() {
+                    break;
+                }
+                continue;
+            }
+            _ = tokio::time::sleep(wait.min(DATA_FILE_POLL)) => {
+                let gap = last_poll.unaccounted();
+                last_poll = clock::Stamp::now();
+                if gap >= SUSPEND_GAP {
+                    slept = Some(gap);
+                    worker.wake_up(Instant::now());
+                    RefreshTask::ALL.to_vec()
+                } else {
+                    let mut tasks = worker.planner.due_tasks(Instant::now());
+                    if !tasks.contains(&RefreshTask::Usage) && worker.data_file_changed() {
+                        tasks.push(RefreshTask::Usage);
+                    }
+                    tasks
+                }
+            }
+        };
+        if tasks.is_empty() {
+            continue;
+        }
+
+        (worker, ()) = run_blocking(worker, move |worker| {
+            worker.run_tasks(&tasks, Instant::now());
+        })
+        .await?;
+        if let Some(slept) = slept
+            && worker.notice.is_none()
+        {
+            worker.notice = Some(format!(
+                "Woke after {}m asleep, reloaded everything",
+                slept.as_secs() / 60
+            ));
+        }
+        let snapshot = worker.snapshot();
+        if worker.collect_imports {
+            route_new_alerts(&snapshot, &routed_file, &delivery_tx);
+        }
+        if snapshots.send(snapshot).is_err() {
+            break;
+        }
+    }
+    worker.save_import_cache()
+}
+
+/// Runs a command on the blocking pool, as the refreshes are: commands
+/// rewrite the config or the data file, and reloading the config can fetch
+/// remote includes.
+async fn run_blocking<T: Send + 'static>(
+    mut worker: RefreshWorker,
+    command: impl FnOnce(&mut RefreshWorker) -> T + Send + 'static,
+) -> Result<(RefreshWorker, T)> {
+    Ok(tokio::task::spawn_blocking(move || {
+        let out = command(&mut worker);
+        (worker, out)
+    })
+    .await?)
+}
+
+/// Hands alerts that just started or stopped firing, against the record in
+/// `routed_file`, to their routed channels in the background and updates
+/// the throttle file; failures come back on `failures`. Viewers leave this
+/// to the daemon so nothing is sent twice.
+fn route_new_alerts(
+    snapshot: &Snapshot,
+    routed_file: &Path,
+    failures: &UnboundedSender<Vec<String>>,
+) {
+    let current = evaluate_alerts(snapshot);
+    if let Err(err) = update_throttle_file(&snapshot.config, &current) {
+        let _ = failures.send(vec![format!("throttle file: {err}")]);
+    }
+    let events = match alert_routing::take_new_events(&snapshot.config, routed_file, &current) {
+        Ok(events) => events,
+        Err(err) => {
+            let _ = failures.send(vec![format!("routed alerts: {err}")]);
+            return;
+        }
+    };
+    if !events.is_empty() {
+        let config = snapshot.config.clone();
+        let failures = failures.clone();
+        tokio::task::spawn_blocking(move || {
+            let failed = alert_routing::deliver(&config, &events);
+            if !failed.is_empty() {
+                let _ = failures.send(failed);
+            }
+        });
+    }
+}
+
+fn build_status_line(
+    config: &AppConfig,
+    cache: &CodexImportCache,
+    planner: &RefreshPlanner,
+) -> String {
+    let idle = match planner.idle_factor() {
+        1 => String::new(),
+        factor => format!(" idle:x{factor}"),
+    };
+    if !config.codex_import.enabled {
+        return format!("Ready{idle}");
+    }
+    let diagnostics = codex_import_diagnostics(cache);
+    let imported_ago_secs = diagnostics
+        .last_import_at
+        .map(|stamp| stamp.age().as_secs())
+        .unwrap_or(0);
+    let quarantined = match diagnostics.quarantined.len() {
+        0 => String::new(),
+        count => format!(" quarantined:{count}"),
+    };
+    format!(
+        "Codex import files:{} refreshed:{} parse:{} no_usage:{} unreadable:{}{quarantined} poll:{}s scan:{}s updated:{}s{idle}",
+        diagnostics.active_files,
+        diagnostics.refreshed_files,
+        diagnostics.parse_error_files,
+        diagnostics.no_usage_or_limits_files,
+        diagnostics.unreadable_files,
+        planner.interval(RefreshTask::CodexImport).as_secs(),
+        diagnostics.discovery_interval.as_secs(),
+        imported_ago_secs
+    )
+}
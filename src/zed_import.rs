@@ -0,0 +1,243 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use serde::de::{DeserializeOwned, IgnoredAny};
+
+use crate::clock;
+use crate::codex_import::{expand_home, home_dir};
+use crate::editor_import::{EditorRecord, ParsedEditorFile};
+use crate::models::ZedConfig;
+
+/// Zed's own hosted models; prompts to them are covered by the Zed plan
+/// rather than billed per token.
+const ZED_HOSTED_PROVIDER: &str = "zed.dev";
+
+/// The parts of a serialized agent thread PromptPetrol reads. Zed keeps a
+/// running token total per thread rather than per request, so each thread
+/// becomes one record dated by its last update. The thread is read
+/// straight from the file, so message text is skipped over, never copied.
+#[derive(Debug, Deserialize)]
+#[serde(bound(deserialize = "S: Deserialize<'de>"))]
+struct SerializedThread<S> {
+    #[serde(default)]
+    updated_at: Option<String>,
+    #[serde(default)]
+    summary: Option<S>,
+    #[serde(default)]
+    model: Option<SerializedModel>,
+    #[serde(default)]
+    messages: Vec<ThreadMessage>,
+    #[serde(default)]
+    cumulative_token_usage: TokenUsage,
+}
+
+/// Who sent a message. Older thread versions put the role on the message,
+/// newer ones under `metadata`.
+#[derive(Debug, Deserialize)]
+struct ThreadMessage {
+    #[serde(default)]
+    role: Option<String>,
+    #[serde(default)]
+    metadata: Option<MessageMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessageMetadata {
+    #[serde(default)]
+    role: Option<String>,
+}
+
+impl ThreadMessage {
+    fn role(&self) -> Option<&str> {
+        self.role
+            .as_deref()
+            .or_else(|| self.metadata.as_ref()?.role.as_deref())
+    }
+}
+
+/// What a thread's `summary`, a title Zed has the model write from the
+/// conversation, is read as: the text, or with `metadata_only` nothing.
+trait ThreadSummary: DeserializeOwned {
+    fn into_tag(self) -> Option<String>;
+}
+
+impl ThreadSummary for String {
+    fn into_tag(self) -> Option<String> {
+        Some(self).filter(|summary| !summary.is_empty())
+    }
+}
+
+impl ThreadSummary for IgnoredAny {
+    fn into_tag(self) -> Option<String> {
+        None
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SerializedModel {
+    provider: String,
+    model: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TokenUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+    #[serde(default)]
+    cache_creation_input_tokens: u64,
+    #[serde(default)]
+    cache_read_input_tokens: u64,
+}
+
+/// `threads_dir` from the config, else `zed/threads` under the platform
+/// data directory.
+pub(crate) fn zed_threads_dir(config: &ZedConfig) -> PathBuf {
+    let home = home_dir();
+    match config.threads_dir.as_deref() {
+        Some(dir) => expand_home(dir, &home),
+        None => dirs::data_dir()
+            .unwrap_or_else(|| home.join(".local").join("share"))
+            .join("zed")
+            .join("threads"),
+    }
+}
+
+pub(crate) fn zed_thread_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "json") {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// Parses one thread file. Threads on Zed's hosted models are recorded as
+/// plan-covered; threads on a bring-your-own-key provider are priced as
+/// that provider.
+pub(crate) fn parse_zed_thread(
+    path: &Path,
+    modified_epoch_secs: i64,
+    metadata_only: bool,
+) -> ParsedEditorFile {
+    if metadata_only {
+        read_thread::<IgnoredAny>(path, modified_epoch_secs)
+    } else {
+        read_thread::<String>(path, modified_epoch_secs)
+    }
+}
+
+fn read_thread<S: ThreadSummary>(path: &Path, modified_epoch_secs: i64) -> ParsedEditorFile {
+    let Some(thread) = File::open(path).ok().and_then(|file| {
+        serde_json::from_reader::<_, SerializedThread<S>>(BufReader::new(file)).ok()
+    }) else {
+        return ParsedEditorFile::Unreadable;
+    };
+    let timestamp = thread
+        .updated_at
+        .filter(|updated_at| clock::parse_timestamp(updated_at).is_some())
+        .unwrap_or_else(|| clock::format_timestamp(modified_epoch_secs));
+    let prompts = thread
+        .messages
+        .iter()
+        .filter(|message| message.role() == Some("user"))
+        .count() as u64;
+    let (model, pricing_provider) = match thread.model {
+        Some(model) => {
+            let provider = model.provider.to_lowercase();
+            let pricing_provider = (provider != ZED_HOSTED_PROVIDER).then_some(provider);
+            (model.model, pricing_provider)
+        }
+        None => ("unknown".to_string(), None),
+    };
+    let usage = thread.cumulative_token_usage;
+    let records = if usage.input_tokens + usage.output_tokens == 0 && prompts == 0 {
+        Vec::new()
+    } else {
+        vec![EditorRecord {
+            timestamp,
+            model,
+            pricing_provider,
+            input_tokens: usage.input_tokens
+                + usage.cache_creation_input_tokens
+                + usage.cache_read_input_tokens,
+            output_tokens: usage.output_tokens,
+            prompts,
+            tag: thread.summary.and_then(ThreadSummary::into_tag),
+        }]
+    };
+    ParsedEditorFile::Parsed {
+        records,
+        skipped_lines: 0,
+        quota: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::make_temp_dir;
+
+    #[test]
+    fn parses_threads_as_plan_or_byok_usage() {
+        let dir = make_temp_dir("zed");
+        let hosted = dir.join("hosted.json");
+        fs::write(
+            &hosted,
+            r#"{
+                "version": "0.2.0", "summary": "Fix flaky test",
+                "updated_at": "2026-03-02T10:00:00Z",
+                "model": {"provider": "zed.dev", "model": "claude-sonnet-4"},
+                "messages": [
+                    {"id": 0, "role": "user", "segments": []},
+                    {"id": 1, "role": "assistant", "segments": []},
+                    {"id": 2, "metadata": {"role": "user"}}
+                ],
+                "cumulative_token_usage": {"input_tokens": 900, "output_tokens": 300, "cache_read_input_tokens": 100}
+            }"#,
+        )
+        .expect("write");
+        let byok = dir.join("byok.json");
+        fs::write(
+            &byok,
+            r#"{"model": {"provider": "OpenAI", "model": "gpt-4.1"}, "messages": [{"role": "user"}],
+                "cumulative_token_usage": {"input_tokens": 10, "output_tokens": 5}}"#,
+        )
+        .expect("write");
+        fs::write(dir.join("broken.json"), "{").expect("write");
+        fs::write(dir.join("threads.db"), "").expect("write");
+
+        let mut files = zed_thread_files(&dir).expect("files");
+        files.sort();
+        assert_eq!(files.len(), 3);
+
+        let ParsedEditorFile::Parsed { records, .. } = parse_zed_thread(&hosted, 0, false) else {
+            panic!("expected parsed thread");
+        };
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].pricing_provider, None);
+        assert_eq!(records[0].prompts, 2);
+        assert_eq!(records[0].input_tokens, 1000);
+        assert_eq!(records[0].tag.as_deref(), Some("Fix flaky test"));
+        let ParsedEditorFile::Parsed { records, .. } = parse_zed_thread(&hosted, 0, true) else {
+            panic!("expected parsed thread");
+        };
+        assert_eq!((records[0].prompts, records[0].tag.as_deref()), (2, None));
+
+        let ParsedEditorFile::Parsed { records, .. } = parse_zed_thread(&byok, 0, false) else {
+            panic!("expected parsed thread");
+        };
+        assert_eq!(records[0].pricing_provider.as_deref(), Some("openai"));
+        assert_eq!(records[0].timestamp, "1970-01-01T00:00:00Z");
+        assert!(matches!(
+            parse_zed_thread(&dir.join("broken.json"), 0, false),
+            ParsedEditorFile::Unreadable
+        ));
+        let _ = fs::remove_dir_all(dir);
+    }
+}